@@ -7,35 +7,47 @@ use crate::error::ApiError::WrongAPI;
 use error::ApiError;
 use jsonrpc_core::{BoxFuture, IoHandler, Value};
 use jsonrpc_derive::rpc;
-use jsonrpc_http_server::{CloseHandle, ServerBuilder};
+use jsonrpc_http_server::ServerBuilder;
+use jsonrpc_pubsub::{typed::Subscriber, Session, SubscriptionId};
+use massa_consensus_exports::events::ConsensusBroadcastEvent;
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
-use massa_execution_exports::ExecutionController;
+use massa_execution_exports::{ExecutionController, SlotExecutionOutput};
+use massa_ledger_exports::LedgerProof;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressHistoryInput, AddressHistoryOutput, AddressInfo, AddressInfoAtSlot, AsyncMessageFilter,
+    AsyncMessageInfo, BlockInfo, BlockSummary, BytecodeHistoryOutput, CreatorBlocksInput,
+    CreatorBlocksOutput, DatastoreEntryAtSlotOutput, DatastoreEntryInput, DatastoreEntryOutput,
+    DatastoreKeysInput, DatastoreKeysOutput, EndorsementInfo, EventFilter, GasEstimationRequest,
+    NodeStatus, OperationInfo, OperationInput, OperationReceiptOutput, OperationStatus,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionSchedule, StakerMisbehaviors, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, GasEstimation, OperationSimulation};
 use massa_models::node::NodeId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::Set;
-use massa_models::{Address, BlockId, EndorsementId, Version};
+use massa_models::{Address, BlockId, EndorsementId, Slot, Version};
 use massa_network_exports::{NetworkCommandSender, NetworkSettings};
 use massa_pool::PoolCommandSender;
 use massa_signature::KeyPair;
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
 use std::thread::JoinHandle;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
 mod error;
 mod private;
 mod public;
 mod settings;
+#[cfg(test)]
+mod tests;
+mod ws;
 pub use settings::APISettings;
 
 /// Public API component
@@ -78,6 +90,27 @@ pub struct Private {
     pub stop_node_channel: mpsc::Sender<()>,
 }
 
+/// Websocket API content, serving subscriptions
+pub struct Ws {
+    /// link to the consensus component
+    pub consensus_command_sender: ConsensusCommandSender,
+    /// link to the block and operation events broadcast by consensus
+    pub consensus_broadcast_sender: broadcast::Sender<ConsensusBroadcastEvent>,
+    /// link to the slot execution outputs broadcast by execution
+    pub execution_broadcast_sender: broadcast::Sender<SlotExecutionOutput>,
+    /// API settings
+    pub api_settings: &'static APISettings,
+    /// runtime on which the subscription tasks are spawned
+    pub runtime: tokio::runtime::Handle,
+    /// id of the next subscription
+    pub next_subscription_id: AtomicU64,
+    /// running subscription tasks
+    pub subscriptions: Arc<Mutex<WsSubscriptions>>,
+}
+
+/// Running websocket subscription tasks along with the session that opened them, by subscription id
+pub type WsSubscriptions = HashMap<SubscriptionId, (Weak<Session>, tokio::task::JoinHandle<()>)>;
+
 /// The API wrapper
 pub struct API<T>(T);
 
@@ -97,7 +130,7 @@ fn serve(api: impl Endpoints, url: &SocketAddr) -> StopHandle {
         .start_http(url)
         .expect("Unable to start RPC server");
 
-    let close_handle = server.close_handle();
+    let close_handle = CloseHandle::Http(server.close_handle());
     let join_handle = thread::spawn(|| server.wait());
 
     StopHandle {
@@ -106,6 +139,21 @@ fn serve(api: impl Endpoints, url: &SocketAddr) -> StopHandle {
     }
 }
 
+/// Close handle of either an HTTP or a websocket server
+enum CloseHandle {
+    Http(jsonrpc_http_server::CloseHandle),
+    Ws(jsonrpc_ws_server::CloseHandle),
+}
+
+impl CloseHandle {
+    fn close(self) {
+        match self {
+            CloseHandle::Http(handle) => handle.close(),
+            CloseHandle::Ws(handle) => handle.close(),
+        }
+    }
+}
+
 /// Used to be able to stop the API
 pub struct StopHandle {
     close_handle: CloseHandle,
@@ -155,6 +203,16 @@ pub trait Endpoints {
         _: Vec<ReadOnlyCall>,
     ) -> BoxFuture<Result<Vec<ExecuteReadOnlyResponse>, ApiError>>;
 
+    /// Estimate the gas cost of `ExecuteSC` and `CallSC` operations by executing them in read-only mode
+    /// on behalf of their sender and with their coins.
+    /// The estimated cost includes the base cost of an operation, which is reserved out of its `max_gas`.
+    /// Also returns the ledger changes and events the execution would produce.
+    #[rpc(name = "estimate_gas")]
+    fn estimate_gas(
+        &self,
+        _: Vec<GasEstimationRequest>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>>;
+
     /// Remove a vector of addresses used to stake.
     /// No confirmation to expect.
     #[rpc(name = "remove_staking_addresses")]
@@ -215,6 +273,16 @@ pub trait Endpoints {
         _: Vec<OperationId>,
     ) -> BoxFuture<Result<Vec<OperationInfo>, ApiError>>;
 
+    /// Returns the final and candidate execution receipts of a given list of operations' IDs.
+    /// Only executed `ExecuteSC` and `CallSC` operations have receipts.
+    /// Final receipts are part of the final state for a retention period,
+    /// older ones are only available if the node archives them.
+    #[rpc(name = "get_operation_receipts")]
+    fn get_operation_receipts(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<OperationReceiptOutput>, ApiError>>;
+
     /// Get endorsements (not yet implemented).
     #[rpc(name = "get_endorsements")]
     fn get_endorsements(
@@ -239,10 +307,94 @@ pub trait Endpoints {
         _: Vec<DatastoreEntryInput>,
     ) -> BoxFuture<Result<Vec<DatastoreEntryOutput>, ApiError>>;
 
+    /// Get a page of the candidate datastore keys of an address, optionally starting with a prefix.
+    /// Pagination is done with an optional limit and a cursor set to the last key of the previous page.
+    #[rpc(name = "get_datastore_keys")]
+    fn get_datastore_keys(
+        &self,
+        _: DatastoreKeysInput,
+    ) -> BoxFuture<Result<DatastoreKeysOutput, ApiError>>;
+
+    /// Get the bytecode history of addresses: the hash and slot of each version of their bytecode.
+    #[rpc(name = "get_bytecode_history")]
+    fn get_bytecode_history(
+        &self,
+        _: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<BytecodeHistoryOutput>, ApiError>>;
+
+    /// Get the final balances of addresses at the output of a past final slot.
+    /// Only available on nodes running the ledger archive mode, for slots since archiving started.
+    #[rpc(name = "get_addresses_at_slot")]
+    fn get_addresses_at_slot(
+        &self,
+        _: Vec<Address>,
+        _: Slot,
+    ) -> BoxFuture<Result<Vec<AddressInfoAtSlot>, ApiError>>;
+
+    /// Get multiple final datastore entries at the output of a past final slot.
+    /// Only available on nodes running the ledger archive mode, for slots since archiving started.
+    #[rpc(name = "get_datastore_entries_at_slot")]
+    fn get_datastore_entries_at_slot(
+        &self,
+        _: Vec<DatastoreEntryInput>,
+        _: Slot,
+    ) -> BoxFuture<Result<Vec<DatastoreEntryAtSlotOutput>, ApiError>>;
+
+    /// Get the final balance, bytecode hash and datastore entries of an address,
+    /// with their inclusion or non-inclusion proofs against the final ledger root.
+    /// The proof also contains the final slot it was generated at and the final async pool hash,
+    /// so that the ledger root can be checked against the final state fingerprint of `get_status`.
+    /// Proofs are generated against the latest final slot by default, or against a past final slot
+    /// still retained by the ledger archive mode, in which case the async pool hash is not provided.
+    #[rpc(name = "get_ledger_proof")]
+    fn get_ledger_proof(
+        &self,
+        _: Address,
+        _: Vec<Vec<u8>>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<LedgerProof, ApiError>>;
+
     /// Get addresses.
     #[rpc(name = "get_addresses")]
     fn get_addresses(&self, _: Vec<Address>) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>>;
 
+    /// Get every block and endorsement slot addresses are selected for,
+    /// from a cycle (the current one by default) to the last cycle whose draws are already known.
+    /// The cycle must be between the current one and the last one that can be drawn from it.
+    #[rpc(name = "get_selection_schedule")]
+    fn get_selection_schedule(
+        &self,
+        _: Vec<Address>,
+        _: Option<u64>,
+    ) -> BoxFuture<Result<Vec<SelectionSchedule>, ApiError>>;
+
+    /// Get the missed block and endorsement slots of stakers and the equivocations detected by the node,
+    /// over the cycles kept in memory.
+    #[rpc(name = "get_stakers_misbehaviors")]
+    fn get_stakers_misbehaviors(
+        &self,
+        _: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<StakerMisbehaviors>, ApiError>>;
+
+    /// Get a page of the blocks created by an address, oldest first, with their finality.
+    /// Pagination is done with an optional limit and a cursor set to the position of the last block of the previous page.
+    /// Pruned final blocks are only listed by nodes running the block archive.
+    #[rpc(name = "get_blocks_by_creator")]
+    fn get_blocks_by_creator(
+        &self,
+        _: CreatorBlocksInput,
+    ) -> BoxFuture<Result<CreatorBlocksOutput, ApiError>>;
+
+    /// Get a page of the final operations involving an address (transfers, roll buys/sells, SC calls),
+    /// oldest first, optionally restricted to one kind of operation.
+    /// Pagination is done with an optional limit and a cursor set to the position of the last operation of the previous page.
+    /// Only available on nodes running the block archive.
+    #[rpc(name = "get_address_history")]
+    fn get_address_history(
+        &self,
+        _: AddressHistoryInput,
+    ) -> BoxFuture<Result<AddressHistoryOutput, ApiError>>;
+
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
     #[rpc(name = "send_operations")]
     fn send_operations(
@@ -250,17 +402,133 @@ pub trait Endpoints {
         _: Vec<OperationInput>,
     ) -> BoxFuture<Result<Vec<OperationId>, ApiError>>;
 
+    /// Simulates the execution of signed operations against the current active state,
+    /// without sending them to the pool. Every operation is simulated independently.
+    /// Returns, for each operation, its success or failure and the changes and events it would produce.
+    /// The ledger changes include the candidate sequential balances and rolls modified by the operation,
+    /// so that transactions and roll operations report their effects too.
+    #[rpc(name = "simulate_operations")]
+    fn simulate_operations(
+        &self,
+        _: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>>;
+
     /// Get events optionally filtered by:
     /// * start slot
     /// * end slot
     /// * emitter address
     /// * original caller address
     /// * operation id
+    /// * finality status
+    /// * substring or prefix of the event data
+    ///
+    /// Events are returned oldest first. Pagination is done with an optional limit
+    /// and a cursor set to the position of the last event of the previous page.
+    /// The limit defaults to, and cannot exceed, the maximum number of arguments of the API.
     #[rpc(name = "get_filtered_sc_output_event")]
     fn get_filtered_sc_output_event(
         &self,
         _: EventFilter,
     ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>>;
+
+    /// Get the pending asynchronous messages, optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * emission slot range (end excluded)
+    #[rpc(name = "get_async_messages")]
+    fn get_async_messages(
+        &self,
+        _: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>>;
+}
+
+/// Exposed websocket API subscriptions.
+/// Each subscription is cancelled by its `unsubscribe_*` counterpart or when the connection closes.
+#[rpc(server)]
+pub trait WsEndpoints {
+    /// Session metadata
+    type Metadata;
+
+    /// Subscribe to the blocks integrated in the block graph.
+    #[pubsub(subscription = "new_blocks", subscribe, name = "subscribe_new_blocks")]
+    fn subscribe_new_blocks(&self, _: Self::Metadata, _: Subscriber<BlockSummary>);
+
+    /// Unsubscribe from the blocks integrated in the block graph.
+    #[pubsub(
+        subscription = "new_blocks",
+        unsubscribe,
+        name = "unsubscribe_new_blocks"
+    )]
+    fn unsubscribe_new_blocks(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> Result<bool, ApiError>;
+
+    /// Subscribe to the blocks becoming final.
+    #[pubsub(
+        subscription = "finalized_blocks",
+        subscribe,
+        name = "subscribe_finalized_blocks"
+    )]
+    fn subscribe_finalized_blocks(&self, _: Self::Metadata, _: Subscriber<BlockSummary>);
+
+    /// Unsubscribe from the blocks becoming final.
+    #[pubsub(
+        subscription = "finalized_blocks",
+        unsubscribe,
+        name = "unsubscribe_finalized_blocks"
+    )]
+    fn unsubscribe_finalized_blocks(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> Result<bool, ApiError>;
+
+    /// Subscribe to the events emitted by smart contracts, filtered the same way as `get_filtered_sc_output_event`.
+    /// Events of candidate slots are sent as soon as the slot is executed,
+    /// and sent again when the slot becomes final.
+    #[pubsub(subscription = "sc_events", subscribe, name = "subscribe_sc_events")]
+    fn subscribe_sc_events(&self, _: Self::Metadata, _: Subscriber<SCOutputEvent>, _: EventFilter);
+
+    /// Unsubscribe from the events emitted by smart contracts.
+    #[pubsub(
+        subscription = "sc_events",
+        unsubscribe,
+        name = "unsubscribe_sc_events"
+    )]
+    fn unsubscribe_sc_events(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> Result<bool, ApiError>;
+
+    /// Subscribe to the status changes of an operation:
+    /// inclusion in a block, then finality of that block.
+    /// The current status is sent right after subscribing if the operation is already in a block.
+    #[pubsub(
+        subscription = "operation_status",
+        subscribe,
+        name = "subscribe_operation_status"
+    )]
+    fn subscribe_operation_status(
+        &self,
+        _: Self::Metadata,
+        _: Subscriber<OperationStatus>,
+        _: OperationId,
+    );
+
+    /// Unsubscribe from the status changes of an operation.
+    #[pubsub(
+        subscription = "operation_status",
+        unsubscribe,
+        name = "unsubscribe_operation_status"
+    )]
+    fn unsubscribe_operation_status(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> Result<bool, ApiError>;
 }
 
 fn wrong_api<T>() -> BoxFuture<Result<T, ApiError>> {
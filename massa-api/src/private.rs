@@ -7,18 +7,22 @@ use jsonrpc_core::BoxFuture;
 use jsonrpc_http_server::tokio::sync::mpsc;
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::ExecutionController;
+use massa_ledger_exports::LedgerProof;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressHistoryInput, AddressHistoryOutput, AddressInfo, AddressInfoAtSlot, AsyncMessageFilter,
+    AsyncMessageInfo, BlockInfo, BlockSummary, BytecodeHistoryOutput, CreatorBlocksInput,
+    CreatorBlocksOutput, DatastoreEntryAtSlotOutput, DatastoreEntryInput, DatastoreEntryOutput,
+    DatastoreKeysInput, DatastoreKeysOutput, EndorsementInfo, EventFilter, GasEstimationRequest,
+    NodeStatus, OperationInfo, OperationInput, OperationReceiptOutput, ReadOnlyBytecodeExecution,
+    ReadOnlyCall, SelectionSchedule, StakerMisbehaviors, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, GasEstimation, OperationSimulation};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::Set;
-use massa_models::{Address, BlockId, EndorsementId, OperationId};
+use massa_models::{Address, BlockId, EndorsementId, OperationId, Slot};
 use massa_network_exports::NetworkCommandSender;
 use massa_signature::KeyPair;
 use std::net::{IpAddr, SocketAddr};
@@ -92,6 +96,13 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<_>()
     }
 
+    fn estimate_gas(
+        &self,
+        _reqs: Vec<GasEstimationRequest>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>> {
+        crate::wrong_api::<_>()
+    }
+
     fn remove_staking_addresses(&self, keys: Vec<Address>) -> BoxFuture<Result<(), ApiError>> {
         let cmd_sender = self.0.consensus_command_sender.clone();
         let closure = async move || {
@@ -151,6 +162,13 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<Vec<OperationInfo>>()
     }
 
+    fn get_operation_receipts(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<OperationReceiptOutput>, ApiError>> {
+        crate::wrong_api::<Vec<OperationReceiptOutput>>()
+    }
+
     fn get_endorsements(
         &self,
         _: Vec<EndorsementId>,
@@ -176,10 +194,78 @@ impl Endpoints for API<Private> {
         crate::wrong_api()
     }
 
+    fn get_datastore_keys(
+        &self,
+        _: DatastoreKeysInput,
+    ) -> BoxFuture<Result<DatastoreKeysOutput, ApiError>> {
+        crate::wrong_api::<DatastoreKeysOutput>()
+    }
+
+    fn get_bytecode_history(
+        &self,
+        _: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<BytecodeHistoryOutput>, ApiError>> {
+        crate::wrong_api::<Vec<BytecodeHistoryOutput>>()
+    }
+
+    fn get_addresses_at_slot(
+        &self,
+        _: Vec<Address>,
+        _: Slot,
+    ) -> BoxFuture<Result<Vec<AddressInfoAtSlot>, ApiError>> {
+        crate::wrong_api::<Vec<AddressInfoAtSlot>>()
+    }
+
+    fn get_datastore_entries_at_slot(
+        &self,
+        _: Vec<DatastoreEntryInput>,
+        _: Slot,
+    ) -> BoxFuture<Result<Vec<DatastoreEntryAtSlotOutput>, ApiError>> {
+        crate::wrong_api::<Vec<DatastoreEntryAtSlotOutput>>()
+    }
+
+    fn get_ledger_proof(
+        &self,
+        _: Address,
+        _: Vec<Vec<u8>>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<LedgerProof, ApiError>> {
+        crate::wrong_api::<LedgerProof>()
+    }
+
     fn get_addresses(&self, _: Vec<Address>) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>> {
         crate::wrong_api::<Vec<AddressInfo>>()
     }
 
+    fn get_selection_schedule(
+        &self,
+        _: Vec<Address>,
+        _: Option<u64>,
+    ) -> BoxFuture<Result<Vec<SelectionSchedule>, ApiError>> {
+        crate::wrong_api::<Vec<SelectionSchedule>>()
+    }
+
+    fn get_stakers_misbehaviors(
+        &self,
+        _: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<StakerMisbehaviors>, ApiError>> {
+        crate::wrong_api::<Vec<StakerMisbehaviors>>()
+    }
+
+    fn get_blocks_by_creator(
+        &self,
+        _: CreatorBlocksInput,
+    ) -> BoxFuture<Result<CreatorBlocksOutput, ApiError>> {
+        crate::wrong_api::<CreatorBlocksOutput>()
+    }
+
+    fn get_address_history(
+        &self,
+        _: AddressHistoryInput,
+    ) -> BoxFuture<Result<AddressHistoryOutput, ApiError>> {
+        crate::wrong_api::<AddressHistoryOutput>()
+    }
+
     fn send_operations(
         &self,
         _: Vec<OperationInput>,
@@ -187,6 +273,13 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<Vec<OperationId>>()
     }
 
+    fn simulate_operations(
+        &self,
+        _: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>> {
+        crate::wrong_api::<Vec<OperationSimulation>>()
+    }
+
     fn get_filtered_sc_output_event(
         &self,
         _: EventFilter,
@@ -194,6 +287,13 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<Vec<SCOutputEvent>>()
     }
 
+    fn get_async_messages(
+        &self,
+        _: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>> {
+        crate::wrong_api::<Vec<AsyncMessageInfo>>()
+    }
+
     fn node_whitelist(&self, ips: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         let network_command_sender = self.0.network_command_sender.clone();
         let closure = async move || Ok(network_command_sender.whitelist(ips).await?);
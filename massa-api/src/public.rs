@@ -5,28 +5,43 @@ use crate::settings::APISettings;
 use crate::{Endpoints, Public, RpcServer, StopHandle, API};
 use futures::{stream::FuturesUnordered, StreamExt};
 use jsonrpc_core::BoxFuture;
+use massa_async_pool::{AsyncPoolChanges, Change};
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::{
-    ExecutionController, ExecutionStackElement, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ExecutionController, ExecutionError, ExecutionStackElement, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
 };
 use massa_graph::{DiscardReason, ExportBlockStatus};
+use massa_ledger_exports::{
+    bytecode_version_count_from_bytes, bytecode_version_key, datastore_prefix_range,
+    BytecodeVersion, LedgerProof, BYTECODE_VERSION_COUNT_KEY,
+};
+use massa_ledger_exports::{LedgerChanges, SetOrDelete, SetOrKeep, SetUpdateOrDelete};
 use massa_models::api::{
-    DatastoreEntryInput, DatastoreEntryOutput, OperationInput, ReadOnlyBytecodeExecution,
-    ReadOnlyCall,
+    AddressHistoryInput, AddressHistoryOutput, AddressInfoAtSlot, AsyncMessageFilter,
+    AsyncMessageInfo, BytecodeHistoryOutput, BytecodeVersionInfo, CreatorBlocksInput,
+    CreatorBlocksOutput, DatastoreEntryAtSlotOutput, DatastoreEntryInput, DatastoreEntryOutput,
+    DatastoreKeysInput, DatastoreKeysOutput, GasEstimationRequest, OperationInput,
+    OperationReceiptOutput, ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionSchedule,
+    StakerMisbehaviors,
+};
+use massa_models::execution::{
+    AddressLedgerChanges, AsyncMessageChange, GasEstimation, OperationSimulation, ReadOnlyResult,
 };
-use massa_models::execution::ReadOnlyResult;
 use massa_models::operation::OperationDeserializer;
 use massa_models::wrapped::WrappedDeserializer;
-use massa_models::{Amount, ModelsError, WrappedOperation};
+use massa_models::{Amount, ModelsError, OperationType, WrappedOperation};
 use massa_serialization::{DeserializeError, Deserializer};
 
 use massa_models::{
+    address::AddressState,
     api::{
         AddressInfo, BlockInfo, BlockInfoContent, BlockSummary, EndorsementInfo, EventFilter,
         IndexedSlot, NodeStatus, OperationInfo, TimeInterval,
     },
     clique::Clique,
     composite::PubkeySig,
+    constants::OPERATION_BASE_GAS_COST,
     execution::ExecuteReadOnlyResponse,
     node::NodeId,
     output_event::SCOutputEvent,
@@ -40,6 +55,7 @@ use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use std::collections::BTreeSet;
 use std::net::{IpAddr, SocketAddr};
+use std::ops::Bound;
 
 impl API<Public> {
     /// generate a new public API
@@ -106,6 +122,7 @@ impl Endpoints for API<Public> {
             address,
             simulated_gas_price,
             bytecode,
+            trace,
         } in reqs
         {
             let address = address.unwrap_or_else(|| {
@@ -128,6 +145,9 @@ impl Endpoints for API<Public> {
                     coins: Default::default(),
                     owned_addresses: vec![address],
                 }],
+                credited_coins: Amount::zero(),
+                transferred_coins: Amount::zero(),
+                trace,
             };
 
             // run
@@ -135,12 +155,21 @@ impl Endpoints for API<Public> {
 
             // map result
             let result = ExecuteReadOnlyResponse {
-                executed_at: result.as_ref().map_or_else(|_| Slot::new(0, 0), |v| v.slot),
+                executed_at: result
+                    .as_ref()
+                    .map_or_else(|_| Slot::new(0, 0), |v| v.out.slot),
                 result: result.as_ref().map_or_else(
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
-                output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
+                gas_cost: result.as_ref().map_or(0, |v| v.gas_cost),
+                trace: match &result {
+                    Ok(output) => output.trace.clone(),
+                    Err(ExecutionError::TracedError { trace, .. }) => Some(trace.clone()),
+                    Err(_) => None,
+                },
+                output_events: result
+                    .map_or_else(|_| Default::default(), |mut v| v.out.events.take()),
             };
 
             res.push(result);
@@ -169,6 +198,7 @@ impl Endpoints for API<Public> {
             target_function,
             parameter,
             caller_address,
+            trace,
         } in reqs
         {
             let caller_address = caller_address.unwrap_or_else(|| {
@@ -202,6 +232,9 @@ impl Endpoints for API<Public> {
                         owned_addresses: vec![target_address],
                     },
                 ],
+                credited_coins: Amount::zero(),
+                transferred_coins: Amount::zero(),
+                trace,
             };
 
             // run
@@ -209,12 +242,21 @@ impl Endpoints for API<Public> {
 
             // map result
             let result = ExecuteReadOnlyResponse {
-                executed_at: result.as_ref().map_or_else(|_| Slot::new(0, 0), |v| v.slot),
+                executed_at: result
+                    .as_ref()
+                    .map_or_else(|_| Slot::new(0, 0), |v| v.out.slot),
                 result: result.as_ref().map_or_else(
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
-                output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
+                gas_cost: result.as_ref().map_or(0, |v| v.gas_cost),
+                trace: match &result {
+                    Ok(output) => output.trace.clone(),
+                    Err(ExecutionError::TracedError { trace, .. }) => Some(trace.clone()),
+                    Err(_) => None,
+                },
+                output_events: result
+                    .map_or_else(|_| Default::default(), |mut v| v.out.events.take()),
             };
 
             res.push(result);
@@ -225,6 +267,122 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn estimate_gas(
+        &self,
+        reqs: Vec<GasEstimationRequest>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>> {
+        if reqs.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+
+        let max_gas_per_block = self.0.consensus_config.max_gas_per_block;
+        let mut res: Vec<GasEstimation> = Vec::with_capacity(reqs.len());
+        for GasEstimationRequest {
+            caller_address,
+            operation,
+        } in reqs
+        {
+            // translate the operation into a read-only request executed on behalf of its sender,
+            // with the coin movements the execution of the operation would do,
+            // and the gas left once the base cost of the operation is reserved
+            let req = match operation {
+                OperationType::ExecuteSC {
+                    data,
+                    max_gas,
+                    coins,
+                    gas_price,
+                } => ReadOnlyExecutionRequest {
+                    max_gas: std::cmp::min(max_gas, max_gas_per_block)
+                        .saturating_sub(OPERATION_BASE_GAS_COST),
+                    simulated_gas_price: gas_price,
+                    target: ReadOnlyExecutionTarget::BytecodeExecution(data),
+                    call_stack: vec![ExecutionStackElement {
+                        address: caller_address,
+                        coins,
+                        owned_addresses: vec![caller_address],
+                    }],
+                    credited_coins: coins,
+                    transferred_coins: Amount::zero(),
+                    trace: false,
+                },
+                OperationType::CallSC {
+                    target_addr,
+                    target_func,
+                    param,
+                    max_gas,
+                    sequential_coins,
+                    parallel_coins,
+                    gas_price,
+                } => {
+                    let coins = sequential_coins.saturating_add(parallel_coins);
+                    ReadOnlyExecutionRequest {
+                        max_gas: std::cmp::min(max_gas, max_gas_per_block)
+                            .saturating_sub(OPERATION_BASE_GAS_COST),
+                        simulated_gas_price: gas_price,
+                        target: ReadOnlyExecutionTarget::FunctionCall {
+                            target_func,
+                            target_addr,
+                            parameter: param,
+                        },
+                        call_stack: vec![
+                            ExecutionStackElement {
+                                address: caller_address,
+                                coins,
+                                owned_addresses: vec![caller_address],
+                            },
+                            ExecutionStackElement {
+                                address: target_addr,
+                                coins,
+                                owned_addresses: vec![target_addr],
+                            },
+                        ],
+                        credited_coins: sequential_coins,
+                        transferred_coins: coins,
+                        trace: false,
+                    }
+                }
+                _ => {
+                    res.push(GasEstimation {
+                        executed_at: Slot::new(0, 0),
+                        result: ReadOnlyResult::Error(
+                            "only ExecuteSC and CallSC operations consume gas".into(),
+                        ),
+                        gas_cost: 0,
+                        ledger_changes: Vec::new(),
+                        output_events: Default::default(),
+                    });
+                    continue;
+                }
+            };
+
+            // run and map the result
+            let estimation = match self.0.execution_controller.execute_readonly_request(req) {
+                Ok(mut output) => GasEstimation {
+                    executed_at: output.out.slot,
+                    result: ReadOnlyResult::Ok,
+                    gas_cost: output.gas_cost.saturating_add(OPERATION_BASE_GAS_COST),
+                    ledger_changes: summarize_ledger_changes(
+                        output.out.state_changes.ledger_changes,
+                    ),
+                    output_events: output.out.events.take(),
+                },
+                Err(err) => GasEstimation {
+                    executed_at: Slot::new(0, 0),
+                    result: ReadOnlyResult::Error(format!("gas estimation failed: {}", err)),
+                    gas_cost: 0,
+                    ledger_changes: Vec::new(),
+                    output_events: Default::default(),
+                },
+            };
+            res.push(estimation);
+        }
+
+        let closure = async move || Ok(res);
+        Box::pin(closure())
+    }
+
     fn remove_staking_addresses(&self, _: Vec<Address>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
@@ -259,6 +417,8 @@ impl Endpoints for API<Public> {
         let mut pool_command_sender = self.0.pool_command_sender.clone();
         let node_id = self.0.node_id;
         let config = CompactConfig::default();
+        let (final_state_slot, final_state_fingerprint) =
+            self.0.execution_controller.get_final_state_fingerprint();
         let closure = async move || {
             let now = MassaTime::compensated_now(compensation_millis)?;
             let last_slot = get_latest_block_slot_at_timestamp(
@@ -292,6 +452,8 @@ impl Endpoints for API<Public> {
                 next_slot: last_slot
                     .unwrap_or_else(|| Slot::new(0, 0))
                     .get_next_slot(consensus_settings.thread_count)?,
+                final_state_slot,
+                final_state_fingerprint,
                 consensus_stats: consensus_stats?,
                 network_stats: network_stats?,
                 pool_stats: pool_stats?,
@@ -329,6 +491,7 @@ impl Endpoints for API<Public> {
         let api_cfg = self.0.api_settings;
         let consensus_command_sender = self.0.consensus_command_sender.clone();
         let mut pool_command_sender = self.0.pool_command_sender.clone();
+        let execution_controller = self.0.execution_controller.clone();
         let closure = async move || {
             if ops.len() as u64 > api_cfg.max_arguments {
                 return Err(ApiError::TooManyArguments("too many arguments".into()));
@@ -357,6 +520,7 @@ impl Endpoints for API<Public> {
                         in_blocks: Vec::new(),
                         id,
                         is_final: false,
+                        receipt: None,
                     },
                 )
             }));
@@ -372,12 +536,23 @@ impl Endpoints for API<Public> {
                         .iter()
                         .any(|(_, (_, is_final))| *is_final),
                     operation: search_new.op,
+                    receipt: None,
                 };
                 res.entry(op_id)
                     .and_modify(|search_old| search_old.extend(&search_new))
                     .or_insert(search_new);
             });
 
+            // add the execution receipts
+            let found_ids: Vec<OperationId> = res.keys().copied().collect();
+            let receipts =
+                execution_controller.get_final_and_active_operation_receipts(found_ids.clone());
+            for (op_id, (_final_receipt, active_receipt)) in found_ids.into_iter().zip(receipts) {
+                if let Some(info) = res.get_mut(&op_id) {
+                    info.receipt = active_receipt;
+                }
+            }
+
             // return values in the right order
             Ok(ops
                 .into_iter()
@@ -387,6 +562,36 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn get_operation_receipts(
+        &self,
+        ops: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<OperationReceiptOutput>, ApiError>> {
+        if ops.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+
+        let receipts = self
+            .0
+            .execution_controller
+            .get_final_and_active_operation_receipts(ops.clone());
+        let res: Vec<OperationReceiptOutput> = ops
+            .into_iter()
+            .zip(receipts)
+            .map(
+                |(operation_id, (final_receipt, candidate_receipt))| OperationReceiptOutput {
+                    operation_id,
+                    final_receipt,
+                    candidate_receipt,
+                },
+            )
+            .collect();
+
+        let closure = async move || Ok(res);
+        Box::pin(closure())
+    }
+
     fn get_endorsements(
         &self,
         eds: Vec<EndorsementId>,
@@ -534,6 +739,177 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn get_datastore_keys(
+        &self,
+        input: DatastoreKeysInput,
+    ) -> BoxFuture<Result<DatastoreKeysOutput, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            let max_limit = api_cfg.max_arguments as usize;
+            let limit = input.limit.unwrap_or(max_limit);
+            if limit > max_limit {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            let (mut start, end) = datastore_prefix_range(&input.prefix.unwrap_or_default());
+            if let Some(cursor) = input.cursor {
+                if matches!(&start, Bound::Included(prefix) if cursor >= *prefix) {
+                    start = Bound::Excluded(cursor);
+                }
+            }
+            let keys = execution_controller.get_candidate_datastore_keys_in_range(
+                &input.address,
+                (start, end),
+                limit,
+            );
+            let next_cursor = if limit > 0 && keys.len() == limit {
+                keys.last().cloned()
+            } else {
+                None
+            };
+            Ok(DatastoreKeysOutput { keys, next_cursor })
+        };
+        Box::pin(closure())
+    }
+
+    fn get_bytecode_history(
+        &self,
+        addresses: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<BytecodeHistoryOutput>, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if addresses.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            // get the final and candidate number of versions of each address
+            let counts: Vec<(u64, u64)> = execution_controller
+                .get_final_and_active_data_entry(
+                    addresses
+                        .iter()
+                        .map(|addr| (*addr, BYTECODE_VERSION_COUNT_KEY.to_vec()))
+                        .collect(),
+                )
+                .into_iter()
+                .map(|(final_count, candidate_count)| {
+                    (
+                        bytecode_version_count_from_bytes(final_count.as_deref()),
+                        bytecode_version_count_from_bytes(candidate_count.as_deref()),
+                    )
+                })
+                .collect();
+
+            // get the versions, each one being stored in its own entry
+            let mut res = Vec::with_capacity(addresses.len());
+            for (address, (final_count, candidate_count)) in addresses.into_iter().zip(counts) {
+                let versions: Vec<u64> =
+                    (1..=std::cmp::max(final_count, candidate_count)).collect();
+                let entries = execution_controller.get_final_and_active_data_entry(
+                    versions
+                        .iter()
+                        .map(|version| (address, bytecode_version_key(*version)))
+                        .collect(),
+                );
+                let to_info = |version: u64, entry: &Option<Vec<u8>>| {
+                    entry
+                        .as_deref()
+                        .and_then(BytecodeVersion::from_bytes)
+                        .map(|v| BytecodeVersionInfo {
+                            version,
+                            slot: v.slot,
+                            hash: v.hash,
+                        })
+                };
+                res.push(BytecodeHistoryOutput {
+                    address,
+                    final_history: versions
+                        .iter()
+                        .zip(entries.iter())
+                        .take(final_count as usize)
+                        .filter_map(|(version, (entry, _))| to_info(*version, entry))
+                        .collect(),
+                    candidate_history: versions
+                        .iter()
+                        .zip(entries.iter())
+                        .take(candidate_count as usize)
+                        .filter_map(|(version, (_, entry))| to_info(*version, entry))
+                        .collect(),
+                });
+            }
+            Ok(res)
+        };
+        Box::pin(closure())
+    }
+
+    fn get_addresses_at_slot(
+        &self,
+        addresses: Vec<Address>,
+        slot: Slot,
+    ) -> BoxFuture<Result<Vec<AddressInfoAtSlot>, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if addresses.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            let balances = execution_controller
+                .get_final_parallel_balances_at_slot(addresses.clone(), slot)?;
+            Ok(addresses
+                .into_iter()
+                .zip(balances)
+                .map(|(address, final_balance)| AddressInfoAtSlot {
+                    address,
+                    slot,
+                    final_balance,
+                })
+                .collect())
+        };
+        Box::pin(closure())
+    }
+
+    fn get_datastore_entries_at_slot(
+        &self,
+        entries: Vec<DatastoreEntryInput>,
+        slot: Slot,
+    ) -> BoxFuture<Result<Vec<DatastoreEntryAtSlotOutput>, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if entries.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            Ok(execution_controller
+                .get_final_data_entries_at_slot(
+                    entries
+                        .into_iter()
+                        .map(|input| (input.address, input.key))
+                        .collect(),
+                    slot,
+                )?
+                .into_iter()
+                .map(|final_value| DatastoreEntryAtSlotOutput { slot, final_value })
+                .collect())
+        };
+        Box::pin(closure())
+    }
+
+    fn get_ledger_proof(
+        &self,
+        address: Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> BoxFuture<Result<LedgerProof, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if keys.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            Ok(execution_controller.get_ledger_proof(&address, keys, slot)?)
+        };
+        Box::pin(closure())
+    }
+
     fn get_addresses(
         &self,
         addresses: Vec<Address>,
@@ -723,6 +1099,77 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn get_selection_schedule(
+        &self,
+        addresses: Vec<Address>,
+        cycle: Option<u64>,
+    ) -> BoxFuture<Result<Vec<SelectionSchedule>, ApiError>> {
+        let cmd_sender = self.0.consensus_command_sender.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if addresses.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            Ok(cmd_sender.get_selection_schedule(addresses, cycle).await?)
+        };
+        Box::pin(closure())
+    }
+
+    fn get_stakers_misbehaviors(
+        &self,
+        addresses: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<StakerMisbehaviors>, ApiError>> {
+        let cmd_sender = self.0.consensus_command_sender.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if addresses.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            Ok(cmd_sender.get_stakers_misbehaviors(addresses).await?)
+        };
+        Box::pin(closure())
+    }
+
+    fn get_blocks_by_creator(
+        &self,
+        mut input: CreatorBlocksInput,
+    ) -> BoxFuture<Result<CreatorBlocksOutput, ApiError>> {
+        let cmd_sender = self.0.consensus_command_sender.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            let max_limit = api_cfg.max_arguments as usize;
+            let limit = input.limit.unwrap_or(max_limit);
+            if limit > max_limit {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            input.limit = Some(limit);
+            Ok(cmd_sender.get_blocks_by_creator(input).await?)
+        };
+        Box::pin(closure())
+    }
+
+    fn get_address_history(
+        &self,
+        mut input: AddressHistoryInput,
+    ) -> BoxFuture<Result<AddressHistoryOutput, ApiError>> {
+        let cmd_sender = self.0.consensus_command_sender.clone();
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            let max_limit = api_cfg.max_arguments as usize;
+            let limit = input.limit.unwrap_or(max_limit);
+            if limit > max_limit {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            input.limit = Some(limit);
+            cmd_sender.get_address_history(input).await?.ok_or_else(|| {
+                ApiError::MissingConfig(
+                    "the address history requires the block archive to be enabled".into(),
+                )
+            })
+        };
+        Box::pin(closure())
+    }
+
     fn send_operations(
         &self,
         ops: Vec<OperationInput>,
@@ -733,31 +1180,9 @@ impl Endpoints for API<Public> {
             if ops.len() as u64 > api_cfg.max_arguments {
                 return Err(ApiError::TooManyArguments("too many arguments".into()));
             }
-            let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new());
             let to_send = ops
                 .into_iter()
-                .map(|op_input| {
-                    let mut op_serialized = Vec::new();
-                    op_serialized.extend(op_input.signature.to_bytes());
-                    op_serialized.extend(op_input.creator_public_key.to_bytes());
-                    op_serialized.extend(op_input.serialized_content);
-                    let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
-                        .deserialize::<DeserializeError>(&op_serialized)
-                        .map_err(|err| {
-                            ApiError::ModelsError(ModelsError::DeserializeError(err.to_string()))
-                        })?;
-                    if rest.is_empty() {
-                        Ok(op)
-                    } else {
-                        Err(ApiError::ModelsError(ModelsError::DeserializeError(
-                            "There is data left after operation deserialization".to_owned(),
-                        )))
-                    }
-                })
-                .map(|op| match op {
-                    Ok(operation) => Ok((operation.verify_integrity()?, operation)),
-                    Err(e) => Err(e),
-                })
+                .map(|op_input| decode_operation_input(op_input).map(|op| (op.id, op)))
                 .collect::<Result<Map<OperationId, _>, ApiError>>()?;
             let ids = to_send.keys().copied().collect();
             cmd_sender.add_operations(to_send).await?;
@@ -766,6 +1191,106 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn simulate_operations(
+        &self,
+        ops: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>> {
+        let cmd_sender = self.0.consensus_command_sender.clone();
+        let execution_controller = self.0.execution_controller.clone();
+        let roll_price = self.0.consensus_config.roll_price;
+        let api_cfg = self.0.api_settings;
+        let closure = async move || {
+            if ops.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            let ops = ops
+                .into_iter()
+                .map(decode_operation_input)
+                .collect::<Result<Vec<WrappedOperation>, ApiError>>()?;
+
+            // the sequential part of the operations (fees, transactions, rolls) is handled by consensus:
+            // simulate it against the candidate balances and rolls of the senders and recipients
+            let mut addresses: Set<Address> = ops.iter().map(|op| op.creator_address).collect();
+            addresses.extend(ops.iter().filter_map(|op| match &op.content.op {
+                OperationType::Transaction {
+                    recipient_address, ..
+                } => Some(*recipient_address),
+                _ => None,
+            }));
+            let states = cmd_sender.get_addresses_info(addresses).await?;
+
+            // every operation is simulated independently against the current active state
+            let mut res = Vec::with_capacity(ops.len());
+            for op in ops {
+                let sequential_changes = match simulate_sequential_changes(&op, &states, roll_price)
+                {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        res.push(OperationSimulation {
+                            operation_id: op.id,
+                            executed_at: Slot::new(0, 0),
+                            result: ReadOnlyResult::Error(err),
+                            gas_cost: 0,
+                            ledger_changes: Vec::new(),
+                            async_pool_changes: Vec::new(),
+                            output_events: Default::default(),
+                        });
+                        continue;
+                    }
+                };
+
+                let operation_id = op.id;
+                let (gas_price, max_gas) = match &op.content.op {
+                    OperationType::ExecuteSC {
+                        gas_price, max_gas, ..
+                    }
+                    | OperationType::CallSC {
+                        gas_price, max_gas, ..
+                    } => (*gas_price, *max_gas),
+                    _ => (Amount::zero(), 0),
+                };
+                let req = ReadOnlyExecutionRequest {
+                    max_gas,
+                    simulated_gas_price: gas_price,
+                    call_stack: Vec::new(),
+                    target: ReadOnlyExecutionTarget::Operation(op),
+                    credited_coins: Amount::zero(),
+                    transferred_coins: Amount::zero(),
+                    trace: false,
+                };
+                let simulation = match execution_controller.execute_readonly_request(req) {
+                    Ok(mut output) => OperationSimulation {
+                        operation_id,
+                        executed_at: output.out.slot,
+                        result: ReadOnlyResult::Ok,
+                        gas_cost: output.gas_cost,
+                        ledger_changes: merge_sequential_changes(
+                            summarize_ledger_changes(output.out.state_changes.ledger_changes),
+                            sequential_changes,
+                        ),
+                        async_pool_changes: summarize_async_pool_changes(
+                            output.out.state_changes.async_pool_changes,
+                        ),
+                        output_events: output.out.events.take(),
+                    },
+                    Err(err) => OperationSimulation {
+                        operation_id,
+                        executed_at: Slot::new(0, 0),
+                        result: ReadOnlyResult::Error(err.to_string()),
+                        gas_cost: 0,
+                        // the sequential part is still applied when the execution fails
+                        ledger_changes: sequential_changes,
+                        async_pool_changes: Vec::new(),
+                        output_events: Default::default(),
+                    },
+                };
+                res.push(simulation);
+            }
+            Ok(res)
+        };
+        Box::pin(closure())
+    }
+
     /// Get events optionally filtered by:
     /// * start slot
     /// * end slot
@@ -774,8 +1299,16 @@ impl Endpoints for API<Public> {
     /// * operation id
     fn get_filtered_sc_output_event(
         &self,
-        filter: EventFilter,
+        mut filter: EventFilter,
     ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>> {
+        let max_limit = self.0.api_settings.max_arguments as usize;
+        let limit = filter.limit.unwrap_or(max_limit);
+        if limit > max_limit {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+        filter.limit = Some(limit);
         let events = self
             .0
             .execution_controller
@@ -786,6 +1319,19 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    /// Get the pending asynchronous messages optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * emission slot range
+    fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>> {
+        let messages = self.0.execution_controller.get_async_messages(filter);
+        let closure = async move || Ok(messages);
+        Box::pin(closure())
+    }
+
     fn node_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
@@ -794,3 +1340,219 @@ impl Endpoints for API<Public> {
         crate::wrong_api::<()>()
     }
 }
+
+/// Deserialize an operation received through the API and check its integrity
+fn decode_operation_input(op_input: OperationInput) -> Result<WrappedOperation, ApiError> {
+    let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new());
+    let mut op_serialized = Vec::new();
+    op_serialized.extend(op_input.signature.to_bytes());
+    op_serialized.extend(op_input.creator_public_key.to_bytes());
+    op_serialized.extend(op_input.serialized_content);
+    let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
+        .deserialize::<DeserializeError>(&op_serialized)
+        .map_err(|err| ApiError::ModelsError(ModelsError::DeserializeError(err.to_string())))?;
+    if !rest.is_empty() {
+        return Err(ApiError::ModelsError(ModelsError::DeserializeError(
+            "There is data left after operation deserialization".to_owned(),
+        )));
+    }
+    op.verify_integrity()?;
+    Ok(op)
+}
+
+/// Check that the sender of an operation can afford its sequential spending:
+/// the fee, and depending on the operation type the transferred amount, the bought rolls,
+/// the gas and the coins debited from the sequential balance, or the sold rolls.
+///
+/// # Returns
+/// The resulting candidate balance and rolls of the sender and, for a transaction, the balance of the recipient.
+/// The fee credited to the block producer and the coins of sold rolls,
+/// credited once the rolls are unlocked, are not included.
+fn simulate_sequential_changes(
+    op: &WrappedOperation,
+    states: &Map<Address, AddressState>,
+    roll_price: Amount,
+) -> Result<Vec<AddressLedgerChanges>, String> {
+    let (candidate_balance, candidate_rolls) = states
+        .get(&op.creator_address)
+        .map(|state| {
+            (
+                state.ledger_info.candidate_ledger_info.balance,
+                state.rolls.candidate_rolls,
+            )
+        })
+        .ok_or_else(|| format!("sender {} not found", op.creator_address))?;
+    let spent = match &op.content.op {
+        OperationType::Transaction { amount, .. } => Some(*amount),
+        OperationType::RollBuy { roll_count } => roll_price.checked_mul_u64(*roll_count),
+        OperationType::RollSell { roll_count } => {
+            if *roll_count > candidate_rolls {
+                return Err(format!(
+                    "cannot sell {} rolls: the sender only has {} candidate rolls",
+                    roll_count, candidate_rolls
+                ));
+            }
+            Some(Amount::zero())
+        }
+        OperationType::ExecuteSC { .. } => Some(op.get_gas_coins()),
+        OperationType::CallSC {
+            sequential_coins, ..
+        } => op.get_gas_coins().checked_add(*sequential_coins),
+    }
+    .and_then(|spent| spent.checked_add(op.content.fee))
+    .ok_or_else(|| "the sequential spending of the operation overflows".to_string())?;
+    if spent > candidate_balance {
+        return Err(format!(
+            "insufficient sequential balance: {} needed, {} available",
+            spent, candidate_balance
+        ));
+    }
+
+    // changes of the sender
+    let mut sender_changes = sequential_changes(op.creator_address);
+    sender_changes.sequential_balance = Some(candidate_balance.saturating_sub(spent));
+    sender_changes.rolls = match &op.content.op {
+        OperationType::RollBuy { roll_count } => Some(candidate_rolls.saturating_add(*roll_count)),
+        OperationType::RollSell { roll_count } => Some(candidate_rolls.saturating_sub(*roll_count)),
+        _ => None,
+    };
+    let mut changes = vec![sender_changes];
+
+    // changes of the recipient of a transaction
+    if let OperationType::Transaction {
+        recipient_address,
+        amount,
+    } = &op.content.op
+    {
+        if *recipient_address == op.creator_address {
+            changes[0].sequential_balance = changes[0]
+                .sequential_balance
+                .map(|balance| balance.saturating_add(*amount));
+        } else {
+            let mut recipient_changes = sequential_changes(*recipient_address);
+            recipient_changes.sequential_balance = Some(
+                states
+                    .get(recipient_address)
+                    .map_or_else(Amount::zero, |state| {
+                        state.ledger_info.candidate_ledger_info.balance
+                    })
+                    .saturating_add(*amount),
+            );
+            changes.push(recipient_changes);
+        }
+    }
+    Ok(changes)
+}
+
+/// Changes of an address that do not modify anything yet
+fn sequential_changes(address: Address) -> AddressLedgerChanges {
+    AddressLedgerChanges {
+        address,
+        deleted: false,
+        sequential_balance: None,
+        rolls: None,
+        parallel_balance: None,
+        bytecode: None,
+        datastore: Vec::new(),
+    }
+}
+
+/// Add the sequential changes of an operation to the ledger changes of its execution
+fn merge_sequential_changes(
+    mut changes: Vec<AddressLedgerChanges>,
+    sequential_changes: Vec<AddressLedgerChanges>,
+) -> Vec<AddressLedgerChanges> {
+    for sequential in sequential_changes {
+        match changes
+            .iter_mut()
+            .find(|changes| changes.address == sequential.address)
+        {
+            Some(changes) => {
+                changes.sequential_balance = sequential.sequential_balance;
+                changes.rolls = sequential.rolls;
+            }
+            None => changes.push(sequential),
+        }
+    }
+    changes
+}
+
+/// Summarize the ledger changes of a read-only execution, address by address
+fn summarize_ledger_changes(changes: LedgerChanges) -> Vec<AddressLedgerChanges> {
+    changes
+        .0
+        .into_iter()
+        .map(|(address, change)| match change {
+            SetUpdateOrDelete::Set(entry) => AddressLedgerChanges {
+                address,
+                deleted: false,
+                sequential_balance: None,
+                rolls: None,
+                parallel_balance: Some(entry.parallel_balance),
+                bytecode: Some(entry.bytecode),
+                datastore: entry
+                    .datastore
+                    .into_iter()
+                    .map(|(key, value)| (key, Some(value)))
+                    .collect(),
+            },
+            SetUpdateOrDelete::Update(update) => AddressLedgerChanges {
+                address,
+                deleted: false,
+                sequential_balance: None,
+                rolls: None,
+                parallel_balance: match update.parallel_balance {
+                    SetOrKeep::Set(balance) => Some(balance),
+                    SetOrKeep::Keep => None,
+                },
+                bytecode: match update.bytecode {
+                    SetOrKeep::Set(bytecode) => Some(bytecode),
+                    SetOrKeep::Keep => None,
+                },
+                datastore: update
+                    .datastore
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        SetOrDelete::Set(value) => (key, Some(value)),
+                        SetOrDelete::Delete => (key, None),
+                    })
+                    .collect(),
+            },
+            SetUpdateOrDelete::Delete => AddressLedgerChanges {
+                address,
+                deleted: true,
+                sequential_balance: None,
+                rolls: None,
+                parallel_balance: None,
+                bytecode: None,
+                datastore: Vec::new(),
+            },
+        })
+        .collect()
+}
+
+/// Summarize the asynchronous pool changes of a read-only execution
+fn summarize_async_pool_changes(changes: AsyncPoolChanges) -> Vec<AsyncMessageChange> {
+    changes
+        .0
+        .into_iter()
+        .map(|change| match change {
+            Change::Add(_, message) => AsyncMessageChange::Added {
+                emission_slot: message.emission_slot,
+                emission_index: message.emission_index,
+                sender: message.sender,
+                destination: message.destination,
+                handler: message.handler,
+                max_gas: message.max_gas,
+                gas_price: message.gas_price,
+                coins: message.coins,
+                validity_start: message.validity_start,
+                validity_end: message.validity_end,
+            },
+            Change::Delete((_, emission_slot, emission_index)) => AsyncMessageChange::Removed {
+                emission_slot,
+                emission_index,
+            },
+        })
+        .collect()
+}
@@ -13,6 +13,8 @@ pub struct APISettings {
     pub bind_private: SocketAddr,
     /// bind for the public API
     pub bind_public: SocketAddr,
+    /// bind for the websocket API (subscriptions)
+    pub bind_ws: SocketAddr,
     /// max argument count
     pub max_arguments: u64,
 }
@@ -0,0 +1,3 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+mod ws;
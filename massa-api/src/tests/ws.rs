@@ -0,0 +1,229 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{APISettings, Ws, WsEndpoints, API};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::StreamExt;
+use jsonrpc_core::serde_json::{self, Value};
+use jsonrpc_pubsub::typed::Subscriber;
+use jsonrpc_pubsub::Session;
+use massa_consensus_exports::commands::ConsensusCommand;
+use massa_consensus_exports::events::ConsensusBroadcastEvent;
+use massa_consensus_exports::ConsensusCommandSender;
+use massa_hash::Hash;
+use massa_models::api::{BlockSummary, OperationStatus};
+use massa_models::composite::{
+    OperationSearchResult, OperationSearchResultBlockStatus, OperationSearchResultStatus,
+};
+use massa_models::prehash::Map;
+use massa_models::wrapped::WrappedContent;
+use massa_models::{
+    Address, Amount, BlockId, Operation, OperationId, OperationSerializer, OperationType, Slot,
+    WrappedOperation,
+};
+use massa_signature::KeyPair;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+const TIMEOUT: Duration = Duration::from_millis(1000);
+
+fn get_ws_api() -> (
+    API<Ws>,
+    mpsc::Receiver<ConsensusCommand>,
+    broadcast::Sender<ConsensusBroadcastEvent>,
+) {
+    let api_settings: &'static APISettings = Box::leak(Box::new(APISettings {
+        draw_lookahead_period_count: 10,
+        bind_private: "127.0.0.1:0".parse().unwrap(),
+        bind_public: "127.0.0.1:0".parse().unwrap(),
+        bind_ws: "127.0.0.1:0".parse().unwrap(),
+        max_arguments: 128,
+    }));
+    let (consensus_command_tx, consensus_command_rx) = mpsc::channel(16);
+    let (consensus_broadcast_tx, _) = broadcast::channel(16);
+    let (execution_broadcast_tx, _) = broadcast::channel(16);
+    let api = API::<Ws>::new(
+        ConsensusCommandSender(consensus_command_tx),
+        consensus_broadcast_tx.clone(),
+        execution_broadcast_tx,
+        api_settings,
+    );
+    (api, consensus_command_rx, consensus_broadcast_tx)
+}
+
+fn get_session() -> Arc<Session> {
+    Arc::new(Session::new(futures::channel::mpsc::unbounded().0))
+}
+
+fn get_transaction() -> WrappedOperation {
+    let content = Operation {
+        fee: Amount::default(),
+        op: OperationType::Transaction {
+            recipient_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+            amount: Amount::default(),
+        },
+        expire_period: 10,
+    };
+    Operation::new_wrapped(content, OperationSerializer::new(), &KeyPair::generate()).unwrap()
+}
+
+fn get_block_summary(id: BlockId, is_final: bool) -> BlockSummary {
+    BlockSummary {
+        id,
+        is_final,
+        is_stale: false,
+        is_in_blockclique: true,
+        slot: Slot::new(1, 0),
+        creator: Address::from_public_key(&KeyPair::generate().get_public_key()),
+        parents: Vec::new(),
+    }
+}
+
+/// Wait for the next notification of a subscription and extract its result
+async fn next_notification<T: DeserializeOwned>(
+    notifications: &mut UnboundedReceiver<String>,
+) -> T {
+    let notification = tokio::time::timeout(TIMEOUT, notifications.next())
+        .await
+        .expect("no notification received")
+        .expect("subscription closed");
+    let notification: Value = serde_json::from_str(&notification).unwrap();
+    serde_json::from_value(notification["params"]["result"].clone()).unwrap()
+}
+
+/// Answer the next `GetOperations` command sent to consensus
+async fn answer_get_operations(
+    consensus_command_rx: &mut mpsc::Receiver<ConsensusCommand>,
+    known_operations: Vec<(WrappedOperation, BlockId, bool)>,
+) {
+    match tokio::time::timeout(TIMEOUT, consensus_command_rx.recv()).await {
+        Ok(Some(ConsensusCommand::GetOperations {
+            operation_ids,
+            response_tx,
+        })) => {
+            let mut res: Map<OperationId, OperationSearchResult> = Map::default();
+            for (op, block_id, is_final) in known_operations {
+                if operation_ids.contains(&op.id) {
+                    res.insert(
+                        op.id,
+                        OperationSearchResult {
+                            op,
+                            in_pool: false,
+                            in_blocks: vec![(block_id, (0, is_final))].into_iter().collect(),
+                            status: OperationSearchResultStatus::InBlock(
+                                OperationSearchResultBlockStatus::Active,
+                            ),
+                        },
+                    );
+                }
+            }
+            response_tx.send(res).unwrap();
+        }
+        _ => panic!("expected a GetOperations consensus command"),
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_operation_status_sends_current_status() {
+    let (api, mut consensus_command_rx, consensus_broadcast_tx) = get_ws_api();
+    let op = get_transaction();
+    let op_id = op.id;
+    let block_id = BlockId(Hash::compute_from("block".as_bytes()));
+
+    let (subscriber, _, mut notifications) = Subscriber::new_test("operation_status");
+    api.subscribe_operation_status(get_session(), subscriber, op_id);
+
+    // the operation is already in a block: its status is sent right away
+    answer_get_operations(&mut consensus_command_rx, vec![(op, block_id, false)]).await;
+    match next_notification(&mut notifications).await {
+        OperationStatus::IncludedInBlock(id) => assert_eq!(id, block_id),
+        status => panic!("unexpected status {}", status),
+    }
+
+    // then only the status changes of that operation are sent
+    let other_op_id = get_transaction().id;
+    consensus_broadcast_tx
+        .send(ConsensusBroadcastEvent::OperationStatus(
+            other_op_id,
+            OperationStatus::Final(block_id),
+        ))
+        .unwrap();
+    consensus_broadcast_tx
+        .send(ConsensusBroadcastEvent::OperationStatus(
+            op_id,
+            OperationStatus::Final(block_id),
+        ))
+        .unwrap();
+    match next_notification(&mut notifications).await {
+        OperationStatus::Final(id) => assert_eq!(id, block_id),
+        status => panic!("unexpected status {}", status),
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_operation_status_unknown_operation() {
+    let (api, mut consensus_command_rx, consensus_broadcast_tx) = get_ws_api();
+    let op_id = get_transaction().id;
+    let block_id = BlockId(Hash::compute_from("block".as_bytes()));
+
+    let (subscriber, _, mut notifications) = Subscriber::new_test("operation_status");
+    api.subscribe_operation_status(get_session(), subscriber, op_id);
+
+    // the operation is not in any block yet: nothing is sent until it is included
+    answer_get_operations(&mut consensus_command_rx, Vec::new()).await;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), notifications.next())
+            .await
+            .is_err()
+    );
+    consensus_broadcast_tx
+        .send(ConsensusBroadcastEvent::OperationStatus(
+            op_id,
+            OperationStatus::IncludedInBlock(block_id),
+        ))
+        .unwrap();
+    match next_notification(&mut notifications).await {
+        OperationStatus::IncludedInBlock(id) => assert_eq!(id, block_id),
+        status => panic!("unexpected status {}", status),
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_new_blocks_and_unsubscribe() {
+    let (api, _consensus_command_rx, consensus_broadcast_tx) = get_ws_api();
+    let block_id = BlockId(Hash::compute_from("block".as_bytes()));
+
+    let session = get_session();
+    let (subscriber, id_rx, mut notifications) = Subscriber::new_test("new_blocks");
+    api.subscribe_new_blocks(session.clone(), subscriber);
+    let subscription_id = id_rx.await.unwrap().unwrap();
+
+    // only the new blocks are sent, not the finalized ones
+    consensus_broadcast_tx
+        .send(ConsensusBroadcastEvent::FinalizedBlock(get_block_summary(
+            block_id, true,
+        )))
+        .unwrap();
+    consensus_broadcast_tx
+        .send(ConsensusBroadcastEvent::NewBlock(get_block_summary(
+            block_id, false,
+        )))
+        .unwrap();
+    let summary: BlockSummary = next_notification(&mut notifications).await;
+    assert_eq!(summary.id, block_id);
+    assert!(!summary.is_final);
+
+    // another session cannot cancel the subscription
+    assert!(api
+        .unsubscribe_new_blocks(Some(get_session()), subscription_id.clone())
+        .is_err());
+
+    // the subscription can be cancelled only once, by its session
+    assert!(api
+        .unsubscribe_new_blocks(Some(session.clone()), subscription_id.clone())
+        .unwrap());
+    assert!(api
+        .unsubscribe_new_blocks(Some(session), subscription_id)
+        .is_err());
+}
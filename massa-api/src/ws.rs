@@ -0,0 +1,324 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::error::ApiError;
+use crate::settings::APISettings;
+use crate::{CloseHandle, StopHandle, Ws, WsEndpoints, API};
+use futures::future::{self, Future};
+use jsonrpc_core::MetaIoHandler;
+use jsonrpc_pubsub::typed::Subscriber;
+use jsonrpc_pubsub::{PubSubHandler, Session, SubscriptionId};
+use jsonrpc_ws_server::{RequestContext, ServerBuilder};
+use massa_consensus_exports::events::ConsensusBroadcastEvent;
+use massa_consensus_exports::ConsensusCommandSender;
+use massa_execution_exports::SlotExecutionOutput;
+use massa_models::api::{BlockSummary, EventFilter, OperationStatus};
+use massa_models::composite::OperationSearchResult;
+use massa_models::output_event::SCOutputEvent;
+use massa_models::prehash::Set;
+use massa_models::OperationId;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+impl API<Ws> {
+    /// generate a new websocket API
+    pub fn new(
+        consensus_command_sender: ConsensusCommandSender,
+        consensus_broadcast_sender: broadcast::Sender<ConsensusBroadcastEvent>,
+        execution_broadcast_sender: broadcast::Sender<SlotExecutionOutput>,
+        api_settings: &'static APISettings,
+    ) -> Self {
+        API(Ws {
+            consensus_command_sender,
+            consensus_broadcast_sender,
+            execution_broadcast_sender,
+            api_settings,
+            runtime: tokio::runtime::Handle::current(),
+            next_subscription_id: AtomicU64::new(0),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Start the websocket API
+    pub fn serve(self, url: &SocketAddr) -> StopHandle {
+        let mut io = PubSubHandler::new(MetaIoHandler::default());
+        io.extend_with(self.to_delegate());
+
+        let server = ServerBuilder::with_meta_extractor(io, |context: &RequestContext| {
+            Arc::new(Session::new(context.sender()))
+        })
+        .event_loop_executor(tokio::runtime::Handle::current())
+        .max_payload(50 * 1024 * 1024)
+        .start(url)
+        .expect("Unable to start WS RPC server");
+
+        let close_handle = CloseHandle::Ws(server.close_handle());
+        let join_handle = thread::spawn(|| {
+            if let Err(err) = server.wait() {
+                warn!("WS RPC server stopped with an error: {}", err);
+            }
+        });
+
+        StopHandle {
+            close_handle,
+            join_handle,
+        }
+    }
+
+    /// Assign an id to a new subscriber and spawn the task forwarding it the broadcast items.
+    ///
+    /// # Arguments
+    /// * `session`: the session opening the subscription, the only one allowed to cancel it
+    /// * `subscriber`: the new subscriber
+    /// * `receiver`: receiver of the broadcast channel feeding the subscription
+    /// * `initial`: items sent to the subscriber before the broadcast ones.
+    ///   The receiver is created beforehand so that no broadcast item is missed in between.
+    /// * `select`: extracts the items to send to the subscriber from each broadcast item
+    fn spawn_subscription<T, R, I, F>(
+        &self,
+        session: &Arc<Session>,
+        subscriber: Subscriber<T>,
+        mut receiver: broadcast::Receiver<R>,
+        initial: I,
+        select: F,
+    ) where
+        T: Serialize + Send + 'static,
+        R: Clone + Send + 'static,
+        I: Future<Output = Vec<T>> + Send + 'static,
+        F: Fn(R) -> Vec<T> + Send + 'static,
+    {
+        let id =
+            SubscriptionId::Number(self.0.next_subscription_id.fetch_add(1, Ordering::Relaxed));
+        let sink = match subscriber.assign_id(id.clone()) {
+            Ok(sink) => sink,
+            Err(_) => {
+                debug!("WS subscriber left before its subscription id was assigned");
+                return;
+            }
+        };
+
+        // keep the lock while spawning so that the task cannot remove itself before being registered
+        let mut subscriptions = self
+            .0
+            .subscriptions
+            .lock()
+            .expect("WS subscriptions lock poisoned");
+        let task_subscriptions = self.0.subscriptions.clone();
+        let task_id = id.clone();
+        let handle = self.0.runtime.spawn(async move {
+            // false once the connection was closed
+            let mut connected = initial
+                .await
+                .into_iter()
+                .all(|value| sink.notify(Ok(value)).is_ok());
+            while connected {
+                match receiver.recv().await {
+                    Ok(item) => {
+                        connected = select(item)
+                            .into_iter()
+                            .all(|value| sink.notify(Ok(value)).is_ok());
+                    }
+                    Err(RecvError::Lagged(count)) => {
+                        warn!(
+                            "WS subscription {:?} lagged: {} items skipped",
+                            task_id, count
+                        )
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            if let Ok(mut subscriptions) = task_subscriptions.lock() {
+                subscriptions.remove(&task_id);
+            }
+        });
+        subscriptions.insert(id, (Arc::downgrade(session), handle));
+    }
+
+    /// Cancel a running subscription.
+    /// A session can only cancel its own subscriptions.
+    /// No session is given when the subscription is cancelled because its session was closed.
+    fn cancel_subscription(
+        &self,
+        session: Option<Arc<Session>>,
+        id: SubscriptionId,
+    ) -> Result<bool, ApiError> {
+        let mut subscriptions =
+            self.0.subscriptions.lock().map_err(|_| {
+                ApiError::InconsistencyError("WS subscriptions lock poisoned".into())
+            })?;
+        let allowed = match (subscriptions.get(&id), session) {
+            (Some((owner, _)), Some(session)) => owner.ptr_eq(&Arc::downgrade(&session)),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        // other sessions are told that the subscription does not exist
+        if !allowed {
+            return Err(ApiError::NotFound);
+        }
+        if let Some((_, handle)) = subscriptions.remove(&id) {
+            handle.abort();
+        }
+        Ok(true)
+    }
+}
+
+/// Status of an operation given its search result: final if one of the blocks including it is final
+fn get_operation_status(search_result: &OperationSearchResult) -> Option<OperationStatus> {
+    search_result
+        .in_blocks
+        .iter()
+        .find(|(_, (_, is_final))| *is_final)
+        .map(|(block_id, _)| OperationStatus::Final(*block_id))
+        .or_else(|| {
+            search_result
+                .in_blocks
+                .keys()
+                .next()
+                .map(|block_id| OperationStatus::IncludedInBlock(*block_id))
+        })
+}
+
+#[doc(hidden)]
+impl WsEndpoints for API<Ws> {
+    type Metadata = Arc<Session>;
+
+    fn subscribe_new_blocks(&self, session: Self::Metadata, subscriber: Subscriber<BlockSummary>) {
+        let receiver = self.0.consensus_broadcast_sender.subscribe();
+        self.spawn_subscription(
+            &session,
+            subscriber,
+            receiver,
+            future::ready(Vec::new()),
+            |event| match event {
+                ConsensusBroadcastEvent::NewBlock(summary) => vec![summary],
+                _ => Vec::new(),
+            },
+        );
+    }
+
+    fn unsubscribe_new_blocks(
+        &self,
+        session: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool, ApiError> {
+        self.cancel_subscription(session, id)
+    }
+
+    fn subscribe_finalized_blocks(
+        &self,
+        session: Self::Metadata,
+        subscriber: Subscriber<BlockSummary>,
+    ) {
+        let receiver = self.0.consensus_broadcast_sender.subscribe();
+        self.spawn_subscription(
+            &session,
+            subscriber,
+            receiver,
+            future::ready(Vec::new()),
+            |event| match event {
+                ConsensusBroadcastEvent::FinalizedBlock(summary) => vec![summary],
+                _ => Vec::new(),
+            },
+        );
+    }
+
+    fn unsubscribe_finalized_blocks(
+        &self,
+        session: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool, ApiError> {
+        self.cancel_subscription(session, id)
+    }
+
+    fn subscribe_sc_events(
+        &self,
+        session: Self::Metadata,
+        subscriber: Subscriber<SCOutputEvent>,
+        filter: EventFilter,
+    ) {
+        let receiver = self.0.execution_broadcast_sender.subscribe();
+        self.spawn_subscription(
+            &session,
+            subscriber,
+            receiver,
+            future::ready(Vec::new()),
+            move |output| {
+                let exec_out = match output {
+                    SlotExecutionOutput::ExecutedSlot(exec_out) => exec_out,
+                    SlotExecutionOutput::FinalizedSlot(exec_out) => exec_out,
+                };
+                exec_out
+                    .events
+                    .get_filtered_sc_output_event(&filter)
+                    .into_iter()
+                    .collect()
+            },
+        );
+    }
+
+    fn unsubscribe_sc_events(
+        &self,
+        session: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool, ApiError> {
+        self.cancel_subscription(session, id)
+    }
+
+    fn subscribe_operation_status(
+        &self,
+        session: Self::Metadata,
+        subscriber: Subscriber<OperationStatus>,
+        operation_id: OperationId,
+    ) {
+        let receiver = self.0.consensus_broadcast_sender.subscribe();
+        // send the current status first, in case the operation was already included
+        let consensus_command_sender = self.0.consensus_command_sender.clone();
+        let current_status = async move {
+            match consensus_command_sender
+                .get_operations(Set::from_iter([operation_id]))
+                .await
+            {
+                Ok(operations) => operations
+                    .get(&operation_id)
+                    .and_then(get_operation_status)
+                    .into_iter()
+                    .collect(),
+                Err(err) => {
+                    warn!(
+                        "could not get the status of operation {} for a WS subscription: {}",
+                        operation_id, err
+                    );
+                    Vec::new()
+                }
+            }
+        };
+        self.spawn_subscription(
+            &session,
+            subscriber,
+            receiver,
+            current_status,
+            move |event| match event {
+                ConsensusBroadcastEvent::OperationStatus(op_id, status)
+                    if op_id == operation_id =>
+                {
+                    vec![status]
+                }
+                _ => Vec::new(),
+            },
+        );
+    }
+
+    fn unsubscribe_operation_status(
+        &self,
+        session: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool, ApiError> {
+        self.cancel_subscription(session, id)
+    }
+}
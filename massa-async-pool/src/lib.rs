@@ -103,7 +103,7 @@ pub use message::{
     AsyncMessage, AsyncMessageDeserializer, AsyncMessageId, AsyncMessageIdDeserializer,
     AsyncMessageIdSerializer, AsyncMessageSerializer,
 };
-pub use pool::AsyncPool;
+pub use pool::{AsyncMessageEliminationReason, AsyncPool};
 
 #[cfg(test)]
 mod tests;
@@ -8,12 +8,31 @@ use crate::{
     message::{AsyncMessage, AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer},
     AsyncMessageDeserializer, AsyncMessageSerializer,
 };
+use massa_hash::Hash;
 use massa_models::{constants::default::ASYNC_POOL_PART_SIZE_MESSAGE_BYTES, ModelsError, Slot};
 use massa_serialization::{Deserializer, Serializer};
 use nom::{multi::many0, sequence::tuple};
 use std::collections::BTreeMap;
 use std::ops::Bound::{Excluded, Unbounded};
 
+/// Reason for which a message left the pool without being executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncMessageEliminationReason {
+    /// the validity end of the message was reached
+    Expired,
+    /// the message was evicted by higher priority messages because the pool was full
+    Evicted,
+}
+
+impl std::fmt::Display for AsyncMessageEliminationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsyncMessageEliminationReason::Expired => write!(f, "expired"),
+            AsyncMessageEliminationReason::Evicted => write!(f, "evicted"),
+        }
+    }
+}
+
 /// Represents a pool of sorted messages in a deterministic way.
 /// The final asynchronous pool is attached to the output of the latest final slot within the context of massa-final-state.
 /// Nodes must bootstrap the final message pool when they join the network.
@@ -24,6 +43,22 @@ pub struct AsyncPool {
 
     /// Messages sorted by decreasing ID (decreasing priority)
     pub(crate) messages: BTreeMap<AsyncMessageId, AsyncMessage>,
+
+    /// XOR of the hashes of all the `(id, message)` pairs of the pool, maintained incrementally
+    pub(crate) hash: Hash,
+}
+
+/// Hash of a message of the pool, along with its ID
+pub(crate) fn message_hash(id: &AsyncMessageId, message: &AsyncMessage) -> Hash {
+    let mut bytes = Vec::new();
+    // the serializers never fail on valid messages
+    AsyncMessageIdSerializer::new()
+        .serialize(id, &mut bytes)
+        .expect("critical: async message id serialization failed");
+    AsyncMessageSerializer::new()
+        .serialize(message, &mut bytes)
+        .expect("critical: async message serialization failed");
+    Hash::compute_from(&bytes)
 }
 
 impl AsyncPool {
@@ -32,7 +67,44 @@ impl AsyncPool {
         AsyncPool {
             config,
             messages: Default::default(),
+            hash: Hash::zero(),
+        }
+    }
+
+    /// Removes every message of the pool.
+    /// Used to restart a bootstrap from scratch.
+    pub fn reset(&mut self) {
+        self.messages.clear();
+        self.hash = Hash::zero();
+    }
+
+    /// Get the hash of the pool content.
+    /// It is the XOR of the hashes of all the messages with their IDs,
+    /// so that it does not depend on the order in which messages were added or removed.
+    pub fn get_hash(&self) -> Hash {
+        self.hash
+    }
+
+    /// Insert a message, keeping the hash up to date
+    fn insert_message(&mut self, id: AsyncMessageId, message: AsyncMessage) {
+        self.hash ^= message_hash(&id, &message);
+        if let Some(previous) = self.messages.insert(id, message) {
+            self.hash ^= message_hash(&id, &previous);
+        }
+    }
+
+    /// Iterates over the messages of the pool, by decreasing priority
+    pub fn iter(&self) -> impl Iterator<Item = (&AsyncMessageId, &AsyncMessage)> {
+        self.messages.iter()
+    }
+
+    /// Remove a message, keeping the hash up to date
+    fn remove_message(&mut self, id: &AsyncMessageId) -> Option<AsyncMessage> {
+        let removed = self.messages.remove(id);
+        if let Some(message) = &removed {
+            self.hash ^= message_hash(id, message);
         }
+        removed
     }
 
     /// Applies pre-compiled `AsyncPoolChanges` to the pool without checking for overflows.
@@ -45,12 +117,12 @@ impl AsyncPool {
             match change {
                 // add a new message to the pool
                 Change::Add(msg_id, msg) => {
-                    self.messages.insert(*msg_id, msg.clone());
+                    self.insert_message(*msg_id, msg.clone());
                 }
 
                 // delete a message from the pool
                 Change::Delete(msg_id) => {
-                    self.messages.remove(msg_id);
+                    self.remove_message(msg_id);
                 }
             }
         }
@@ -65,7 +137,7 @@ impl AsyncPool {
     /// * `new_messages`: list of `AsyncMessage` to add to the pool
     ///
     /// # returns
-    /// The list of `(message_id, message)` that were eliminated from the pool after the changes were applied, sorted in the following order:
+    /// The list of `(message_id, message, reason)` that were eliminated from the pool after the changes were applied, sorted in the following order:
     /// * expired messages from the pool, in priority order (from highest to lowest priority)
     /// * expired messages from `new_messages` (in the order they appear in `new_messages`)
     /// * excess messages after inserting all remaining `new_messages`, in priority order (from highest to lowest priority)
@@ -73,17 +145,27 @@ impl AsyncPool {
         &mut self,
         slot: Slot,
         new_messages: &mut Vec<(AsyncMessageId, AsyncMessage)>,
-    ) -> Vec<(AsyncMessageId, AsyncMessage)> {
+    ) -> Vec<(AsyncMessageId, AsyncMessage, AsyncMessageEliminationReason)> {
         // Filter out all messages for which the validity end is expired.
         // Note that the validity_end bound is NOT included in the validity interval of the message.
         let mut eliminated: Vec<_> = self
             .messages
             .drain_filter(|_k, v| slot >= v.validity_end)
-            .chain(new_messages.drain_filter(|(_k, v)| slot >= v.validity_end))
+            .map(|(id, message)| (id, message, AsyncMessageEliminationReason::Expired))
             .collect();
+        for (id, message, _reason) in eliminated.iter() {
+            self.hash ^= message_hash(id, message);
+        }
+        eliminated.extend(
+            new_messages
+                .drain_filter(|(_k, v)| slot >= v.validity_end)
+                .map(|(id, message)| (id, message, AsyncMessageEliminationReason::Expired)),
+        );
 
         // Insert new messages into the pool
-        self.messages.extend(new_messages.clone());
+        for (id, message) in new_messages.iter() {
+            self.insert_message(*id, message.clone());
+        }
 
         // Truncate message pool to its max size, removing non-prioritary items
         let excess_count = self
@@ -92,7 +174,9 @@ impl AsyncPool {
             .saturating_sub(self.config.max_length as usize);
         eliminated.reserve_exact(excess_count);
         for _ in 0..excess_count {
-            eliminated.push(self.messages.pop_last().unwrap()); // will not panic (checked at excess_count computation)
+            let (id, message) = self.messages.pop_last().unwrap(); // will not panic (checked at excess_count computation)
+            self.hash ^= message_hash(&id, &message);
+            eliminated.push((id, message, AsyncMessageEliminationReason::Evicted));
         }
         eliminated
     }
@@ -101,33 +185,43 @@ impl AsyncPool {
     /// The returned messages are removed from the pool.
     /// This method is used at the beginning of a slot execution to list asynchronous messages to execute.
     ///
+    /// Messages are selected by decreasing gas price, ties being broken by priority.
+    /// A message that does not fit in the remaining gas is skipped
+    /// and the batch keeps being filled with the next messages that fit.
+    ///
     /// # arguments
     /// * `slot`: select only messages that are valid within this slot
     /// * `available_gas`: maximum amount of available gas
     ///
     /// # returns
-    /// A vector of messages, sorted from the most priority to the least priority
+    /// A vector of messages, sorted by decreasing gas price then by decreasing priority
     pub fn take_batch_to_execute(
         &mut self,
         slot: Slot,
         mut available_gas: u64,
     ) -> Vec<(AsyncMessageId, AsyncMessage)> {
-        // gather all selected items and remove them from self.messages
-        // iterate in decreasing priority order
-        self.messages
-            .drain_filter(|_, msg| {
-                // check available gas and validity period
-                if available_gas >= msg.max_gas
-                    && slot >= msg.validity_start
-                    && slot < msg.validity_end
-                {
-                    available_gas -= msg.max_gas;
-                    true
-                } else {
-                    false
-                }
-            })
-            .collect()
+        // list the valid messages in decreasing priority order,
+        // then stable-sort them by decreasing gas price
+        let mut candidates: Vec<_> = self
+            .messages
+            .iter()
+            .filter(|(_, msg)| slot >= msg.validity_start && slot < msg.validity_end)
+            .map(|(id, msg)| (*id, msg.gas_price, msg.max_gas))
+            .collect();
+        candidates.sort_by(|(_, price_a, _), (_, price_b, _)| price_b.cmp(price_a));
+
+        // fill the batch, skipping the messages that do not fit in the remaining gas
+        let mut taken = Vec::new();
+        for (id, _gas_price, max_gas) in candidates {
+            if max_gas > available_gas {
+                continue;
+            }
+            available_gas -= max_gas;
+            if let Some(message) = self.remove_message(&id) {
+                taken.push((id, message));
+            }
+        }
+        taken
     }
 
     /// Used for bootstrap
@@ -186,7 +280,9 @@ impl AsyncPool {
             ))(input)
         })(part)?;
         if rest.is_empty() {
-            self.messages.extend(messages);
+            for (id, message) in messages {
+                self.insert_message(id, message);
+            }
             Ok(self.messages.last_key_value().map(|(id, _)| *id))
         } else {
             Err(ModelsError::SerializeError(
@@ -198,7 +294,6 @@ impl AsyncPool {
 
 #[test]
 fn test_take_batch() {
-    use massa_hash::Hash;
     use massa_models::{Address, Amount, Slot};
     use std::str::FromStr;
 
@@ -206,7 +301,7 @@ fn test_take_batch() {
     let mut pool = AsyncPool::new(config);
     let address = Address(Hash::compute_from(b"abc"));
     for i in 1..10 {
-        pool.messages.insert(
+        pool.insert_message(
             (
                 std::cmp::Reverse(Amount::from_mantissa_scale(i, 0)),
                 Slot::new(0, 0),
@@ -230,4 +325,9 @@ fn test_take_batch() {
     assert_eq!(pool.messages.len(), 9);
     pool.take_batch_to_execute(Slot::new(2, 0), 19);
     assert_eq!(pool.messages.len(), 6);
+    let expected_hash = pool
+        .messages
+        .iter()
+        .fold(Hash::zero(), |acc, (id, msg)| acc ^ message_hash(id, msg));
+    assert_eq!(pool.get_hash(), expected_hash);
 }
@@ -2,7 +2,7 @@
 
 use std::{cmp::Reverse, collections::BTreeMap, str::FromStr};
 
-use crate::{AsyncMessage, AsyncPool, AsyncPoolConfig};
+use crate::{pool::message_hash, AsyncMessage, AsyncPool, AsyncPoolConfig};
 use massa_models::{constants::THREAD_COUNT, Address, Amount, Slot};
 use massa_signature::KeyPair;
 use rand::Rng;
@@ -15,6 +15,9 @@ pub fn create_async_pool(
     messages: BTreeMap<(Reverse<Amount>, Slot, u64), AsyncMessage>,
 ) -> AsyncPool {
     let mut async_pool = AsyncPool::new(config);
+    for (id, message) in messages.iter() {
+        async_pool.hash ^= message_hash(id, message);
+    }
     async_pool.messages = messages;
     async_pool
 }
@@ -72,4 +75,5 @@ pub fn assert_eq_async_pool_bootstrap_state(v1: &AsyncPool, v2: &AsyncPool) {
     for (val1, val2) in v1.messages.iter().zip(v2.messages.iter()) {
         assert_eq_async_message(val1.1, val2.1);
     }
+    assert_eq!(v1.hash, v2.hash, "hash mismatch");
 }
@@ -1 +1,94 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{AsyncMessage, AsyncMessageEliminationReason, AsyncPool, AsyncPoolConfig};
+use massa_hash::Hash;
+use massa_models::{Address, Amount, Slot};
+use std::str::FromStr;
+
+/// Creates a message valid from slot `(1, 0)` to slot `(3, 0)` (excluded)
+fn create_message(emission_index: u64, max_gas: u64, gas_price: &str, coins: &str) -> AsyncMessage {
+    let address = Address(Hash::compute_from(b"abc"));
+    AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index,
+        sender: address,
+        destination: address,
+        handler: "function".to_string(),
+        validity_start: Slot::new(1, 0),
+        validity_end: Slot::new(3, 0),
+        max_gas,
+        gas_price: Amount::from_str(gas_price).unwrap(),
+        coins: Amount::from_str(coins).unwrap(),
+        data: Vec::new(),
+    }
+}
+
+#[test]
+fn test_settle_slot_elimination_reasons() {
+    let mut pool = AsyncPool::new(AsyncPoolConfig { max_length: 2 });
+
+    // a message already in the pool that expires at the settled slot
+    let mut expired = create_message(0, 10, "1", "0.5");
+    expired.validity_end = Slot::new(2, 0);
+    let mut new_messages = vec![(expired.compute_id(), expired.clone())];
+    assert!(pool
+        .settle_slot(Slot::new(1, 0), &mut new_messages)
+        .is_empty());
+
+    // three new messages for two places: the lowest priority one is evicted
+    let mut new_messages: Vec<_> = [("3", "1"), ("2", "2"), ("1", "3")]
+        .iter()
+        .enumerate()
+        .map(|(index, (gas_price, coins))| {
+            let msg = create_message(index as u64 + 1, 10, gas_price, coins);
+            (msg.compute_id(), msg)
+        })
+        .collect();
+    let evicted_id = new_messages[2].0;
+    let eliminated = pool.settle_slot(Slot::new(2, 0), &mut new_messages);
+    assert_eq!(eliminated.len(), 2);
+    assert_eq!(eliminated[0].0, expired.compute_id());
+    assert_eq!(eliminated[0].1.coins, expired.coins);
+    assert_eq!(eliminated[0].2, AsyncMessageEliminationReason::Expired);
+    assert_eq!(eliminated[1].0, evicted_id);
+    assert_eq!(eliminated[1].1.coins, Amount::from_str("3").unwrap());
+    assert_eq!(eliminated[1].2, AsyncMessageEliminationReason::Evicted);
+    assert_eq!(pool.messages.len(), 2);
+    assert!(!pool.messages.contains_key(&evicted_id));
+}
+
+#[test]
+fn test_take_batch_skips_oversized_messages() {
+    let mut pool = AsyncPool::new(AsyncPoolConfig { max_length: 10 });
+
+    // the highest gas price message does not fit in the available gas,
+    // the next ones are taken by decreasing gas price until the gas runs out
+    let mut new_messages: Vec<_> = [
+        (100, "10"),
+        (6, "5"),
+        (5, "4"),
+        (8, "2"),
+        (4, "3"),
+        (2, "1"),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(index, (max_gas, gas_price))| {
+        let msg = create_message(index as u64, *max_gas, gas_price, "0");
+        (msg.compute_id(), msg)
+    })
+    .collect();
+    assert!(pool
+        .settle_slot(Slot::new(1, 0), &mut new_messages)
+        .is_empty());
+
+    let batch = pool.take_batch_to_execute(Slot::new(1, 0), 12);
+    let batch_gas: Vec<u64> = batch.iter().map(|(_id, msg)| msg.max_gas).collect();
+    assert_eq!(batch_gas, vec![6, 5]);
+
+    // the gas price prevails over the priority, which also accounts for the max gas
+    let batch = pool.take_batch_to_execute(Slot::new(1, 1), 12);
+    let batch_gas: Vec<u64> = batch.iter().map(|(_id, msg)| msg.max_gas).collect();
+    assert_eq!(batch_gas, vec![4, 8]);
+    assert_eq!(pool.messages.len(), 2);
+}
@@ -62,6 +62,7 @@ async fn stream_final_state(
                 BootstrapServerMessage::FinalStatePart {
                     ledger_data,
                     async_pool_part,
+                    receipts_part,
                     slot,
                     final_state_changes,
                 } => {
@@ -70,12 +71,18 @@ async fn stream_final_state(
                     let last_last_async_id = write_final_state
                         .async_pool
                         .set_pool_part(async_pool_part.as_bytes())?;
+                    let last_receipt_id = write_final_state
+                        .receipts
+                        .set_receipts_part(receipts_part.as_bytes())?;
                     write_final_state
                         .ledger
                         .apply_changes(final_state_changes.ledger_changes.clone(), slot);
                     write_final_state
                         .async_pool
                         .apply_changes_unchecked(&final_state_changes.async_pool_changes);
+                    write_final_state
+                        .receipts
+                        .apply_changes(&final_state_changes.receipts_changes, slot);
                     write_final_state.slot = slot;
                     if let BootstrapClientMessage::AskFinalStatePart {
                         last_key: old_key,
@@ -90,19 +97,39 @@ async fn stream_final_state(
                         last_key,
                         slot: Some(slot),
                         last_async_message_id: last_last_async_id,
+                        last_receipt_id,
                     };
                 }
-                BootstrapServerMessage::FinalStateFinished => {
-                    info!("State bootstrap complete");
+                BootstrapServerMessage::FinalStateFinished { slot, fingerprint } => {
+                    // check that the streamed state matches the one of the server
+                    let mut write_final_state = global_bootstrap_state.final_state.write();
+                    let local_fingerprint = write_final_state.compute_fingerprint();
+                    if write_final_state.slot != slot || local_fingerprint != fingerprint {
+                        warn!("Bootstrapped final state at slot {} with fingerprint {} does not match the fingerprint {} of the server at slot {}, retry bootstrap from scratch", write_final_state.slot, local_fingerprint, fingerprint, slot);
+                        write_final_state.reset();
+                        *next_bootstrap_message = BootstrapClientMessage::AskFinalStatePart {
+                            last_key: None,
+                            slot: None,
+                            last_async_message_id: None,
+                            last_receipt_id: None,
+                        };
+                        return Err(BootstrapError::GeneralError(
+                            "final state fingerprint mismatch".to_string(),
+                        ));
+                    }
+                    write_final_state.fingerprint = local_fingerprint;
+                    info!("State bootstrap complete, fingerprint {}", fingerprint);
                     *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
                     return Ok(());
                 }
                 BootstrapServerMessage::SlotTooOld => {
                     info!("Slot is too old retry bootstrap from scratch");
+                    global_bootstrap_state.final_state.write().reset();
                     *next_bootstrap_message = BootstrapClientMessage::AskFinalStatePart {
                         last_key: None,
                         slot: None,
                         last_async_message_id: None,
+                        last_receipt_id: None,
                     };
                     return Ok(());
                 }
@@ -379,6 +406,7 @@ pub async fn get_state(
             last_key: None,
             slot: None,
             last_async_message_id: None,
+            last_receipt_id: None,
         };
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state.clone());
     loop {
@@ -7,6 +7,9 @@
 //! On server side, the server will query consensus for the graph and the ledger,
 //! execution for execution related data and network for the peer list.
 //!
+//! A node can also save its consensus state on local disk (see `local_state`),
+//! allowing it to restart from it instead of bootstrapping again.
+//!
 #![feature(async_closure)]
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
@@ -25,12 +28,14 @@ mod client;
 mod client_binder;
 mod error;
 mod establisher;
+mod local_state;
 mod messages;
 mod server;
 mod server_binder;
 mod settings;
 pub use client::get_state;
 pub use establisher::types;
+pub use local_state::{resume_local_state, save_local_state};
 pub use server::{start_bootstrap_server, BootstrapManager};
 pub use settings::BootstrapSettings;
 
@@ -0,0 +1,147 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Persistence of the consensus state on local disk, allowing a node to restart without bootstrapping.
+//!
+//! The saved state is made of the slot, fingerprint, async pool and operation receipts of the final state,
+//! along with the proof of stake and graph exports of consensus, encoded as in a bootstrap `ConsensusState` message.
+//! The final ledger is not part of it: it is already kept on disk, attached to its final slot.
+//! The state is saved when the node stops cleanly, after execution is stopped so that the final state no longer moves.
+//! On restart, the saved state is only resumed if it is consistent with the final state rebuilt from the disk ledger,
+//! that is if both are attached to the same slot and have the same fingerprint. Otherwise the node bootstraps:
+//! this is the case after a non-clean stop, as the disk ledger has kept finalizing slots since the last save.
+
+use crate::error::BootstrapError;
+use crate::messages::{
+    BootstrapServerMessage, BootstrapServerMessageDeserializer, BootstrapServerMessageSerializer,
+};
+use massa_consensus_exports::ConsensusCommandSender;
+use massa_final_state::FinalState;
+use massa_graph::BootstrapableGraph;
+use massa_hash::HashDeserializer;
+use massa_models::constants::THREAD_COUNT;
+use massa_models::slot::{SlotDeserializer, SlotSerializer};
+use massa_models::{Slot, VecU8Deserializer, VecU8Serializer};
+use massa_proof_of_stake_exports::ExportProofOfStake;
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use parking_lot::RwLock;
+use std::ops::Bound::Included;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Save the consensus state to disk, along with the slot, fingerprint, async pool and receipts of the final state.
+///
+/// The final state is read before consensus is queried so that the saved final state is never ahead
+/// of the saved consensus state, as done by the bootstrap server.
+///
+/// # Returns
+/// The final slot the saved state is attached to
+pub async fn save_local_state(
+    path: &Path,
+    final_state: &Arc<RwLock<FinalState>>,
+    consensus_command_sender: &ConsensusCommandSender,
+) -> Result<Slot, BootstrapError> {
+    let mut bytes = Vec::new();
+    let slot = {
+        let final_state = final_state.read();
+        SlotSerializer::new().serialize(&final_state.slot, &mut bytes)?;
+        bytes.extend(final_state.fingerprint.to_bytes());
+        let mut async_pool_data = Vec::new();
+        let mut last_id = None;
+        loop {
+            let (part, new_last_id) = final_state.async_pool.get_pool_part(last_id)?;
+            if part.is_empty() {
+                break;
+            }
+            async_pool_data.extend(part);
+            last_id = new_last_id;
+        }
+        VecU8Serializer::new().serialize(&async_pool_data, &mut bytes)?;
+        let mut receipts_data = Vec::new();
+        let mut last_id = None;
+        loop {
+            let (part, new_last_id) = final_state.receipts.get_receipts_part(last_id)?;
+            if part.is_empty() {
+                break;
+            }
+            receipts_data.extend(part);
+            last_id = new_last_id;
+        }
+        VecU8Serializer::new().serialize(&receipts_data, &mut bytes)?;
+        final_state.slot
+    };
+    let (pos, graph) = consensus_command_sender.get_bootstrap_state().await?;
+    BootstrapServerMessageSerializer::new().serialize(
+        &BootstrapServerMessage::ConsensusState { pos, graph },
+        &mut bytes,
+    )?;
+
+    // write a temporary file first so that a crash while writing does not corrupt the previously saved state
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, bytes).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(slot)
+}
+
+/// Resume the state saved on disk: the async pool and receipts of the final state are restored from it,
+/// and the proof of stake and graph exports to start consensus from are returned.
+///
+/// Fails if the saved state is not consistent with the final state, which then needs to be bootstrapped.
+/// Note that the async pool and receipts may have been partially restored in that case.
+pub fn resume_local_state(
+    path: &Path,
+    final_state: &mut FinalState,
+) -> Result<(ExportProofOfStake, BootstrapableGraph), BootstrapError> {
+    let bytes = std::fs::read(path)?;
+    let deserialize_error = |err: nom::Err<DeserializeError>| {
+        BootstrapError::GeneralError(format!("invalid local state: {}", err))
+    };
+
+    let (rest, slot) = SlotDeserializer::new(
+        (Included(0), Included(u64::MAX)),
+        (Included(0), Included(THREAD_COUNT)),
+    )
+    .deserialize::<DeserializeError>(&bytes)
+    .map_err(deserialize_error)?;
+    if slot != final_state.slot {
+        return Err(BootstrapError::GeneralError(format!(
+            "the local state was saved at slot {} while the final state is attached to slot {}",
+            slot, final_state.slot
+        )));
+    }
+    let (rest, fingerprint) = HashDeserializer::new()
+        .deserialize::<DeserializeError>(rest)
+        .map_err(deserialize_error)?;
+    let (rest, async_pool_data) = VecU8Deserializer::new(Included(0), Included(u64::MAX))
+        .deserialize::<DeserializeError>(rest)
+        .map_err(deserialize_error)?;
+    let (rest, receipts_data) = VecU8Deserializer::new(Included(0), Included(u64::MAX))
+        .deserialize::<DeserializeError>(rest)
+        .map_err(deserialize_error)?;
+    let (rest, message) = BootstrapServerMessageDeserializer::new()
+        .deserialize::<DeserializeError>(rest)
+        .map_err(deserialize_error)?;
+    if !rest.is_empty() {
+        return Err(BootstrapError::GeneralError(
+            "invalid local state: data left after the consensus state".to_string(),
+        ));
+    }
+    let (pos, graph) = match message {
+        BootstrapServerMessage::ConsensusState { pos, graph } => (pos, graph),
+        _ => {
+            return Err(BootstrapError::GeneralError(
+                "invalid local state: missing consensus state".to_string(),
+            ))
+        }
+    };
+
+    final_state.async_pool.set_pool_part(&async_pool_data)?;
+    final_state.receipts.set_receipts_part(&receipts_data)?;
+    final_state.fingerprint = final_state.compute_fingerprint();
+    if final_state.fingerprint != fingerprint {
+        return Err(BootstrapError::GeneralError(format!(
+            "the fingerprint {} of the resumed final state does not match the saved fingerprint {}",
+            final_state.fingerprint, fingerprint
+        )));
+    }
+    Ok((pos, graph))
+}
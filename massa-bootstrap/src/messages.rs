@@ -3,6 +3,7 @@
 use massa_async_pool::{AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer};
 use massa_final_state::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
 use massa_graph::BootstrapableGraph;
+use massa_hash::{Hash, HashDeserializer};
 use massa_ledger_exports::{KeyDeserializer, KeySerializer};
 use massa_models::constants::MAX_ADVERTISE_LENGTH;
 use massa_models::slot::SlotDeserializer;
@@ -10,7 +11,10 @@ use massa_models::{
     constants::THREAD_COUNT, slot::SlotSerializer, DeserializeCompact, SerializeCompact, Slot,
     Version,
 };
-use massa_models::{VecU8Deserializer, VecU8Serializer, VersionDeserializer, VersionSerializer};
+use massa_models::{
+    OperationId, OperationIdDeserializer, OperationIdSerializer, VecU8Deserializer,
+    VecU8Serializer, VersionDeserializer, VersionSerializer,
+};
 use massa_network_exports::{BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer};
 use massa_proof_of_stake_exports::{
     ExportProofOfStake, ExportProofOfStakeDeserializer, ExportProofOfStakeSerializer,
@@ -58,13 +62,20 @@ pub enum BootstrapServerMessage {
         ledger_data: Vec<u8>,
         /// Part of the async pool
         async_pool_part: Vec<u8>,
+        /// Part of the final operation receipts
+        receipts_part: Vec<u8>,
         /// Slot the state changes are attached to
         slot: Slot,
         /// Ledger change for addresses inferior to `address` of the client message until the actual slot.
         final_state_changes: StateChanges,
     },
     /// Message sent when there is no state part left
-    FinalStateFinished,
+    FinalStateFinished {
+        /// Slot the final state is attached to
+        slot: Slot,
+        /// Fingerprint of the final state at that slot
+        fingerprint: Hash,
+    },
     /// Slot sent to get state changes is too old
     SlotTooOld,
     /// Bootstrap error
@@ -143,6 +154,7 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
             BootstrapServerMessage::FinalStatePart {
                 ledger_data,
                 async_pool_part,
+                receipts_part,
                 slot,
                 final_state_changes,
             } => {
@@ -150,13 +162,16 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
                     .serialize(&u32::from(MessageServerTypeId::FinalStatePart), buffer)?;
                 self.vec_u8_serializer.serialize(ledger_data, buffer)?;
                 self.vec_u8_serializer.serialize(async_pool_part, buffer)?;
+                self.vec_u8_serializer.serialize(receipts_part, buffer)?;
                 self.slot_serializer.serialize(slot, buffer)?;
                 self.state_changes_serializer
                     .serialize(final_state_changes, buffer)?;
             }
-            BootstrapServerMessage::FinalStateFinished => {
+            BootstrapServerMessage::FinalStateFinished { slot, fingerprint } => {
                 self.u32_serializer
                     .serialize(&u32::from(MessageServerTypeId::FinalStateFinished), buffer)?;
+                self.slot_serializer.serialize(slot, buffer)?;
+                buffer.extend(fingerprint.to_bytes());
             }
             BootstrapServerMessage::SlotTooOld => {
                 self.u32_serializer
@@ -188,6 +203,7 @@ pub struct BootstrapServerMessageDeserializer {
     state_changes_deserializer: StateChangesDeserializer,
     vec_u8_deserializer: VecU8Deserializer,
     slot_deserializer: SlotDeserializer,
+    hash_deserializer: HashDeserializer,
 }
 
 impl BootstrapServerMessageDeserializer {
@@ -208,6 +224,7 @@ impl BootstrapServerMessageDeserializer {
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Included(THREAD_COUNT)),
             ),
+            hash_deserializer: HashDeserializer::new(),
         }
     }
 }
@@ -257,25 +274,33 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
                 .map(|(pos, graph)| BootstrapServerMessage::ConsensusState { pos, graph })
                 .parse(input),
                 MessageServerTypeId::FinalStatePart => tuple((
+                    |input| self.vec_u8_deserializer.deserialize(input),
                     |input| self.vec_u8_deserializer.deserialize(input),
                     |input| self.vec_u8_deserializer.deserialize(input),
                     |input| self.slot_deserializer.deserialize(input),
                     |input| self.state_changes_deserializer.deserialize(input),
                 ))
                 .map(
-                    |(ledger_data, async_pool_part, slot, final_state_changes)| {
+                    |(ledger_data, async_pool_part, receipts_part, slot, final_state_changes)| {
                         BootstrapServerMessage::FinalStatePart {
                             ledger_data,
                             async_pool_part,
+                            receipts_part,
                             slot,
                             final_state_changes,
                         }
                     },
                 )
                 .parse(input),
-                MessageServerTypeId::FinalStateFinished => {
-                    Ok((input, BootstrapServerMessage::FinalStateFinished))
-                }
+                MessageServerTypeId::FinalStateFinished => tuple((
+                    |input| self.slot_deserializer.deserialize(input),
+                    |input| self.hash_deserializer.deserialize(input),
+                ))
+                .map(|(slot, fingerprint)| BootstrapServerMessage::FinalStateFinished {
+                    slot,
+                    fingerprint,
+                })
+                .parse(input),
                 MessageServerTypeId::SlotTooOld => Ok((input, BootstrapServerMessage::SlotTooOld)),
                 MessageServerTypeId::BootstrapError => {
                     length_data(|input| self.u32_deserializer.deserialize(input))
@@ -290,6 +315,45 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
     }
 }
 
+/// Serializes an optional value, prefixed by a byte telling whether it is present
+fn serialize_option<T>(
+    serializer: &impl Serializer<T>,
+    value: &Option<T>,
+    buffer: &mut Vec<u8>,
+) -> Result<(), SerializeError> {
+    match value {
+        Some(value) => {
+            buffer.push(1);
+            serializer.serialize(value, buffer)
+        }
+        None => {
+            buffer.push(0);
+            Ok(())
+        }
+    }
+}
+
+/// Deserializes an optional value serialized by `serialize_option`
+fn deserialize_option<'a, T, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+    deserializer: &impl Deserializer<T>,
+    input: &'a [u8],
+) -> IResult<&'a [u8], Option<T>, E> {
+    match input.first() {
+        Some(0) => Ok((&input[1..], None)),
+        Some(1) => deserializer
+            .deserialize(&input[1..])
+            .map(|(rest, value)| (rest, Some(value))),
+        Some(_) => Err(nom::Err::Error(ParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::Digit,
+        ))),
+        None => Err(nom::Err::Error(ParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::LengthValue,
+        ))),
+    }
+}
+
 /// Messages used during bootstrap by client
 #[derive(Debug)]
 pub enum BootstrapClientMessage {
@@ -305,6 +369,8 @@ pub enum BootstrapClientMessage {
         slot: Option<Slot>,
         /// Last async message id  of the async message pool we received from the server
         last_async_message_id: Option<AsyncMessageId>,
+        /// Last operation id of the final receipts we received from the server
+        last_receipt_id: Option<OperationId>,
     },
     /// Bootstrap error
     BootstrapError { error: String },
@@ -327,6 +393,7 @@ pub struct BootstrapClientMessageSerializer {
     u32_serializer: U32VarIntSerializer,
     slot_serializer: SlotSerializer,
     async_message_id_serializer: AsyncMessageIdSerializer,
+    operation_id_serializer: OperationIdSerializer,
     key_serializer: KeySerializer,
 }
 
@@ -337,6 +404,7 @@ impl BootstrapClientMessageSerializer {
             u32_serializer: U32VarIntSerializer::new(),
             slot_serializer: SlotSerializer::new(),
             async_message_id_serializer: AsyncMessageIdSerializer::new(),
+            operation_id_serializer: OperationIdSerializer::new(),
             key_serializer: KeySerializer::new(),
         }
     }
@@ -361,15 +429,19 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                 last_key,
                 slot,
                 last_async_message_id,
+                last_receipt_id,
             } => {
                 self.u32_serializer
                     .serialize(&u32::from(MessageClientTypeId::AskFinalStatePart), buffer)?;
-                // If we have a cursor we must have also a slot
-                if let Some(key) = last_key && let Some(slot) = slot && let Some(last_async_message_id) = last_async_message_id  {
-                    self.key_serializer.serialize(key, buffer)?;
-                    self.slot_serializer.serialize(slot, buffer)?;
-                    self.async_message_id_serializer.serialize(last_async_message_id, buffer)?;
-                }
+                // each cursor is optional since a part of the state may have nothing streamed yet
+                serialize_option(&self.key_serializer, last_key, buffer)?;
+                serialize_option(&self.slot_serializer, slot, buffer)?;
+                serialize_option(
+                    &self.async_message_id_serializer,
+                    last_async_message_id,
+                    buffer,
+                )?;
+                serialize_option(&self.operation_id_serializer, last_receipt_id, buffer)?;
             }
             BootstrapClientMessage::BootstrapError { error } => {
                 self.u32_serializer
@@ -396,6 +468,7 @@ pub struct BootstrapClientMessageDeserializer {
     u32_deserializer: U32VarIntDeserializer,
     slot_deserializer: SlotDeserializer,
     async_message_id_deserializer: AsyncMessageIdDeserializer,
+    operation_id_deserializer: OperationIdDeserializer,
     key_deserializer: KeyDeserializer,
 }
 
@@ -409,6 +482,7 @@ impl BootstrapClientMessageDeserializer {
                 (Included(0), Included(THREAD_COUNT)),
             ),
             async_message_id_deserializer: AsyncMessageIdDeserializer::new(),
+            operation_id_deserializer: OperationIdDeserializer::new(),
             key_deserializer: KeyDeserializer::new(),
         }
     }
@@ -434,38 +508,29 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                 MessageClientTypeId::AskConsensusState => {
                     Ok((input, BootstrapClientMessage::AskConsensusState))
                 }
-                MessageClientTypeId::AskFinalStatePart => {
-                    if input.is_empty() {
-                        Ok((
-                            input,
-                            BootstrapClientMessage::AskFinalStatePart {
-                                last_key: None,
-                                slot: None,
-                                last_async_message_id: None,
-                            },
-                        ))
-                    } else {
-                        tuple((
-                            context("Faild key deserialization", |input| {
-                                self.key_deserializer.deserialize(input)
-                            }),
-                            context("Failed slot deserialization", |input| {
-                                self.slot_deserializer.deserialize(input)
-                            }),
-                            context("Failed async_message_id deserialization", |input| {
-                                self.async_message_id_deserializer.deserialize(input)
-                            }),
-                        ))
-                        .map(|(last_key, slot, last_async_message_id)| {
-                            BootstrapClientMessage::AskFinalStatePart {
-                                last_key: Some(last_key),
-                                slot: Some(slot),
-                                last_async_message_id: Some(last_async_message_id),
-                            }
-                        })
-                        .parse(input)
+                MessageClientTypeId::AskFinalStatePart => tuple((
+                    context("Failed key deserialization", |input| {
+                        deserialize_option(&self.key_deserializer, input)
+                    }),
+                    context("Failed slot deserialization", |input| {
+                        deserialize_option(&self.slot_deserializer, input)
+                    }),
+                    context("Failed async_message_id deserialization", |input| {
+                        deserialize_option(&self.async_message_id_deserializer, input)
+                    }),
+                    context("Failed receipt_id deserialization", |input| {
+                        deserialize_option(&self.operation_id_deserializer, input)
+                    }),
+                ))
+                .map(|(last_key, slot, last_async_message_id, last_receipt_id)| {
+                    BootstrapClientMessage::AskFinalStatePart {
+                        last_key,
+                        slot,
+                        last_async_message_id,
+                        last_receipt_id,
                     }
-                }
+                })
+                .parse(input),
                 MessageClientTypeId::BootstrapError => {
                     length_data(|input| self.u32_deserializer.deserialize(input))
                         .map(|error| BootstrapClientMessage::BootstrapError {
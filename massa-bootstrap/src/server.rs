@@ -13,7 +13,9 @@ use massa_final_state::{FinalState, StateChanges};
 use massa_graph::BootstrapableGraph;
 use massa_ledger_exports::get_address_from_key;
 use massa_logging::massa_trace;
-use massa_models::{Slot, Version};
+use massa_models::{
+    constants::MAX_BOOTSTRAP_FINAL_STATE_CATCH_UP_PARTS, OperationId, Slot, Version,
+};
 use massa_network_exports::{BootstrapPeers, NetworkCommandSender};
 use massa_proof_of_stake_exports::ExportProofOfStake;
 use massa_signature::KeyPair;
@@ -254,18 +256,25 @@ pub async fn send_final_state_stream(
     final_state: Arc<RwLock<FinalState>>,
     slot: Option<Slot>,
     last_async_message_id: Option<AsyncMessageId>,
+    last_receipt_id: Option<OperationId>,
     write_timeout: Duration,
 ) -> Result<(), BootstrapError> {
     let mut old_key = last_key;
     let mut old_last_async_id = last_async_message_id;
+    let mut old_last_receipt_id = last_receipt_id;
     let mut old_slot = slot;
+    // number of parts sent only to catch up with new final slots
+    let mut catch_up_parts: u32 = 0;
 
     loop {
         // Scope of the read in the final state
         let ledger_data;
         let async_pool_data;
+        let receipts_data;
         let final_state_changes;
         let current_slot;
+        let previous_slot;
+        let fingerprint;
         {
             // Get all data for the next message
             let final_state_read = final_state.read();
@@ -285,11 +294,21 @@ pub async fn send_final_state_stream(
                 .get_pool_part(old_last_async_id)?;
             async_pool_data = pool_data;
 
-            if let Some(slot) = old_slot && let Some(key) = &old_key && let Some(async_pool_id) = old_last_async_id && slot != final_state_read.slot {
+            let (receipts_part, last_part_receipt_id) = final_state_read
+                .receipts
+                .get_receipts_part(old_last_receipt_id)?;
+            receipts_data = receipts_part;
+
+            if let Some(slot) = old_slot && slot != final_state_read.slot {
+                let last_address = match &old_key {
+                    Some(key) => Some(get_address_from_key(key).ok_or_else(|| BootstrapError::GeneralError("Malformed key in slot changes".to_string()))?),
+                    None => None,
+                };
                 final_state_changes = final_state_read.get_state_changes_part(
                     slot,
-                    get_address_from_key(key).ok_or_else(|| BootstrapError::GeneralError("Malformed key in slot changes".to_string()))?,
-                    async_pool_id,
+                    last_address,
+                    old_last_async_id,
+                    old_last_receipt_id,
                 );
             } else {
                 final_state_changes = Ok(StateChanges::default());
@@ -299,14 +318,34 @@ pub async fn send_final_state_stream(
             if last_async_pool_id.is_some() || !async_pool_data.is_empty() {
                 old_last_async_id = last_async_pool_id;
             }
+            if last_part_receipt_id.is_some() || !receipts_data.is_empty() {
+                old_last_receipt_id = last_part_receipt_id;
+            }
             if new_last_key.is_some() || !ledger_data.is_empty() {
                 old_key = new_last_key;
             }
+            previous_slot = old_slot;
             old_slot = Some(final_state_read.slot);
             current_slot = final_state_read.slot;
+            fingerprint = final_state_read.fingerprint;
         }
 
-        if !ledger_data.is_empty() || !async_pool_data.is_empty() {
+        // keep streaming while there is data left or while the state keeps changing,
+        // so that the client ends up at the exact slot of the fingerprint it is sent
+        if !ledger_data.is_empty()
+            || !async_pool_data.is_empty()
+            || !receipts_data.is_empty()
+            || previous_slot != Some(current_slot)
+        {
+            if ledger_data.is_empty() && async_pool_data.is_empty() && receipts_data.is_empty() {
+                // the slots keep finalizing faster than we stream: let the client resume later
+                catch_up_parts += 1;
+                if catch_up_parts > MAX_BOOTSTRAP_FINAL_STATE_CATCH_UP_PARTS {
+                    return Err(BootstrapError::GeneralError(
+                        "final state catch-up did not converge".to_string(),
+                    ));
+                }
+            }
             if let Ok(final_state_changes) = final_state_changes {
                 match tokio::time::timeout(
                     write_timeout,
@@ -314,6 +353,7 @@ pub async fn send_final_state_stream(
                         ledger_data,
                         slot: current_slot,
                         async_pool_part: async_pool_data,
+                        receipts_part: receipts_data,
                         final_state_changes,
                     }),
                 )
@@ -345,10 +385,13 @@ pub async fn send_final_state_stream(
                 break;
             }
         } else {
-            // There is no ledger data nor async pool data.
+            // There is no ledger data, async pool data nor receipts data, and the client is up to date.
             match tokio::time::timeout(
                 write_timeout,
-                server.send(BootstrapServerMessage::FinalStateFinished),
+                server.send(BootstrapServerMessage::FinalStateFinished {
+                    slot: current_slot,
+                    fingerprint,
+                }),
             )
             .await
             {
@@ -456,6 +499,7 @@ async fn manage_bootstrap(
                     last_key,
                     slot,
                     last_async_message_id,
+                    last_receipt_id,
                 } => {
                     send_final_state_stream(
                         server,
@@ -463,6 +507,7 @@ async fn manage_bootstrap(
                         final_state.clone(),
                         slot,
                         last_async_message_id,
+                        last_receipt_id,
                         write_timeout,
                     )
                     .await?;
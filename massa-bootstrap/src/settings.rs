@@ -4,6 +4,7 @@ use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 /// Bootstrap settings.
 /// Both bootstrapping at start-up and bootstrap server settings
@@ -39,4 +40,6 @@ pub struct BootstrapSettings {
     pub ip_list_max_size: usize,
     /// Read-Write limitation for a connection in bytes per seconds
     pub max_bytes_read_write: f64,
+    /// path to the file where the consensus state is saved on shutdown to restart without bootstrapping, disabled if None
+    pub local_state_path: Option<PathBuf>,
 }
@@ -9,20 +9,27 @@ use super::{
 };
 use crate::BootstrapSettings;
 use crate::{
-    get_state, start_bootstrap_server,
+    get_state, resume_local_state, save_local_state, start_bootstrap_server,
     tests::tools::{
         assert_eq_bootstrap_graph, assert_eq_thread_cycle_states, get_bootstrap_config,
     },
 };
+use massa_async_pool::AsyncPool;
 use massa_consensus_exports::{commands::ConsensusCommand, ConsensusCommandSender};
-use massa_final_state::{test_exports::assert_eq_final_state, FinalState};
-use massa_models::Version;
+use massa_final_state::{
+    test_exports::{assert_eq_final_receipts, assert_eq_final_state},
+    FinalReceipts, FinalState, StateChanges,
+};
+use massa_graph::BootstrapableGraph;
+use massa_models::{Slot, Version};
 use massa_network_exports::{NetworkCommand, NetworkCommandSender};
+use massa_proof_of_stake_exports::ExportProofOfStake;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
 use serial_test::serial;
-use std::{str::FromStr, sync::Arc};
+use std::{path::Path, str::FromStr, sync::Arc};
+use tempfile::TempDir;
 use tokio::sync::mpsc;
 
 lazy_static::lazy_static! {
@@ -173,3 +180,100 @@ async fn test_bootstrap_server() {
         .await
         .expect("could not stop bootstrap server");
 }
+
+/// Save the local state as done on a clean shutdown, answering the consensus state query with `pos` and `graph`
+async fn save_local_state_on_shutdown(
+    path: &Path,
+    final_state: &Arc<RwLock<FinalState>>,
+    pos: ExportProofOfStake,
+    graph: BootstrapableGraph,
+) -> Slot {
+    let (consensus_cmd_tx, mut consensus_cmd_rx) = mpsc::channel::<ConsensusCommand>(5);
+    let consensus_h = tokio::spawn(async move {
+        match wait_consensus_command(&mut consensus_cmd_rx, 1000.into(), |cmd| match cmd {
+            ConsensusCommand::GetBootstrapState(resp) => Some(resp),
+            _ => None,
+        })
+        .await
+        {
+            Some(resp) => resp.send((pos, graph)).unwrap(),
+            None => panic!("timeout waiting for get boot graph consensus command"),
+        }
+    });
+    let saved_slot = save_local_state(path, final_state, &ConsensusCommandSender(consensus_cmd_tx))
+        .await
+        .unwrap();
+    consensus_h.await.unwrap();
+    saved_slot
+}
+
+#[tokio::test]
+#[serial]
+async fn test_local_state() {
+    // init the serialization context
+    get_bootstrap_config(KeyPair::generate().get_public_key());
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("consensus_state.bin");
+    let final_state = Arc::new(RwLock::new(get_random_final_state_bootstrap(2)));
+    let (sent_pos, sent_graph) = get_boot_state();
+    let saved_slot =
+        save_local_state_on_shutdown(&path, &final_state, sent_pos.clone(), sent_graph.clone())
+            .await;
+
+    // on restart, the disk ledger is kept but the async pool and the receipts are lost
+    let mut restarted = final_state.write();
+    assert_eq!(saved_slot, restarted.slot);
+    let fingerprint = restarted.fingerprint;
+    let receipts = restarted.receipts.clone();
+    assert!(receipts.iter().next().is_some());
+    restarted.async_pool = AsyncPool::new(Default::default());
+    restarted.receipts = FinalReceipts::new(restarted.get_config().receipts_retention_periods);
+    let (pos, graph) = resume_local_state(&path, &mut restarted).unwrap();
+    assert_eq!(restarted.fingerprint, fingerprint);
+    assert_eq_final_receipts(&receipts, &restarted.receipts);
+    assert_eq_thread_cycle_states(&sent_pos, &pos);
+    assert_eq_bootstrap_graph(&sent_graph, &graph);
+
+    // a final state attached to another slot cannot resume from the saved state
+    restarted.async_pool = AsyncPool::new(Default::default());
+    restarted.receipts.reset();
+    restarted.slot = Slot::new(restarted.slot.period.wrapping_add(1), restarted.slot.thread);
+    assert!(resume_local_state(&path, &mut restarted).is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_local_state_after_non_clean_stop() {
+    // init the serialization context
+    get_bootstrap_config(KeyPair::generate().get_public_key());
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("consensus_state.bin");
+    let final_state = Arc::new(RwLock::new(get_random_final_state_bootstrap(2)));
+    let (sent_pos, sent_graph) = get_boot_state();
+    save_local_state_on_shutdown(&path, &final_state, sent_pos.clone(), sent_graph.clone()).await;
+
+    // the node is killed while saving its state again on shutdown, before any slot became final:
+    // the partially written state is ignored and the node resumes from the previous save
+    std::fs::write(path.with_extension("tmp"), [0u8; 10]).unwrap();
+    let mut restarted = final_state.write();
+    let fingerprint = restarted.fingerprint;
+    restarted.async_pool = AsyncPool::new(Default::default());
+    restarted.receipts = FinalReceipts::new(restarted.get_config().receipts_retention_periods);
+    let (pos, graph) = resume_local_state(&path, &mut restarted).unwrap();
+    assert_eq!(restarted.fingerprint, fingerprint);
+    assert_eq_thread_cycle_states(&sent_pos, &pos);
+    assert_eq_bootstrap_graph(&sent_graph, &graph);
+
+    // the node then finalizes a slot and is killed without saving its state:
+    // the disk ledger is ahead of the saved state, so the node bootstraps instead of resuming
+    let next_slot = restarted
+        .slot
+        .get_next_slot(restarted.get_config().thread_count)
+        .unwrap();
+    restarted.finalize(next_slot, StateChanges::default());
+    restarted.async_pool = AsyncPool::new(Default::default());
+    restarted.receipts = FinalReceipts::new(restarted.get_config().receipts_retention_periods);
+    assert!(resume_local_state(&path, &mut restarted).is_err());
+}
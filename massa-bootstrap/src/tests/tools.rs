@@ -6,13 +6,14 @@ use bitvec::prelude::*;
 use massa_async_pool::test_exports::{create_async_pool, get_random_message};
 use massa_consensus_exports::commands::ConsensusCommand;
 use massa_final_state::test_exports::create_final_state;
-use massa_final_state::FinalState;
+use massa_final_state::{FinalReceipts, FinalState, FinalStateConfig, ReceiptsChanges};
 use massa_graph::{
     export_active_block::ExportActiveBlock, ledger::ConsensusLedgerSubset, BootstrapableGraph,
 };
 use massa_hash::Hash;
 use massa_ledger_exports::LedgerEntry;
 use massa_ledger_worker::test_exports::create_final_ledger;
+use massa_models::execution::OperationReceipt;
 use massa_models::operation::OperationSerializer;
 use massa_models::wrapped::WrappedContent;
 use massa_models::{
@@ -20,7 +21,7 @@ use massa_models::{
     ledger_models::{LedgerChange, LedgerChanges, LedgerData},
     rolls::{RollCounts, RollUpdate, RollUpdateSerializer, RollUpdates},
     Address, Amount, Block, BlockHeader, BlockHeaderSerializer, BlockId, DeserializeCompact,
-    Endorsement, Operation, SerializeCompact, Slot,
+    Endorsement, Operation, OperationId, SerializeCompact, Slot,
 };
 use massa_models::{BlockSerializer, EndorsementSerializer};
 use massa_network_exports::{BootstrapPeers, NetworkCommand};
@@ -70,6 +71,32 @@ fn get_random_ledger_entry() -> LedgerEntry {
     }
 }
 
+/// generates random final receipts of operations executed at a slot
+pub fn get_random_final_receipts(config: &FinalStateConfig, slot: Slot) -> FinalReceipts {
+    let mut rng = rand::thread_rng();
+    let mut changes = ReceiptsChanges::default();
+    for _ in 0usize..rng.gen_range(3..10) {
+        let success = rng.gen::<bool>();
+        changes.0.insert(
+            OperationId::from_bytes(&rng.gen()),
+            OperationReceipt {
+                slot,
+                success,
+                error: (!success).then(|| "random error".to_string()),
+                gas_used: rng.gen(),
+                gas_charged: rng.gen(),
+                refunded_coins: Amount::from_raw(rng.gen()),
+                event_count: rng.gen_range(0..10),
+                storage_cost_charged: Amount::from_raw(rng.gen()),
+                storage_cost_refunded: Amount::from_raw(rng.gen()),
+            },
+        );
+    }
+    let mut receipts = FinalReceipts::new(config.receipts_retention_periods);
+    receipts.apply_changes(&changes, slot);
+    receipts
+}
+
 /// generates a random bootstrap state for the final state
 pub fn get_random_final_state_bootstrap(thread_count: u8) -> FinalState {
     let mut rng = rand::thread_rng();
@@ -87,11 +114,14 @@ pub fn get_random_final_state_bootstrap(thread_count: u8) -> FinalState {
     let slot = Slot::new(rng.gen::<u64>(), rng.gen_range(0..thread_count));
     let final_ledger = create_final_ledger(Some(sorted_ledger), Default::default());
     let async_pool = create_async_pool(Default::default(), messages);
+    let config = FinalStateConfig::default();
+    let receipts = get_random_final_receipts(&config, slot);
     create_final_state(
-        Default::default(),
+        config,
         slot,
         Box::new(final_ledger),
         async_pool,
+        receipts,
         VecDeque::new(),
     )
 }
@@ -153,6 +183,7 @@ pub fn get_bootstrap_config(bootstrap_public_key: PublicKey) -> BootstrapSetting
         ip_list_max_size: 10,
         per_ip_min_interval: 10000.into(),
         max_bytes_read_write: std::f64::INFINITY,
+        local_state_path: None,
     }
 }
 
@@ -255,6 +286,14 @@ pub fn assert_eq_thread_cycle_states(v1: &ExportProofOfStake, v2: &ExportProofOf
                 itm1.production_stats, itm2.production_stats,
                 "ThreadCycleState.production_stats mismatch between sent and received pos"
             );
+            assert_eq!(
+                itm1.missed_blocks, itm2.missed_blocks,
+                "ThreadCycleState.missed_blocks mismatch between sent and received pos"
+            );
+            assert_eq!(
+                itm1.missed_endorsements, itm2.missed_endorsements,
+                "ThreadCycleState.missed_endorsements mismatch between sent and received pos"
+            );
         }
     }
 }
@@ -386,6 +425,15 @@ pub fn get_boot_state() -> (ExportProofOfStake, BootstrapableGraph) {
         ]
         .into_iter()
         .collect(),
+        missed_blocks: vec![(get_random_address(), vec![Slot::new(1, 1)])]
+            .into_iter()
+            .collect(),
+        missed_endorsements: vec![(
+            get_random_address(),
+            vec![(Slot::new(0, 1), 2), (Slot::new(1, 1), 0)],
+        )]
+        .into_iter()
+        .collect(),
     };
     let boot_pos = ExportProofOfStake {
         cycle_states: vec![
@@ -4,9 +4,12 @@ use crate::repl::Output;
 use anyhow::{anyhow, bail, Result};
 use console::style;
 use massa_models::api::{
-    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, OperationInput,
+    AddressHistoryInput, AddressInfo, AsyncMessageFilter, CompactAddressInfo, CreatorBlocksInput,
+    DatastoreEntryInput, DatastoreKeysInput, EventFilter, OperationInput,
 };
-use massa_models::api::{ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::api::{GasEstimationRequest, ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::constants::MAX_GAS_PER_BLOCK;
+use massa_models::execution::ReadOnlyResult;
 use massa_models::node::NodeId;
 use massa_models::prehash::Map;
 use massa_models::timeslots::get_current_latest_block_slot;
@@ -121,6 +124,29 @@ pub enum Command {
     )]
     get_addresses,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(
+            args = "Address cursor=Slot,index_in_block limit=usize op_type=Transaction|RollBuy|RollSell|ExecuteSC|CallSC"
+        ),
+        message = "list a page of the final operations involving an address (requires the block archive on the node)"
+    )]
+    get_address_history,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address cursor=Slot,BlockId limit=usize"),
+        message = "list a page of the blocks created by an address"
+    )]
+    get_blocks_by_creator,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address1 Address2 ... cycle=u64"),
+        message = "get every block and endorsement slot the addresses are selected for, from a cycle (the current one by default) to the last known one"
+    )]
+    get_schedule,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address Key"),
@@ -128,6 +154,20 @@ pub enum Command {
     )]
     get_datastore_entry,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address prefix=String cursor=String limit=usize"),
+        message = "list a page of the datastore keys of an address (prefix and cursor must be UTF-8)"
+    )]
+    get_datastore_keys,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address1 Address2 ..."),
+        message = "show the bytecode versions of a list of addresses"
+    )]
+    get_bytecode_history,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "BlockId"),
@@ -149,15 +189,29 @@ pub enum Command {
     )]
     get_operations,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "OperationId1 OperationId2 ..."),
+        message = "show the execution receipts (success, error, gas used ...) of a list of operations"
+    )]
+    get_operation_receipts,
+
     #[strum(
         ascii_case_insensitive,
         props(
-            args = "start=Slot end=Slot emitter_address=Address caller_address=Address operation_id=OperationId"
+            args = "start=Slot end=Slot emitter_address=Address caller_address=Address operation_id=OperationId is_final=bool data_substring=String data_prefix=String cursor=Slot,index_in_slot limit=usize"
         ),
         message = "show events emitted by smart contracts with various filters"
     )]
     get_filtered_sc_output_event,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "sender=Address destination=Address start=Slot end=Slot"),
+        message = "show the pending asynchronous messages, filtered by sender, destination or emission slot range"
+    )]
+    get_async_messages,
+
     #[strum(
         ascii_case_insensitive,
         message = "show wallet info (keys, addresses, balances ...)"
@@ -214,17 +268,17 @@ pub enum Command {
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "SenderAddress PathToBytecode MaxGas GasPrice Coins Fee",),
-        message = "create and send an operation containing byte code"
+        props(args = "SenderAddress PathToBytecode [MaxGas] GasPrice Coins Fee",),
+        message = "create and send an operation containing byte code, MaxGas is estimated by the node if omitted"
     )]
     send_smart_contract,
 
     #[strum(
         ascii_case_insensitive,
         props(
-            args = "SenderAddress TargetAddress FunctionName Parameter MaxGas GasPrice Coins Fee",
+            args = "SenderAddress TargetAddress FunctionName Parameter [MaxGas] GasPrice Coins Fee",
         ),
-        message = "create and send an operation to call a function of a smart contract"
+        message = "create and send an operation to call a function of a smart contract, MaxGas is estimated by the node if omitted"
     )]
     call_smart_contract,
 
@@ -532,6 +586,77 @@ impl Command {
                 }
             }
 
+            Command::get_address_history => {
+                if parameters.is_empty() {
+                    bail!("wrong param numbers")
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let p_list: [&str; 3] = ["cursor", "limit", "op_type"];
+                let mut p: HashMap<&str, &str> = HashMap::new();
+                for v in &parameters[1..] {
+                    let s: Vec<&str> = v.splitn(2, '=').collect();
+                    if s.len() == 2 && p_list.contains(&s[0]) {
+                        p.insert(s[0], s[1]);
+                    } else {
+                        bail!("invalid parameter");
+                    }
+                }
+                let input = AddressHistoryInput {
+                    address,
+                    cursor: parse_value(&p, p_list[0]),
+                    limit: parse_value(&p, p_list[1]),
+                    op_type: parse_value(&p, p_list[2]),
+                };
+                match client.public.get_address_history(input).await {
+                    Ok(page) => Ok(Box::new(page)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_blocks_by_creator => {
+                if parameters.is_empty() {
+                    bail!("wrong param numbers")
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let p_list: [&str; 2] = ["cursor", "limit"];
+                let mut p: HashMap<&str, &str> = HashMap::new();
+                for v in &parameters[1..] {
+                    let s: Vec<&str> = v.splitn(2, '=').collect();
+                    if s.len() == 2 && p_list.contains(&s[0]) {
+                        p.insert(s[0], s[1]);
+                    } else {
+                        bail!("invalid parameter");
+                    }
+                }
+                let input = CreatorBlocksInput {
+                    address,
+                    cursor: parse_value(&p, p_list[0]),
+                    limit: parse_value(&p, p_list[1]),
+                };
+                match client.public.get_blocks_by_creator(input).await {
+                    Ok(page) => Ok(Box::new(page)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_schedule => {
+                let mut addresses = Vec::new();
+                let mut cycle = None;
+                for v in parameters {
+                    match v.strip_prefix("cycle=") {
+                        Some(c) => cycle = Some(c.parse::<u64>()?),
+                        None => addresses.push(v.parse::<Address>()?),
+                    }
+                }
+                if addresses.is_empty() {
+                    bail!("wrong param numbers")
+                }
+                match client.public.get_selection_schedule(addresses, cycle).await {
+                    Ok(schedules) => Ok(Box::new(schedules)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_datastore_entry => {
                 if parameters.len() != 2 {
                     bail!("invalid number of parameters");
@@ -548,6 +673,41 @@ impl Command {
                 }
             }
 
+            Command::get_datastore_keys => {
+                if parameters.is_empty() {
+                    bail!("wrong param numbers")
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let p_list: [&str; 3] = ["prefix", "cursor", "limit"];
+                let mut p: HashMap<&str, &str> = HashMap::new();
+                for v in &parameters[1..] {
+                    let s: Vec<&str> = v.splitn(2, '=').collect();
+                    if s.len() == 2 && p_list.contains(&s[0]) {
+                        p.insert(s[0], s[1]);
+                    } else {
+                        bail!("invalid parameter");
+                    }
+                }
+                let input = DatastoreKeysInput {
+                    address,
+                    prefix: p.get(p_list[0]).map(|prefix| prefix.as_bytes().to_vec()),
+                    cursor: p.get(p_list[1]).map(|cursor| cursor.as_bytes().to_vec()),
+                    limit: parse_value(&p, p_list[2]),
+                };
+                match client.public.get_datastore_keys(input).await {
+                    Ok(page) => Ok(Box::new(page)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_bytecode_history => {
+                let addresses = parse_vec::<Address>(parameters)?;
+                match client.public.get_bytecode_history(addresses).await {
+                    Ok(histories) => Ok(Box::new(histories)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_block => {
                 if parameters.len() != 1 {
                     bail!("wrong param numbers")
@@ -575,17 +735,31 @@ impl Command {
                 }
             }
 
+            Command::get_operation_receipts => {
+                let operations = parse_vec::<OperationId>(parameters)?;
+                match client.public.get_operation_receipts(operations).await {
+                    Ok(receipts) => Ok(Box::new(receipts)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_filtered_sc_output_event => {
-                let p_list: [&str; 5] = [
+                let p_list: [&str; 10] = [
                     "start",
                     "end",
                     "emitter_address",
                     "caller_address",
                     "operation_id",
+                    "is_final",
+                    "data_substring",
+                    "data_prefix",
+                    "cursor",
+                    "limit",
                 ];
                 let mut p: HashMap<&str, &str> = HashMap::new();
                 for v in parameters {
-                    let s: Vec<&str> = v.split('=').collect();
+                    // the matched data may itself contain '='
+                    let s: Vec<&str> = v.splitn(2, '=').collect();
                     if s.len() == 2 && p_list.contains(&s[0]) {
                         p.insert(s[0], s[1]);
                     } else {
@@ -598,6 +772,11 @@ impl Command {
                     emitter_address: parse_value(&p, p_list[2]),
                     original_caller_address: parse_value(&p, p_list[3]),
                     original_operation_id: parse_value(&p, p_list[4]),
+                    is_final: parse_value(&p, p_list[5]),
+                    data_substring: parse_value(&p, p_list[6]),
+                    data_prefix: parse_value(&p, p_list[7]),
+                    cursor: parse_value(&p, p_list[8]),
+                    limit: parse_value(&p, p_list[9]),
                 };
                 match client.public.get_filtered_sc_output_event(filter).await {
                     Ok(events) => Ok(Box::new(events)),
@@ -605,6 +784,29 @@ impl Command {
                 }
             }
 
+            Command::get_async_messages => {
+                let p_list: [&str; 4] = ["sender", "destination", "start", "end"];
+                let mut p: HashMap<&str, &str> = HashMap::new();
+                for v in parameters {
+                    let s: Vec<&str> = v.split('=').collect();
+                    if s.len() == 2 && p_list.contains(&s[0]) {
+                        p.insert(s[0], s[1]);
+                    } else {
+                        bail!("invalid parameter");
+                    }
+                }
+                let filter = AsyncMessageFilter {
+                    sender: parse_value(&p, p_list[0]),
+                    destination: parse_value(&p, p_list[1]),
+                    start: parse_value(&p, p_list[2]),
+                    end: parse_value(&p, p_list[3]),
+                };
+                match client.public.get_async_messages(filter).await {
+                    Ok(messages) => Ok(Box::new(messages)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::wallet_info => {
                 if !json {
                     client_warning!("do not share your key");
@@ -838,15 +1040,34 @@ impl Command {
                 Ok(Box::new(()))
             }
             Command::send_smart_contract => {
-                if parameters.len() != 6 {
+                if parameters.len() != 5 && parameters.len() != 6 {
                     bail!("wrong number of parameters");
                 }
+                // MaxGas is the only optional parameter
+                let with_max_gas = parameters.len() == 6;
+                let offset = with_max_gas as usize;
                 let addr = parameters[0].parse::<Address>()?;
                 let path = parameters[1].parse::<PathBuf>()?;
-                let max_gas = parameters[2].parse::<u64>()?;
-                let gas_price = parameters[3].parse::<Amount>()?;
-                let coins = parameters[4].parse::<Amount>()?;
-                let fee = parameters[5].parse::<Amount>()?;
+                let gas_price = parameters[2 + offset].parse::<Amount>()?;
+                let coins = parameters[3 + offset].parse::<Amount>()?;
+                let fee = parameters[4 + offset].parse::<Amount>()?;
+                let data = get_file_as_byte_vec(&path).await?;
+                let max_gas = if with_max_gas {
+                    parameters[2].parse::<u64>()?
+                } else {
+                    estimate_max_gas(
+                        client,
+                        addr,
+                        OperationType::ExecuteSC {
+                            data: data.clone(),
+                            max_gas: MAX_GAS_PER_BLOCK,
+                            coins,
+                            gas_price,
+                        },
+                        json,
+                    )
+                    .await?
+                };
 
                 if !json {
                     match gas_price
@@ -875,7 +1096,6 @@ impl Command {
                         }
                     }
                 };
-                let data = get_file_as_byte_vec(&path).await?;
                 if !json {
                     let max_block_size = match client.public.get_status().await {
                         Ok(node_status) => node_status.config.max_block_size,
@@ -902,17 +1122,38 @@ impl Command {
                 .await
             }
             Command::call_smart_contract => {
-                if parameters.len() != 8 {
+                if parameters.len() != 7 && parameters.len() != 8 {
                     bail!("wrong number of parameters");
                 }
+                // MaxGas is the only optional parameter
+                let with_max_gas = parameters.len() == 8;
+                let offset = with_max_gas as usize;
                 let addr = parameters[0].parse::<Address>()?;
                 let target_addr = parameters[1].parse::<Address>()?;
                 let target_func = parameters[2].clone();
                 let param = parameters[3].clone();
-                let max_gas = parameters[4].parse::<u64>()?;
-                let gas_price = parameters[5].parse::<Amount>()?;
-                let coins = parameters[6].parse::<Amount>()?;
-                let fee = parameters[7].parse::<Amount>()?;
+                let gas_price = parameters[4 + offset].parse::<Amount>()?;
+                let coins = parameters[5 + offset].parse::<Amount>()?;
+                let fee = parameters[6 + offset].parse::<Amount>()?;
+                let max_gas = if with_max_gas {
+                    parameters[4].parse::<u64>()?
+                } else {
+                    estimate_max_gas(
+                        client,
+                        addr,
+                        OperationType::CallSC {
+                            target_addr,
+                            target_func: target_func.clone(),
+                            param: param.clone(),
+                            max_gas: MAX_GAS_PER_BLOCK,
+                            sequential_coins: Amount::zero(),
+                            parallel_coins: coins,
+                            gas_price,
+                        },
+                        json,
+                    )
+                    .await?
+                };
                 if !json {
                     match gas_price
                         .checked_mul_u64(max_gas)
@@ -996,6 +1237,7 @@ impl Command {
                         simulated_gas_price,
                         bytecode,
                         address,
+                        trace: false,
                     })
                     .await
                 {
@@ -1027,6 +1269,7 @@ impl Command {
                         parameter,
                         max_gas,
                         simulated_gas_price,
+                        trace: false,
                     })
                     .await
                 {
@@ -1112,6 +1355,42 @@ async fn send_operation(
     }
 }
 
+/// helper to estimate the gas needed by an `ExecuteSC` or `CallSC` operation,
+/// including the base cost of an operation reported by the node,
+/// with a 10% margin since the state may change before the operation is executed
+async fn estimate_max_gas(
+    client: &Client,
+    addr: Address,
+    op: OperationType,
+    json: bool,
+) -> Result<u64> {
+    let estimation = match client
+        .public
+        .estimate_gas(GasEstimationRequest {
+            caller_address: addr,
+            operation: op,
+        })
+        .await
+    {
+        Ok(estimation) => estimation,
+        Err(e) => rpc_error!(e),
+    };
+    if let ReadOnlyResult::Error(err) = estimation.result {
+        bail!("gas estimation failed: {}", err);
+    }
+    let max_gas = std::cmp::min(
+        estimation.gas_cost.saturating_add(estimation.gas_cost / 10),
+        MAX_GAS_PER_BLOCK,
+    );
+    if !json {
+        println!(
+            "Estimated gas cost: {}, using MaxGas {}",
+            estimation.gas_cost, max_gas
+        );
+    }
+    Ok(max_gas)
+}
+
 /// TODO: ugly utilities functions
 /// takes a slice of string and makes it into a `Vec<T>`
 pub fn parse_vec<T: std::str::FromStr>(args: &[String]) -> anyhow::Result<Vec<T>, T::Err> {
@@ -8,7 +8,9 @@ use dialoguer::{theme::ColorfulTheme, Completion, History, Input};
 use erased_serde::{Serialize, Serializer};
 use glob::glob;
 use massa_models::api::{
-    AddressInfo, BlockInfo, DatastoreEntryOutput, EndorsementInfo, NodeStatus, OperationInfo,
+    AddressHistoryOutput, AddressInfo, AsyncMessageInfo, BlockInfo, BytecodeHistoryOutput,
+    CreatorBlocksOutput, DatastoreEntryOutput, DatastoreKeysOutput, EndorsementInfo, NodeStatus,
+    OperationInfo, OperationReceiptOutput, SelectionSchedule,
 };
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
@@ -274,6 +276,40 @@ impl Output for Vec<DatastoreEntryOutput> {
     }
 }
 
+impl Output for Vec<BytecodeHistoryOutput> {
+    fn pretty_print(&self) {
+        for history in self {
+            println!("{}", history);
+        }
+    }
+}
+
+impl Output for AddressHistoryOutput {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for CreatorBlocksOutput {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for Vec<SelectionSchedule> {
+    fn pretty_print(&self) {
+        for schedule in self {
+            println!("{}", schedule);
+        }
+    }
+}
+
+impl Output for DatastoreKeysOutput {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
 impl Output for Vec<EndorsementInfo> {
     fn pretty_print(&self) {
         for endorsement_info in self {
@@ -290,6 +326,14 @@ impl Output for Vec<OperationInfo> {
     }
 }
 
+impl Output for Vec<OperationReceiptOutput> {
+    fn pretty_print(&self) {
+        for receipt in self {
+            println!("{}", receipt);
+        }
+    }
+}
+
 impl Output for Vec<OperationId> {
     fn pretty_print(&self) {
         for operation_id in self {
@@ -314,6 +358,14 @@ impl Output for Vec<SCOutputEvent> {
     }
 }
 
+impl Output for Vec<AsyncMessageInfo> {
+    fn pretty_print(&self) {
+        for msg in self {
+            println!("{}", msg);
+        }
+    }
+}
+
 impl Output for PubkeySig {
     fn pretty_print(&self) {
         println!("{}", self);
@@ -3,6 +3,10 @@
 //! Contains definitions of commands used by the controller
 use massa_graph::ledger::ConsensusLedgerSubset;
 use massa_graph::{BlockGraphExport, BootstrapableGraph, ExportBlockStatus, Status};
+use massa_models::api::{
+    AddressHistoryInput, AddressHistoryOutput, CreatorBlocksInput, CreatorBlocksOutput,
+    SelectionSchedule, StakerMisbehaviors,
+};
 use massa_models::{address::AddressState, api::EndorsementInfo, EndorsementId, OperationId};
 use massa_models::{clique::Clique, stats::ConsensusStats};
 use massa_models::{
@@ -45,6 +49,15 @@ pub enum ConsensusCommand {
         /// response channel
         response_tx: oneshot::Sender<Result<SelectionDraws, ConsensusError>>,
     },
+    /// Returns through a channel every slot the addresses are selected for, from a cycle to the last known one.
+    GetSelectionSchedule {
+        /// addresses to get the schedule of
+        addresses: Vec<Address>,
+        /// first cycle of the schedule, the current one if `None`
+        start_cycle: Option<u64>,
+        /// response channel
+        response_tx: oneshot::Sender<Result<Vec<SelectionSchedule>, ConsensusError>>,
+    },
     /// Returns the bootstrap state
     GetBootstrapState(oneshot::Sender<(ExportProofOfStake, BootstrapableGraph)>),
     /// Returns a part of the ledger
@@ -94,6 +107,13 @@ pub enum ConsensusCommand {
         /// response channel
         response_tx: oneshot::Sender<Vec<StakersCycleProductionStats>>,
     },
+    /// Get the missed slots and equivocations of stakers
+    GetStakersMisbehaviors {
+        /// wanted addresses
+        addrs: Vec<Address>,
+        /// response channel
+        response_tx: oneshot::Sender<Vec<StakerMisbehaviors>>,
+    },
     /// Get block id and status by block creator address
     GetBlockIdsByCreator {
         /// wanted address
@@ -101,6 +121,20 @@ pub enum ConsensusCommand {
         /// response channel
         response_tx: oneshot::Sender<Map<BlockId, Status>>,
     },
+    /// Get a page of the blocks created by an address
+    GetBlocksByCreator {
+        /// page query
+        input: CreatorBlocksInput,
+        /// response channel
+        response_tx: oneshot::Sender<CreatorBlocksOutput>,
+    },
+    /// Get a page of the final operations involving an address
+    GetAddressHistory {
+        /// page query
+        input: AddressHistoryInput,
+        /// response channel, `None` if the address history is not indexed
+        response_tx: oneshot::Sender<Option<AddressHistoryOutput>>,
+    },
     /// Get Endorsements by involved addresses
     GetEndorsementsByAddress {
         /// wanted address
@@ -1,6 +1,10 @@
 use massa_graph::ledger::ConsensusLedgerSubset;
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 use massa_graph::{BlockGraphExport, BootstrapableGraph, ExportBlockStatus, Status};
+use massa_models::api::{
+    AddressHistoryInput, AddressHistoryOutput, CreatorBlocksInput, CreatorBlocksOutput,
+    SelectionSchedule, StakerMisbehaviors,
+};
 use massa_models::{address::AddressState, api::EndorsementInfo, EndorsementId, OperationId};
 use massa_models::{clique::Clique, stats::ConsensusStats};
 use massa_models::{
@@ -141,6 +145,34 @@ impl ConsensusCommandSender {
         })?
     }
 
+    /// get every slot the addresses are selected for,
+    /// from `start_cycle` (the current cycle if `None`) to the last cycle whose draws are known
+    pub async fn get_selection_schedule(
+        &self,
+        addresses: Vec<Address>,
+        start_cycle: Option<u64>,
+    ) -> Result<Vec<SelectionSchedule>, ConsensusError> {
+        massa_trace!("consensus.consensus_controller.get_selection_schedule", {});
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(ConsensusCommand::GetSelectionSchedule {
+                addresses,
+                start_cycle,
+                response_tx,
+            })
+            .await
+            .map_err(|_| {
+                ConsensusError::SendChannelError(
+                    "send error consensus command get_selection_schedule".into(),
+                )
+            })?;
+        response_rx.await.map_err(|_| {
+            ConsensusError::ReceiveChannelError(
+                "consensus command get_selection_schedule response read error".to_string(),
+            )
+        })?
+    }
+
     /// get bootstrap snapshot
     pub async fn get_bootstrap_state(
         &self,
@@ -216,6 +248,51 @@ impl ConsensusCommandSender {
         })
     }
 
+    /// get a page of the blocks created by an address
+    pub async fn get_blocks_by_creator(
+        &self,
+        input: CreatorBlocksInput,
+    ) -> Result<CreatorBlocksOutput, ConsensusError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        massa_trace!("consensus.consensus_controller.get_blocks_by_creator", {});
+        self.0
+            .send(ConsensusCommand::GetBlocksByCreator { input, response_tx })
+            .await
+            .map_err(|_| {
+                ConsensusError::SendChannelError(
+                    "send error consensus command get_blocks_by_creator".into(),
+                )
+            })?;
+        response_rx.await.map_err(|_| {
+            ConsensusError::ReceiveChannelError(
+                "consensus command get_blocks_by_creator response read error".to_string(),
+            )
+        })
+    }
+
+    /// get a page of the final operations involving an address,
+    /// `None` if the address history is not indexed by this node
+    pub async fn get_address_history(
+        &self,
+        input: AddressHistoryInput,
+    ) -> Result<Option<AddressHistoryOutput>, ConsensusError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        massa_trace!("consensus.consensus_controller.get_address_history", {});
+        self.0
+            .send(ConsensusCommand::GetAddressHistory { input, response_tx })
+            .await
+            .map_err(|_| {
+                ConsensusError::SendChannelError(
+                    "send error consensus command get_address_history".into(),
+                )
+            })?;
+        response_rx.await.map_err(|_| {
+            ConsensusError::ReceiveChannelError(
+                "consensus command get_address_history response read error".to_string(),
+            )
+        })
+    }
+
     /// get operation info by operation id
     pub async fn get_operations(
         &self,
@@ -409,6 +486,29 @@ impl ConsensusCommandSender {
         })
     }
 
+    /// get the missed slots and equivocations of stakers
+    pub async fn get_stakers_misbehaviors(
+        &self,
+        addrs: Vec<Address>,
+    ) -> Result<Vec<StakerMisbehaviors>, ConsensusError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        massa_trace!("consensus.consensus_controller.get_stakers_misbehaviors", {
+        });
+        self.0
+            .send(ConsensusCommand::GetStakersMisbehaviors { addrs, response_tx })
+            .await
+            .map_err(|_| {
+                ConsensusError::SendChannelError(
+                    "send error consensus command get_stakers_misbehaviors".to_string(),
+                )
+            })?;
+        response_rx.await.map_err(|_| {
+            ConsensusError::ReceiveChannelError(
+                "consensus command get_stakers_misbehaviors response read error".to_string(),
+            )
+        })
+    }
+
     /// get endorsements info by involved address
     pub async fn get_endorsements_by_address(
         &self,
@@ -1,6 +1,23 @@
+use massa_models::{
+    api::{BlockSummary, OperationStatus},
+    OperationId,
+};
+
 /// Events that are emitted by consensus.
 #[derive(Debug, Clone)]
 pub enum ConsensusEvent {
     /// probable desynchronization detected, need re-synchronization
     NeedSync,
 }
+
+/// Events that are broadcast by consensus to every subscriber (e.g. the websocket API).
+/// Sending them never blocks consensus: lagging subscribers miss events.
+#[derive(Debug, Clone)]
+pub enum ConsensusBroadcastEvent {
+    /// a new block was integrated in the graph
+    NewBlock(BlockSummary),
+    /// a block became final
+    FinalizedBlock(BlockSummary),
+    /// the status of an operation changed
+    OperationStatus(OperationId, OperationStatus),
+}
@@ -44,6 +44,7 @@
 //! In unit test your allowed to use the `testing` feature flag that will
 //! use the default values from `/node_configuration/default_testing.rs` in the
 //! `massa-models` crate sources.
+use crate::events::ConsensusBroadcastEvent;
 use massa_execution_exports::ExecutionController;
 use massa_graph::{settings::GraphConfig, LedgerConfig};
 use massa_models::Amount;
@@ -55,7 +56,7 @@ use massa_time::MassaTime;
 use num::rational::Ratio;
 use serde::{Deserialize, Serialize};
 use std::{default::Default, path::PathBuf, usize};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 
 use crate::{
     commands::{ConsensusCommand, ConsensusManagementCommand},
@@ -100,6 +101,8 @@ pub struct ConsensusSettings {
     pub max_send_wait: MassaTime,
     /// force keep at least this number of final periods in RAM for each thread
     pub force_keep_final_periods: u64,
+    /// path to the on-disk archive of final blocks, no archive if None
+    pub block_archive_path: Option<PathBuf>,
     /// old blocks are pruned every `block_db_prune_interval`
     pub block_db_prune_interval: MassaTime,
     /// max number of items returned while querying
@@ -156,6 +159,8 @@ pub struct ConsensusConfig {
     pub pos_draw_cached_cycles: usize,
     /// number of cycle misses (strictly) above which stakers are deactivated
     pub pos_miss_rate_deactivation_threshold: Ratio<u64>,
+    /// number of endorsements missed in a cycle (strictly) above which stakers are deactivated, `None` to disable
+    pub pos_missed_endorsements_deactivation_threshold: Option<u64>,
     /// path to ledger db after initialization (merge with `initial_ledger_path` on node start)
     pub ledger_path: PathBuf,
     /// Cache capacity allowed to the ledger
@@ -182,6 +187,8 @@ pub struct ConsensusConfig {
     pub max_send_wait: MassaTime,
     /// force keep at least this number of final periods in RAM for each thread
     pub force_keep_final_periods: u64,
+    /// path to the on-disk archive of final blocks, no archive if None
+    pub block_archive_path: Option<PathBuf>,
     /// target number of endorsement per block
     pub endorsement_count: u32,
     /// old blocks are pruned every `block_db_prune_interval`
@@ -234,6 +241,8 @@ impl Clone for ConsensusConfig {
             pos_lock_cycles: self.pos_lock_cycles,
             pos_draw_cached_cycles: self.pos_draw_cached_cycles,
             pos_miss_rate_deactivation_threshold: self.pos_miss_rate_deactivation_threshold,
+            pos_missed_endorsements_deactivation_threshold: self
+                .pos_missed_endorsements_deactivation_threshold,
             ledger_path: self.ledger_path.clone(),
             ledger_cache_capacity: self.ledger_cache_capacity,
             ledger_flush_interval: self.ledger_flush_interval,
@@ -247,6 +256,7 @@ impl Clone for ConsensusConfig {
             stats_timespan: self.stats_timespan,
             max_send_wait: self.max_send_wait,
             force_keep_final_periods: self.force_keep_final_periods,
+            block_archive_path: self.block_archive_path.clone(),
             endorsement_count: self.endorsement_count,
             block_db_prune_interval: self.block_db_prune_interval,
             max_item_return_count: self.max_item_return_count,
@@ -272,6 +282,7 @@ impl From<&ConsensusConfig> for GraphConfig {
             block_reward: cfg.block_reward,
             roll_price: cfg.roll_price,
             force_keep_final_periods: cfg.force_keep_final_periods,
+            block_archive_path: cfg.block_archive_path.clone(),
             endorsement_count: cfg.endorsement_count,
             max_item_return_count: cfg.max_item_return_count,
             ledger_path: cfg.ledger_path.clone(),
@@ -291,6 +302,8 @@ impl From<&ConsensusConfig> for ProofOfStakeConfig {
             pos_lock_cycles: cfg.pos_lock_cycles,
             pos_draw_cached_cycles: cfg.pos_draw_cached_cycles,
             pos_miss_rate_deactivation_threshold: cfg.pos_miss_rate_deactivation_threshold,
+            pos_missed_endorsements_deactivation_threshold: cfg
+                .pos_missed_endorsements_deactivation_threshold,
             initial_rolls_path: cfg.initial_rolls_path.clone(),
             initial_draw_seed: cfg.initial_draw_seed.clone(),
             roll_price: cfg.roll_price,
@@ -330,6 +343,8 @@ pub struct ConsensusWorkerChannels {
     pub controller_event_tx: mpsc::Sender<ConsensusEvent>,
     /// Channel receiving consensus management commands.
     pub controller_manager_rx: mpsc::Receiver<ConsensusManagementCommand>,
+    /// Channel broadcasting block and operation events to subscribers.
+    pub broadcast_event_sender: broadcast::Sender<ConsensusBroadcastEvent>,
 }
 
 /// Public channels associated to the consensus module.
@@ -343,6 +358,8 @@ pub struct ConsensusChannels {
     pub protocol_event_receiver: ProtocolEventReceiver,
     /// outgoing link to pool component
     pub pool_command_sender: PoolCommandSender,
+    /// broadcast link to the subscribers of block and operation events
+    pub broadcast_event_sender: broadcast::Sender<ConsensusBroadcastEvent>,
 }
 
 impl From<&ConsensusSettings> for ConsensusConfig {
@@ -376,6 +393,8 @@ impl From<&ConsensusSettings> for ConsensusConfig {
             pos_lock_cycles: POS_LOCK_CYCLES,
             pos_draw_cached_cycles: settings.pos_draw_cached_cycles,
             pos_miss_rate_deactivation_threshold: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+            pos_missed_endorsements_deactivation_threshold:
+                POS_MISSED_ENDORSEMENTS_DEACTIVATION_THRESHOLD,
             ledger_path: settings.ledger_path.clone(),
             ledger_cache_capacity: settings.ledger_cache_capacity,
             ledger_flush_interval: settings.ledger_flush_interval,
@@ -389,6 +408,7 @@ impl From<&ConsensusSettings> for ConsensusConfig {
             stats_timespan: settings.stats_timespan,
             max_send_wait: settings.max_send_wait,
             force_keep_final_periods: settings.force_keep_final_periods,
+            block_archive_path: settings.block_archive_path.clone(),
             endorsement_count: ENDORSEMENT_COUNT,
             block_db_prune_interval: settings.block_db_prune_interval,
             max_item_return_count: settings.max_item_return_count,
@@ -429,6 +449,8 @@ impl From<ConsensusSettings> for ConsensusConfig {
             pos_lock_cycles: POS_LOCK_CYCLES,
             pos_draw_cached_cycles: settings.pos_draw_cached_cycles,
             pos_miss_rate_deactivation_threshold: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+            pos_missed_endorsements_deactivation_threshold:
+                POS_MISSED_ENDORSEMENTS_DEACTIVATION_THRESHOLD,
             ledger_path: settings.ledger_path,
             ledger_cache_capacity: settings.ledger_cache_capacity,
             ledger_flush_interval: settings.ledger_flush_interval,
@@ -442,6 +464,7 @@ impl From<ConsensusSettings> for ConsensusConfig {
             stats_timespan: settings.stats_timespan,
             max_send_wait: settings.max_send_wait,
             force_keep_final_periods: settings.force_keep_final_periods,
+            block_archive_path: settings.block_archive_path,
             endorsement_count: ENDORSEMENT_COUNT,
             block_db_prune_interval: settings.block_db_prune_interval,
             max_item_return_count: settings.max_item_return_count,
@@ -481,6 +504,7 @@ impl Default for ConsensusSettings {
             stats_timespan: *STATS_TIMESPAN,
             max_send_wait: *MAX_SEND_WAIT,
             force_keep_final_periods: FORCE_KEEP_FINAL_PERIOD,
+            block_archive_path: None,
             block_db_prune_interval: *BLOCK_DB_PRUNE_INTERVAL,
             max_item_return_count: MAX_ITEM_RETURN_COUNT,
             disable_block_creation: DISABLE_BLOCK_CREATION,
@@ -575,6 +599,8 @@ impl Default for ConsensusConfig {
             pos_lock_cycles: POS_LOCK_CYCLES,
             pos_draw_cached_cycles: POS_DRAW_CACHED_CYCLE,
             pos_miss_rate_deactivation_threshold: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+            pos_missed_endorsements_deactivation_threshold:
+                POS_MISSED_ENDORSEMENTS_DEACTIVATION_THRESHOLD,
             ledger_path: path_buf,
             ledger_cache_capacity: LEDGER_CACHE_CAPACITY,
             ledger_flush_interval: *LEDGER_FLUSH_INTERVAL,
@@ -588,6 +614,7 @@ impl Default for ConsensusConfig {
             stats_timespan: *STATS_TIMESPAN,
             max_send_wait: *MAX_SEND_WAIT,
             force_keep_final_periods: FORCE_KEEP_FINAL_PERIOD,
+            block_archive_path: None,
             endorsement_count: ENDORSEMENT_COUNT,
             block_db_prune_interval: *BLOCK_DB_PRUNE_INTERVAL,
             max_item_return_count: MAX_ITEM_RETURN_COUNT,
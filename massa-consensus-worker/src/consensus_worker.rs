@@ -3,7 +3,7 @@ use massa_cipher::encrypt;
 use massa_consensus_exports::{
     commands::ConsensusCommand,
     error::{ConsensusError, ConsensusResult as Result},
-    events::ConsensusEvent,
+    events::{ConsensusBroadcastEvent, ConsensusEvent},
     settings::ConsensusWorkerChannels,
     ConsensusConfig,
 };
@@ -15,7 +15,7 @@ use massa_models::{
     OperationId, WrappedBlock,
 };
 use massa_models::{
-    api::{LedgerInfo, RollsInfo},
+    api::{BlockSummary, LedgerInfo, OperationStatus, RollsInfo},
     WrappedEndorsement,
 };
 use massa_models::{ledger_models::LedgerData, WrappedOperation};
@@ -786,6 +786,32 @@ impl ConsensusWorker {
                 }
                 Ok(())
             }
+            ConsensusCommand::GetSelectionSchedule {
+                addresses,
+                start_cycle,
+                response_tx,
+            } => {
+                massa_trace!(
+                    "consensus.consensus_worker.process_consensus_command.get_selection_schedule",
+                    {}
+                );
+                let current_cycle = self
+                    .previous_slot
+                    .unwrap_or_else(|| Slot::new(0, 0))
+                    .get_cycle(self.cfg.periods_per_cycle);
+                let result = self
+                    .pos
+                    .get_selection_schedule(
+                        &addresses,
+                        start_cycle.unwrap_or(current_cycle),
+                        current_cycle,
+                    )
+                    .map_err(ConsensusError::from);
+                if response_tx.send(result).is_err() {
+                    warn!("consensus: could not send GetSelectionSchedule response");
+                }
+                Ok(())
+            }
             ConsensusCommand::GetBootstrapState(response_tx) => {
                 massa_trace!(
                     "consensus.consensus_worker.process_consensus_command.get_bootstrap_state",
@@ -919,6 +945,19 @@ impl ConsensusWorker {
                 }
                 Ok(())
             }
+            ConsensusCommand::GetStakersMisbehaviors { addrs, response_tx } => {
+                massa_trace!(
+                    "consensus.consensus_worker.process_consensus_command.get_stakers_misbehaviors",
+                    {}
+                );
+                if response_tx
+                    .send(self.pos.get_stakers_misbehaviors(&addrs))
+                    .is_err()
+                {
+                    warn!("consensus: could not send get stakers misbehaviors response");
+                }
+                Ok(())
+            }
             ConsensusCommand::GetStakersProductionStats { addrs, response_tx } => {
                 massa_trace!(
                     "consensus.consensus_worker.process_consensus_command.get_stakers_production_stats",
@@ -944,6 +983,32 @@ impl ConsensusWorker {
                 }
                 Ok(())
             }
+            ConsensusCommand::GetBlocksByCreator { input, response_tx } => {
+                massa_trace!(
+                    "consensus.consensus_worker.process_consensus_command.get_blocks_by_creator",
+                    {}
+                );
+                if response_tx
+                    .send(self.block_db.get_blocks_by_creator(&input))
+                    .is_err()
+                {
+                    warn!("consensus: could not send get blocks by creator response");
+                }
+                Ok(())
+            }
+            ConsensusCommand::GetAddressHistory { input, response_tx } => {
+                massa_trace!(
+                    "consensus.consensus_worker.process_consensus_command.get_address_history",
+                    {}
+                );
+                if response_tx
+                    .send(self.block_db.get_address_history(&input))
+                    .is_err()
+                {
+                    warn!("consensus: could not send get address history response");
+                }
+                Ok(())
+            }
             ConsensusCommand::GetEndorsementsByAddress {
                 address,
                 response_tx,
@@ -1197,6 +1262,46 @@ impl ConsensusWorker {
         Ok(())
     }
 
+    /// Broadcast a block event, and the matching status update of each of its operations,
+    /// to the subscribers. Having no subscriber is not an error.
+    ///
+    /// # Arguments
+    /// * `block_id`: the new or newly final block
+    /// * `op_ids`: the operations included in the block
+    /// * `is_final`: true if the block just became final, false if it was just integrated
+    fn broadcast_block_event(&self, block_id: &BlockId, op_ids: &Set<OperationId>, is_final: bool) {
+        let sender = &self.channels.broadcast_event_sender;
+        if sender.receiver_count() == 0 {
+            return;
+        }
+        let a_block = match self.block_db.get_active_block(block_id) {
+            Some(a_block) => a_block,
+            None => return,
+        };
+        let summary = BlockSummary {
+            id: *block_id,
+            is_final,
+            is_stale: false,
+            is_in_blockclique: self.block_db.get_blockclique().contains(block_id),
+            slot: a_block.slot,
+            creator: a_block.creator_address,
+            parents: a_block.parents.iter().map(|(id, _)| *id).collect(),
+        };
+        let _ = sender.send(if is_final {
+            ConsensusBroadcastEvent::FinalizedBlock(summary)
+        } else {
+            ConsensusBroadcastEvent::NewBlock(summary)
+        });
+        for op_id in op_ids.iter() {
+            let status = if is_final {
+                OperationStatus::Final(*block_id)
+            } else {
+                OperationStatus::IncludedInBlock(*block_id)
+            };
+            let _ = sender.send(ConsensusBroadcastEvent::OperationStatus(*op_id, status));
+        }
+    }
+
     /// call me if the block database changed
     /// Processing of final blocks, pruning and producing endorsement.
     /// Please refactor me
@@ -1221,6 +1326,7 @@ impl ConsensusWorker {
             massa_trace!("consensus.consensus_worker.block_db_changed.integrated", {
                 "block_id": block_id
             });
+            self.broadcast_block_event(&block_id, &op_ids, false);
             self.channels
                 .protocol_command_sender
                 .integrated_block(block_id, op_ids, endo_ids)
@@ -1278,6 +1384,12 @@ impl ConsensusWorker {
         let timestamp = MassaTime::compensated_now(self.clock_compensation)?;
         for b_id in new_final_block_ids.into_iter() {
             if let Some(a_block) = self.block_db.get_active_block(&b_id) {
+                // notify subscribers
+                self.broadcast_block_event(
+                    &b_id,
+                    &a_block.operation_set.keys().copied().collect(),
+                    true,
+                );
                 // List new final ops
                 new_final_ops.extend(
                     a_block
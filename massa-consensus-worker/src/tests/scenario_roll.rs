@@ -3,7 +3,7 @@
 use massa_consensus_exports::tools;
 use massa_consensus_exports::{settings::ConsensusChannels, tools::TEST_PASSWORD, ConsensusConfig};
 use massa_execution_exports::test_exports::MockExecutionController;
-use massa_models::{prehash::Map, Address, Amount, BlockId, Slot};
+use massa_models::{constants::CHANNEL_SIZE, prehash::Map, Address, Amount, BlockId, Slot};
 use massa_pool::PoolCommand;
 use massa_protocol_exports::ProtocolCommand;
 use massa_storage::Storage;
@@ -531,6 +531,7 @@ async fn test_roll_block_creation() {
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
@@ -814,6 +815,7 @@ async fn test_roll_deactivation() {
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
@@ -962,3 +964,176 @@ async fn test_roll_deactivation() {
         }
     }
 }
+
+#[tokio::test]
+#[serial]
+async fn test_roll_deactivation_for_missed_endorsements() {
+    /*
+        Scenario:
+            * missed endorsements deactivation threshold at 0, miss rate deactivation disabled
+            * thread_count = 4
+            * lookback_cycles = 1
+            * periods_per_cycle = 5
+            * 2 endorsements per block
+            * addresses A0 in thread 0 and A1 in thread 1 have 1 roll initially
+            * all blocks are produced, without endorsements:
+              A0 and A1 miss the endorsements they are drawn for in cycle 0
+            * at the 1st block of thread 0 in cycle 2:
+              * address A0 has 0 candidate rolls
+              * address A1 has 1 candidate roll
+            * at the 1st block of thread 1 in cycle 2:
+              * addresses A0 and A1 have 0 candidate rolls
+    */
+
+    let mut cfg = ConsensusConfig {
+        delta_f0: 2,
+        thread_count: 4,
+        periods_per_cycle: 5,
+        pos_lookback_cycles: 1,
+        t0: 400.into(),
+        endorsement_count: 2,
+        roll_price: Amount::from_mantissa_scale(10, 0),
+        pos_miss_rate_deactivation_threshold: Ratio::new(1, 1),
+        pos_missed_endorsements_deactivation_threshold: Some(0),
+        ..Default::default()
+    };
+    let storage: Storage = Default::default();
+
+    // setup addresses
+    let (address_a0, keypair_a0) = random_address_on_thread(0, cfg.thread_count).into();
+    let (address_a1, keypair_a1) = random_address_on_thread(1, cfg.thread_count).into();
+
+    let initial_ledger_file = tools::generate_ledger_file(&HashMap::new());
+    let staking_keys_file = tools::generate_staking_keys_file(&[]);
+    let initial_rolls_file =
+        tools::generate_default_roll_counts_file(vec![keypair_a0.clone(), keypair_a1.clone()]);
+
+    cfg.initial_ledger_path = initial_ledger_file.path().to_path_buf();
+    cfg.staking_keys_path = staking_keys_file.path().to_path_buf();
+    cfg.initial_rolls_path = initial_rolls_file.path().to_path_buf();
+
+    // mock protocol & pool
+    let (mut protocol_controller, protocol_command_sender, protocol_event_receiver) =
+        MockProtocolController::new();
+    let (mut pool_controller, pool_command_sender) = MockPoolController::new();
+    let (execution_controller, _execution_rx) = MockExecutionController::new_with_receiver();
+
+    cfg.genesis_timestamp = MassaTime::now().unwrap().saturating_add(300.into());
+
+    // launch consensus controller
+    let (consensus_command_sender, _consensus_event_receiver, _consensus_manager) =
+        start_consensus_controller(
+            cfg.clone(),
+            ConsensusChannels {
+                execution_controller,
+                protocol_command_sender: protocol_command_sender.clone(),
+                protocol_event_receiver,
+                pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
+            },
+            None,
+            None,
+            storage,
+            0,
+            TEST_PASSWORD.to_string(),
+            Map::default(),
+        )
+        .await
+        .expect("could not start consensus controller");
+
+    // the endorsements of the slots endorsed by the blocks of cycle 0 are all missed
+    let endorsement_draws = consensus_command_sender
+        .get_selection_draws(Slot::new(1, 0), Slot::new(cfg.periods_per_cycle - 1, 0))
+        .await
+        .unwrap();
+    for address in [address_a0, address_a1] {
+        assert!(
+            endorsement_draws
+                .iter()
+                .any(|(_slot, (_creator, endorsers))| endorsers.contains(&address)),
+            "unlucky seed: address has no endorsement draws in cycle 0, cannot perform test"
+        );
+    }
+
+    let mut cur_slot = Slot::new(0, 0);
+    let mut best_parents = consensus_command_sender
+        .get_block_graph_status(None, None)
+        .await
+        .unwrap()
+        .genesis_blocks;
+    let mut cycle_draws = HashMap::new();
+    let mut draws_cycle = None;
+    'outer: loop {
+        // wait for slot info
+        let latest_slot = pool_controller
+            .wait_command(cfg.t0.checked_mul(2).unwrap(), |cmd| match cmd {
+                PoolCommand::UpdateCurrentSlot(s) => Some(s),
+                _ => None,
+            })
+            .await
+            .expect("timeout while waiting for slot");
+        // apply all slots in-between
+        while cur_slot <= latest_slot {
+            // skip genesis
+            if cur_slot.period == 0 {
+                cur_slot = cur_slot.get_next_slot(cfg.thread_count).unwrap();
+                continue;
+            }
+            let cur_cycle = cur_slot.get_cycle(cfg.periods_per_cycle);
+
+            // get draws
+            if draws_cycle != Some(cur_cycle) {
+                cycle_draws = consensus_command_sender
+                    .get_selection_draws(
+                        Slot::new(std::cmp::max(cur_cycle * cfg.periods_per_cycle, 1), 0),
+                        Slot::new((cur_cycle + 1) * cfg.periods_per_cycle, 0),
+                    )
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(k, (v, _e))| (k, v))
+                    .collect::<HashMap<Slot, Address>>();
+                draws_cycle = Some(cur_cycle);
+            }
+
+            // create and propagate block
+            let creator_privkey = get_creator_for_draw(
+                &cycle_draws[&cur_slot],
+                &vec![keypair_a0.clone(), keypair_a1.clone()],
+            );
+            let block_id = propagate_block(
+                &mut protocol_controller,
+                create_block(&cfg, cur_slot, best_parents.clone(), &creator_privkey),
+                true,
+                500,
+            )
+            .await;
+
+            // update best parents
+            best_parents[cur_slot.thread as usize] = block_id;
+
+            // check candidate rolls
+            let addrs_info = consensus_command_sender
+                .get_addresses_info(vec![address_a0, address_a1].into_iter().collect())
+                .await
+                .unwrap()
+                .clone();
+            if cur_slot.period == (1 + cfg.pos_lookback_cycles) * cfg.periods_per_cycle {
+                if cur_slot.thread == 0 {
+                    assert_eq!(addrs_info[&address_a0].rolls.candidate_rolls, 0);
+                    assert_eq!(addrs_info[&address_a1].rolls.candidate_rolls, 1);
+                } else if cur_slot.thread == 1 {
+                    assert_eq!(addrs_info[&address_a0].rolls.candidate_rolls, 0);
+                    assert_eq!(addrs_info[&address_a1].rolls.candidate_rolls, 0);
+                } else {
+                    break 'outer;
+                }
+            } else {
+                assert_eq!(addrs_info[&address_a0].rolls.candidate_rolls, 1);
+                assert_eq!(addrs_info[&address_a1].rolls.candidate_rolls, 1);
+            }
+
+            cur_slot = cur_slot.get_next_slot(cfg.thread_count).unwrap();
+        }
+    }
+}
@@ -4,7 +4,8 @@ use super::tools;
 use crate::tests::block_factory::BlockFactory;
 use massa_consensus_exports::ConsensusConfig;
 use massa_hash::Hash;
-use massa_models::{BlockId, Slot};
+use massa_models::prehash::Set;
+use massa_models::{Address, BlockId, Slot};
 use massa_signature::KeyPair;
 use serial_test::serial;
 
@@ -188,11 +189,13 @@ async fn test_double_staking_does_not_propagate() {
             block_factory.creator_keypair = staking_keys[0].clone();
             block_factory.slot = Slot::new(1, 0);
             let mut block_1 = block_factory.create_and_receive_block(true).await;
+            let block_1_id = block_1.id;
 
             // Same creator, same slot, different block
             block_1.content.header.content.operation_merkle_root =
                 Hash::compute_from("hello world".as_bytes());
             let block = block_factory.sign_header(block_1.content.header.content);
+            let block_id = block.id;
 
             // Note: currently does propagate, see #190.
             block_factory.receieve_block(true, block).await;
@@ -203,6 +206,24 @@ async fn test_double_staking_does_not_propagate() {
                 .await
                 .expect("could not get block graph status");
             assert_eq!(status.discarded_blocks.len(), 0);
+
+            // The equivocation was recorded.
+            let creator = Address::from_public_key(&staking_keys[0].get_public_key());
+            let misbehaviors = consensus_command_sender
+                .get_stakers_misbehaviors(vec![creator])
+                .await
+                .expect("could not get stakers misbehaviors");
+            assert_eq!(misbehaviors.len(), 1);
+            assert_eq!(misbehaviors[0].equivocations.len(), 1);
+            let equivocation = &misbehaviors[0].equivocations[0];
+            assert_eq!(equivocation.slot, Slot::new(1, 0));
+            assert_eq!(equivocation.address, creator);
+            assert_eq!(
+                equivocation.block_ids,
+                vec![block_1_id, block_id]
+                    .into_iter()
+                    .collect::<Set<BlockId>>()
+            );
             (
                 block_factory.take_protocol_controller(),
                 consensus_command_sender,
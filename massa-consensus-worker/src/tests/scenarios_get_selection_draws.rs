@@ -71,3 +71,76 @@ async fn test_get_selection_draws_high_end_slot() {
     )
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_get_selection_schedule() {
+    init_serialization_context(SerializationContext::default());
+    let mut cfg = ConsensusConfig {
+        periods_per_cycle: 2,
+        t0: 500.into(),
+        delta_f0: 3,
+        block_reward: Amount::default(),
+        roll_price: Amount::from_mantissa_scale(1000, 0),
+        operation_validity_periods: 100,
+        genesis_timestamp: MassaTime::now().unwrap().saturating_add(300.into()),
+        ..Default::default()
+    };
+    // addr_1 owns all the rolls, addr_2 none
+    let addr_1 = random_address_on_thread(0, cfg.thread_count);
+    let addr_2 = random_address_on_thread(0, cfg.thread_count);
+
+    let mut ledger = HashMap::new();
+    ledger.insert(
+        addr_2.address,
+        LedgerData::new(Amount::from_str("10000").unwrap()),
+    );
+    let initial_ledger_file = generate_ledger_file(&ledger);
+    let initial_rolls_file = generate_default_roll_counts_file(vec![addr_1.keypair]);
+    let staking_keys_file = generate_staking_keys_file(&[addr_2.keypair]);
+
+    cfg.initial_ledger_path = initial_ledger_file.path().to_path_buf();
+    cfg.initial_rolls_path = initial_rolls_file.path().to_path_buf();
+    cfg.staking_keys_path = staking_keys_file.path().to_path_buf();
+    consensus_without_pool_test(
+        cfg.clone(),
+        async move |protocol_controller, consensus_command_sender, consensus_event_receiver| {
+            let schedules = consensus_command_sender
+                .get_selection_schedule(vec![addr_1.address, addr_2.address], Some(0))
+                .await
+                .unwrap();
+            assert_eq!(schedules.len(), 2);
+            assert_eq!(schedules[0].address, addr_1.address);
+            assert_eq!(schedules[0].start_cycle, 0);
+            // nothing is final yet: only the cycles drawn from the initial rolls are known
+            assert_eq!(schedules[0].end_cycle, cfg.pos_lookback_cycles);
+
+            // the schedule covers the same slots as the draws
+            let end_slot = Slot::new((schedules[0].end_cycle + 1) * cfg.periods_per_cycle, 0);
+            let draws = consensus_command_sender
+                .get_selection_draws(Slot::new(1, 0), end_slot)
+                .await
+                .unwrap();
+            let block_slots: Vec<Slot> = draws.iter().map(|(slot, _)| *slot).collect();
+            assert_eq!(schedules[0].block_draws, block_slots);
+            assert_eq!(
+                schedules[0].endorsement_draws.len(),
+                block_slots.len() * cfg.endorsement_count as usize
+            );
+            assert!(schedules[1].block_draws.is_empty());
+            assert!(schedules[1].endorsement_draws.is_empty());
+
+            // a cycle whose draws are unknown should return an error
+            let unknown_schedule = consensus_command_sender
+                .get_selection_schedule(vec![addr_1.address], Some(100))
+                .await;
+            assert!(unknown_schedule.is_err());
+            (
+                protocol_controller,
+                consensus_command_sender,
+                consensus_event_receiver,
+            )
+        },
+    )
+    .await;
+}
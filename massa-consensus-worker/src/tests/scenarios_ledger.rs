@@ -12,6 +12,7 @@ use massa_consensus_exports::tools::TEST_PASSWORD;
 use massa_consensus_exports::ConsensusConfig;
 use massa_execution_exports::test_exports::MockExecutionController;
 use massa_graph::{ledger::Ledger, LedgerConfig};
+use massa_models::constants::CHANNEL_SIZE;
 use massa_models::ledger_models::LedgerData;
 use massa_models::ledger_models::{LedgerChange, LedgerChanges};
 use massa_models::prehash::Map;
@@ -512,6 +513,7 @@ async fn test_ledger_update_when_a_batch_of_blocks_becomes_final() {
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
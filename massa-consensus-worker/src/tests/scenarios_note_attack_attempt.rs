@@ -12,6 +12,7 @@ use massa_consensus_exports::tools::TEST_PASSWORD;
 use massa_consensus_exports::ConsensusConfig;
 use massa_execution_exports::test_exports::MockExecutionController;
 use massa_hash::Hash;
+use massa_models::constants::CHANNEL_SIZE;
 use massa_models::prehash::Map;
 use massa_models::{BlockId, Slot};
 use massa_signature::KeyPair;
@@ -45,6 +46,7 @@ async fn test_invalid_block_notified_as_attack_attempt() {
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
@@ -113,6 +115,7 @@ async fn test_invalid_header_notified_as_attack_attempt() {
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
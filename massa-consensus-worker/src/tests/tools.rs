@@ -15,6 +15,7 @@ use massa_consensus_exports::{
 use massa_execution_exports::test_exports::MockExecutionController;
 use massa_graph::{export_active_block::ExportActiveBlock, BlockGraphExport, BootstrapableGraph};
 use massa_hash::Hash;
+use massa_models::constants::CHANNEL_SIZE;
 use massa_models::prehash::Map;
 use massa_models::{
     prehash::Set,
@@ -697,6 +698,7 @@ pub async fn consensus_pool_test<F, V>(
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             boot_pos,
             boot_graph,
@@ -791,6 +793,7 @@ pub async fn consensus_pool_test_with_storage<F, V>(
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             boot_pos,
             boot_graph,
@@ -871,6 +874,7 @@ where
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
@@ -943,6 +947,7 @@ where
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender,
+                broadcast_event_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
             },
             None,
             None,
@@ -85,6 +85,7 @@ pub async fn start_consensus_controller(
                 controller_command_rx: command_rx,
                 controller_event_tx: event_tx,
                 controller_manager_rx: manager_rx,
+                broadcast_event_sender: channels.broadcast_event_sender,
             },
             block_db,
             pos,
@@ -0,0 +1,13 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the channels used by the execution worker to broadcast its outputs
+
+use crate::types::SlotExecutionOutput;
+use tokio::sync::broadcast;
+
+/// channels used by the execution worker
+#[derive(Clone)]
+pub struct ExecutionChannels {
+    /// broadcast channel for the outputs of executed active and final slots
+    pub slot_execution_output_sender: broadcast::Sender<SlotExecutionOutput>,
+}
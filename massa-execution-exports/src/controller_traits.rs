@@ -2,17 +2,22 @@
 
 //! This module exports generic traits representing interfaces for interacting with the Execution worker
 
-use crate::types::ExecutionOutput;
+use crate::types::ReadOnlyExecutionOutput;
 use crate::types::ReadOnlyExecutionRequest;
 use crate::ExecutionError;
-use massa_models::api::EventFilter;
+use massa_hash::Hash;
+use massa_ledger_exports::LedgerProof;
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::OperationReceipt;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::Address;
 use massa_models::Amount;
 use massa_models::BlockId;
+use massa_models::OperationId;
 use massa_models::Slot;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::ops::Bound;
 
 /// interface that communicates with the execution worker thread
 pub trait ExecutionController: Send + Sync {
@@ -35,6 +40,13 @@ pub trait ExecutionController: Send + Sync {
     /// * operation id
     fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent>;
 
+    /// Get the pending asynchronous messages, including the ones of the active slots,
+    /// optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * emission slot range
+    fn get_async_messages(&self, filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo>;
+
     /// Get a balance final and active values
     ///
     /// # Return value
@@ -54,6 +66,40 @@ pub trait ExecutionController: Send + Sync {
         input: Vec<(Address, Vec<u8>)>,
     ) -> Vec<(Option<Vec<u8>>, Option<Vec<u8>>)>;
 
+    /// Get the receipts of some operations with their final and active values.
+    /// Final receipts are kept in the final state for a retention period,
+    /// older ones are only available if the event archive is enabled.
+    ///
+    /// # Return value
+    /// * `(final_receipt, active_receipt)`
+    #[allow(clippy::type_complexity)]
+    fn get_final_and_active_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> Vec<(Option<OperationReceipt>, Option<OperationReceipt>)>;
+
+    /// Get the final balances of some addresses at the output of a past final slot.
+    /// Requires the final ledger archive mode.
+    ///
+    /// # Return value
+    /// * the final balance of each address at that slot, `None` for missing entries
+    fn get_final_parallel_balances_at_slot(
+        &self,
+        addresses: Vec<Address>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Amount>>, ExecutionError>;
+
+    /// Get copies of some final datastore entries at the output of a past final slot.
+    /// Requires the final ledger archive mode.
+    ///
+    /// # Return value
+    /// * the final value of each `(address, key)` datastore entry at that slot, `None` for missing entries
+    fn get_final_data_entries_at_slot(
+        &self,
+        input: Vec<(Address, Vec<u8>)>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError>;
+
     /// Get every datastore key of the given address.
     ///
     /// # Returns
@@ -63,18 +109,48 @@ pub trait ExecutionController: Send + Sync {
         addr: &Address,
     ) -> (BTreeSet<Vec<u8>>, BTreeSet<Vec<u8>>);
 
+    /// Get the candidate datastore keys of the given address within a range, in increasing order.
+    ///
+    /// # Arguments
+    /// * `range`: range of the datastore keys to return
+    /// * `limit`: maximum number of keys to return
+    fn get_candidate_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> Vec<Vec<u8>>;
+
+    /// Get the slot the final state is attached to, along with the fingerprint of the final state at that slot
+    fn get_final_state_fingerprint(&self) -> (Slot, Hash);
+
+    /// Get the balance, bytecode hash and some datastore entries of an address in the final state,
+    /// along with their inclusion or non-inclusion proofs against the final ledger root
+    ///
+    /// # Arguments
+    /// * `addr`: address to query
+    /// * `keys`: datastore keys to prove
+    /// * `slot`: past final slot to prove against, the latest one if `None`.
+    ///   Past slots require the final ledger archive mode.
+    fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> Result<LedgerProof, ExecutionError>;
+
     /// Execute read-only SC function call without causing modifications to the consensus state
     ///
     /// # arguments
     /// * `req`: an instance of `ReadOnlyCallRequest` describing the parameters of the execution
     ///
     /// # returns
-    /// An instance of `ExecutionOutput` containing a summary of the effects of the execution,
+    /// An instance of `ReadOnlyExecutionOutput` containing a summary of the effects of the execution and its gas cost,
     /// or an error if the execution failed.
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError>;
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError>;
 
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ExecutionController>`.
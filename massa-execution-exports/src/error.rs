@@ -20,4 +20,18 @@ pub enum ExecutionError {
 
     /// `ModelsError`: {0}
     ModelsError(#[from] massa_models::ModelsError),
+
+    /// Ledger error: {0}
+    LedgerError(String),
+
+    /// Replay error: {0}
+    ReplayError(String),
+
+    /// {error}
+    TracedError {
+        /// error that interrupted a traced read-only execution
+        error: Box<ExecutionError>,
+        /// trace of the execution until the error
+        trace: Vec<massa_models::execution::ExecutionTraceStep>,
+    },
 }
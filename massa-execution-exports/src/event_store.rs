@@ -34,6 +34,18 @@ impl EventStore {
         }
     }
 
+    /// Iterate over the events of the store, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &SCOutputEvent> {
+        self.0.iter()
+    }
+
+    /// Mark all the events of the store as final
+    pub fn finalize(&mut self) {
+        for event in self.0.iter_mut() {
+            event.context.is_final = true;
+        }
+    }
+
     /// Extend the event store with another store
     pub fn extend(&mut self, other: EventStore) {
         self.0.extend(other.0.into_iter());
@@ -45,37 +57,16 @@ impl EventStore {
     /// * emitter address
     /// * original caller address
     /// * operation id
+    /// * finality status
+    /// * data substring or prefix
+    /// * pagination cursor
+    ///
+    /// The limit of the filter is applied, keeping the oldest matching events.
     pub fn get_filtered_sc_output_event(&self, filter: &EventFilter) -> VecDeque<SCOutputEvent> {
         self.0
             .iter()
-            .filter(|x| {
-                if let Some(start) = filter.start {
-                    if x.context.slot < start {
-                        return false;
-                    }
-                }
-                if let Some(end) = filter.end {
-                    if x.context.slot >= end {
-                        return false;
-                    }
-                }
-                match (filter.emitter_address, x.context.call_stack.front()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.original_caller_address, x.context.call_stack.back()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.original_operation_id, x.context.origin_operation_id) {
-                    (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                true
-            })
+            .filter(|x| filter.matches(x))
+            .take(filter.limit.unwrap_or(usize::MAX))
             .cloned()
             .collect()
     }
@@ -93,6 +84,7 @@ fn test_prune() {
                 slot: Slot::new(i, 0),
                 block: None,
                 read_only: false,
+                is_final: false,
                 index_in_slot: 1,
                 call_stack: VecDeque::new(),
                 origin_operation_id: None,
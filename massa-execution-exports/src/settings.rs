@@ -3,6 +3,7 @@
 //! This module provides the structures used to provide configuration parameters to the Execution system
 
 use massa_time::MassaTime;
+use std::path::PathBuf;
 
 /// Execution module configuration
 #[derive(Debug, Clone)]
@@ -13,6 +14,8 @@ pub struct ExecutionConfig {
     pub max_final_events: usize,
     /// maximum available gas for asynchronous messages execution
     pub max_async_gas: u64,
+    /// gas charged to every executed `ExecuteSC` and `CallSC` operation on top of the gas it consumes
+    pub operation_base_gas_cost: u64,
     /// number of threads
     pub thread_count: u8,
     /// extra lag to add on the execution cursor to improve performance
@@ -23,4 +26,10 @@ pub struct ExecutionConfig {
     pub genesis_timestamp: MassaTime,
     /// period duration
     pub t0: MassaTime,
+    /// path to the on-disk archive of final SC output events, no archive if None
+    pub event_archive_path: Option<PathBuf>,
+    /// number of periods during which archived events are kept
+    pub event_archive_retention_periods: u64,
+    /// directory in which the final slots are recorded for offline replay (see massa-replay), no recording if None
+    pub replay_recording_path: Option<PathBuf>,
 }
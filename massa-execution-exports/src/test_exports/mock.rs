@@ -2,11 +2,20 @@
 
 //! This file defines utilities to mock the crate for testing purposes
 
-use crate::{ExecutionController, ExecutionError, ExecutionOutput, ReadOnlyExecutionRequest};
-use massa_ledger_exports::LedgerEntry;
-use massa_models::{api::EventFilter, output_event::SCOutputEvent, Address, Amount, BlockId, Slot};
+use crate::{
+    ExecutionController, ExecutionError, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+};
+use massa_hash::Hash;
+use massa_ledger_exports::{LedgerEntry, LedgerKeyProof, LedgerProof};
+use massa_models::{
+    api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter},
+    execution::OperationReceipt,
+    output_event::SCOutputEvent,
+    Address, Amount, BlockId, OperationId, Slot,
+};
 use std::{
     collections::{BTreeSet, HashMap},
+    ops::Bound,
     sync::{
         mpsc::{self, Receiver},
         Arc, Mutex,
@@ -46,7 +55,7 @@ pub enum MockExecutionControllerMessage {
         /// read only execution request
         req: ReadOnlyExecutionRequest,
         /// response channel
-        response_tx: mpsc::Sender<Result<ExecutionOutput, ExecutionError>>,
+        response_tx: mpsc::Sender<Result<ReadOnlyExecutionOutput, ExecutionError>>,
     },
 }
 
@@ -106,6 +115,10 @@ impl ExecutionController for MockExecutionController {
         response_rx.recv().unwrap()
     }
 
+    fn get_async_messages(&self, _filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        Vec::default()
+    }
+
     fn get_final_and_active_parallel_balance(
         &self,
         _address: Vec<Address>,
@@ -120,6 +133,29 @@ impl ExecutionController for MockExecutionController {
         Vec::default()
     }
 
+    fn get_final_and_active_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> Vec<(Option<OperationReceipt>, Option<OperationReceipt>)> {
+        vec![(None, None); operation_ids.len()]
+    }
+
+    fn get_final_parallel_balances_at_slot(
+        &self,
+        _addresses: Vec<Address>,
+        _slot: Slot,
+    ) -> Result<Vec<Option<Amount>>, ExecutionError> {
+        Ok(Vec::default())
+    }
+
+    fn get_final_data_entries_at_slot(
+        &self,
+        _input: Vec<(Address, Vec<u8>)>,
+        _slot: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError> {
+        Ok(Vec::default())
+    }
+
     fn get_final_and_active_datastore_keys(
         &self,
         _addr: &Address,
@@ -127,10 +163,50 @@ impl ExecutionController for MockExecutionController {
         (BTreeSet::default(), BTreeSet::default())
     }
 
+    fn get_candidate_datastore_keys_in_range(
+        &self,
+        _addr: &Address,
+        _range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        _limit: usize,
+    ) -> Vec<Vec<u8>> {
+        Vec::default()
+    }
+
+    fn get_final_state_fingerprint(&self) -> (Slot, Hash) {
+        (Slot::new(0, 0), Hash::zero())
+    }
+
+    fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        keys: Vec<Vec<u8>>,
+        _slot: Option<Slot>,
+    ) -> Result<LedgerProof, ExecutionError> {
+        let empty_proof = LedgerKeyProof {
+            value_hash: None,
+            other_leaf: None,
+            siblings: Vec::new(),
+        };
+        Ok(LedgerProof {
+            address: *addr,
+            slot: Slot::new(0, 0),
+            ledger_root: Hash::zero(),
+            async_pool_hash: Some(Hash::zero()),
+            balance: None,
+            balance_proof: empty_proof.clone(),
+            bytecode_hash: None,
+            bytecode_proof: empty_proof.clone(),
+            datastore: keys
+                .into_iter()
+                .map(|key| (key, None, empty_proof.clone()))
+                .collect(),
+        })
+    }
+
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
             .lock()
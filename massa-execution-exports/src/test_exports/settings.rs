@@ -4,7 +4,7 @@
 
 use massa_time::MassaTime;
 
-use crate::ExecutionConfig;
+use crate::{ExecutionChannels, ExecutionConfig};
 
 /// Default value of `ExecutionConfig` used for tests
 impl Default for ExecutionConfig {
@@ -15,12 +15,27 @@ impl Default for ExecutionConfig {
             readonly_queue_length: READONLY_QUEUE_LENGTH,
             max_final_events: MAX_FINAL_EVENTS,
             max_async_gas: MAX_ASYNC_GAS,
+            operation_base_gas_cost: OPERATION_BASE_GAS_COST,
             thread_count: THREAD_COUNT,
             cursor_delay: CURSOR_DELAY,
             clock_compensation: Default::default(),
             // reset genesis timestamp because we are in test mode that can take a while to process
             genesis_timestamp: MassaTime::now().expect("Impossible to reset the timestamp in test"),
             t0: 10.into(),
+            event_archive_path: None,
+            event_archive_retention_periods: 1000,
+            replay_recording_path: None,
+        }
+    }
+}
+
+/// Default value of `ExecutionChannels` used for tests: nobody listens to the outputs
+impl Default for ExecutionChannels {
+    fn default() -> Self {
+        use massa_models::constants::default_testing::*;
+
+        Self {
+            slot_execution_output_sender: tokio::sync::broadcast::channel(CHANNEL_SIZE).0,
         }
     }
 }
@@ -4,7 +4,8 @@
 
 use crate::event_store::EventStore;
 use massa_final_state::StateChanges;
-use massa_models::{Address, Amount, BlockId, Slot};
+use massa_models::execution::ExecutionTraceStep;
+use massa_models::{Address, Amount, BlockId, Slot, WrappedOperation};
 
 /// structure describing the output of a single execution
 #[derive(Debug, Clone)]
@@ -19,6 +20,26 @@ pub struct ExecutionOutput {
     pub events: EventStore,
 }
 
+/// execution output of a slot, broadcast to the subscribers of the execution component
+#[derive(Debug, Clone)]
+pub enum SlotExecutionOutput {
+    /// output of the execution of an active (candidate) slot
+    ExecutedSlot(ExecutionOutput),
+    /// output of the execution of a final slot
+    FinalizedSlot(ExecutionOutput),
+}
+
+/// structure describing the output of a read-only execution
+#[derive(Debug, Clone)]
+pub struct ReadOnlyExecutionOutput {
+    /// output of the execution
+    pub out: ExecutionOutput,
+    /// gas consumed by the execution
+    pub gas_cost: u64,
+    /// trace of the execution, if it was requested
+    pub trace: Option<Vec<ExecutionTraceStep>>,
+}
+
 /// structure describing different types of read-only execution request
 #[derive(Debug, Clone)]
 pub struct ReadOnlyExecutionRequest {
@@ -30,6 +51,13 @@ pub struct ReadOnlyExecutionRequest {
     pub call_stack: Vec<ExecutionStackElement>,
     /// Target of the request
     pub target: ReadOnlyExecutionTarget,
+    /// Parallel coins credited to the caller (bottom of the call stack) before the execution,
+    /// as done for the coins of an `ExecuteSC` or the sequential coins of a `CallSC` operation
+    pub credited_coins: Amount,
+    /// Parallel coins transferred from the caller to the target address of a function call before the call
+    pub transferred_coins: Amount,
+    /// Whether to record the trace of the execution
+    pub trace: bool,
 }
 
 /// structure describing different possible targets of a read-only execution request
@@ -47,6 +75,10 @@ pub enum ReadOnlyExecutionTarget {
         /// Parameter to pass to the target function
         parameter: String,
     },
+
+    /// Execute an operation as if it was included in a block, without crediting any block producer.
+    /// The call stack of the request is ignored: the operation is executed on behalf of its sender.
+    Operation(WrappedOperation),
 }
 
 /// structure describing a read-only call
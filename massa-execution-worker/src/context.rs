@@ -12,18 +12,30 @@ use crate::speculative_async_pool::SpeculativeAsyncPool;
 use crate::speculative_ledger::SpeculativeLedger;
 use massa_async_pool::{AsyncMessage, AsyncMessageId};
 use massa_execution_exports::{EventStore, ExecutionError, ExecutionOutput, ExecutionStackElement};
-use massa_final_state::{FinalState, StateChanges};
-use massa_ledger_exports::LedgerChanges;
+use massa_final_state::{FinalState, ReceiptsChanges, StateChanges};
+use massa_ledger_exports::{
+    bytecode_version_count_from_bytes, bytecode_version_key, is_reserved_datastore_key,
+    storage_deposit_from_bytes, storage_deposit_key, storage_deposit_to_bytes, BytecodeVersion,
+    LedgerChanges, BYTECODE_VERSION_COUNT_KEY, STORAGE_DEPOSIT_BYTECODE_KEY, STORAGE_DEPOSIT_SIZE,
+};
 use massa_models::{
+    execution::ExecutionTraceStep,
     output_event::{EventExecutionContext, SCOutputEvent},
     Address, Amount, BlockId, OperationId, Slot,
 };
 use parking_lot::RwLock;
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256PlusPlus;
+use std::cmp::Ordering;
 use std::sync::Arc;
 use tracing::debug;
 
+/// maximal number of steps recorded in the trace of an execution
+pub(crate) const MAX_TRACE_STEPS: usize = 10_000;
+
+/// maximal number of bytes of a datastore value recorded in the trace of an execution
+pub(crate) const MAX_TRACE_VALUE_LENGTH: usize = 256;
+
 /// A snapshot taken from an `ExecutionContext` and that represents its current state.
 /// The `ExecutionContext` state can then be restored later from this snapshot.
 pub(crate) struct ExecutionContextSnapshot {
@@ -47,6 +59,12 @@ pub(crate) struct ExecutionContextSnapshot {
 
     /// Unsafe random state
     pub unsafe_rng: Xoshiro256PlusPlus,
+
+    /// storage costs charged so far in the context
+    pub storage_costs_charged: Amount,
+
+    /// storage costs refunded so far in the context
+    pub storage_costs_refunded: Amount,
 }
 
 /// An execution context that needs to be initialized before executing bytecode,
@@ -96,6 +114,22 @@ pub(crate) struct ExecutionContext {
 
     /// operation id that originally caused this execution (if any)
     pub origin_operation_id: Option<OperationId>,
+
+    /// receipts of the operations executed so far in the context
+    pub receipts: ReceiptsChanges,
+
+    /// cost in coins of each byte stored in the ledger at the slot of the context
+    storage_cost_per_byte: Amount,
+
+    /// storage costs charged so far in the context, when the storage of an address grows
+    pub storage_costs_charged: Amount,
+
+    /// storage costs refunded so far in the context, when the storage of an address shrinks
+    pub storage_costs_refunded: Amount,
+
+    /// trace of the execution, recorded only if it is `Some`.
+    /// It is not part of the snapshots, so that the steps of reverted calls remain visible.
+    pub trace: Option<Vec<ExecutionTraceStep>>,
 }
 
 impl ExecutionContext {
@@ -113,6 +147,10 @@ impl ExecutionContext {
         final_state: Arc<RwLock<FinalState>>,
         active_history: Arc<RwLock<ActiveHistory>>,
     ) -> Self {
+        let storage_cost_per_byte = final_state
+            .read()
+            .ledger
+            .get_storage_cost_per_byte(&Slot::new(0, 0));
         ExecutionContext {
             speculative_ledger: SpeculativeLedger::new(final_state.clone(), active_history.clone()),
             speculative_async_pool: SpeculativeAsyncPool::new(final_state, active_history),
@@ -128,6 +166,11 @@ impl ExecutionContext {
             events: Default::default(),
             unsafe_rng: Xoshiro256PlusPlus::from_seed([0u8; 32]),
             origin_operation_id: Default::default(),
+            receipts: Default::default(),
+            storage_cost_per_byte,
+            storage_costs_charged: Default::default(),
+            storage_costs_refunded: Default::default(),
+            trace: None,
         }
     }
 
@@ -142,6 +185,8 @@ impl ExecutionContext {
             stack: self.stack.clone(),
             events: self.events.clone(),
             unsafe_rng: self.unsafe_rng.clone(),
+            storage_costs_charged: self.storage_costs_charged,
+            storage_costs_refunded: self.storage_costs_refunded,
         }
     }
 
@@ -174,6 +219,8 @@ impl ExecutionContext {
         self.stack = snapshot.stack;
         self.events = snapshot.events;
         self.unsafe_rng = snapshot.unsafe_rng;
+        self.storage_costs_charged = snapshot.storage_costs_charged;
+        self.storage_costs_refunded = snapshot.storage_costs_refunded;
 
         // If there was an error, emit the corresponding event now.
         // Note that the context event counter is properly handled by event_emit (see doc).
@@ -216,10 +263,12 @@ impl ExecutionContext {
         let unsafe_rng = Xoshiro256PlusPlus::from_seed(seed);
 
         // return readonly context
+        let storage_cost_per_byte = final_state.read().ledger.get_storage_cost_per_byte(&slot);
         ExecutionContext {
             max_gas,
             gas_price,
             slot,
+            storage_cost_per_byte,
             stack: call_stack,
             read_only: true,
             unsafe_rng,
@@ -279,8 +328,10 @@ impl ExecutionContext {
         let unsafe_rng = Xoshiro256PlusPlus::from_seed(seed);
 
         // return active slot execution context
+        let storage_cost_per_byte = final_state.read().ledger.get_storage_cost_per_byte(&slot);
         ExecutionContext {
             slot,
+            storage_cost_per_byte,
             opt_block_id,
             unsafe_rng,
             ..ExecutionContext::new(final_state, active_history)
@@ -356,9 +407,21 @@ impl ExecutionContext {
         // hash the seed to get a unique address
         let address = Address(massa_hash::Hash::compute_from(&data));
 
-        // add this address with its bytecode to the speculative ledger
+        // add this address with its bytecode to the speculative ledger,
+        // the creator paying for the storage of the bytecode and of its first version
+        // since the new address has no coins yet
+        let creator = self.get_current_address()?;
+        let bytecode_hash = massa_hash::Hash::compute_from(&bytecode);
+        let deposit = self.pay_storage_deposit(
+            &address,
+            STORAGE_DEPOSIT_BYTECODE_KEY,
+            &creator,
+            bytecode.len(),
+        )?;
         self.speculative_ledger
             .create_new_sc_address(address, bytecode)?;
+        self.record_storage_deposit(&address, STORAGE_DEPOSIT_BYTECODE_KEY, deposit)?;
+        self.push_bytecode_version(&address, bytecode_hash, &creator)?;
 
         // add the address to owned addresses
         // so that the current call has write access to it
@@ -402,7 +465,7 @@ impl ExecutionContext {
     }
 
     /// Sets a datastore entry for an address in the speculative ledger.
-    /// Fail if the address is absent from the ledger.
+    /// Fail if the address is absent from the ledger or cannot pay for the storage of the entry.
     /// The datastore entry is created if it is absent for that address.
     ///
     /// # Arguments
@@ -422,13 +485,31 @@ impl ExecutionContext {
                 address
             )));
         }
-
-        // set data entry
-        self.speculative_ledger.set_data_entry(address, key, data)
+        check_datastore_key_writable(&key)?;
+
+        // pay for the storage of the new data, then set data entry
+        let deposit_key = storage_deposit_key(&key);
+        let deposit =
+            self.pay_storage_deposit(address, &deposit_key, address, key.len() + data.len())?;
+        let traced_entry = self
+            .trace
+            .is_some()
+            .then(|| (key.clone(), trace_value(&data), data.len()));
+        self.speculative_ledger.set_data_entry(address, key, data)?;
+        self.record_storage_deposit(address, &deposit_key, deposit)?;
+        if let Some((key, value, value_size)) = traced_entry {
+            self.trace_step(|| ExecutionTraceStep::DatastoreWrite {
+                address: *address,
+                key,
+                value: Some(value),
+                value_size,
+            });
+        }
+        Ok(())
     }
 
     /// Appends data to a datastore entry for an address in the speculative ledger.
-    /// Fail if the address is absent from the ledger.
+    /// Fail if the address is absent from the ledger or cannot pay for the storage of the appended data.
     /// Fails if the datastore entry is absent for that address.
     ///
     /// # Arguments
@@ -448,6 +529,7 @@ impl ExecutionContext {
                 address
             )));
         }
+        check_datastore_key_writable(&key)?;
 
         // get current data entry
         let mut res_data = self
@@ -463,12 +545,29 @@ impl ExecutionContext {
         // append data
         res_data.extend(data);
 
-        // set data entry
+        // pay for the storage of the appended data, then set data entry
+        let deposit_key = storage_deposit_key(&key);
+        let deposit =
+            self.pay_storage_deposit(address, &deposit_key, address, key.len() + res_data.len())?;
+        let traced_entry = self
+            .trace
+            .is_some()
+            .then(|| (key.clone(), trace_value(&res_data), res_data.len()));
         self.speculative_ledger
-            .set_data_entry(address, key, res_data)
+            .set_data_entry(address, key, res_data)?;
+        self.record_storage_deposit(address, &deposit_key, deposit)?;
+        if let Some((key, value, value_size)) = traced_entry {
+            self.trace_step(|| ExecutionTraceStep::DatastoreWrite {
+                address: *address,
+                key,
+                value: Some(value),
+                value_size,
+            });
+        }
+        Ok(())
     }
 
-    /// Deletes a datastore entry for an address.
+    /// Deletes a datastore entry for an address, refunding the storage deposit of the entry.
     /// Fails if the address or the entry does not exist or if write access rights are missing.
     ///
     /// # Arguments
@@ -486,9 +585,125 @@ impl ExecutionContext {
                 address
             )));
         }
+        check_datastore_key_writable(key)?;
+
+        // delete entry and refund its storage deposit
+        self.speculative_ledger.delete_data_entry(address, key)?;
+        let deposit_key = storage_deposit_key(key);
+        let deposit = self.pay_storage_deposit(address, &deposit_key, address, 0)?;
+        self.record_storage_deposit(address, &deposit_key, deposit)?;
+        self.trace_step(|| ExecutionTraceStep::DatastoreWrite {
+            address: *address,
+            key: key.to_vec(),
+            value: None,
+            value_size: 0,
+        });
+        Ok(())
+    }
+
+    /// Pays the storage deposit of a ledger entry of an address for its new size, before the entry is written
+    /// (see `LedgerConfig::storage_cost_per_byte`).
+    /// The deposit covers the entry and the reserved datastore entry recording the deposit.
+    /// If the new deposit is larger than the recorded one, the payer is charged the difference,
+    /// otherwise the address is refunded the difference, so that no more than what was paid is ever refunded.
+    /// Fails if the payer cannot pay for the added storage.
+    ///
+    /// # Arguments
+    /// * `address`: the address holding the entry and its deposit
+    /// * `deposit_key`: the reserved datastore key recording the deposit of the entry
+    /// * `payer`: the address paying for the added storage
+    /// * `entry_size`: size in bytes of the entry after the change, 0 if it is deleted
+    ///
+    /// # Returns
+    /// The new deposit of the entry, to be recorded with `record_storage_deposit` once the entry is written
+    fn pay_storage_deposit(
+        &mut self,
+        address: &Address,
+        deposit_key: &[u8],
+        payer: &Address,
+        entry_size: usize,
+    ) -> Result<Amount, ExecutionError> {
+        let old_deposit = storage_deposit_from_bytes(
+            self.speculative_ledger
+                .get_data_entry(address, deposit_key)
+                .as_deref(),
+        );
+        let new_deposit = if entry_size == 0 {
+            Amount::zero()
+        } else {
+            self.storage_cost_per_byte
+                .saturating_mul_u64((entry_size + deposit_key.len() + STORAGE_DEPOSIT_SIZE) as u64)
+        };
+        if new_deposit > old_deposit {
+            self.charge_storage_cost(payer, new_deposit.saturating_sub(old_deposit))?;
+        } else if new_deposit < old_deposit {
+            let refund = old_deposit.saturating_sub(new_deposit);
+            self.speculative_ledger
+                .transfer_parallel_coins(None, Some(*address), refund)?;
+            self.storage_costs_refunded = self.storage_costs_refunded.saturating_add(refund);
+            self.trace_step(|| ExecutionTraceStep::Transfer {
+                from: None,
+                to: Some(*address),
+                amount: refund,
+            });
+        }
+        Ok(new_deposit)
+    }
+
+    /// Records the storage deposit of a ledger entry of an address, once the entry is written.
+    /// A zero deposit is not recorded.
+    ///
+    /// # Arguments
+    /// * `address`: the address holding the entry and its deposit
+    /// * `deposit_key`: the reserved datastore key recording the deposit of the entry
+    /// * `deposit`: the deposit returned by `pay_storage_deposit`
+    fn record_storage_deposit(
+        &mut self,
+        address: &Address,
+        deposit_key: &[u8],
+        deposit: Amount,
+    ) -> Result<(), ExecutionError> {
+        let recorded = self.speculative_ledger.get_data_entry(address, deposit_key);
+        if deposit.is_zero() {
+            if recorded.is_some() {
+                self.speculative_ledger
+                    .delete_data_entry(address, deposit_key)?;
+            }
+        } else if storage_deposit_from_bytes(recorded.as_deref()) != deposit {
+            self.speculative_ledger.set_data_entry(
+                address,
+                deposit_key.to_vec(),
+                storage_deposit_to_bytes(deposit),
+            )?;
+        }
+        Ok(())
+    }
 
-        // delete entry
-        self.speculative_ledger.delete_data_entry(address, key)
+    /// Charges a storage cost to an address by burning it.
+    /// Fails if the address cannot pay for it.
+    ///
+    /// # Arguments
+    /// * `payer`: the address paying for the storage
+    /// * `cost`: the cost to charge
+    fn charge_storage_cost(&mut self, payer: &Address, cost: Amount) -> Result<(), ExecutionError> {
+        if cost.is_zero() {
+            return Ok(());
+        }
+        self.speculative_ledger
+            .transfer_parallel_coins(Some(*payer), None, cost)
+            .map_err(|err| {
+                ExecutionError::RuntimeError(format!(
+                    "address {} could not pay the storage cost of {} coins: {}",
+                    payer, cost, err
+                ))
+            })?;
+        self.storage_costs_charged = self.storage_costs_charged.saturating_add(cost);
+        self.trace_step(|| ExecutionTraceStep::Transfer {
+            from: Some(*payer),
+            to: None,
+            amount: cost,
+        });
+        Ok(())
     }
 
     /// Transfers parallel coins from one address to another.
@@ -516,15 +731,41 @@ impl ExecutionContext {
         }
         // do the transfer
         self.speculative_ledger
-            .transfer_parallel_coins(from_addr, to_addr, amount)
+            .transfer_parallel_coins(from_addr, to_addr, amount)?;
+        self.trace_step(|| ExecutionTraceStep::Transfer {
+            from: from_addr,
+            to: to_addr,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Records a step in the trace of the execution, if it is traced.
+    /// Once the trace holds `MAX_TRACE_STEPS` steps, a last `Truncated` step is recorded
+    /// and the following steps are dropped.
+    ///
+    /// # Arguments
+    /// * `step`: builds the step to record, only called if the execution is traced
+    pub fn trace_step(&mut self, step: impl FnOnce() -> ExecutionTraceStep) {
+        if let Some(trace) = &mut self.trace {
+            match trace.len().cmp(&MAX_TRACE_STEPS) {
+                Ordering::Less => trace.push(step()),
+                Ordering::Equal => trace.push(ExecutionTraceStep::Truncated),
+                Ordering::Greater => (),
+            }
+        }
     }
 
-    /// Add a new asynchronous message to speculative pool
+    /// Add a new asynchronous message to speculative pool.
+    /// The sender of the message spends its coins, that are reimbursed if the message is not executed
+    /// (see `cancel_async_message`).
     ///
     /// # Arguments
     /// * `msg`: asynchronous message to add
-    pub fn push_new_message(&mut self, msg: AsyncMessage) {
+    pub fn push_new_message(&mut self, msg: AsyncMessage) -> Result<(), ExecutionError> {
+        self.transfer_parallel_coins(Some(msg.sender), None, msg.coins)?;
         self.speculative_async_pool.push_new_message(msg);
+        Ok(())
     }
 
     /// Cancels an asynchronous message, reimbursing `msg.coins` to the sender
@@ -548,16 +789,33 @@ impl ExecutionContext {
     /// This is used to get the output of an execution before discarding the context.
     /// Note that we are not taking self by value to consume it because the context is shared.
     pub fn settle_slot(&mut self) -> ExecutionOutput {
-        // settle emitted async messages and reimburse the senders of deleted messages
+        // settle emitted async messages and reimburse the senders of deleted messages,
+        // notifying them of the reason of the deletion
         let deleted_messages = self.speculative_async_pool.settle_slot(self.slot);
-        for (_msg_id, msg) in deleted_messages {
+        for (_msg_id, msg, reason) in deleted_messages {
             self.cancel_async_message(&msg);
+            let mut event = self.event_create(
+                serde_json::json!({
+                    "massa_async_message_eliminated": {
+                        "reason": reason.to_string(),
+                        "emission_slot": msg.emission_slot,
+                        "emission_index": msg.emission_index,
+                        "destination": msg.destination,
+                        "handler": msg.handler,
+                        "refunded_coins": msg.coins.to_string(),
+                    }
+                })
+                .to_string(),
+            );
+            event.context.call_stack = vec![msg.sender].into();
+            self.event_emit(event);
         }
 
         // generate the execution output
         let state_changes = StateChanges {
             ledger_changes: self.speculative_ledger.take(),
             async_pool_changes: self.speculative_async_pool.take(),
+            receipts_changes: std::mem::take(&mut self.receipts),
         };
         ExecutionOutput {
             slot: self.slot,
@@ -567,8 +825,12 @@ impl ExecutionContext {
         }
     }
 
-    /// Sets a bytecode for an address in the speculative ledger.
-    /// Fail if the address is absent from the ledger.
+    /// Sets a bytecode for an address in the speculative ledger,
+    /// recording the new version in the bytecode history of the address and emitting a `BytecodeUpdated` event.
+    /// The address pays for the growth of its bytecode or is refunded from its storage deposit for its shrinkage,
+    /// and pays for the storage of the new version of its history.
+    /// Fail if the address is absent from the ledger
+    /// or if the address cannot pay for the storage of the new bytecode and version.
     ///
     /// # Arguments
     /// * address: the address of the ledger entry
@@ -586,8 +848,80 @@ impl ExecutionContext {
             )));
         }
 
-        // set data entry
-        self.speculative_ledger.set_bytecode(address, bytecode)
+        // set bytecode and record the new version
+        let bytecode_hash = massa_hash::Hash::compute_from(&bytecode);
+        let deposit = self.pay_storage_deposit(
+            address,
+            STORAGE_DEPOSIT_BYTECODE_KEY,
+            address,
+            bytecode.len(),
+        )?;
+        self.speculative_ledger.set_bytecode(address, bytecode)?;
+        self.record_storage_deposit(address, STORAGE_DEPOSIT_BYTECODE_KEY, deposit)?;
+        let version = self.push_bytecode_version(address, bytecode_hash, address)?;
+
+        let event = self.event_create(
+            serde_json::json!({
+                "massa_bytecode_updated": {
+                    "address": address,
+                    "version": version,
+                    "hash": bytecode_hash,
+                }
+            })
+            .to_string(),
+        );
+        self.event_emit(event);
+        Ok(())
+    }
+
+    /// Records a new version in the bytecode history of an address,
+    /// in its own reserved datastore entry, and updates the version count.
+    /// The storage of the new entries is paid before they are written,
+    /// and is not refundable since the history is never shortened.
+    ///
+    /// # Arguments
+    /// * `address`: the address whose bytecode changed
+    /// * `hash`: the hash of the new bytecode
+    /// * `payer`: the address paying for the storage of the new entries
+    ///
+    /// # Returns
+    /// The number of the new version
+    fn push_bytecode_version(
+        &mut self,
+        address: &Address,
+        hash: massa_hash::Hash,
+        payer: &Address,
+    ) -> Result<u64, ExecutionError> {
+        let old_count_entry = self
+            .speculative_ledger
+            .get_data_entry(address, BYTECODE_VERSION_COUNT_KEY);
+        let version = bytecode_version_count_from_bytes(old_count_entry.as_deref()) + 1;
+
+        let version_key = bytecode_version_key(version);
+        let version_value = BytecodeVersion {
+            slot: self.slot,
+            hash,
+        }
+        .to_bytes();
+        let count_value = version.to_be_bytes().to_vec();
+        let mut new_size = version_key.len() + version_value.len();
+        if old_count_entry.is_none() {
+            new_size += BYTECODE_VERSION_COUNT_KEY.len() + count_value.len();
+        }
+        self.charge_storage_cost(
+            payer,
+            self.storage_cost_per_byte
+                .saturating_mul_u64(new_size as u64),
+        )?;
+
+        self.speculative_ledger
+            .set_data_entry(address, version_key, version_value)?;
+        self.speculative_ledger.set_data_entry(
+            address,
+            BYTECODE_VERSION_COUNT_KEY.to_vec(),
+            count_value,
+        )?;
+        Ok(version)
     }
 
     /// Creates a new event but does not emit it.
@@ -602,6 +936,7 @@ impl ExecutionContext {
             block: self.opt_block_id,
             call_stack: self.stack.iter().map(|e| e.address).collect(),
             read_only: self.read_only,
+            is_final: false,
             index_in_slot: self.created_event_index,
             origin_operation_id: self.origin_operation_id,
         };
@@ -623,3 +958,21 @@ impl ExecutionContext {
         self.events.push(event);
     }
 }
+
+/// Checks that a datastore key can be written by contracts,
+/// the keys reserved for the metadata maintained by the node being read-only
+fn check_datastore_key_writable(key: &[u8]) -> Result<(), ExecutionError> {
+    if is_reserved_datastore_key(key) {
+        return Err(ExecutionError::RuntimeError(format!(
+            "datastore key {:?} is reserved",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// Datastore value as recorded in the trace of an execution,
+/// truncated to `MAX_TRACE_VALUE_LENGTH` bytes
+pub(crate) fn trace_value(value: &[u8]) -> Vec<u8> {
+    value[..value.len().min(MAX_TRACE_VALUE_LENGTH)].to_vec()
+}
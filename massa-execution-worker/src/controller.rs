@@ -6,15 +6,19 @@
 use crate::execution::ExecutionState;
 use crate::request_queue::{RequestQueue, RequestWithResponseSender};
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager, ExecutionOutput,
-    ReadOnlyExecutionRequest,
+    ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager,
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
 };
-use massa_models::api::EventFilter;
+use massa_hash::Hash;
+use massa_ledger_exports::LedgerProof;
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::OperationReceipt;
 use massa_models::output_event::SCOutputEvent;
-use massa_models::{Address, Amount};
+use massa_models::{Address, Amount, OperationId};
 use massa_models::{BlockId, Slot};
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
 use std::sync::Arc;
 use tracing::info;
 
@@ -27,7 +31,7 @@ pub(crate) struct ExecutionInputData {
     /// new blockclique (if there is a new one), blocks indexed by slot
     pub new_blockclique: Option<HashMap<Slot, BlockId>>,
     /// queue for read-only execution requests and response MPSCs to send back their outputs
-    pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
+    pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
 }
 
 impl ExecutionInputData {
@@ -93,6 +97,15 @@ impl ExecutionController for ExecutionControllerImpl {
             .get_filtered_sc_output_event(filter)
     }
 
+    /// Get the pending asynchronous messages, including the ones of the active slots,
+    /// optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * emission slot range
+    fn get_async_messages(&self, filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        self.execution_state.read().get_async_messages(&filter)
+    }
+
     /// Get a balance final and active values
     ///
     /// # Return value
@@ -125,6 +138,47 @@ impl ExecutionController for ExecutionControllerImpl {
         result
     }
 
+    /// Get the receipts of some operations with their final and active values
+    ///
+    /// # Return value
+    /// * `(final_receipt, active_receipt)`
+    fn get_final_and_active_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> Vec<(Option<OperationReceipt>, Option<OperationReceipt>)> {
+        let lock = self.execution_state.read();
+        operation_ids
+            .iter()
+            .map(|op_id| lock.get_final_and_active_operation_receipt(op_id))
+            .collect()
+    }
+
+    /// Get the final balances of some addresses at the output of a past final slot
+    fn get_final_parallel_balances_at_slot(
+        &self,
+        addresses: Vec<Address>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Amount>>, ExecutionError> {
+        let lock = self.execution_state.read();
+        addresses
+            .iter()
+            .map(|addr| lock.get_final_parallel_balance_at_slot(addr, slot))
+            .collect()
+    }
+
+    /// Get copies of some final datastore entries at the output of a past final slot
+    fn get_final_data_entries_at_slot(
+        &self,
+        input: Vec<(Address, Vec<u8>)>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError> {
+        let lock = self.execution_state.read();
+        input
+            .iter()
+            .map(|(addr, key)| lock.get_final_data_entry_at_slot(addr, key, slot))
+            .collect()
+    }
+
     /// Get every datastore key of the given address.
     ///
     /// # Returns
@@ -138,12 +192,45 @@ impl ExecutionController for ExecutionControllerImpl {
             .get_final_and_active_datastore_keys(addr)
     }
 
+    /// Get the candidate datastore keys of the given address within a range, in increasing order.
+    ///
+    /// # Arguments
+    /// * `range`: range of the datastore keys to return
+    /// * `limit`: maximum number of keys to return
+    fn get_candidate_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> Vec<Vec<u8>> {
+        self.execution_state
+            .read()
+            .get_candidate_datastore_keys_in_range(addr, range, limit)
+    }
+
+    /// Get the slot and the fingerprint of the final state
+    fn get_final_state_fingerprint(&self) -> (Slot, Hash) {
+        self.execution_state.read().get_final_state_fingerprint()
+    }
+
+    /// Get the final values of an address along with their proofs against the final ledger root
+    fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> Result<LedgerProof, ExecutionError> {
+        self.execution_state
+            .read()
+            .get_ledger_proof(addr, keys, slot)
+    }
+
     /// Executes a read-only request
     /// Read-only requests do not modify consensus state
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         let resp_rx = {
             let mut input_data = self.input_data.1.lock();
 
@@ -156,7 +243,7 @@ impl ExecutionController for ExecutionControllerImpl {
 
             // prepare the channel to send back the result of the read-only execution
             let (resp_tx, resp_rx) =
-                std::sync::mpsc::channel::<Result<ExecutionOutput, ExecutionError>>();
+                std::sync::mpsc::channel::<Result<ReadOnlyExecutionOutput, ExecutionError>>();
 
             // append the request to the queue of input read-only requests
             input_data
@@ -0,0 +1,319 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Optional on-disk archive of the SC output events and operation receipts that became final.
+//!
+//! Events are stored in a RocksDB database, keyed by `(slot, index_in_slot)`,
+//! with secondary indexes by emitter address, original caller address and origin operation id.
+//! Operation receipts are keyed by operation id, with an index by slot used for pruning.
+//! Events and receipts older than the configured retention (in periods) are pruned as new slots are archived,
+//! starting from the period up to which the archive was last pruned, which is stored along with them.
+
+use massa_execution_exports::EventStore;
+use massa_final_state::ReceiptsChanges;
+use massa_models::api::EventFilter;
+use massa_models::constants::SLOT_KEY_SIZE;
+use massa_models::execution::OperationReceipt;
+use massa_models::output_event::SCOutputEvent;
+use massa_models::{OperationId, Slot};
+use rocksdb::{ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB};
+use std::path::PathBuf;
+
+const EVENTS_CF: &str = "events";
+const EMITTER_INDEX_CF: &str = "events_by_emitter";
+const CALLER_INDEX_CF: &str = "events_by_caller";
+const OPERATION_INDEX_CF: &str = "events_by_operation";
+const RECEIPTS_CF: &str = "receipts";
+const RECEIPT_SLOT_INDEX_CF: &str = "receipts_by_slot";
+const METADATA_CF: &str = "metadata";
+const PRUNED_UNTIL_KEY: &[u8] = b"pruned_until_period";
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const EVENT_SER_ERROR: &str = "critical: archived event serialization failed";
+const EVENT_DESER_ERROR: &str = "critical: archived event deserialization failed";
+const RECEIPT_SER_ERROR: &str = "critical: archived receipt serialization failed";
+const RECEIPT_DESER_ERROR: &str = "critical: archived receipt deserialization failed";
+
+/// Size of the primary key of an event: slot key followed by the index in the slot
+const EVENT_KEY_SIZE: usize = SLOT_KEY_SIZE + 8;
+
+/// Primary key of an event, ordered by slot then by index in the slot
+fn event_key(slot: &Slot, index_in_slot: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(EVENT_KEY_SIZE);
+    key.extend(slot.to_bytes_key());
+    key.extend(index_in_slot.to_be_bytes());
+    key
+}
+
+/// Secondary index key: indexed value followed by the primary key of the event
+fn index_key(prefix: &[u8], event_key: &[u8]) -> Vec<u8> {
+    [prefix, event_key].concat()
+}
+
+/// Deserialize an archived event.
+/// The archive only holds final events, some of which were archived before events recorded their finality.
+pub(crate) fn deserialize_event(value: &[u8]) -> SCOutputEvent {
+    let mut event: SCOutputEvent = serde_json::from_slice(value).expect(EVENT_DESER_ERROR);
+    event.context.is_final = true;
+    event
+}
+
+/// On-disk archive of final SC output events
+pub(crate) struct EventArchive {
+    /// RocksDB instance
+    db: DB,
+    /// number of periods during which events are kept
+    retention_periods: u64,
+    /// period before which everything was pruned
+    pruned_until_period: u64,
+}
+
+impl EventArchive {
+    /// Open or create the event archive
+    ///
+    /// # Arguments
+    /// * `path`: path to the archive db directory
+    /// * `retention_periods`: number of periods during which events are kept
+    pub fn new(path: PathBuf, retention_periods: u64) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            path,
+            vec![
+                ColumnFamilyDescriptor::new(EVENTS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(EMITTER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(CALLER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(OPERATION_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(RECEIPTS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(RECEIPT_SLOT_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+            ],
+        )
+        .expect(OPEN_ERROR);
+
+        let metadata_cf = db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        let pruned_until_period = db
+            .get_cf(metadata_cf, PRUNED_UNTIL_KEY)
+            .expect(CRUD_ERROR)
+            .map(|value| {
+                u64::from_be_bytes(
+                    value
+                        .as_slice()
+                        .try_into()
+                        .expect("critical: archive pruning cursor is corrupted"),
+                )
+            })
+            .unwrap_or_default();
+
+        EventArchive {
+            db,
+            retention_periods,
+            pruned_until_period,
+        }
+    }
+
+    /// Archive the events and operation receipts of a newly final slot
+    /// and prune the ones that are out of retention
+    ///
+    /// # Arguments
+    /// * `slot`: the final slot
+    /// * `events`: the events emitted during the execution of that slot
+    /// * `receipts`: the receipts of the operations executed during that slot
+    pub fn archive_slot(&mut self, slot: Slot, events: &EventStore, receipts: &ReceiptsChanges) {
+        let mut batch = WriteBatch::default();
+        for event in events.iter() {
+            self.put_event(event, &mut batch);
+        }
+        for (op_id, receipt) in receipts.0.iter() {
+            self.put_receipt(op_id, receipt, &mut batch);
+        }
+        let min_period = slot.period.saturating_sub(self.retention_periods);
+        if min_period > self.pruned_until_period {
+            self.prune_before(min_period, &mut batch);
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+        self.pruned_until_period = std::cmp::max(self.pruned_until_period, min_period);
+    }
+
+    /// Add an operation receipt and its slot index entry to the batch
+    fn put_receipt(&self, op_id: &OperationId, receipt: &OperationReceipt, batch: &mut WriteBatch) {
+        let receipts_cf = self.db.cf_handle(RECEIPTS_CF).expect(CF_ERROR);
+        let slot_index_cf = self.db.cf_handle(RECEIPT_SLOT_INDEX_CF).expect(CF_ERROR);
+        batch.put_cf(
+            receipts_cf,
+            op_id.to_bytes(),
+            serde_json::to_vec(receipt).expect(RECEIPT_SER_ERROR),
+        );
+        batch.put_cf(
+            slot_index_cf,
+            index_key(&receipt.slot.to_bytes_key(), op_id.to_bytes()),
+            b"",
+        );
+    }
+
+    /// Get the archived receipt of an operation
+    pub fn get_operation_receipt(&self, op_id: &OperationId) -> Option<OperationReceipt> {
+        let receipts_cf = self.db.cf_handle(RECEIPTS_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(receipts_cf, op_id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|value| serde_json::from_slice(&value).expect(RECEIPT_DESER_ERROR))
+    }
+
+    /// Add an event and its index entries to the batch
+    fn put_event(&self, event: &SCOutputEvent, batch: &mut WriteBatch) {
+        let events_cf = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let key = event_key(&event.context.slot, event.context.index_in_slot);
+        batch.put_cf(
+            events_cf,
+            &key,
+            serde_json::to_vec(event).expect(EVENT_SER_ERROR),
+        );
+        for (cf_name, prefix) in Self::index_prefixes(event) {
+            let cf = self.db.cf_handle(cf_name).expect(CF_ERROR);
+            batch.put_cf(cf, index_key(&prefix, &key), b"");
+        }
+    }
+
+    /// Add the deletion of every event emitted and every receipt recorded
+    /// from `self.pruned_until_period` to strictly before `period` (and of their index entries) to the batch,
+    /// along with the update of the stored pruning cursor
+    fn prune_before(&self, period: u64, batch: &mut WriteBatch) {
+        let start = Slot::new(self.pruned_until_period, 0);
+        let end = Slot::new(period, 0);
+
+        let events_cf = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let start_key = event_key(&start, 0);
+        let end_key = event_key(&end, 0);
+        for (key, value) in self.db.iterator_cf(
+            events_cf,
+            IteratorMode::From(&start_key, Direction::Forward),
+        ) {
+            if *key >= *end_key {
+                break;
+            }
+            let event = deserialize_event(&value);
+            for (cf_name, prefix) in Self::index_prefixes(&event) {
+                let cf = self.db.cf_handle(cf_name).expect(CF_ERROR);
+                batch.delete_cf(cf, index_key(&prefix, &key));
+            }
+            batch.delete_cf(events_cf, key);
+        }
+
+        let receipts_cf = self.db.cf_handle(RECEIPTS_CF).expect(CF_ERROR);
+        let slot_index_cf = self.db.cf_handle(RECEIPT_SLOT_INDEX_CF).expect(CF_ERROR);
+        let start_key = start.to_bytes_key();
+        let end_key = end.to_bytes_key();
+        for (key, _) in self.db.iterator_cf(
+            slot_index_cf,
+            IteratorMode::From(&start_key, Direction::Forward),
+        ) {
+            if key[..SLOT_KEY_SIZE] >= end_key[..] {
+                break;
+            }
+            batch.delete_cf(receipts_cf, &key[SLOT_KEY_SIZE..]);
+            batch.delete_cf(slot_index_cf, key);
+        }
+
+        let metadata_cf = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        batch.put_cf(metadata_cf, PRUNED_UNTIL_KEY, period.to_be_bytes());
+    }
+
+    /// Secondary indexes an event belongs to, with the indexed value
+    fn index_prefixes(event: &SCOutputEvent) -> Vec<(&'static str, Vec<u8>)> {
+        let mut prefixes = Vec::with_capacity(3);
+        if let Some(emitter) = event.context.call_stack.front() {
+            prefixes.push((EMITTER_INDEX_CF, emitter.to_bytes().to_vec()));
+        }
+        if let Some(caller) = event.context.call_stack.back() {
+            prefixes.push((CALLER_INDEX_CF, caller.to_bytes().to_vec()));
+        }
+        if let Some(op_id) = event.context.origin_operation_id {
+            prefixes.push((OPERATION_INDEX_CF, op_id.to_bytes().to_vec()));
+        }
+        prefixes
+    }
+
+    /// Get the archived events matching a filter, oldest first, up to the filter's limit.
+    /// The most selective available index is used to iterate over the candidates,
+    /// starting from the latest of the start slot and the pagination cursor.
+    pub fn get_filtered_sc_output_event(&self, filter: &EventFilter) -> Vec<SCOutputEvent> {
+        let start_key = std::cmp::max(
+            filter
+                .start
+                .map(|slot| event_key(&slot, 0))
+                .unwrap_or_default(),
+            filter
+                .cursor
+                .map(|cursor| event_key(&cursor.slot, cursor.index_in_slot))
+                .unwrap_or_default(),
+        );
+        let limit = filter.limit.unwrap_or(usize::MAX);
+        if limit == 0 {
+            return Vec::new();
+        }
+        let end_key = filter.end.map(|slot| event_key(&slot, 0));
+
+        // pick the index to iterate over
+        let index = if let Some(op_id) = filter.original_operation_id {
+            Some((OPERATION_INDEX_CF, op_id.to_bytes().to_vec()))
+        } else if let Some(emitter) = filter.emitter_address {
+            Some((EMITTER_INDEX_CF, emitter.to_bytes().to_vec()))
+        } else {
+            filter
+                .original_caller_address
+                .map(|caller| (CALLER_INDEX_CF, caller.to_bytes().to_vec()))
+        };
+
+        let events_cf = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let mut res = Vec::new();
+        match index {
+            Some((cf_name, prefix)) => {
+                let cf = self.db.cf_handle(cf_name).expect(CF_ERROR);
+                let from = index_key(&prefix, &start_key);
+                for (key, _) in self
+                    .db
+                    .iterator_cf(cf, IteratorMode::From(&from, Direction::Forward))
+                {
+                    if !key.starts_with(&prefix) {
+                        break;
+                    }
+                    let event_key = &key[prefix.len()..];
+                    if matches!(&end_key, Some(end_key) if event_key >= end_key.as_slice()) {
+                        break;
+                    }
+                    if let Some(value) = self.db.get_cf(events_cf, event_key).expect(CRUD_ERROR) {
+                        let event = deserialize_event(&value);
+                        if filter.matches(&event) {
+                            res.push(event);
+                            if res.len() >= limit {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                for (key, value) in self.db.iterator_cf(
+                    events_cf,
+                    IteratorMode::From(&start_key, Direction::Forward),
+                ) {
+                    if matches!(&end_key, Some(end_key) if *key >= **end_key) {
+                        break;
+                    }
+                    let event = deserialize_event(&value);
+                    if filter.matches(&event) {
+                        res.push(event);
+                        if res.len() >= limit {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+}
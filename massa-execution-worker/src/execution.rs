@@ -10,24 +10,31 @@
 
 use crate::active_history::{ActiveHistory, HistorySearchResult};
 use crate::context::ExecutionContext;
+use crate::event_archive::EventArchive;
 use crate::interface_impl::InterfaceImpl;
-use massa_async_pool::AsyncMessage;
+use crate::replay::ReplayRecorder;
+use crate::speculative_ledger::get_datastore_keys_in_range;
+use massa_async_pool::{AsyncMessage, AsyncMessageId, Change};
 use massa_execution_exports::{
-    EventStore, ExecutionConfig, ExecutionError, ExecutionOutput, ExecutionStackElement,
-    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    EventStore, ExecutionChannels, ExecutionConfig, ExecutionError, ExecutionOutput,
+    ExecutionStackElement, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget, SlotExecutionOutput,
 };
 use massa_final_state::FinalState;
-use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
-use massa_models::api::EventFilter;
+use massa_hash::Hash;
+use massa_ledger_exports::{LedgerProof, SetOrDelete, SetUpdateOrDelete};
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::OperationReceipt;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::{Address, BlockId, OperationId, OperationType, WrappedOperation};
 use massa_models::{Amount, Slot};
 use massa_sc_runtime::Interface;
 use massa_storage::Storage;
 use parking_lot::{Mutex, RwLock};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
 use std::{collections::HashMap, sync::Arc};
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Used to acquire a lock on the execution context
 macro_rules! context_guard {
@@ -53,6 +60,10 @@ pub(crate) struct ExecutionState {
     pub final_cursor: Slot,
     // store containing execution events that became final
     final_events: EventStore,
+    // optional on-disk archive of the execution events and operation receipts that became final
+    event_archive: Option<EventArchive>,
+    // optional recording of the final slots for offline replay, stopped on the first failure
+    replay_recorder: Option<ReplayRecorder>,
     // final state with atomic R/W access
     final_state: Arc<RwLock<FinalState>>,
     // execution context (see documentation in context.rs)
@@ -61,6 +72,8 @@ pub(crate) struct ExecutionState {
     execution_interface: Box<dyn Interface>,
     /// Shared storage across all modules
     storage: Storage,
+    /// channels used to broadcast execution outputs
+    channels: ExecutionChannels,
 }
 
 impl ExecutionState {
@@ -70,6 +83,7 @@ impl ExecutionState {
     /// * `config`: execution configuration
     /// * `final_state`: atomic access to the final state
     /// * `storage`: Shared storage with data shared all across the modules
+    /// * `channels`: channels used to broadcast execution outputs
     ///
     /// # returns
     /// A new `ExecutionState`
@@ -77,6 +91,7 @@ impl ExecutionState {
         config: ExecutionConfig,
         final_state: Arc<RwLock<FinalState>>,
         storage: Storage,
+        channels: ExecutionChannels,
     ) -> ExecutionState {
         // Get the slot at the output of which the final state is attached.
         // This should be among the latest final slots.
@@ -97,6 +112,19 @@ impl ExecutionState {
             execution_context.clone(),
         ));
 
+        // open the optional event archive
+        let event_archive = config
+            .event_archive_path
+            .as_ref()
+            .map(|path| EventArchive::new(path.clone(), config.event_archive_retention_periods));
+
+        // start the optional recording of the final slots from the current final state
+        let replay_recorder = config.replay_recording_path.as_ref().and_then(|path| {
+            ReplayRecorder::start(path, &config, &final_state.read())
+                .map_err(|err| warn!("could not start recording final slots for replay: {}", err))
+                .ok()
+        });
+
         // build the execution state
         ExecutionState {
             config,
@@ -107,10 +135,13 @@ impl ExecutionState {
             active_history,
             // empty final event store: it is not recovered through bootstrap
             final_events: Default::default(),
+            event_archive,
+            replay_recorder,
             // no active slots executed yet: set active_cursor to the last final block
             active_cursor: last_final_slot,
             final_cursor: last_final_slot,
             storage,
+            channels,
         }
     }
 
@@ -127,12 +158,40 @@ impl ExecutionState {
     ///
     /// # Arguments
     /// * `exec_ou`t: execution output to apply
-    pub fn apply_final_execution_output(&mut self, exec_out: ExecutionOutput) {
+    pub fn apply_final_execution_output(&mut self, mut exec_out: ExecutionOutput) {
         if self.final_cursor >= exec_out.slot {
             panic!("attempting to apply a final execution output at or before the current final_cursor");
         }
 
-        // apply state changes to the final ledger
+        // the events of the slot are now final
+        exec_out.events.finalize();
+
+        // broadcast the output to the subscribers (no subscriber is not an error)
+        if self.channels.slot_execution_output_sender.receiver_count() > 0 {
+            let _ = self
+                .channels
+                .slot_execution_output_sender
+                .send(SlotExecutionOutput::FinalizedSlot(exec_out.clone()));
+        }
+
+        // record the final slot for offline replay
+        if let Some(recorder) = self.replay_recorder.as_mut() {
+            if let Err(err) = record_final_slot(recorder, &self.storage, &exec_out) {
+                warn!("stopped recording final slots for replay: {}", err);
+                self.replay_recorder = None;
+            }
+        }
+
+        // archive generated events and receipts
+        if let Some(event_archive) = self.event_archive.as_mut() {
+            event_archive.archive_slot(
+                exec_out.slot,
+                &exec_out.events,
+                &exec_out.state_changes.receipts_changes,
+            );
+        }
+
+        // apply state changes to the final ledger, including the receipts of the slot
         self.final_state
             .write()
             .finalize(exec_out.slot, exec_out.state_changes);
@@ -166,6 +225,14 @@ impl ExecutionState {
         // update active cursor to reflect the new latest active slot
         self.active_cursor = exec_out.slot;
 
+        // broadcast the output to the subscribers (no subscriber is not an error)
+        if self.channels.slot_execution_output_sender.receiver_count() > 0 {
+            let _ = self
+                .channels
+                .slot_execution_output_sender
+                .send(SlotExecutionOutput::ExecutedSlot(exec_out.clone()));
+        }
+
         // add the execution output at the end of the output history
         self.active_history.write().0.push_back(exec_out);
     }
@@ -236,37 +303,159 @@ impl ExecutionState {
     /// Execute an operation in the context of a block.
     /// Assumes the execution context was initialized at the beginning of the slot.
     ///
+    /// The base cost of an operation is reserved out of its `max_gas` before the execution,
+    /// which is only given the rest. An operation whose `max_gas` does not cover the base cost
+    /// is therefore run without gas and fails.
+    /// Once the operation is executed, only the consumed gas plus the base cost is charged:
+    /// the block creator is credited with the charged gas and the sender is refunded the rest of `max_gas`.
+    /// When simulating, nothing was paid for the operation so nothing is credited,
+    /// but the receipt still reports the coins that would be refunded.
+    ///
     /// # Arguments
     /// * `operation`: operation to execute
-    /// * `block_creator_addr`: address of the block creator
+    /// * `block_creator_addr`: address of the block creator to credit with the gas fees, `None` when simulating
+    ///
+    /// # Returns
+    /// The remaining gas of the operation, or an error
     pub fn execute_operation(
         &self,
         operation: &WrappedOperation,
-        block_creator_addr: Address,
-    ) -> Result<(), ExecutionError> {
+        block_creator_addr: Option<Address>,
+    ) -> Result<u64, ExecutionError> {
         // prefilter only SC operations
-        match &operation.content.op {
-            OperationType::ExecuteSC { .. } => {}
-            OperationType::CallSC { .. } => {}
-            _ => return Ok(()),
+        let (max_gas, gas_price) = match &operation.content.op {
+            OperationType::ExecuteSC {
+                max_gas, gas_price, ..
+            } => (*max_gas, *gas_price),
+            OperationType::CallSC {
+                max_gas, gas_price, ..
+            } => (*max_gas, *gas_price),
+            _ => return Ok(0),
         };
 
+        // index of the next event and storage costs so far, to report the ones of the operation
+        let (first_event_index, previous_storage_charged, previous_storage_refunded) = {
+            let context = context_guard!(self);
+            (
+                context.created_event_index,
+                context.storage_costs_charged,
+                context.storage_costs_refunded,
+            )
+        };
+
+        // reserve the base cost before the execution
+        let execution_gas = max_gas.saturating_sub(self.config.operation_base_gas_cost);
+
         // call the execution process specific to the operation type
-        match &operation.content.op {
+        let result = match &operation.content.op {
             OperationType::ExecuteSC { .. } => self.execute_executesc_op(
                 &operation.content.op,
-                block_creator_addr,
+                execution_gas,
                 operation.id,
                 operation.creator_address,
             ),
             OperationType::CallSC { .. } => self.execute_callsc_op(
                 &operation.content.op,
-                block_creator_addr,
+                execution_gas,
                 operation.id,
                 operation.creator_address,
             ),
             _ => panic!("unexpected operation type"), // checked at the beginning of the function
+        };
+
+        // Charge the consumed gas plus the base cost, that fit in max_gas since the base cost was reserved.
+        // A failed execution is charged its whole max_gas.
+        let (gas_used, gas_charged) = match &result {
+            Ok(remaining_gas) => {
+                let gas_used = execution_gas.saturating_sub(*remaining_gas);
+                (
+                    gas_used,
+                    gas_used.saturating_add(self.config.operation_base_gas_cost),
+                )
+            }
+            Err(_) => (execution_gas, max_gas),
+        };
+        let mut refunded_coins = gas_price.saturating_mul_u64(max_gas - gas_charged);
+
+        let mut context = context_guard!(self);
+
+        // Credit the block producer with the charged gas fees.
+        // Note that errors are deterministic and do not cancel the operation execution.
+        if let Some(block_creator_addr) = block_creator_addr {
+            let gas_fees = gas_price.saturating_mul_u64(gas_charged);
+            if let Err(err) =
+                context.transfer_parallel_coins(None, Some(block_creator_addr), gas_fees)
+            {
+                debug!(
+                    "failed to credit block producer {} with {} gas fee coins: {}",
+                    block_creator_addr, gas_fees, err
+                );
+            }
         }
+
+        // Refund the unused gas to the sender, that paid for max_gas when the operation was included.
+        if block_creator_addr.is_some() && refunded_coins > Amount::zero() {
+            match context.transfer_parallel_coins(
+                None,
+                Some(operation.creator_address),
+                refunded_coins,
+            ) {
+                Ok(()) => {
+                    // emit the refund event on behalf of the sender, keeping the stack of the context as is
+                    let stack = std::mem::replace(
+                        &mut context.stack,
+                        vec![ExecutionStackElement {
+                            address: operation.creator_address,
+                            coins: Amount::zero(),
+                            owned_addresses: vec![operation.creator_address],
+                        }],
+                    );
+                    context.origin_operation_id = Some(operation.id);
+                    let event = context.event_create(
+                        serde_json::json!({
+                            "massa_gas_refund": {
+                                "gas_used": gas_used,
+                                "gas_charged": gas_charged,
+                                "refunded_coins": refunded_coins.to_string(),
+                            }
+                        })
+                        .to_string(),
+                    );
+                    context.event_emit(event);
+                    context.origin_operation_id = None;
+                    context.stack = stack;
+                }
+                Err(err) => {
+                    debug!(
+                        "failed to refund operation sender {} with {} gas coins: {}",
+                        operation.creator_address, refunded_coins, err
+                    );
+                    refunded_coins = Amount::zero();
+                }
+            }
+        }
+
+        // record the receipt of the operation
+        let receipt = OperationReceipt {
+            slot: context.slot,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|err| err.to_string()),
+            gas_used,
+            gas_charged,
+            refunded_coins,
+            event_count: context
+                .created_event_index
+                .saturating_sub(first_event_index),
+            storage_cost_charged: context
+                .storage_costs_charged
+                .saturating_sub(previous_storage_charged),
+            storage_cost_refunded: context
+                .storage_costs_refunded
+                .saturating_sub(previous_storage_refunded),
+        };
+        context.receipts.0.insert(operation.id, receipt);
+
+        result
     }
 
     /// Execute an operation of type `ExecuteSC`
@@ -274,24 +463,27 @@ impl ExecutionState {
     ///
     /// # Arguments
     /// * `operation`: the `WrappedOperation` to process, must be an `ExecuteSC`
-    /// * `block_creator_addr`: address of the block creator
+    /// * `max_gas`: gas available for the execution, the `max_gas` of the operation minus the base cost
     /// * `operation_id`: ID of the operation
     /// * `sender_addr`: address of the sender
+    ///
+    /// # Returns
+    /// The remaining gas of the operation, or an error
     pub fn execute_executesc_op(
         &self,
         operation: &OperationType,
-        block_creator_addr: Address,
+        max_gas: u64,
         operation_id: OperationId,
         sender_addr: Address,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // process ExecuteSC operations only
-        let (bytecode, max_gas, coins, gas_price) = match &operation {
+        let (bytecode, coins, gas_price) = match &operation {
             OperationType::ExecuteSC {
                 data,
-                max_gas,
                 coins,
                 gas_price,
-            } => (data, max_gas, coins, gas_price),
+                ..
+            } => (data, coins, gas_price),
             _ => panic!("unexpected operation type"),
         };
 
@@ -301,19 +493,6 @@ impl ExecutionState {
             // acquire write access to the context
             let mut context = context_guard!(self);
 
-            // Use the context to credit the producer of the block with max_gas * gas_price parallel coins.
-            // Note that errors are deterministic and do not cancel the operation execution.
-            // That way, even if the sender sent an invalid operation, the block producer will still get credited.
-            let gas_fees = gas_price.saturating_mul_u64(*max_gas);
-            if let Err(err) =
-                context.transfer_parallel_coins(None, Some(block_creator_addr), gas_fees)
-            {
-                debug!(
-                    "failed to credit block producer {} with {} gas fee coins: {}",
-                    block_creator_addr, gas_fees, err
-                );
-            }
-
             // Credit the operation sender with `coins` parallel coins.
             // Note that errors are deterministic and do not cancel op execution.
             if let Err(err) = context.transfer_parallel_coins(None, Some(sender_addr), *coins) {
@@ -330,8 +509,8 @@ impl ExecutionState {
             // set the context gas price to match the one defined in the operation
             context.gas_price = *gas_price;
 
-            // set the context max gas to match the one defined in the operation
-            context.max_gas = *max_gas;
+            // set the context max gas to the gas available for the execution
+            context.max_gas = max_gas;
 
             // Set the call stack to a single element:
             // * the execution will happen in the context of the address of the operation's sender
@@ -348,18 +527,19 @@ impl ExecutionState {
         };
 
         // run the VM on the bytecode contained in the operation
-        let run_result = massa_sc_runtime::run_main(bytecode, *max_gas, &*self.execution_interface);
-        if let Err(err) = run_result {
-            // there was an error during bytecode execution:
-            // cancel the effects of the execution by resetting the context to the previously saved snapshot
-            let err = ExecutionError::RuntimeError(format!("bytecode execution error: {}", err));
-            let mut context = context_guard!(self);
-            context.reset_to_snapshot(context_snapshot, Some(err.clone()));
-            context.origin_operation_id = None;
-            return Err(err);
+        match massa_sc_runtime::run_main(bytecode, max_gas, &*self.execution_interface) {
+            Ok(remaining_gas) => Ok(remaining_gas),
+            Err(err) => {
+                // there was an error during bytecode execution:
+                // cancel the effects of the execution by resetting the context to the previously saved snapshot
+                let err =
+                    ExecutionError::RuntimeError(format!("bytecode execution error: {}", err));
+                let mut context = context_guard!(self);
+                context.reset_to_snapshot(context_snapshot, Some(err.clone()));
+                context.origin_operation_id = None;
+                Err(err)
+            }
         }
-
-        Ok(())
     }
 
     /// Execute an operation of type `CallSC`
@@ -367,30 +547,32 @@ impl ExecutionState {
     ///
     /// # Arguments
     /// * `operation`: the `WrappedOperation` to process, must be an `CallSC`
-    /// * `block_creator_addr`: address of the block creator
+    /// * `max_gas`: gas available for the execution, the `max_gas` of the operation minus the base cost
     /// * `operation_id`: ID of the operation
     /// * `sender_addr`: address of the sender
+    ///
+    /// # Returns
+    /// The remaining gas of the operation, or an error
     pub fn execute_callsc_op(
         &self,
         operation: &OperationType,
-        block_creator_addr: Address,
+        max_gas: u64,
         operation_id: OperationId,
         sender_addr: Address,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // process CallSC operations only
-        let (gas_price, max_gas, target_addr, target_func, param, parallel_coins, sequential_coins) =
+        let (gas_price, target_addr, target_func, param, parallel_coins, sequential_coins) =
             match &operation {
                 OperationType::CallSC {
                     gas_price,
-                    max_gas,
                     target_addr,
                     target_func,
                     param,
                     parallel_coins,
                     sequential_coins,
+                    ..
                 } => (
                     *gas_price,
-                    *max_gas,
                     *target_addr,
                     target_func,
                     param,
@@ -407,19 +589,6 @@ impl ExecutionState {
             // acquire write access to the context
             let mut context = context_guard!(self);
 
-            // Use the context to credit the producer of the block with max_gas * gas_price parallel coins.
-            // Note that errors are deterministic and do not cancel the operation execution.
-            // That way, even if the sender sent an invalid operation, the block producer will still get credited.
-            let gas_fees = gas_price.saturating_mul_u64(max_gas);
-            if let Err(err) =
-                context.transfer_parallel_coins(None, Some(block_creator_addr), gas_fees)
-            {
-                debug!(
-                    "failed to credit block producer {} with {} gas fee coins: {}",
-                    block_creator_addr, gas_fees, err
-                );
-            }
-
             // Credit the operation sender with `sequential_coins` parallel coins.
             // This is used to ensure that those coins are not lost in case of failure,
             // since they have been debited by consensus beforehand.
@@ -447,7 +616,7 @@ impl ExecutionState {
             // set the context gas price to match the one defined in the operation
             context.gas_price = gas_price;
 
-            // set the context max gas to match the one defined in the operation
+            // set the context max gas to the gas available for the execution
             context.max_gas = max_gas;
 
             // set the context origin operation ID
@@ -488,28 +657,29 @@ impl ExecutionState {
 
         // quit if there is no function to be called
         if target_func.is_empty() {
-            return Ok(());
+            return Ok(max_gas);
         }
 
         // run the VM on the called fucntion of the bytecode
-        let run_result = massa_sc_runtime::run_function(
+        match massa_sc_runtime::run_function(
             &bytecode,
             max_gas,
             target_func,
             param,
             &*self.execution_interface,
-        );
-        if let Err(err) = run_result {
-            // there was an error during bytecode execution:
-            // cancel the effects of the execution by resetting the context to the previously saved snapshot
-            let err = ExecutionError::RuntimeError(format!("bytecode execution error: {}", err));
-            let mut context = context_guard!(self);
-            context.reset_to_snapshot(context_snapshot, Some(err.clone()));
-            context.origin_operation_id = None;
-            return Err(err);
+        ) {
+            Ok(remaining_gas) => Ok(remaining_gas),
+            Err(err) => {
+                // there was an error during bytecode execution:
+                // cancel the effects of the execution by resetting the context to the previously saved snapshot
+                let err =
+                    ExecutionError::RuntimeError(format!("bytecode execution error: {}", err));
+                let mut context = context_guard!(self);
+                context.reset_to_snapshot(context_snapshot, Some(err.clone()));
+                context.origin_operation_id = None;
+                Err(err)
+            }
         }
-
-        Ok(())
     }
 
     /// Tries to execute an asynchronous message
@@ -596,6 +766,26 @@ impl ExecutionState {
             context.cancel_async_message(&message);
             Err(err)
         } else {
+            // report the storage costs of the message, that has no receipt
+            let mut context = context_guard!(self);
+            let storage_cost_charged = context
+                .storage_costs_charged
+                .saturating_sub(context_snapshot.storage_costs_charged);
+            let storage_cost_refunded = context
+                .storage_costs_refunded
+                .saturating_sub(context_snapshot.storage_costs_refunded);
+            if storage_cost_charged > Amount::zero() || storage_cost_refunded > Amount::zero() {
+                let event = context.event_create(
+                    serde_json::json!({
+                        "massa_storage_cost": {
+                            "charged": storage_cost_charged.to_string(),
+                            "refunded": storage_cost_refunded.to_string(),
+                        }
+                    })
+                    .to_string(),
+                );
+                context.event_emit(event);
+            }
             Ok(())
         }
     }
@@ -644,8 +834,8 @@ impl ExecutionState {
             // Try executing the operations of this block in the order in which they appear in the block.
             // Errors are logged but do not interrupt the execution of the slot.
             for (op_idx, operation) in stored_block.content.operations.iter().enumerate() {
-                if let Err(err) =
-                    self.execute_operation(operation, stored_block.content.header.creator_address)
+                if let Err(err) = self
+                    .execute_operation(operation, Some(stored_block.content.header.creator_address))
                 {
                     debug!(
                         "failed executing operation index {} in block {}: {}",
@@ -668,19 +858,22 @@ impl ExecutionState {
     /// * `req`: a read-only execution request
     ///
     /// # Returns
-    ///  `ExecutionOutput` describing the output of the execution, or an error
+    ///  `ReadOnlyExecutionOutput` describing the output of the execution and its gas cost, or an error
     pub(crate) fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         // set the execution slot to be the one after the latest executed active slot
         let slot = self
             .active_cursor
             .get_next_slot(self.config.thread_count)
             .expect("slot overflow in readonly execution");
 
-        // create a readonly execution context
-        let execution_context = ExecutionContext::readonly(
+        // the caller is at the bottom of the call stack
+        let caller_addr = req.call_stack.first().map(|element| element.address);
+
+        // create a readonly execution context, recording the trace of the execution if requested
+        let mut execution_context = ExecutionContext::readonly(
             slot,
             req.max_gas,
             req.simulated_gas_price,
@@ -688,44 +881,143 @@ impl ExecutionState {
             self.final_state.clone(),
             self.active_history.clone(),
         );
+        execution_context.trace = req.trace.then(Vec::new);
 
         // run the intepreter according to the target type
-        match req.target {
-            ReadOnlyExecutionTarget::BytecodeExecution(bytecode) => {
-                // set the execution context for execution
-                *context_guard!(self) = execution_context;
-
-                // run the bytecode's main function
-                massa_sc_runtime::run_main(&bytecode, req.max_gas, &*self.execution_interface)
-                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
-            }
-            ReadOnlyExecutionTarget::FunctionCall {
-                target_addr,
-                target_func,
-                parameter,
-            } => {
-                // get the bytecode, default to an empty vector
-                let bytecode = execution_context
-                    .get_bytecode(&target_addr)
-                    .unwrap_or_default();
-
-                // set the execution context for execution
-                *context_guard!(self) = execution_context;
-
-                // run the target function in the bytecode
-                massa_sc_runtime::run_function(
-                    &bytecode,
-                    req.max_gas,
-                    &target_func,
-                    &parameter,
-                    &*self.execution_interface,
-                )
-                .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
-            }
+        let run = || -> Result<u64, ExecutionError> {
+            Ok(match req.target {
+                ReadOnlyExecutionTarget::BytecodeExecution(bytecode) => {
+                    // set the execution context for execution
+                    *context_guard!(self) = execution_context;
+
+                    // credit the caller as it would be for an operation
+                    self.credit_readonly_caller(caller_addr, req.credited_coins)?;
+
+                    // run the bytecode's main function
+                    massa_sc_runtime::run_main(&bytecode, req.max_gas, &*self.execution_interface)
+                        .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?
+                }
+                ReadOnlyExecutionTarget::FunctionCall {
+                    target_addr,
+                    target_func,
+                    parameter,
+                } => {
+                    // get the bytecode, default to an empty vector
+                    let bytecode = execution_context
+                        .get_bytecode(&target_addr)
+                        .unwrap_or_default();
+
+                    // set the execution context for execution
+                    *context_guard!(self) = execution_context;
+
+                    // credit the caller and transfer the call coins as it would be for an operation
+                    self.credit_readonly_caller(caller_addr, req.credited_coins)?;
+                    if req.transferred_coins > Amount::zero() {
+                        let mut context = context_guard!(self);
+                        // the caller needs to be on top of the stack to spend its coins
+                        let target_element = context.stack.pop();
+                        let transfer_result = context.transfer_parallel_coins(
+                            caller_addr,
+                            Some(target_addr),
+                            req.transferred_coins,
+                        );
+                        context.stack.extend(target_element);
+                        transfer_result?;
+                    }
+
+                    // run the target function in the bytecode
+                    massa_sc_runtime::run_function(
+                        &bytecode,
+                        req.max_gas,
+                        &target_func,
+                        &parameter,
+                        &*self.execution_interface,
+                    )
+                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?
+                }
+                ReadOnlyExecutionTarget::Operation(operation) => {
+                    // set the execution context for execution
+                    *context_guard!(self) = execution_context;
+
+                    // execute the operation as if it was included in a block at the next slot,
+                    // the error is returned instead of the execution output on failure
+                    self.execute_operation(&operation, None)?
+                }
+            })
+        };
+        let result = run();
+
+        // return the execution output, or the error along with the trace of the execution
+        let mut context = context_guard!(self);
+        let trace = context.trace.take();
+        match result {
+            Ok(remaining_gas) => Ok(ReadOnlyExecutionOutput {
+                gas_cost: req.max_gas.saturating_sub(remaining_gas),
+                out: context.settle_slot(),
+                trace,
+            }),
+            Err(error) => Err(match trace {
+                Some(trace) => ExecutionError::TracedError {
+                    error: Box::new(error),
+                    trace,
+                },
+                None => error,
+            }),
         }
+    }
 
-        // return the execution output
-        Ok(context_guard!(self).settle_slot())
+    /// Credit the caller of a read-only execution with parallel coins, if any
+    fn credit_readonly_caller(
+        &self,
+        caller_addr: Option<Address>,
+        amount: Amount,
+    ) -> Result<(), ExecutionError> {
+        if amount == Amount::zero() {
+            return Ok(());
+        }
+        let caller_addr = caller_addr.ok_or_else(|| {
+            ExecutionError::RuntimeError("cannot credit coins without a caller".into())
+        })?;
+        context_guard!(self).transfer_parallel_coins(None, Some(caller_addr), amount)
+    }
+
+    /// Gets the receipt of an operation both at the latest final and active executed slots
+    ///
+    /// # Returns
+    /// `(final_receipt, active_receipt)`
+    pub fn get_final_and_active_operation_receipt(
+        &self,
+        op_id: &OperationId,
+    ) -> (Option<OperationReceipt>, Option<OperationReceipt>) {
+        // receipts out of the retention of the final state may still be in the event archive
+        let final_receipt = self
+            .final_state
+            .read()
+            .receipts
+            .get(op_id)
+            .cloned()
+            .or_else(|| {
+                self.event_archive
+                    .as_ref()
+                    .and_then(|event_archive| event_archive.get_operation_receipt(op_id))
+            });
+        // the latest execution of the operation in the active history takes precedence
+        let active_receipt = self
+            .active_history
+            .read()
+            .0
+            .iter()
+            .rev()
+            .find_map(|exec_out| {
+                exec_out
+                    .state_changes
+                    .receipts_changes
+                    .0
+                    .get(op_id)
+                    .cloned()
+            })
+            .or_else(|| final_receipt.clone());
+        (final_receipt, active_receipt)
     }
 
     /// Gets a parallel balance both at the latest final and active executed slots
@@ -769,6 +1061,74 @@ impl ExecutionState {
         )
     }
 
+    /// Gets the final parallel balance of an address at the output of a past final slot
+    pub fn get_final_parallel_balance_at_slot(
+        &self,
+        address: &Address,
+        slot: Slot,
+    ) -> Result<Option<Amount>, ExecutionError> {
+        self.final_state
+            .read()
+            .ledger
+            .get_parallel_balance_at_slot(address, slot)
+            .map_err(|err| ExecutionError::LedgerError(err.to_string()))
+    }
+
+    /// Gets a final data entry at the output of a past final slot
+    pub fn get_final_data_entry_at_slot(
+        &self,
+        address: &Address,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, ExecutionError> {
+        self.final_state
+            .read()
+            .ledger
+            .get_data_entry_at_slot(address, key, slot)
+            .map_err(|err| ExecutionError::LedgerError(err.to_string()))
+    }
+
+    /// Get the slot the final state is attached to and the fingerprint of the final state at that slot
+    pub fn get_final_state_fingerprint(&self) -> (Slot, Hash) {
+        let final_state = self.final_state.read();
+        (final_state.slot, final_state.fingerprint)
+    }
+
+    /// Get the final values of an address along with their proofs against the final ledger root,
+    /// at the output of the latest final slot or of a past one
+    pub fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> Result<LedgerProof, ExecutionError> {
+        self.final_state
+            .read()
+            .get_ledger_proof(addr, keys, slot)
+            .map_err(|err| ExecutionError::LedgerError(err.to_string()))
+    }
+
+    /// Get the candidate datastore keys of the given address within a range, in increasing order
+    pub fn get_candidate_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> Vec<Vec<u8>> {
+        let final_state = self.final_state.read();
+        let active_history = self.active_history.read();
+        get_datastore_keys_in_range(
+            &final_state,
+            active_history
+                .0
+                .iter()
+                .map(|output| &output.state_changes.ledger_changes),
+            addr,
+            range,
+            limit,
+        )
+    }
+
     /// Get every final and active datastore key of the given address
     pub fn get_final_and_active_datastore_keys(
         &self,
@@ -809,6 +1169,61 @@ impl ExecutionState {
         (final_keys, candidate_keys)
     }
 
+    /// Get the pending messages of the candidate asynchronous pool matching a filter, by decreasing priority
+    pub fn get_async_messages(&self, filter: &AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        let matches = |msg: &AsyncMessage| {
+            filter.sender.map_or(true, |sender| msg.sender == sender)
+                && filter
+                    .destination
+                    .map_or(true, |destination| msg.destination == destination)
+                && filter
+                    .start
+                    .map_or(true, |start| msg.emission_slot >= start)
+                && filter.end.map_or(true, |end| msg.emission_slot < end)
+        };
+
+        // here, get the matching final messages, then traverse the history from oldest to newest,
+        // applying additions and deletions
+        let mut messages: BTreeMap<AsyncMessageId, AsyncMessage> = self
+            .final_state
+            .read()
+            .async_pool
+            .iter()
+            .filter(|(_id, msg)| matches(msg))
+            .map(|(id, msg)| (*id, msg.clone()))
+            .collect();
+        for output in &self.active_history.read().0 {
+            for change in &output.state_changes.async_pool_changes.0 {
+                match change {
+                    Change::Add(id, msg) if matches(msg) => {
+                        messages.insert(*id, msg.clone());
+                    }
+                    Change::Add(..) => {}
+                    Change::Delete(id) => {
+                        messages.remove(id);
+                    }
+                }
+            }
+        }
+
+        messages
+            .into_values()
+            .map(|msg| AsyncMessageInfo {
+                emission_slot: msg.emission_slot,
+                emission_index: msg.emission_index,
+                sender: msg.sender,
+                destination: msg.destination,
+                handler: msg.handler,
+                max_gas: msg.max_gas,
+                gas_price: msg.gas_price,
+                coins: msg.coins,
+                validity_start: msg.validity_start,
+                validity_end: msg.validity_end,
+                data: msg.data,
+            })
+            .collect()
+    }
+
     /// Gets execution events optionally filtered by:
     /// * start slot
     /// * end slot
@@ -816,8 +1231,16 @@ impl ExecutionState {
     /// * original caller address
     /// * operation id
     pub fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent> {
-        self.final_events
-            .get_filtered_sc_output_event(&filter)
+        // the archive, if any, holds a superset of the final events kept in memory
+        let final_events: Vec<SCOutputEvent> = match &self.event_archive {
+            Some(event_archive) => event_archive.get_filtered_sc_output_event(&filter),
+            None => self
+                .final_events
+                .get_filtered_sc_output_event(&filter)
+                .into_iter()
+                .collect(),
+        };
+        final_events
             .into_iter()
             .chain(
                 self.active_history
@@ -826,6 +1249,23 @@ impl ExecutionState {
                     .iter()
                     .flat_map(|item| item.events.get_filtered_sc_output_event(&filter)),
             )
+            .take(filter.limit.unwrap_or(usize::MAX))
             .collect()
     }
 }
+
+/// Record a final slot for offline replay, with its final block retrieved from storage
+fn record_final_slot(
+    recorder: &mut ReplayRecorder,
+    storage: &Storage,
+    exec_out: &ExecutionOutput,
+) -> Result<(), ExecutionError> {
+    let block = match exec_out.block_id {
+        Some(block_id) => Some(storage.retrieve_block(&block_id).ok_or_else(|| {
+            ExecutionError::ReplayError(format!("missing final block {} in storage", block_id))
+        })?),
+        None => None,
+    };
+    let block = block.as_ref().map(|block| block.read());
+    recorder.record_slot(exec_out.slot, block.as_deref(), &exec_out.state_changes)
+}
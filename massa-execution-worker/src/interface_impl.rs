@@ -5,19 +5,24 @@
 //! for example to interact with the ledger.
 //! See the definition of Interface in the massa-sc-runtime crate for functional details.
 
-use crate::context::ExecutionContext;
+use crate::context::{trace_value, ExecutionContext};
 use anyhow::{bail, Result};
 use massa_async_pool::AsyncMessage;
 use massa_execution_exports::ExecutionConfig;
 use massa_execution_exports::ExecutionStackElement;
+use massa_models::execution::ExecutionTraceStep;
 use massa_models::{timeslots::get_block_slot_timestamp, Address, Amount, Slot};
 use massa_sc_runtime::{Interface, InterfaceClone};
 use parking_lot::Mutex;
 use rand::Rng;
+use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::Arc;
 use tracing::debug;
 
+/// maximal length of the representation of an ABI argument or result in the trace of an execution
+const MAX_TRACE_REPR_LENGTH: usize = 256;
+
 /// helper for locking the context mutex
 macro_rules! context_guard {
     ($self:ident) => {
@@ -43,6 +48,50 @@ impl InterfaceImpl {
     pub fn new(config: ExecutionConfig, context: Arc<Mutex<ExecutionContext>>) -> InterfaceImpl {
         InterfaceImpl { config, context }
     }
+
+    /// Runs the implementation of an ABI function,
+    /// recording the call in the trace of the execution if it is traced.
+    /// The call is recorded when it returns, after the steps it caused.
+    ///
+    /// # Arguments
+    /// * `function`: name of the ABI function
+    /// * `arguments`: arguments of the call
+    /// * `run`: implementation of the ABI function
+    fn traced<T: Debug>(
+        &self,
+        function: &str,
+        arguments: &[&dyn Debug],
+        run: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let result = run();
+        context_guard!(self).trace_step(|| ExecutionTraceStep::AbiCall {
+            function: function.to_string(),
+            arguments: arguments
+                .iter()
+                .map(|argument| trace_repr(argument))
+                .collect(),
+            result: result.as_ref().ok().map(|value| trace_repr(value)),
+            error: result.as_ref().err().map(|err| err.to_string()),
+            // the gas meter of the runtime is not exposed through its `Interface` trait
+            remaining_gas: None,
+        });
+        result
+    }
+}
+
+/// Debug representation of an ABI argument or result in the trace of an execution,
+/// truncated to `MAX_TRACE_REPR_LENGTH` characters
+fn trace_repr(value: &dyn Debug) -> String {
+    let repr = format!("{:?}", value);
+    let length = repr.chars().count();
+    if length <= MAX_TRACE_REPR_LENGTH {
+        return repr;
+    }
+    format!(
+        "{}... ({} characters)",
+        repr.chars().take(MAX_TRACE_REPR_LENGTH).collect::<String>(),
+        length
+    )
 }
 
 impl InterfaceClone for InterfaceImpl {
@@ -59,8 +108,10 @@ impl InterfaceClone for InterfaceImpl {
 impl Interface for InterfaceImpl {
     /// prints a message in the node logs at log level 3 (debug)
     fn print(&self, message: &str) -> Result<()> {
-        debug!("SC print: {}", message);
-        Ok(())
+        self.traced("print", &[&message], || {
+            debug!("SC print: {}", message);
+            Ok(())
+        })
     }
 
     /// Initialize the call when bytecode calls a function from another bytecode
@@ -75,59 +126,72 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The target bytecode or an error
     fn init_call(&self, address: &str, raw_coins: u64) -> Result<Vec<u8>> {
-        // get target address
-        let to_address = massa_models::Address::from_str(address)?;
-
-        // write-lock context
-        let mut context = context_guard!(self);
-
-        // get target bytecode
-        let bytecode = match context.get_bytecode(&to_address) {
-            Some(bytecode) => bytecode,
-            None => bail!("bytecode not found for address {}", to_address),
-        };
-
-        // get caller address
-        let from_address = match context.stack.last() {
-            Some(addr) => addr.address,
-            _ => bail!("failed to read call stack current address"),
-        };
-
-        // transfer coins from caller to target address
-        let coins = massa_models::Amount::from_raw(raw_coins);
-        if let Err(err) =
-            context.transfer_parallel_coins(Some(from_address), Some(to_address), coins)
-        {
-            bail!(
-                "error transferring {} parallel coins from {} to {}: {}",
+        self.traced("init_call", &[&address, &raw_coins], || {
+            // get target address
+            let to_address = massa_models::Address::from_str(address)?;
+
+            // write-lock context
+            let mut context = context_guard!(self);
+
+            // get target bytecode
+            let bytecode = match context.get_bytecode(&to_address) {
+                Some(bytecode) => bytecode,
+                None => bail!("bytecode not found for address {}", to_address),
+            };
+
+            // get caller address
+            let from_address = match context.stack.last() {
+                Some(addr) => addr.address,
+                _ => bail!("failed to read call stack current address"),
+            };
+
+            // transfer coins from caller to target address
+            let coins = massa_models::Amount::from_raw(raw_coins);
+            if let Err(err) =
+                context.transfer_parallel_coins(Some(from_address), Some(to_address), coins)
+            {
+                bail!(
+                    "error transferring {} parallel coins from {} to {}: {}",
+                    coins,
+                    from_address,
+                    to_address,
+                    err
+                );
+            }
+
+            // push a new call stack element on top of the current call stack
+            context.stack.push(ExecutionStackElement {
+                address: to_address,
                 coins,
-                from_address,
-                to_address,
-                err
-            );
-        }
-
-        // push a new call stack element on top of the current call stack
-        context.stack.push(ExecutionStackElement {
-            address: to_address,
-            coins,
-            owned_addresses: vec![to_address],
-        });
+                owned_addresses: vec![to_address],
+            });
+            context.trace_step(|| ExecutionTraceStep::CallEnter {
+                address: to_address,
+                coins,
+            });
 
-        // return the target bytecode
-        Ok(bytecode)
+            // return the target bytecode
+            Ok(bytecode)
+        })
     }
 
     /// Called to finish the call process after a bytecode calls a function from another one.
     /// This function just pops away the top element of the call stack.
     fn finish_call(&self) -> Result<()> {
-        let mut context = context_guard!(self);
+        self.traced("finish_call", &[], || {
+            let mut context = context_guard!(self);
 
-        if context.stack.pop().is_none() {
-            bail!("call stack out of bounds")
-        }
+            let address = match context.stack.pop() {
+                Some(element) => element.address,
+                None => bail!("call stack out of bounds"),
+            };
+            context.trace_step(|| ExecutionTraceStep::CallExit {
+                address,
+                gas_used: None,
+            });
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Gets the parallel balance of the current address address (top of the stack).
@@ -136,12 +200,14 @@ impl Interface for InterfaceImpl {
     /// The raw representation (no decimal factor) of the parallel balance of the address,
     /// or zero if the address is not found in the ledger.
     fn get_balance(&self) -> Result<u64> {
-        let context = context_guard!(self);
-        let address = context.get_current_address()?;
-        Ok(context
-            .get_parallel_balance(&address)
-            .unwrap_or_default()
-            .to_raw())
+        self.traced("get_balance", &[], || {
+            let context = context_guard!(self);
+            let address = context.get_current_address()?;
+            Ok(context
+                .get_parallel_balance(&address)
+                .unwrap_or_default()
+                .to_raw())
+        })
     }
 
     /// Gets the parallel balance of arbitrary address passed as argument.
@@ -153,11 +219,13 @@ impl Interface for InterfaceImpl {
     /// The raw representation (no decimal factor) of the parallel balance of the address,
     /// or zero if the address is not found in the ledger.
     fn get_balance_for(&self, address: &str) -> Result<u64> {
-        let address = massa_models::Address::from_str(address)?;
-        Ok(context_guard!(self)
-            .get_parallel_balance(&address)
-            .unwrap_or_default()
-            .to_raw())
+        self.traced("get_balance_for", &[&address], || {
+            let address = massa_models::Address::from_str(address)?;
+            Ok(context_guard!(self)
+                .get_parallel_balance(&address)
+                .unwrap_or_default()
+                .to_raw())
+        })
     }
 
     /// Creates a new ledger entry with the initial bytecode given as argument.
@@ -169,10 +237,12 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the newly created address
     fn create_module(&self, bytecode: &[u8]) -> Result<String> {
-        match context_guard!(self).create_new_sc_address(bytecode.to_vec()) {
-            Ok(addr) => Ok(addr.to_string()),
-            Err(err) => bail!("couldn't create new SC address: {}", err),
-        }
+        self.traced("create_module", &[&bytecode], || {
+            match context_guard!(self).create_new_sc_address(bytecode.to_vec()) {
+                Ok(addr) => Ok(addr.to_string()),
+                Err(err) => bail!("couldn't create new SC address: {}", err),
+            }
+        })
     }
 
     /// Gets a datastore value by key for a given address.
@@ -184,12 +254,21 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data_for(&self, address: &str, key: &str) -> Result<Vec<u8>> {
-        let addr = &massa_models::Address::from_str(address)?;
-        let context = context_guard!(self);
-        match context.get_data_entry(addr, key.as_bytes()) {
-            Some(value) => Ok(value),
-            _ => bail!("data entry not found"),
-        }
+        self.traced("raw_get_data_for", &[&address, &key], || {
+            let addr = massa_models::Address::from_str(address)?;
+            let mut context = context_guard!(self);
+            let value = context.get_data_entry(&addr, key.as_bytes());
+            context.trace_step(|| ExecutionTraceStep::DatastoreRead {
+                address: addr,
+                key: key.as_bytes().to_vec(),
+                value: value.as_deref().map(trace_value),
+                value_size: value.as_ref().map_or(0, Vec::len),
+            });
+            match value {
+                Some(value) => Ok(value),
+                _ => bail!("data entry not found"),
+            }
+        })
     }
 
     /// Sets a datastore entry for a given address.
@@ -201,10 +280,12 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry to set
     /// * value: new value to set
     fn raw_set_data_for(&self, address: &str, key: &str, value: &[u8]) -> Result<()> {
-        let addr = massa_models::Address::from_str(address)?;
-        let mut context = context_guard!(self);
-        context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
-        Ok(())
+        self.traced("raw_set_data_for", &[&address, &key, &value], || {
+            let addr = massa_models::Address::from_str(address)?;
+            let mut context = context_guard!(self);
+            context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+            Ok(())
+        })
     }
 
     /// Appends a value to a datastore entry for a given address.
@@ -215,9 +296,15 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry
     /// * value: value to append
     fn raw_append_data_for(&self, address: &str, key: &str, value: &[u8]) -> Result<()> {
-        let addr = massa_models::Address::from_str(address)?;
-        context_guard!(self).append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
-        Ok(())
+        self.traced("raw_append_data_for", &[&address, &key, &value], || {
+            let addr = massa_models::Address::from_str(address)?;
+            context_guard!(self).append_data_entry(
+                &addr,
+                key.as_bytes().to_vec(),
+                value.to_vec(),
+            )?;
+            Ok(())
+        })
     }
 
     /// Deletes a datastore entry by key for a given address.
@@ -227,9 +314,11 @@ impl Interface for InterfaceImpl {
     /// * address: string representation of the address
     /// * key: string key of the datastore entry to delete
     fn raw_delete_data_for(&self, address: &str, key: &str) -> Result<()> {
-        let addr = &massa_models::Address::from_str(address)?;
-        context_guard!(self).delete_data_entry(addr, key.as_bytes())?;
-        Ok(())
+        self.traced("raw_delete_data_for", &[&address, &key], || {
+            let addr = &massa_models::Address::from_str(address)?;
+            context_guard!(self).delete_data_entry(addr, key.as_bytes())?;
+            Ok(())
+        })
     }
 
     /// Checks if a datastore entry exists for a given address.
@@ -241,9 +330,11 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the address exists and has the entry matching the provided key in its datastore, otherwise false
     fn has_data_for(&self, address: &str, key: &str) -> Result<bool> {
-        let addr = massa_models::Address::from_str(address)?;
-        let context = context_guard!(self);
-        Ok(context.has_data_entry(&addr, key.as_bytes()))
+        self.traced("has_data_for", &[&address, &key], || {
+            let addr = massa_models::Address::from_str(address)?;
+            let context = context_guard!(self);
+            Ok(context.has_data_entry(&addr, key.as_bytes()))
+        })
     }
 
     /// Gets a datastore value by key for the current address (top of the call stack).
@@ -254,12 +345,21 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data(&self, key: &str) -> Result<Vec<u8>> {
-        let context = context_guard!(self);
-        let addr = context.get_current_address()?;
-        match context.get_data_entry(&addr, key.as_bytes()) {
-            Some(data) => Ok(data),
-            _ => bail!("data entry not found"),
-        }
+        self.traced("raw_get_data", &[&key], || {
+            let mut context = context_guard!(self);
+            let addr = context.get_current_address()?;
+            let value = context.get_data_entry(&addr, key.as_bytes());
+            context.trace_step(|| ExecutionTraceStep::DatastoreRead {
+                address: addr,
+                key: key.as_bytes().to_vec(),
+                value: value.as_deref().map(trace_value),
+                value_size: value.as_ref().map_or(0, Vec::len),
+            });
+            match value {
+                Some(data) => Ok(data),
+                _ => bail!("data entry not found"),
+            }
+        })
     }
 
     /// Sets a datastore entry for the current address (top of the call stack).
@@ -271,10 +371,12 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry to set
     /// * value: new value to set
     fn raw_set_data(&self, key: &str, value: &[u8]) -> Result<()> {
-        let mut context = context_guard!(self);
-        let addr = context.get_current_address()?;
-        context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
-        Ok(())
+        self.traced("raw_set_data", &[&key, &value], || {
+            let mut context = context_guard!(self);
+            let addr = context.get_current_address()?;
+            context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+            Ok(())
+        })
     }
 
     /// Appends data to a datastore entry for the current address (top of the call stack).
@@ -285,10 +387,12 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry
     /// * value: value to append
     fn raw_append_data(&self, key: &str, value: &[u8]) -> Result<()> {
-        let mut context = context_guard!(self);
-        let addr = context.get_current_address()?;
-        context.append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
-        Ok(())
+        self.traced("raw_append_data", &[&key, &value], || {
+            let mut context = context_guard!(self);
+            let addr = context.get_current_address()?;
+            context.append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+            Ok(())
+        })
     }
 
     /// Deletes a datastore entry by key for the current address (top of the call stack).
@@ -297,10 +401,12 @@ impl Interface for InterfaceImpl {
     /// # Arguments
     /// * key: string key of the datastore entry to delete
     fn raw_delete_data(&self, key: &str) -> Result<()> {
-        let mut context = context_guard!(self);
-        let addr = context.get_current_address()?;
-        context.delete_data_entry(&addr, key.as_bytes())?;
-        Ok(())
+        self.traced("raw_delete_data", &[&key], || {
+            let mut context = context_guard!(self);
+            let addr = context.get_current_address()?;
+            context.delete_data_entry(&addr, key.as_bytes())?;
+            Ok(())
+        })
     }
 
     /// Checks if a datastore entry exists for the current address (top of the call stack).
@@ -311,9 +417,11 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the address exists and has the entry matching the provided key in its datastore, otherwise false
     fn has_data(&self, key: &str) -> Result<bool> {
-        let context = context_guard!(self);
-        let addr = context.get_current_address()?;
-        Ok(context.has_data_entry(&addr, key.as_bytes()))
+        self.traced("has_data", &[&key], || {
+            let context = context_guard!(self);
+            let addr = context.get_current_address()?;
+            Ok(context.has_data_entry(&addr, key.as_bytes()))
+        })
     }
 
     /// Hashes arbitrary data
@@ -324,7 +432,9 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the resulting hash
     fn hash(&self, data: &[u8]) -> Result<String> {
-        Ok(massa_hash::Hash::compute_from(data).to_bs58_check())
+        self.traced("hash", &[&data], || {
+            Ok(massa_hash::Hash::compute_from(data).to_bs58_check())
+        })
     }
 
     /// Converts a public key to an address
@@ -335,9 +445,11 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the resulting address
     fn address_from_public_key(&self, public_key: &str) -> Result<String> {
-        let public_key = massa_signature::PublicKey::from_bs58_check(public_key)?;
-        let addr = massa_models::Address::from_public_key(&public_key);
-        Ok(addr.to_string())
+        self.traced("address_from_public_key", &[&public_key], || {
+            let public_key = massa_signature::PublicKey::from_bs58_check(public_key)?;
+            let addr = massa_models::Address::from_public_key(&public_key);
+            Ok(addr.to_string())
+        })
     }
 
     /// Verifies a signature
@@ -350,16 +462,22 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the signature verification succeeded, false otherwise
     fn signature_verify(&self, data: &[u8], signature: &str, public_key: &str) -> Result<bool> {
-        let signature = match massa_signature::Signature::from_bs58_check(signature) {
-            Ok(sig) => sig,
-            Err(_) => return Ok(false),
-        };
-        let public_key = match massa_signature::PublicKey::from_bs58_check(public_key) {
-            Ok(pubk) => pubk,
-            Err(_) => return Ok(false),
-        };
-        let h = massa_hash::Hash::compute_from(data);
-        Ok(public_key.verify_signature(&h, &signature).is_ok())
+        self.traced(
+            "signature_verify",
+            &[&data, &signature, &public_key],
+            || {
+                let signature = match massa_signature::Signature::from_bs58_check(signature) {
+                    Ok(sig) => sig,
+                    Err(_) => return Ok(false),
+                };
+                let public_key = match massa_signature::PublicKey::from_bs58_check(public_key) {
+                    Ok(pubk) => pubk,
+                    Err(_) => return Ok(false),
+                };
+                let h = massa_hash::Hash::compute_from(data);
+                Ok(public_key.verify_signature(&h, &signature).is_ok())
+            },
+        )
     }
 
     /// Transfer parallel coins from the current address (top of the call stack) towards a target address.
@@ -368,12 +486,14 @@ impl Interface for InterfaceImpl {
     /// * `to_address`: string representation of the address to which the coins are sent
     /// * `raw_amount`: raw representation (no decimal factor) of the amount of coins to transfer
     fn transfer_coins(&self, to_address: &str, raw_amount: u64) -> Result<()> {
-        let to_address = massa_models::Address::from_str(to_address)?;
-        let amount = massa_models::Amount::from_raw(raw_amount);
-        let mut context = context_guard!(self);
-        let from_address = context.get_current_address()?;
-        context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
-        Ok(())
+        self.traced("transfer_coins", &[&to_address, &raw_amount], || {
+            let to_address = massa_models::Address::from_str(to_address)?;
+            let amount = massa_models::Amount::from_raw(raw_amount);
+            let mut context = context_guard!(self);
+            let from_address = context.get_current_address()?;
+            context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
+            Ok(())
+        })
     }
 
     /// Transfer parallel coins from a given address towards a target address.
@@ -388,12 +508,18 @@ impl Interface for InterfaceImpl {
         to_address: &str,
         raw_amount: u64,
     ) -> Result<()> {
-        let from_address = massa_models::Address::from_str(from_address)?;
-        let to_address = massa_models::Address::from_str(to_address)?;
-        let amount = massa_models::Amount::from_raw(raw_amount);
-        let mut context = context_guard!(self);
-        context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
-        Ok(())
+        self.traced(
+            "transfer_coins_for",
+            &[&from_address, &to_address, &raw_amount],
+            || {
+                let from_address = massa_models::Address::from_str(from_address)?;
+                let to_address = massa_models::Address::from_str(to_address)?;
+                let amount = massa_models::Amount::from_raw(raw_amount);
+                let mut context = context_guard!(self);
+                context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
+                Ok(())
+            },
+        )
     }
 
     /// Returns the list of owned addresses (top of the call stack).
@@ -405,11 +531,13 @@ impl Interface for InterfaceImpl {
     /// A vector with the string representation of each owned address.
     /// Note that the ordering of this vector is deterministic and conserved.
     fn get_owned_addresses(&self) -> Result<Vec<String>> {
-        Ok(context_guard!(self)
-            .get_current_owned_addresses()?
-            .into_iter()
-            .map(|addr| addr.to_string())
-            .collect())
+        self.traced("get_owned_addresses", &[], || {
+            Ok(context_guard!(self)
+                .get_current_owned_addresses()?
+                .into_iter()
+                .map(|addr| addr.to_string())
+                .collect())
+        })
     }
 
     /// Returns the addresses in the call stack, from the bottom to the top.
@@ -417,11 +545,13 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// A vector with the string representation of each call stack address.
     fn get_call_stack(&self) -> Result<Vec<String>> {
-        Ok(context_guard!(self)
-            .get_call_stack()
-            .into_iter()
-            .map(|addr| addr.to_string())
-            .collect())
+        self.traced("get_call_stack", &[], || {
+            Ok(context_guard!(self)
+                .get_call_stack()
+                .into_iter()
+                .map(|addr| addr.to_string())
+                .collect())
+        })
     }
 
     /// Gets the amount of coins that have been transferred at the beginning of the call.
@@ -430,7 +560,9 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The raw representation (no decimal factor) of the amount of coins
     fn get_call_coins(&self) -> Result<u64> {
-        Ok(context_guard!(self).get_current_call_coins()?.to_raw())
+        self.traced("get_call_coins", &[], || {
+            Ok(context_guard!(self).get_current_call_coins()?.to_raw())
+        })
     }
 
     /// Emits an execution event to be stored.
@@ -438,23 +570,27 @@ impl Interface for InterfaceImpl {
     /// # Arguments:
     /// data: the string data that is the payload of the event
     fn generate_event(&self, data: String) -> Result<()> {
-        let mut context = context_guard!(self);
-        let event = context.event_create(data);
-        context.event_emit(event);
-        Ok(())
+        self.traced("generate_event", &[&data], || {
+            let mut context = context_guard!(self);
+            let event = context.event_create(data);
+            context.event_emit(event);
+            Ok(())
+        })
     }
 
     /// Returns the current time (millisecond UNIX timestamp)
     /// Note that in order to ensure determinism, this is actually the time of the context slot.
     fn get_time(&self) -> Result<u64> {
-        let slot = context_guard!(self).slot;
-        let ts = get_block_slot_timestamp(
-            self.config.thread_count,
-            self.config.t0,
-            self.config.genesis_timestamp,
-            slot,
-        )?;
-        Ok(ts.to_millis())
+        self.traced("get_time", &[], || {
+            let slot = context_guard!(self).slot;
+            let ts = get_block_slot_timestamp(
+                self.config.thread_count,
+                self.config.t0,
+                self.config.genesis_timestamp,
+                slot,
+            )?;
+            Ok(ts.to_millis())
+        })
     }
 
     /// Returns a pseudo-random deterministic `i64` number
@@ -463,11 +599,14 @@ impl Interface for InterfaceImpl {
     /// This random number generator is unsafe:
     /// it can be both predicted and manipulated before the execution
     fn unsafe_random(&self) -> Result<i64> {
-        let distr = rand::distributions::Uniform::new_inclusive(i64::MIN, i64::MAX);
-        Ok(context_guard!(self).unsafe_rng.sample(distr))
+        self.traced("unsafe_random", &[], || {
+            let distr = rand::distributions::Uniform::new_inclusive(i64::MIN, i64::MAX);
+            Ok(context_guard!(self).unsafe_rng.sample(distr))
+        })
     }
 
-    /// Adds an asynchronous message to the context speculative asynchronous pool
+    /// Adds an asynchronous message to the context speculative asynchronous pool.
+    /// The current address spends the coins of the message.
     ///
     /// # Arguments
     /// * `target_address`: Destination address hash in format string
@@ -489,63 +628,86 @@ impl Interface for InterfaceImpl {
         raw_coins: u64,
         data: &[u8],
     ) -> Result<()> {
-        if validity_start.1 >= self.config.thread_count {
-            bail!("validity start thread exceeds the configuration thread count")
-        }
-        if validity_end.1 >= self.config.thread_count {
-            bail!("validity end thread exceeds the configuration thread count")
-        }
-        let mut execution_context = context_guard!(self);
-        let emission_slot = execution_context.slot;
-        let emission_index = execution_context.created_message_index;
-        let sender = execution_context.get_current_address()?;
-        execution_context.push_new_message(AsyncMessage {
-            emission_slot,
-            emission_index,
-            sender,
-            destination: Address::from_str(target_address)?,
-            handler: target_handler.to_string(),
-            validity_start: Slot::new(validity_start.0, validity_start.1),
-            validity_end: Slot::new(validity_end.0, validity_end.1),
-            max_gas,
-            gas_price: Amount::from_raw(gas_price),
-            coins: Amount::from_raw(raw_coins),
-            data: data.to_vec(),
-        });
-        execution_context.created_message_index += 1;
-        Ok(())
+        self.traced(
+            "send_message",
+            &[
+                &target_address,
+                &target_handler,
+                &validity_start,
+                &validity_end,
+                &max_gas,
+                &gas_price,
+                &raw_coins,
+                &data,
+            ],
+            || {
+                if validity_start.1 >= self.config.thread_count {
+                    bail!("validity start thread exceeds the configuration thread count")
+                }
+                if validity_end.1 >= self.config.thread_count {
+                    bail!("validity end thread exceeds the configuration thread count")
+                }
+                let mut execution_context = context_guard!(self);
+                let emission_slot = execution_context.slot;
+                let emission_index = execution_context.created_message_index;
+                let sender = execution_context.get_current_address()?;
+                execution_context.push_new_message(AsyncMessage {
+                    emission_slot,
+                    emission_index,
+                    sender,
+                    destination: Address::from_str(target_address)?,
+                    handler: target_handler.to_string(),
+                    validity_start: Slot::new(validity_start.0, validity_start.1),
+                    validity_end: Slot::new(validity_end.0, validity_end.1),
+                    max_gas,
+                    gas_price: Amount::from_raw(gas_price),
+                    coins: Amount::from_raw(raw_coins),
+                    data: data.to_vec(),
+                })?;
+                execution_context.created_message_index += 1;
+                Ok(())
+            },
+        )
     }
 
     /// Returns the period of the current execution slot
     fn get_current_period(&self) -> Result<u64> {
-        let slot = context_guard!(self).slot;
-        Ok(slot.period)
+        self.traced("get_current_period", &[], || {
+            let slot = context_guard!(self).slot;
+            Ok(slot.period)
+        })
     }
 
     /// Returns the thread of the current execution slot
     fn get_current_thread(&self) -> Result<u8> {
-        let slot = context_guard!(self).slot;
-        Ok(slot.thread)
+        self.traced("get_current_thread", &[], || {
+            let slot = context_guard!(self).slot;
+            Ok(slot.thread)
+        })
     }
 
     /// Sets the bytecode of the current address
     fn raw_set_bytecode(&self, bytecode: &[u8]) -> Result<()> {
-        let mut execution_context = context_guard!(self);
-        let address = execution_context.get_current_address()?;
-        match execution_context.set_bytecode(&address, bytecode.to_vec()) {
-            Ok(()) => Ok(()),
-            Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
-        }
+        self.traced("raw_set_bytecode", &[&bytecode], || {
+            let mut execution_context = context_guard!(self);
+            let address = execution_context.get_current_address()?;
+            match execution_context.set_bytecode(&address, bytecode.to_vec()) {
+                Ok(()) => Ok(()),
+                Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
+            }
+        })
     }
 
     /// Sets the bytecode of an arbitrary address.
     /// Fails if the address does not exist of if the context doesn't have write access rights on it.
     fn raw_set_bytecode_for(&self, address: &str, bytecode: &[u8]) -> Result<()> {
-        let address = massa_models::Address::from_str(address)?;
-        let mut execution_context = context_guard!(self);
-        match execution_context.set_bytecode(&address, bytecode.to_vec()) {
-            Ok(()) => Ok(()),
-            Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
-        }
+        self.traced("raw_set_bytecode_for", &[&address, &bytecode], || {
+            let address = massa_models::Address::from_str(address)?;
+            let mut execution_context = context_guard!(self);
+            match execution_context.set_bytecode(&address, bytecode.to_vec()) {
+                Ok(()) => Ok(()),
+                Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
+            }
+        })
     }
 }
@@ -57,6 +57,14 @@
 //! It also serves as an access point to the current execution state and speculative ledger
 //! as defined in `speculative_ledger.rs`.
 //!
+//! ## `event_archive.rs`
+//! An optional RocksDB archive of the final SC output events,
+//! indexed by slot, emitter address, original caller address and origin operation id.
+//!
+//! ## `replay.rs`
+//! Recording of the final slots of a node, and their offline replay from a final state snapshot and the final blocks,
+//! comparing the resulting state changes with the ones recorded by the node.
+//!
 //! ## `speculative_ledger.rs`
 //! A speculative (non-final) ledger that supports canceling already-executed operations
 //! in the case of some blockclique changes.
@@ -73,8 +81,10 @@
 mod active_history;
 mod context;
 mod controller;
+mod event_archive;
 mod execution;
 mod interface_impl;
+pub mod replay;
 mod request_queue;
 mod speculative_async_pool;
 mod speculative_ledger;
@@ -0,0 +1,515 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Recording and offline replay of final slots, used to chase divergences in execution between nodes.
+//!
+//! A node configured with `ExecutionConfig::replay_recording_path` records in that directory,
+//! from its startup, everything needed to replay its final slots (see `ReplayRecorder`).
+//!
+//! A replay starts from a snapshot of the final state at a given slot,
+//! and re-executes every following slot up to an end slot with the final blocks of those slots.
+//! The state changes produced by each slot are compared with the ones recorded by a node,
+//! then applied to the final state before executing the next slot.
+//!
+//! A recording holds four files:
+//! * `settings.json`: the settings of the node that affect execution (see `ReplaySettings`)
+//! * `snapshot.json`: the final state snapshot, holding the ledger, async pool and receipts parts as streamed during bootstrap
+//! * `blocks`: the final blocks, as concatenated binary serialized blocks
+//! * `state_changes`: the recorded state changes, as concatenated `(slot key, binary serialized StateChanges)` pairs
+
+use crate::execution::ExecutionState;
+use massa_async_pool::{AsyncMessage, AsyncMessageId, Change};
+use massa_execution_exports::{ExecutionChannels, ExecutionConfig, ExecutionError};
+use massa_final_state::{
+    FinalState, FinalStateConfig, StateChanges, StateChangesDeserializer, StateChangesSerializer,
+};
+use massa_hash::Hash;
+use massa_ledger_exports::{LedgerController, LedgerEntry, LedgerEntryUpdate, SetUpdateOrDelete};
+use massa_models::constants::SLOT_KEY_SIZE;
+use massa_models::execution::OperationReceipt;
+use massa_models::wrapped::{WrappedDeserializer, WrappedSerializer};
+use massa_models::{
+    Address, Amount, BlockDeserializer, BlockId, ModelsError, OperationId, Slot, WrappedBlock,
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_storage::Storage;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Name of the file of a recording holding the settings of the node
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Name of the file of a recording holding the final state snapshot
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Name of the file of a recording holding the final blocks
+pub const BLOCKS_FILE: &str = "blocks";
+
+/// Name of the file of a recording holding the state changes of the final slots
+pub const STATE_CHANGES_FILE: &str = "state_changes";
+
+/// Change of a ledger entry, as found in `LedgerChanges`
+pub type LedgerEntryChange = SetUpdateOrDelete<LedgerEntry, LedgerEntryUpdate>;
+
+/// Change of the async pool, as found in `AsyncPoolChanges`
+pub type AsyncPoolChange = Change<AsyncMessageId, AsyncMessage>;
+
+/// Snapshot of a final state, with its ledger, async pool and receipts in the format streamed during bootstrap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalStateSnapshot {
+    /// slot at the output of which the snapshot was taken
+    pub slot: Slot,
+    /// ledger parts, as returned by `LedgerController::get_ledger_part`
+    pub ledger_parts: Vec<Vec<u8>>,
+    /// async pool parts, as returned by `AsyncPool::get_pool_part`
+    pub async_pool_parts: Vec<Vec<u8>>,
+    /// receipts parts, as returned by `FinalReceipts::get_receipts_part`
+    pub receipts_parts: Vec<Vec<u8>>,
+    /// fingerprint of the final state at `slot`
+    pub fingerprint: Hash,
+}
+
+impl FinalStateSnapshot {
+    /// Take a snapshot of a final state
+    pub fn take(final_state: &FinalState) -> Result<Self, ExecutionError> {
+        let mut ledger_parts = Vec::new();
+        let mut last_key = None;
+        loop {
+            let (part, new_last_key) = final_state.ledger.get_ledger_part(&last_key)?;
+            if part.is_empty() {
+                break;
+            }
+            ledger_parts.push(part);
+            last_key = new_last_key;
+        }
+        let mut async_pool_parts = Vec::new();
+        let mut last_id = None;
+        loop {
+            let (part, new_last_id) = final_state.async_pool.get_pool_part(last_id)?;
+            if part.is_empty() {
+                break;
+            }
+            async_pool_parts.push(part);
+            last_id = new_last_id;
+        }
+        let mut receipts_parts = Vec::new();
+        let mut last_id = None;
+        loop {
+            let (part, new_last_id) = final_state.receipts.get_receipts_part(last_id)?;
+            if part.is_empty() {
+                break;
+            }
+            receipts_parts.push(part);
+            last_id = new_last_id;
+        }
+        Ok(FinalStateSnapshot {
+            slot: final_state.slot,
+            ledger_parts,
+            async_pool_parts,
+            receipts_parts,
+            fingerprint: final_state.fingerprint,
+        })
+    }
+
+    /// Rebuild the final state from the snapshot
+    ///
+    /// # Arguments
+    /// * `config`: final state configuration
+    /// * `ledger`: empty final ledger in which the ledger parts are written
+    pub fn restore(
+        self,
+        config: FinalStateConfig,
+        ledger: Box<dyn LedgerController>,
+    ) -> Result<FinalState, ExecutionError> {
+        let mut final_state = FinalState::new(config, ledger)
+            .map_err(|err| ExecutionError::ReplayError(err.to_string()))?;
+        for part in self.ledger_parts {
+            final_state.ledger.set_ledger_part(part)?;
+        }
+        for part in self.async_pool_parts {
+            final_state.async_pool.set_pool_part(&part)?;
+        }
+        for part in self.receipts_parts {
+            final_state.receipts.set_receipts_part(&part)?;
+        }
+        final_state.slot = self.slot;
+        final_state.fingerprint = final_state.compute_fingerprint();
+        if final_state.fingerprint != self.fingerprint {
+            return Err(ExecutionError::ReplayError(format!(
+                "restored final state fingerprint {} does not match the snapshot fingerprint {}",
+                final_state.fingerprint, self.fingerprint
+            )));
+        }
+        Ok(final_state)
+    }
+
+    /// Load a snapshot from a JSON file
+    pub fn load(path: &Path) -> Result<Self, ExecutionError> {
+        let bytes = std::fs::read(path).map_err(|err| replay_file_error(path, err))?;
+        serde_json::from_slice(&bytes).map_err(|err| replay_file_error(path, err))
+    }
+
+    /// Save the snapshot to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), ExecutionError> {
+        let bytes = serde_json::to_vec(self).map_err(|err| replay_file_error(path, err))?;
+        std::fs::write(path, bytes).map_err(|err| replay_file_error(path, err))
+    }
+}
+
+/// Build a replay error about a file
+fn replay_file_error(path: &Path, err: impl std::fmt::Display) -> ExecutionError {
+    ExecutionError::ReplayError(format!("file {}: {}", path.display(), err))
+}
+
+/// Load final blocks from a file of concatenated binary serialized blocks
+pub fn load_blocks(path: &Path) -> Result<Vec<WrappedBlock>, ExecutionError> {
+    let bytes = std::fs::read(path).map_err(|err| replay_file_error(path, err))?;
+    let deserializer = WrappedDeserializer::new(BlockDeserializer::new());
+    let mut rest = &bytes[..];
+    let mut blocks = Vec::new();
+    while !rest.is_empty() {
+        let (new_rest, block) = deserializer
+            .deserialize::<DeserializeError>(rest)
+            .map_err(|err| replay_file_error(path, err))?;
+        blocks.push(block);
+        rest = new_rest;
+    }
+    Ok(blocks)
+}
+
+/// Load recorded state changes from a file of concatenated `(slot key, serialized StateChanges)` pairs
+pub fn load_state_changes(path: &Path) -> Result<Vec<(Slot, StateChanges)>, ExecutionError> {
+    let bytes = std::fs::read(path).map_err(|err| replay_file_error(path, err))?;
+    let deserializer = StateChangesDeserializer::new();
+    let mut rest = &bytes[..];
+    let mut changes = Vec::new();
+    while !rest.is_empty() {
+        let slot_key: &[u8; SLOT_KEY_SIZE] = rest
+            .get(..SLOT_KEY_SIZE)
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| replay_file_error(path, "truncated slot key"))?;
+        let (new_rest, slot_changes) = deserializer
+            .deserialize::<DeserializeError>(&rest[SLOT_KEY_SIZE..])
+            .map_err(|err| replay_file_error(path, err))?;
+        changes.push((Slot::from_bytes_key(slot_key), slot_changes));
+        rest = new_rest;
+    }
+    Ok(changes)
+}
+
+/// Settings of a node that affect the execution of final slots,
+/// recorded with its final slots so that they are replayed with the same settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaySettings {
+    /// number of threads
+    pub thread_count: u8,
+    /// period duration
+    pub t0: MassaTime,
+    /// genesis timestamp
+    pub genesis_timestamp: MassaTime,
+    /// maximum available gas for asynchronous messages execution
+    pub max_async_gas: u64,
+    /// gas charged to every executed `ExecuteSC` and `CallSC` operation on top of the gas it consumes
+    pub operation_base_gas_cost: u64,
+    /// max number of messages in the async pool
+    pub async_pool_max_length: u64,
+    /// cost in coins of each byte stored in the ledger
+    pub storage_cost_per_byte: Amount,
+    /// period from which the storage cost is charged
+    pub storage_cost_activation_period: Option<u64>,
+    /// number of periods during which the receipts of the executed operations are kept in the final state
+    pub receipts_retention_periods: u64,
+}
+
+impl ReplaySettings {
+    /// Gather the settings of a node
+    pub fn new(execution_config: &ExecutionConfig, final_state_config: &FinalStateConfig) -> Self {
+        ReplaySettings {
+            thread_count: execution_config.thread_count,
+            t0: execution_config.t0,
+            genesis_timestamp: execution_config.genesis_timestamp,
+            max_async_gas: execution_config.max_async_gas,
+            operation_base_gas_cost: execution_config.operation_base_gas_cost,
+            async_pool_max_length: final_state_config.async_pool_config.max_length,
+            storage_cost_per_byte: final_state_config.ledger_config.storage_cost_per_byte,
+            storage_cost_activation_period: final_state_config
+                .ledger_config
+                .storage_cost_activation_period,
+            receipts_retention_periods: final_state_config.receipts_retention_periods,
+        }
+    }
+
+    /// Execution configuration of a replay with these settings
+    pub fn execution_config(&self) -> ExecutionConfig {
+        ExecutionConfig {
+            max_final_events: 0,
+            readonly_queue_length: 0,
+            cursor_delay: 0.into(),
+            clock_compensation: 0,
+            max_async_gas: self.max_async_gas,
+            operation_base_gas_cost: self.operation_base_gas_cost,
+            thread_count: self.thread_count,
+            t0: self.t0,
+            genesis_timestamp: self.genesis_timestamp,
+            event_archive_path: None,
+            event_archive_retention_periods: 0,
+            replay_recording_path: None,
+        }
+    }
+
+    /// Load settings from a JSON file
+    pub fn load(path: &Path) -> Result<Self, ExecutionError> {
+        let bytes = std::fs::read(path).map_err(|err| replay_file_error(path, err))?;
+        serde_json::from_slice(&bytes).map_err(|err| replay_file_error(path, err))
+    }
+
+    /// Save the settings to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), ExecutionError> {
+        let bytes = serde_json::to_vec(self).map_err(|err| replay_file_error(path, err))?;
+        std::fs::write(path, bytes).map_err(|err| replay_file_error(path, err))
+    }
+}
+
+/// Records the final slots of a node in a directory, in the format read by the replay
+pub struct ReplayRecorder {
+    /// file of the recorded final blocks
+    blocks: File,
+    /// file of the recorded state changes
+    state_changes: File,
+}
+
+impl ReplayRecorder {
+    /// Start a recording from the current final state of a node.
+    /// Any previous recording in the directory is replaced.
+    ///
+    /// # Arguments
+    /// * `path`: directory of the recording, created if missing
+    /// * `config`: execution configuration of the node
+    /// * `final_state`: final state of the node, from which the following final slots are recorded
+    pub fn start(
+        path: &Path,
+        config: &ExecutionConfig,
+        final_state: &FinalState,
+    ) -> Result<Self, ExecutionError> {
+        std::fs::create_dir_all(path).map_err(|err| replay_file_error(path, err))?;
+        ReplaySettings::new(config, final_state.get_config()).save(&path.join(SETTINGS_FILE))?;
+        FinalStateSnapshot::take(final_state)?.save(&path.join(SNAPSHOT_FILE))?;
+        let create = |name: &str| {
+            let path = path.join(name);
+            File::create(&path).map_err(|err| replay_file_error(&path, err))
+        };
+        Ok(ReplayRecorder {
+            blocks: create(BLOCKS_FILE)?,
+            state_changes: create(STATE_CHANGES_FILE)?,
+        })
+    }
+
+    /// Record a final slot, following the previously recorded one
+    ///
+    /// # Arguments
+    /// * `slot`: the final slot
+    /// * `block`: the final block of the slot, `None` if the slot was a miss
+    /// * `changes`: the state changes produced by the execution of the slot
+    pub fn record_slot(
+        &mut self,
+        slot: Slot,
+        block: Option<&WrappedBlock>,
+        changes: &StateChanges,
+    ) -> Result<(), ExecutionError> {
+        if let Some(block) = block {
+            let mut bytes = Vec::new();
+            WrappedSerializer::new()
+                .serialize(block, &mut bytes)
+                .map_err(ModelsError::from)?;
+            self.blocks
+                .write_all(&bytes)
+                .map_err(|err| ExecutionError::ReplayError(err.to_string()))?;
+        }
+        let mut bytes = slot.to_bytes_key().to_vec();
+        StateChangesSerializer::new()
+            .serialize(changes, &mut bytes)
+            .map_err(ModelsError::from)?;
+        self.state_changes
+            .write_all(&bytes)
+            .map_err(|err| ExecutionError::ReplayError(err.to_string()))
+    }
+}
+
+/// Result of the replay of a slot
+#[derive(Debug, Clone)]
+pub struct SlotReplayReport {
+    /// replayed slot
+    pub slot: Slot,
+    /// final block of the slot, `None` if the slot was a miss
+    pub block_id: Option<BlockId>,
+    /// whether state changes were recorded for this slot, if not nothing was compared
+    pub checked: bool,
+    /// addresses whose ledger changes differ, with the recorded and the replayed change.
+    /// A `None` change means that the entry was not changed.
+    pub ledger_divergences:
+        BTreeMap<Address, (Option<LedgerEntryChange>, Option<LedgerEntryChange>)>,
+    /// async pool changes that were recorded but not replayed
+    pub missing_async_pool_changes: Vec<AsyncPoolChange>,
+    /// async pool changes that were replayed but not recorded
+    pub unexpected_async_pool_changes: Vec<AsyncPoolChange>,
+    /// operations whose receipts differ, with the recorded and the replayed receipt.
+    /// A `None` receipt means that the operation was not executed.
+    pub receipt_divergences:
+        BTreeMap<OperationId, (Option<OperationReceipt>, Option<OperationReceipt>)>,
+    /// state changes produced by the replay of the slot
+    pub replayed_changes: StateChanges,
+    /// fingerprint of the final state after applying the replayed changes of the slot
+    pub fingerprint: Hash,
+}
+
+impl SlotReplayReport {
+    /// Whether the replayed state changes are the recorded ones
+    pub fn is_consistent(&self) -> bool {
+        self.ledger_divergences.is_empty()
+            && self.missing_async_pool_changes.is_empty()
+            && self.unexpected_async_pool_changes.is_empty()
+            && self.receipt_divergences.is_empty()
+    }
+}
+
+/// Compare the replayed changes of a slot with the recorded ones
+fn diff_state_changes(
+    recorded: &StateChanges,
+    replayed: &StateChanges,
+) -> (
+    BTreeMap<Address, (Option<LedgerEntryChange>, Option<LedgerEntryChange>)>,
+    Vec<AsyncPoolChange>,
+    Vec<AsyncPoolChange>,
+    BTreeMap<OperationId, (Option<OperationReceipt>, Option<OperationReceipt>)>,
+) {
+    let addresses: BTreeSet<&Address> = recorded
+        .ledger_changes
+        .0
+        .keys()
+        .chain(replayed.ledger_changes.0.keys())
+        .collect();
+    let ledger_divergences = addresses
+        .into_iter()
+        .filter_map(|addr| {
+            let recorded_change = recorded.ledger_changes.0.get(addr);
+            let replayed_change = replayed.ledger_changes.0.get(addr);
+            (recorded_change != replayed_change)
+                .then(|| (*addr, (recorded_change.cloned(), replayed_change.cloned())))
+        })
+        .collect();
+    let missing_async_pool_changes = recorded
+        .async_pool_changes
+        .0
+        .iter()
+        .filter(|change| !replayed.async_pool_changes.0.contains(change))
+        .cloned()
+        .collect();
+    let unexpected_async_pool_changes = replayed
+        .async_pool_changes
+        .0
+        .iter()
+        .filter(|change| !recorded.async_pool_changes.0.contains(change))
+        .cloned()
+        .collect();
+    let operation_ids: BTreeSet<&OperationId> = recorded
+        .receipts_changes
+        .0
+        .keys()
+        .chain(replayed.receipts_changes.0.keys())
+        .collect();
+    let receipt_divergences = operation_ids
+        .into_iter()
+        .filter_map(|op_id| {
+            let recorded_receipt = recorded.receipts_changes.0.get(op_id);
+            let replayed_receipt = replayed.receipts_changes.0.get(op_id);
+            (recorded_receipt != replayed_receipt).then(|| {
+                (
+                    *op_id,
+                    (recorded_receipt.cloned(), replayed_receipt.cloned()),
+                )
+            })
+        })
+        .collect();
+    (
+        ledger_divergences,
+        missing_async_pool_changes,
+        unexpected_async_pool_changes,
+        receipt_divergences,
+    )
+}
+
+/// Re-execute the final slots following a final state up to an end slot (included),
+/// and compare the state changes of each slot with the recorded ones.
+///
+/// # Arguments
+/// * `config`: execution configuration, the event archive path should be unset
+/// * `final_state`: final state at the output of the slot preceding the first replayed slot
+/// * `blocks`: final blocks of the replayed slots, slots without block are executed as misses
+/// * `recorded_changes`: state changes recorded by a node for the replayed slots
+/// * `end_slot`: last slot to replay
+///
+/// # Returns
+/// One report per replayed slot, in slot order
+pub fn replay_final_slots(
+    config: ExecutionConfig,
+    final_state: FinalState,
+    blocks: Vec<WrappedBlock>,
+    recorded_changes: Vec<(Slot, StateChanges)>,
+    end_slot: Slot,
+) -> Result<Vec<SlotReplayReport>, ExecutionError> {
+    let thread_count = config.thread_count;
+    let mut slot = final_state.slot;
+
+    // index the blocks by slot and make them available to the execution
+    let storage = Storage::default();
+    let mut block_ids: BTreeMap<Slot, BlockId> = BTreeMap::new();
+    for block in blocks {
+        let block_slot = block.content.header.content.slot;
+        if let Some(other_id) = block_ids.insert(block_slot, block.id) {
+            return Err(ExecutionError::ReplayError(format!(
+                "blocks {} and {} are both at slot {}",
+                other_id, block.id, block_slot
+            )));
+        }
+        storage.store_block(block);
+    }
+    let recorded_changes: BTreeMap<Slot, StateChanges> = recorded_changes.into_iter().collect();
+
+    let final_state = Arc::new(RwLock::new(final_state));
+    let channels = ExecutionChannels {
+        slot_execution_output_sender: broadcast::channel(1).0,
+    };
+    let mut execution_state = ExecutionState::new(config, final_state.clone(), storage, channels);
+
+    let mut reports = Vec::new();
+    while slot < end_slot {
+        slot = slot.get_next_slot(thread_count)?;
+        let block_id = block_ids.get(&slot).copied();
+        let exec_out = execution_state.execute_slot(slot, block_id);
+        let (checked, (ledger_divergences, missing, unexpected, receipt_divergences)) =
+            match recorded_changes.get(&slot) {
+                Some(recorded) => (true, diff_state_changes(recorded, &exec_out.state_changes)),
+                None => (false, Default::default()),
+            };
+        let replayed_changes = exec_out.state_changes.clone();
+        execution_state.apply_final_execution_output(exec_out);
+        reports.push(SlotReplayReport {
+            slot,
+            block_id,
+            checked,
+            ledger_divergences,
+            missing_async_pool_changes: missing,
+            unexpected_async_pool_changes: unexpected,
+            receipt_divergences,
+            replayed_changes,
+            fingerprint: final_state.read().fingerprint,
+        });
+    }
+    Ok(reports)
+}
@@ -4,7 +4,9 @@
 //! the pool at an arbitrary execution slot.
 
 use crate::active_history::ActiveHistory;
-use massa_async_pool::{AsyncMessage, AsyncMessageId, AsyncPool, AsyncPoolChanges};
+use massa_async_pool::{
+    AsyncMessage, AsyncMessageEliminationReason, AsyncMessageId, AsyncPool, AsyncPoolChanges,
+};
 use massa_final_state::FinalState;
 use massa_models::Slot;
 use parking_lot::RwLock;
@@ -100,13 +102,16 @@ impl SpeculativeAsyncPool {
     /// * slot: slot that is being settled
     ///
     /// # Returns
-    /// the list of deleted `(message_id, message)`, used for reimbursement
-    pub fn settle_slot(&mut self, slot: Slot) -> Vec<(AsyncMessageId, AsyncMessage)> {
+    /// the list of deleted `(message_id, message, reason)`, used for reimbursement
+    pub fn settle_slot(
+        &mut self,
+        slot: Slot,
+    ) -> Vec<(AsyncMessageId, AsyncMessage, AsyncMessageEliminationReason)> {
         let deleted_messages = self.async_pool.settle_slot(slot, &mut self.emitted);
         for (msg_id, msg) in std::mem::take(&mut self.emitted) {
             self.settled_changes.push_add(msg_id, msg);
         }
-        for (msg_id, _msg) in deleted_messages.iter() {
+        for (msg_id, _msg, _reason) in deleted_messages.iter() {
             self.settled_changes.push_delete(*msg_id);
         }
         deleted_messages
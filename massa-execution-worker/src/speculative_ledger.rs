@@ -7,9 +7,11 @@
 
 use massa_execution_exports::ExecutionError;
 use massa_final_state::FinalState;
-use massa_ledger_exports::{Applicable, LedgerChanges};
+use massa_ledger_exports::{Applicable, LedgerChanges, SetOrDelete, SetUpdateOrDelete};
 use massa_models::{constants::default::MAX_DATASTORE_KEY_LENGTH, Address, Amount};
 use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::ops::Bound;
 use std::sync::Arc;
 
 use crate::active_history::{ActiveHistory, HistorySearchResult};
@@ -329,3 +331,81 @@ impl SpeculativeLedger {
         Ok(())
     }
 }
+
+/// Gets the datastore keys of an address within a range, in increasing order,
+/// as seen after applying a sequence of ledger changes to the final ledger.
+///
+/// Only the first keys of the final ledger are read: deleting `n` keys
+/// can at most hide `n` of the `limit + n` first final keys of the range.
+///
+/// # Arguments
+/// * `final_state`: final state containing the final ledger
+/// * `changes`: ledger changes to apply, oldest first
+/// * `addr`: address to query
+/// * `range`: range of the datastore keys to return
+/// * `limit`: maximum number of keys to return
+pub(crate) fn get_datastore_keys_in_range<'a>(
+    final_state: &FinalState,
+    changes: impl Iterator<Item = &'a LedgerChanges>,
+    addr: &Address,
+    range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    limit: usize,
+) -> Vec<Vec<u8>> {
+    // empty ranges (BTreeMap::range panics on reversed bounds)
+    if let (
+        Bound::Included(start) | Bound::Excluded(start),
+        Bound::Included(end) | Bound::Excluded(end),
+    ) = &range
+    {
+        let both_included = matches!(range, (Bound::Included(_), Bound::Included(_)));
+        if start > end || (start == end && !both_included) {
+            return Vec::new();
+        }
+    }
+
+    // gather the presence of the keys touched by the changes,
+    // and whether the final datastore was replaced altogether
+    let mut touched_keys: BTreeMap<Vec<u8>, bool> = BTreeMap::new();
+    let mut final_keys_replaced = false;
+    for ledger_changes in changes {
+        match ledger_changes.get(addr) {
+            None => (),
+            Some(SetUpdateOrDelete::Set(new_entry)) => {
+                final_keys_replaced = true;
+                touched_keys = new_entry
+                    .datastore
+                    .keys()
+                    .map(|key| (key.clone(), true))
+                    .collect();
+            }
+            Some(SetUpdateOrDelete::Update(entry_update)) => {
+                for (key, update) in &entry_update.datastore {
+                    touched_keys.insert(key.clone(), matches!(update, SetOrDelete::Set(_)));
+                }
+            }
+            Some(SetUpdateOrDelete::Delete) => {
+                final_keys_replaced = true;
+                touched_keys.clear();
+            }
+        }
+    }
+
+    let mut keys = if final_keys_replaced {
+        Default::default()
+    } else {
+        let deleted_count = touched_keys.values().filter(|present| !**present).count();
+        final_state.ledger.get_datastore_keys_in_range(
+            addr,
+            range.clone(),
+            limit.saturating_add(deleted_count),
+        )
+    };
+    for (key, present) in touched_keys.range::<Vec<u8>, _>(range) {
+        if *present {
+            keys.insert(key.clone());
+        } else {
+            keys.remove(key);
+        }
+    }
+    keys.into_iter().take(limit).collect()
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::event_archive::{deserialize_event, EventArchive};
+use massa_execution_exports::EventStore;
+use massa_models::api::{EventCursor, EventFilter};
+use massa_models::output_event::{EventExecutionContext, SCOutputEvent};
+use massa_models::{Address, Slot};
+use massa_signature::KeyPair;
+use std::collections::VecDeque;
+use tempfile::TempDir;
+
+fn event(slot: Slot, index_in_slot: u64, call_stack: Vec<Address>) -> SCOutputEvent {
+    SCOutputEvent {
+        context: EventExecutionContext {
+            slot,
+            block: None,
+            read_only: false,
+            is_final: true,
+            index_in_slot,
+            call_stack: call_stack.into_iter().collect::<VecDeque<_>>(),
+            origin_operation_id: None,
+        },
+        data: format!("{}-{}", slot, index_in_slot),
+    }
+}
+
+#[test]
+fn test_event_archive_filter_and_retention() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut archive = EventArchive::new(temp_dir.path().to_path_buf(), 2);
+    let addr_a = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let addr_b = Address::from_public_key(&KeyPair::generate().get_public_key());
+
+    for period in 0..3 {
+        let slot = Slot::new(period, 0);
+        let mut events = EventStore::default();
+        events.push(event(slot, 0, vec![addr_a]));
+        events.push(event(slot, 1, vec![addr_b, addr_a]));
+        archive.archive_slot(slot, &events, &Default::default());
+    }
+
+    // everything is kept while in retention
+    assert_eq!(
+        archive
+            .get_filtered_sc_output_event(&EventFilter::default())
+            .len(),
+        6
+    );
+    let by_emitter = archive.get_filtered_sc_output_event(&EventFilter {
+        emitter_address: Some(addr_b),
+        ..Default::default()
+    });
+    assert_eq!(by_emitter.len(), 3);
+    assert!(by_emitter.iter().all(|e| e.context.index_in_slot == 1));
+    let by_caller_and_slot = archive.get_filtered_sc_output_event(&EventFilter {
+        original_caller_address: Some(addr_a),
+        start: Some(Slot::new(1, 0)),
+        end: Some(Slot::new(2, 0)),
+        ..Default::default()
+    });
+    assert_eq!(by_caller_and_slot.len(), 2);
+
+    // paginate through the events of addr_a
+    let first_page = archive.get_filtered_sc_output_event(&EventFilter {
+        original_caller_address: Some(addr_a),
+        limit: Some(4),
+        ..Default::default()
+    });
+    assert_eq!(first_page.len(), 4);
+    let second_page = archive.get_filtered_sc_output_event(&EventFilter {
+        original_caller_address: Some(addr_a),
+        cursor: Some(EventCursor::of_event(first_page.last().unwrap())),
+        limit: Some(4),
+        ..Default::default()
+    });
+    assert_eq!(second_page.len(), 2);
+    assert!(second_page
+        .iter()
+        .all(|e| e.context.slot == Slot::new(2, 0)));
+
+    // match on the event data
+    let by_prefix = archive.get_filtered_sc_output_event(&EventFilter {
+        data_prefix: Some("(period: 1".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(by_prefix.len(), 2);
+    let by_substring = archive.get_filtered_sc_output_event(&EventFilter {
+        data_substring: Some(")-1".to_string()),
+        is_final: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(by_substring.len(), 3);
+
+    // archiving period 3 prunes period 0
+    archive.archive_slot(Slot::new(3, 0), &EventStore::default(), &Default::default());
+    let remaining = archive.get_filtered_sc_output_event(&EventFilter::default());
+    assert_eq!(remaining.len(), 4);
+    assert!(remaining.iter().all(|e| e.context.slot.period > 0));
+    assert_eq!(
+        archive
+            .get_filtered_sc_output_event(&EventFilter {
+                emitter_address: Some(addr_a),
+                ..Default::default()
+            })
+            .len(),
+        2
+    );
+
+    // the pruning cursor survives a restart: archiving period 4 prunes period 1 only
+    drop(archive);
+    let mut archive = EventArchive::new(temp_dir.path().to_path_buf(), 2);
+    assert_eq!(
+        archive
+            .get_filtered_sc_output_event(&EventFilter::default())
+            .len(),
+        4
+    );
+    archive.archive_slot(Slot::new(4, 0), &EventStore::default(), &Default::default());
+    let remaining = archive.get_filtered_sc_output_event(&EventFilter::default());
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining.iter().all(|e| e.context.slot.period == 2));
+}
+
+#[test]
+fn test_event_archive_reads_events_without_finality() {
+    let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let mut value = serde_json::to_value(event(Slot::new(1, 0), 0, vec![addr])).unwrap();
+    value["context"]
+        .as_object_mut()
+        .unwrap()
+        .remove("is_final")
+        .unwrap();
+    let bytes = serde_json::to_vec(&value).unwrap();
+
+    // events serialized before the finality flag existed are read as candidate events
+    let event: SCOutputEvent = serde_json::from_slice(&bytes).unwrap();
+    assert!(!event.context.is_final);
+    // but archived events are all final
+    let archived = deserialize_event(&bytes);
+    assert!(archived.context.is_final);
+    assert_eq!(archived.context.call_stack, VecDeque::from(vec![addr]));
+}
@@ -1,3 +1,4 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+mod event_archive;
 mod scenarios_mandatories;
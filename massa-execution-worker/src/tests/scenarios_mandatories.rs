@@ -1,17 +1,30 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::context::{ExecutionContext, MAX_TRACE_STEPS, MAX_TRACE_VALUE_LENGTH};
+use crate::replay::{
+    load_blocks, load_state_changes, replay_final_slots, FinalStateSnapshot, ReplayRecorder,
+    ReplaySettings, BLOCKS_FILE, SETTINGS_FILE, SNAPSHOT_FILE, STATE_CHANGES_FILE,
+};
+use crate::speculative_ledger::get_datastore_keys_in_range;
 use crate::start_execution_worker;
-use massa_async_pool::AsyncPoolConfig;
+use massa_async_pool::{AsyncMessage, AsyncPoolConfig};
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionError, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ExecutionConfig, ExecutionError, ExecutionStackElement, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
 };
-use massa_final_state::{FinalState, FinalStateConfig};
+use massa_final_state::{FinalState, FinalStateConfig, StateChanges};
 use massa_hash::Hash;
-use massa_ledger_exports::{LedgerConfig, LedgerError};
+use massa_ledger_exports::{
+    bytecode_version_key, datastore_prefix_range, storage_deposit_from_bytes, storage_deposit_key,
+    BytecodeVersion, LedgerChanges, LedgerConfig, LedgerEntry, LedgerError, SetUpdateOrDelete,
+    BYTECODE_VERSION_COUNT_KEY, RESERVED_DATASTORE_PREFIX, STORAGE_DEPOSIT_BYTECODE_KEY,
+    STORAGE_DEPOSIT_SIZE,
+};
 use massa_ledger_worker::FinalLedger;
 use massa_models::{
     api::EventFilter,
-    constants::{FINAL_HISTORY_LENGTH, THREAD_COUNT},
+    constants::{FINAL_HISTORY_LENGTH, FINAL_RECEIPTS_RETENTION_PERIODS, THREAD_COUNT},
+    execution::ExecutionTraceStep,
     wrapped::WrappedContent,
     Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, Operation,
     OperationSerializer, OperationType, WrappedBlock, WrappedOperation,
@@ -24,6 +37,7 @@ use serial_test::serial;
 use std::{
     cmp::Reverse,
     collections::{BTreeMap, HashMap},
+    ops::Bound,
     str::FromStr,
     sync::Arc,
     time::Duration,
@@ -43,16 +57,26 @@ pub fn get_random_address() -> Address {
 }
 
 fn get_sample_state() -> Result<(Arc<RwLock<FinalState>>, NamedTempFile, TempDir), LedgerError> {
-    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    get_sample_state_with(Default::default(), Amount::zero())
+}
+
+/// Same as `get_sample_state()` with additional initial balances and a storage cost charged from period 1
+fn get_sample_state_with(
+    mut initial: BTreeMap<Address, Amount>,
+    storage_cost_per_byte: Amount,
+) -> Result<(Arc<RwLock<FinalState>>, NamedTempFile, TempDir), LedgerError> {
     initial.insert(get_random_address(), Amount::from_str("129").unwrap());
     initial.insert(get_random_address(), Amount::from_str("878").unwrap());
-    let (ledger_config, tempfile, tempdir) = LedgerConfig::sample(&initial);
+    let (mut ledger_config, tempfile, tempdir) = LedgerConfig::sample(&initial);
+    ledger_config.storage_cost_per_byte = storage_cost_per_byte;
+    ledger_config.storage_cost_activation_period = Some(1);
     let ledger = FinalLedger::new(ledger_config.clone()).expect("could not init final ledger");
     let async_pool_config = AsyncPoolConfig { max_length: 100 };
     let cfg = FinalStateConfig {
         ledger_config,
         async_pool_config,
         final_history_length: FINAL_HISTORY_LENGTH,
+        receipts_retention_periods: FINAL_RECEIPTS_RETENTION_PERIODS,
         thread_count: THREAD_COUNT,
     };
     Ok((
@@ -66,8 +90,12 @@ fn get_sample_state() -> Result<(Arc<RwLock<FinalState>>, NamedTempFile, TempDir
 #[serial]
 fn test_execution_shutdown() {
     let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
-    let (mut manager, _) =
-        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let (mut manager, _) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
     manager.stop();
 }
 
@@ -75,8 +103,12 @@ fn test_execution_shutdown() {
 #[serial]
 fn test_sending_command() {
     let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
-    let (mut manager, controller) =
-        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let (mut manager, controller) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
     controller.update_blockclique_status(Default::default(), Default::default());
     manager.stop();
 }
@@ -85,9 +117,13 @@ fn test_sending_command() {
 #[serial]
 fn test_sending_read_only_execution_command() {
     let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
-    let (mut manager, controller) =
-        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
-    controller
+    let (mut manager, controller) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
+    let output = controller
         .execute_readonly_request(ReadOnlyExecutionRequest {
             max_gas: 1_000_000,
             simulated_gas_price: Amount::from_mantissa_scale(1_000_000, 0),
@@ -95,11 +131,86 @@ fn test_sending_read_only_execution_command() {
             target: ReadOnlyExecutionTarget::BytecodeExecution(
                 include_bytes!("./wasm/event_test.wasm").to_vec(),
             ),
+            credited_coins: Amount::zero(),
+            transferred_coins: Amount::zero(),
+            trace: false,
+        })
+        .unwrap();
+    assert!(output.gas_cost > 0 && output.gas_cost <= 1_000_000);
+    assert!(output.trace.is_none());
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_read_only_execution_trace() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
+    let output = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            simulated_gas_price: Amount::zero(),
+            call_stack: vec![],
+            target: ReadOnlyExecutionTarget::BytecodeExecution(
+                include_bytes!("./wasm/event_test.wasm").to_vec(),
+            ),
+            credited_coins: Amount::zero(),
+            transferred_coins: Amount::zero(),
+            trace: true,
         })
         .unwrap();
+    // the event emitted by the bytecode is recorded as an ABI call
+    let trace = output.trace.expect("missing trace");
+    assert!(trace.iter().any(|step| matches!(
+        step,
+        ExecutionTraceStep::AbiCall { function, error: None, .. } if function == "generate_event"
+    )));
     manager.stop();
 }
 
+#[test]
+#[serial]
+fn trace_limits() {
+    let (address, _keypair) = get_random_address_full();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(address, Amount::from_str("100").unwrap());
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
+    let mut context =
+        ExecutionContext::active_slot(Slot::new(1, 0), None, sample_state, Default::default());
+    context.stack = vec![ExecutionStackElement {
+        address,
+        coins: Amount::zero(),
+        owned_addresses: vec![address],
+    }];
+    context.trace = Some(Vec::new());
+
+    // written values are truncated in the trace
+    context
+        .set_data_entry(&address, b"key".to_vec(), vec![1; 1000])
+        .unwrap();
+    assert!(matches!(
+        context.trace.as_ref().unwrap().last(),
+        Some(ExecutionTraceStep::DatastoreWrite { value: Some(value), value_size: 1000, .. })
+            if value.len() == MAX_TRACE_VALUE_LENGTH
+    ));
+
+    // steps beyond the maximal length are dropped after a truncation marker
+    for _ in 0..MAX_TRACE_STEPS + 10 {
+        context
+            .transfer_parallel_coins(Some(address), None, Amount::zero())
+            .unwrap();
+    }
+    let trace = context.trace.unwrap();
+    assert_eq!(trace.len(), MAX_TRACE_STEPS + 1);
+    assert!(matches!(trace.last(), Some(ExecutionTraceStep::Truncated)));
+}
+
 /// Test the gas usage in nested calls using call SC operation
 ///
 /// Create a smart contract and send it in the blockclique.
@@ -121,7 +232,8 @@ fn test_nested_call_gas_usage() {
     // init the storage
     let storage = Storage::default();
     // start the execution worker
-    let (mut manager, controller) = start_execution_worker(exec_cfg, sample_state, storage.clone());
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
     // get random keypair
     let (_, keypair) = get_random_address_full();
     // load bytecode you can check the source code of the
@@ -200,6 +312,7 @@ fn test_nested_call_gas_usage() {
 /// 10. `receive_message` handler function should have emitted an event
 /// 11. we check if they are events
 /// 12. if they are some, we verify that the data has the correct value
+/// 13. the sender spent the coins of the message
 ///
 #[test]
 #[serial]
@@ -211,15 +324,20 @@ fn send_and_receive_async_message() {
         max_async_gas: 100_000,
         ..ExecutionConfig::default()
     };
+    // get random keypair, funded to pay for the message
+    let (sender_address, keypair) = get_random_address_full();
+    let initial_balance = Amount::from_str("100").unwrap();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(sender_address, initial_balance);
     // get a sample final state
-    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
 
     // init the storage
     let storage = Storage::default();
     // start the execution worker
-    let (mut manager, controller) = start_execution_worker(exec_cfg, sample_state, storage.clone());
-    // get random keypair
-    let (_, keypair) = get_random_address_full();
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
     // load send_message bytecode you can check the source code of the
     // following wasm file in massa-sc-examples
     let bytecode = include_bytes!("./wasm/send_message.wasm");
@@ -249,10 +367,83 @@ fn send_and_receive_async_message() {
     // match the events
     assert!(!events.is_empty(), "One event was expected");
     assert_eq!(events[0].data, "message received: hello my good friend!");
+    // the message carries 100 raw coins
+    let (_, active_balance) = controller
+        .get_final_and_active_parallel_balance(vec![sender_address])
+        .pop()
+        .unwrap();
+    assert_eq!(
+        active_balance,
+        Some(initial_balance.saturating_sub(Amount::from_raw(100)))
+    );
     // stop the execution controller
     manager.stop();
 }
 
+#[test]
+#[serial]
+fn async_message_spending() {
+    let (sender_address, _keypair) = get_random_address_full();
+    let initial_balance = Amount::from_str("10").unwrap();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(sender_address, initial_balance);
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
+    let slot = Slot::new(1, 0);
+    let mut context = ExecutionContext::active_slot(slot, None, sample_state, Default::default());
+    context.stack = vec![ExecutionStackElement {
+        address: sender_address,
+        coins: Amount::zero(),
+        owned_addresses: vec![sender_address],
+    }];
+    let message = |coins: &str, validity_end: Slot| AsyncMessage {
+        emission_slot: slot,
+        emission_index: 0,
+        sender: sender_address,
+        destination: get_random_address(),
+        handler: "receive".to_string(),
+        validity_start: slot,
+        validity_end,
+        max_gas: 1_000,
+        gas_price: Amount::from_str("0.001").unwrap(),
+        coins: Amount::from_str(coins).unwrap(),
+        data: Vec::new(),
+    };
+
+    // the sender cannot send more than it owns
+    assert!(context
+        .push_new_message(message("11", Slot::new(2, 0)))
+        .is_err());
+    assert_eq!(
+        context.get_parallel_balance(&sender_address),
+        Some(initial_balance)
+    );
+
+    // the coins are spent when sending
+    context
+        .push_new_message(message("3", Slot::new(2, 0)))
+        .unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sender_address),
+        Some(Amount::from_str("7").unwrap())
+    );
+
+    // an expired message is eliminated when settling the slot: its coins are reimbursed
+    context.push_new_message(message("2", slot)).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sender_address),
+        Some(Amount::from_str("5").unwrap())
+    );
+    let output = context.settle_slot();
+    assert_eq!(
+        output
+            .state_changes
+            .ledger_changes
+            .get_parallel_balance_or_else(&sender_address, || None),
+        Some(Amount::from_str("7").unwrap())
+    );
+}
+
 #[test]
 #[serial]
 fn generate_events() {
@@ -265,7 +456,8 @@ fn generate_events() {
     };
     let storage: Storage = Default::default();
     let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
-    let (mut manager, controller) = start_execution_worker(exec_cfg, sample_state, storage.clone());
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
 
     let (sender_address, keypair) = get_random_address_full();
     let event_test_data = include_bytes!("./wasm/event_test.wasm");
@@ -295,6 +487,773 @@ fn generate_events() {
     manager.stop();
 }
 
+#[test]
+#[serial]
+fn operation_receipts() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Execute a block containing `event_test.wasm` and check that the receipt
+    // of the operation reports a success and the emitted events.
+    let exec_cfg = ExecutionConfig {
+        t0: 10.into(),
+        ..ExecutionConfig::default()
+    };
+    let storage: Storage = Default::default();
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
+
+    let (_sender_address, keypair) = get_random_address_full();
+    let event_test_data = include_bytes!("./wasm/event_test.wasm");
+    let operation = create_execute_sc_operation(&keypair, event_test_data).unwrap();
+    let operation_id = operation.id;
+    let block = create_block(vec![operation], Slot::new(1, 0)).unwrap();
+    let slot = block.content.header.content.slot;
+
+    storage.store_block(block.clone());
+
+    let finalized_blocks: HashMap<Slot, BlockId> = Default::default();
+    let mut blockclique: HashMap<Slot, BlockId> = Default::default();
+
+    blockclique.insert(slot, block.id);
+
+    controller.update_blockclique_status(finalized_blocks, blockclique);
+
+    std::thread::sleep(Duration::from_millis(1000));
+    let events = controller.get_filtered_sc_output_event(EventFilter {
+        original_operation_id: Some(operation_id),
+        ..Default::default()
+    });
+    let (final_receipt, active_receipt) = controller
+        .get_final_and_active_operation_receipts(vec![operation_id])
+        .pop()
+        .unwrap();
+    assert!(final_receipt.is_none(), "the block is not final");
+    let receipt = active_receipt.expect("missing receipt");
+    assert_eq!(receipt.slot, slot);
+    assert!(receipt.success, "unexpected failure: {:?}", receipt.error);
+    assert!(receipt.gas_used > 0);
+    assert_eq!(receipt.event_count, events.len() as u64);
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn gas_refund() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Execute a block containing `event_test.wasm` with a large max_gas
+    // and check that only the consumed gas plus the base cost is charged.
+    let exec_cfg = ExecutionConfig {
+        t0: 10.into(),
+        ..ExecutionConfig::default()
+    };
+    let base_gas_cost = exec_cfg.operation_base_gas_cost;
+    let storage: Storage = Default::default();
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
+
+    let (sender_address, keypair) = get_random_address_full();
+    let max_gas = 100_000_000;
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::ExecuteSC {
+                data: include_bytes!("./wasm/event_test.wasm").to_vec(),
+                max_gas,
+                coins: Amount::zero(),
+                gas_price: Amount::from_raw(1),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    let operation_id = operation.id;
+    let block = create_block(vec![operation], Slot::new(1, 0)).unwrap();
+    let slot = block.content.header.content.slot;
+
+    storage.store_block(block.clone());
+
+    let mut blockclique: HashMap<Slot, BlockId> = Default::default();
+    blockclique.insert(slot, block.id);
+    controller.update_blockclique_status(Default::default(), blockclique);
+
+    std::thread::sleep(Duration::from_millis(1000));
+    let (_, active_receipt) = controller
+        .get_final_and_active_operation_receipts(vec![operation_id])
+        .pop()
+        .unwrap();
+    let receipt = active_receipt.expect("missing receipt");
+    assert!(receipt.success, "unexpected failure: {:?}", receipt.error);
+    assert_eq!(receipt.gas_charged, receipt.gas_used + base_gas_cost);
+    assert_eq!(
+        receipt.refunded_coins,
+        Amount::from_raw(max_gas - receipt.gas_charged)
+    );
+    // the refund is credited to the sender and reported by an event
+    let (_, active_balance) = controller
+        .get_final_and_active_parallel_balance(vec![sender_address])
+        .pop()
+        .unwrap();
+    assert_eq!(active_balance, Some(receipt.refunded_coins));
+    let events = controller.get_filtered_sc_output_event(EventFilter {
+        original_operation_id: Some(operation_id),
+        ..Default::default()
+    });
+    assert!(events
+        .iter()
+        .any(|event| event.data.contains("massa_gas_refund")));
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn simulate_operation() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Simulate an operation executing `event_test.wasm` with a large max_gas
+    // and check that neither gas fees nor refunds are credited.
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
+
+    let (_, keypair) = get_random_address_full();
+    let max_gas = 100_000_000;
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::ExecuteSC {
+                data: include_bytes!("./wasm/event_test.wasm").to_vec(),
+                max_gas,
+                coins: Amount::zero(),
+                gas_price: Amount::from_raw(1),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    let operation_id = operation.id;
+    let output = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas,
+            simulated_gas_price: Amount::from_raw(1),
+            call_stack: vec![],
+            target: ReadOnlyExecutionTarget::Operation(operation),
+            credited_coins: Amount::zero(),
+            transferred_coins: Amount::zero(),
+            trace: false,
+        })
+        .unwrap();
+
+    // the receipt reports the refund, but no coins are credited to anyone
+    let receipt = output
+        .out
+        .state_changes
+        .receipts_changes
+        .0
+        .get(&operation_id)
+        .expect("missing receipt");
+    assert!(receipt.success, "unexpected failure: {:?}", receipt.error);
+    assert_eq!(
+        receipt.refunded_coins,
+        Amount::from_raw(max_gas - receipt.gas_charged)
+    );
+    assert!(output.out.state_changes.ledger_changes.0.is_empty());
+    assert!(output.out.events.iter().next().is_some());
+    assert!(output
+        .out
+        .events
+        .iter()
+        .all(|event| !event.data.contains("massa_gas_refund")));
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn operation_base_gas_cost() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Simulate an operation executing `event_test.wasm` with a max_gas that only covers the base cost
+    // and check that it is not executed and is charged its whole max_gas.
+    let exec_cfg = ExecutionConfig::default();
+    let max_gas = exec_cfg.operation_base_gas_cost;
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(
+        exec_cfg,
+        sample_state,
+        Default::default(),
+        Default::default(),
+    );
+
+    let (_, keypair) = get_random_address_full();
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::ExecuteSC {
+                data: include_bytes!("./wasm/event_test.wasm").to_vec(),
+                max_gas,
+                coins: Amount::zero(),
+                gas_price: Amount::from_raw(1),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    let operation_id = operation.id;
+    let output = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas,
+            simulated_gas_price: Amount::from_raw(1),
+            call_stack: vec![],
+            target: ReadOnlyExecutionTarget::Operation(operation),
+            credited_coins: Amount::zero(),
+            transferred_coins: Amount::zero(),
+            trace: false,
+        })
+        .unwrap();
+
+    let receipt = output
+        .out
+        .state_changes
+        .receipts_changes
+        .0
+        .get(&operation_id)
+        .expect("missing receipt");
+    assert!(!receipt.success);
+    assert_eq!(receipt.gas_used, 0);
+    assert_eq!(receipt.gas_charged, max_gas);
+    assert_eq!(receipt.refunded_coins, Amount::zero());
+    // the only event is the execution error
+    assert!(output
+        .out
+        .events
+        .iter()
+        .all(|event| event.data.contains("massa_execution_error")));
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn storage_cost() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Execute a block containing `nested_call.wasm`, that creates a smart contract,
+    // and check that the sender pays for the storage of its bytecode.
+    let exec_cfg = ExecutionConfig {
+        t0: 10.into(),
+        ..ExecutionConfig::default()
+    };
+    let (sender_address, keypair) = get_random_address_full();
+    let initial_balance = Amount::from_str("100").unwrap();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(sender_address, initial_balance);
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::from_mantissa_scale(1, 3)).unwrap();
+    let storage: Storage = Default::default();
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, storage.clone(), Default::default());
+
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::ExecuteSC {
+                data: include_bytes!("./wasm/nested_call.wasm").to_vec(),
+                max_gas: 100_000_000,
+                coins: Amount::zero(),
+                gas_price: Amount::zero(),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    let operation_id = operation.id;
+    let block = create_block(vec![operation], Slot::new(1, 0)).unwrap();
+    let slot = block.content.header.content.slot;
+
+    storage.store_block(block.clone());
+
+    let mut blockclique: HashMap<Slot, BlockId> = Default::default();
+    blockclique.insert(slot, block.id);
+    controller.update_blockclique_status(Default::default(), blockclique);
+
+    std::thread::sleep(Duration::from_millis(1000));
+    let (_, active_receipt) = controller
+        .get_final_and_active_operation_receipts(vec![operation_id])
+        .pop()
+        .unwrap();
+    let receipt = active_receipt.expect("missing receipt");
+    assert!(receipt.success, "unexpected failure: {:?}", receipt.error);
+    assert!(receipt.storage_cost_charged > Amount::zero());
+    let (_, active_balance) = controller
+        .get_final_and_active_parallel_balance(vec![sender_address])
+        .pop()
+        .unwrap();
+    assert_eq!(
+        active_balance,
+        Some(
+            initial_balance
+                .saturating_sub(receipt.storage_cost_charged)
+                .saturating_add(receipt.storage_cost_refunded)
+        )
+    );
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn bytecode_history() {
+    let (creator_address, _keypair) = get_random_address_full();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(creator_address, Amount::from_str("100").unwrap());
+    let storage_cost_per_byte = Amount::from_mantissa_scale(1, 3);
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, storage_cost_per_byte).unwrap();
+    let slot = Slot::new(1, 0);
+    let mut context = ExecutionContext::active_slot(slot, None, sample_state, Default::default());
+    context.stack = vec![ExecutionStackElement {
+        address: creator_address,
+        coins: Amount::zero(),
+        owned_addresses: vec![creator_address],
+    }];
+    let version_size = bytecode_version_key(1).len()
+        + BytecodeVersion {
+            slot,
+            hash: Hash::compute_from(b""),
+        }
+        .to_bytes()
+        .len();
+    let count_size = BYTECODE_VERSION_COUNT_KEY.len() + 8;
+    let bytecode_deposit_size = STORAGE_DEPOSIT_BYTECODE_KEY.len() + STORAGE_DEPOSIT_SIZE;
+
+    // the creator pays for the bytecode and the first version of the new address
+    let sc_address = context.create_new_sc_address(vec![1; 10]).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&creator_address),
+        Some(Amount::from_str("100").unwrap().saturating_sub(
+            storage_cost_per_byte.saturating_mul_u64(
+                (10 + bytecode_deposit_size + version_size + count_size) as u64
+            )
+        ))
+    );
+
+    // the address pays for the growth of its bytecode and for its new version
+    context
+        .transfer_parallel_coins(
+            Some(creator_address),
+            Some(sc_address),
+            Amount::from_str("1").unwrap(),
+        )
+        .unwrap();
+    context.set_bytecode(&sc_address, vec![2; 20]).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sc_address),
+        Some(
+            Amount::from_str("1").unwrap().saturating_sub(
+                storage_cost_per_byte.saturating_mul_u64((10 + version_size) as u64)
+            )
+        )
+    );
+
+    // each version is stored in its own entry
+    assert_eq!(
+        context.get_data_entry(&sc_address, BYTECODE_VERSION_COUNT_KEY),
+        Some(2u64.to_be_bytes().to_vec())
+    );
+    for (version, bytecode) in [(1, vec![1; 10]), (2, vec![2; 20])] {
+        assert_eq!(
+            context
+                .get_data_entry(&sc_address, &bytecode_version_key(version))
+                .and_then(|bytes| BytecodeVersion::from_bytes(&bytes)),
+            Some(BytecodeVersion {
+                slot,
+                hash: Hash::compute_from(&bytecode),
+            })
+        );
+    }
+
+    // the update was notified
+    let events: Vec<_> = context
+        .events
+        .iter()
+        .filter(|event| event.data.contains("massa_bytecode_updated"))
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].data.contains(&sc_address.to_string()));
+    assert!(events[0].data.contains("\"version\":2"));
+}
+
+#[test]
+#[serial]
+fn reserved_datastore_keys() {
+    let (creator_address, _keypair) = get_random_address_full();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(creator_address, Amount::from_str("100").unwrap());
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
+    let mut context =
+        ExecutionContext::active_slot(Slot::new(1, 0), None, sample_state, Default::default());
+    context.stack = vec![ExecutionStackElement {
+        address: creator_address,
+        coins: Amount::zero(),
+        owned_addresses: vec![creator_address],
+    }];
+    let address = context.create_new_sc_address(vec![1; 10]).unwrap();
+    let version_count = context.get_data_entry(&address, BYTECODE_VERSION_COUNT_KEY);
+    assert!(version_count.is_some());
+
+    // contracts cannot write under the reserved prefix, whether the entry exists or not
+    for key in [
+        BYTECODE_VERSION_COUNT_KEY.to_vec(),
+        storage_deposit_key(b"key"),
+        [RESERVED_DATASTORE_PREFIX, b"unused"].concat(),
+    ] {
+        assert!(context
+            .set_data_entry(&address, key.clone(), vec![1])
+            .is_err());
+        assert!(context
+            .append_data_entry(&address, key.clone(), vec![1])
+            .is_err());
+        assert!(context.delete_data_entry(&address, &key).is_err());
+    }
+    assert_eq!(
+        context.get_data_entry(&address, BYTECODE_VERSION_COUNT_KEY),
+        version_count
+    );
+
+    // keys that only look like reserved keys are regular user keys
+    for key in [
+        b"massa/bytecode_version_count".to_vec(),
+        b"\0massa".to_vec(),
+    ] {
+        context
+            .set_data_entry(&address, key.clone(), vec![1])
+            .unwrap();
+        assert_eq!(context.get_data_entry(&address, &key), Some(vec![1]));
+    }
+}
+
+#[test]
+#[serial]
+fn storage_deposits() {
+    let (address, _keypair) = get_random_address_full();
+    let initial_balance = Amount::from_str("100").unwrap();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(address, initial_balance);
+    let storage_cost_per_byte = Amount::from_mantissa_scale(1, 3);
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, storage_cost_per_byte).unwrap();
+    let stack = vec![ExecutionStackElement {
+        address,
+        coins: Amount::zero(),
+        owned_addresses: vec![address],
+    }];
+    let deposit = |entry_size: usize| {
+        storage_cost_per_byte.saturating_mul_u64(
+            (entry_size + storage_deposit_key(b"").len() + STORAGE_DEPOSIT_SIZE) as u64,
+        )
+    };
+    let recorded_deposit = |context: &ExecutionContext, key: &[u8]| {
+        storage_deposit_from_bytes(
+            context
+                .get_data_entry(&address, &storage_deposit_key(key))
+                .as_deref(),
+        )
+    };
+
+    // an entry written before storage deposits existed
+    let mut legacy_changes = LedgerChanges::default();
+    legacy_changes.set_data_entry(address, b"legacy".to_vec(), vec![1; 10]);
+    sample_state
+        .write()
+        .ledger
+        .apply_changes(legacy_changes, Slot::new(0, 0));
+
+    // before the activation period, storage is free and no deposit is recorded
+    let mut context = ExecutionContext::active_slot(
+        Slot::new(0, 1),
+        None,
+        sample_state.clone(),
+        Default::default(),
+    );
+    context.stack = stack.clone();
+    context
+        .set_data_entry(&address, b"key".to_vec(), vec![1; 10])
+        .unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance)
+    );
+    assert!(!context.has_data_entry(&address, &storage_deposit_key(b"key")));
+
+    // from the activation period, the deposit covers the entry and its record
+    let mut context =
+        ExecutionContext::active_slot(Slot::new(1, 0), None, sample_state, Default::default());
+    context.stack = stack;
+    context
+        .set_data_entry(&address, b"key".to_vec(), vec![1; 10])
+        .unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance.saturating_sub(deposit(13)))
+    );
+    assert_eq!(recorded_deposit(&context, b"key"), deposit(13));
+
+    // growing the entry is charged the difference
+    context
+        .append_data_entry(&address, b"key".to_vec(), vec![2; 5])
+        .unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance.saturating_sub(deposit(18)))
+    );
+    assert_eq!(recorded_deposit(&context, b"key"), deposit(18));
+
+    // deleting the entry refunds its deposit and removes its record
+    context.delete_data_entry(&address, b"key").unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance)
+    );
+    assert!(!context.has_data_entry(&address, &storage_deposit_key(b"key")));
+
+    // deleting an entry without deposit refunds nothing
+    context.delete_data_entry(&address, b"legacy").unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance)
+    );
+
+    // an entry that cannot be paid for is not written
+    assert!(context
+        .set_data_entry(&address, b"big".to_vec(), vec![1; 200_000])
+        .is_err());
+    assert!(!context.has_data_entry(&address, b"big"));
+    assert_eq!(
+        context.get_parallel_balance(&address),
+        Some(initial_balance)
+    );
+}
+
+#[test]
+#[serial]
+fn datastore_keys_in_range() {
+    let (address, _keypair) = get_random_address_full();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(address, Amount::from_str("100").unwrap());
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
+    let key = |name: &str| name.as_bytes().to_vec();
+
+    // final datastore: a, b, c, d
+    let mut final_changes = LedgerChanges::default();
+    for name in ["a", "b", "c", "d"] {
+        final_changes.set_data_entry(address, key(name), vec![1]);
+    }
+    sample_state
+        .write()
+        .ledger
+        .apply_changes(final_changes, Slot::new(1, 0));
+
+    // pending changes: b is deleted and e added by a first slot, then e and d are deleted and bb added
+    let mut first_changes = LedgerChanges::default();
+    first_changes.delete_data_entry(address, key("b"));
+    first_changes.set_data_entry(address, key("e"), vec![2]);
+    let mut second_changes = LedgerChanges::default();
+    second_changes.delete_data_entry(address, key("e"));
+    second_changes.delete_data_entry(address, key("d"));
+    second_changes.set_data_entry(address, key("bb"), vec![3]);
+    let pending = [first_changes, second_changes];
+
+    let final_state = sample_state.read();
+    let keys_in_range = |changes: &[LedgerChanges], range, limit| {
+        get_datastore_keys_in_range(&final_state, changes.iter(), &address, range, limit)
+    };
+    let all = (Bound::Unbounded, Bound::Unbounded);
+    assert_eq!(
+        keys_in_range(&pending[..0], all.clone(), 10),
+        vec![key("a"), key("b"), key("c"), key("d")]
+    );
+    assert_eq!(
+        keys_in_range(&pending[..1], all.clone(), 10),
+        vec![key("a"), key("c"), key("d"), key("e")]
+    );
+    assert_eq!(
+        keys_in_range(&pending[..], all.clone(), 10),
+        vec![key("a"), key("bb"), key("c")]
+    );
+
+    // deleted final keys do not shorten a page
+    assert_eq!(
+        keys_in_range(&pending[..], all.clone(), 2),
+        vec![key("a"), key("bb")]
+    );
+    assert_eq!(
+        keys_in_range(
+            &pending[..],
+            (Bound::Excluded(key("a")), Bound::Unbounded),
+            2
+        ),
+        vec![key("bb"), key("c")]
+    );
+    assert_eq!(
+        keys_in_range(&pending[..], datastore_prefix_range(b"b"), 10),
+        vec![key("bb")]
+    );
+    assert!(keys_in_range(
+        &pending[..],
+        (Bound::Included(key("d")), Bound::Excluded(key("a"))),
+        10
+    )
+    .is_empty());
+
+    // replacing the whole entry hides the final keys
+    let mut replaced = LedgerChanges::default();
+    replaced.0.insert(
+        address,
+        SetUpdateOrDelete::Set(LedgerEntry {
+            datastore: [(key("z"), vec![4])].into_iter().collect(),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(keys_in_range(&[replaced][..], all, 10), vec![key("z")]);
+}
+
+#[test]
+#[serial]
+fn replay_from_snapshot() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Replay a block containing `nested_call.wasm` from a snapshot of the final state,
+    // then replay it again from a recording of the first replay and against wrong changes.
+    let (sender_address, keypair) = get_random_address_full();
+    let mut initial: BTreeMap<Address, Amount> = Default::default();
+    initial.insert(sender_address, Amount::from_str("100").unwrap());
+    let (sample_state, _keep_file, _keep_dir) =
+        get_sample_state_with(initial, Amount::zero()).unwrap();
+    let snapshot = FinalStateSnapshot::take(&sample_state.read()).unwrap();
+
+    // restore the snapshot in an empty ledger, its fingerprint is checked on restoration
+    let restore = |snapshot: FinalStateSnapshot| {
+        let (ledger_config, keep_file, keep_dir) = LedgerConfig::sample(&BTreeMap::new());
+        let ledger = FinalLedger::new(ledger_config.clone()).unwrap();
+        let cfg = FinalStateConfig {
+            ledger_config,
+            async_pool_config: AsyncPoolConfig { max_length: 100 },
+            final_history_length: FINAL_HISTORY_LENGTH,
+            receipts_retention_periods: FINAL_RECEIPTS_RETENTION_PERIODS,
+            thread_count: THREAD_COUNT,
+        };
+        (
+            snapshot.restore(cfg, Box::new(ledger)).unwrap(),
+            keep_file,
+            keep_dir,
+        )
+    };
+
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::from_str("1").unwrap(),
+            expire_period: 10,
+            op: OperationType::ExecuteSC {
+                data: include_bytes!("./wasm/nested_call.wasm").to_vec(),
+                max_gas: 100_000_000,
+                coins: Amount::zero(),
+                gas_price: Amount::zero(),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    let block_slot = Slot::new(1, 0);
+    let block = create_block(vec![operation], block_slot).unwrap();
+    let end_slot = block_slot.get_next_slot(THREAD_COUNT).unwrap();
+
+    // first replay, without recorded changes
+    let (final_state, _keep_file, _keep_dir) = restore(snapshot.clone());
+    let reports = replay_final_slots(
+        ExecutionConfig::default(),
+        final_state,
+        vec![block.clone()],
+        Vec::new(),
+        end_slot,
+    )
+    .unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].block_id, Some(block.id));
+    assert!(!reports[0].checked);
+    assert!(reports[0]
+        .replayed_changes
+        .ledger_changes
+        .0
+        .contains_key(&sender_address));
+
+    // record the replayed slots like a node does
+    let recording_dir = TempDir::new().unwrap();
+    let (final_state, _keep_file, _keep_dir) = restore(snapshot.clone());
+    let mut recorder = ReplayRecorder::start(
+        recording_dir.path(),
+        &ExecutionConfig::default(),
+        &final_state,
+    )
+    .unwrap();
+    for report in reports.iter() {
+        let slot_block = (report.block_id == Some(block.id)).then(|| &block);
+        recorder
+            .record_slot(report.slot, slot_block, &report.replayed_changes)
+            .unwrap();
+    }
+    drop(recorder);
+
+    // the replay of the recording is deterministic
+    let recorded_snapshot =
+        FinalStateSnapshot::load(&recording_dir.path().join(SNAPSHOT_FILE)).unwrap();
+    assert_eq!(recorded_snapshot.fingerprint, snapshot.fingerprint);
+    let settings = ReplaySettings::load(&recording_dir.path().join(SETTINGS_FILE)).unwrap();
+    let recorded_blocks = load_blocks(&recording_dir.path().join(BLOCKS_FILE)).unwrap();
+    assert_eq!(recorded_blocks.len(), 1);
+    let recorded_changes =
+        load_state_changes(&recording_dir.path().join(STATE_CHANGES_FILE)).unwrap();
+    assert_eq!(recorded_changes.len(), 2);
+    let (final_state, _keep_file, _keep_dir) = restore(recorded_snapshot);
+    let reports_again = replay_final_slots(
+        settings.execution_config(),
+        final_state,
+        recorded_blocks,
+        recorded_changes,
+        end_slot,
+    )
+    .unwrap();
+    assert!(reports_again
+        .iter()
+        .all(|report| report.checked && report.is_consistent()));
+    assert_eq!(reports_again[1].fingerprint, reports[1].fingerprint);
+
+    // divergences are reported
+    let (final_state, _keep_file, _keep_dir) = restore(snapshot);
+    let reports_wrong = replay_final_slots(
+        ExecutionConfig::default(),
+        final_state,
+        vec![block],
+        vec![(block_slot, StateChanges::default())],
+        end_slot,
+    )
+    .unwrap();
+    assert!(!reports_wrong[0].is_consistent());
+    assert!(matches!(
+        reports_wrong[0].ledger_divergences.get(&sender_address),
+        Some((None, Some(_)))
+    ));
+    assert!(!reports_wrong[1].checked);
+}
+
 /// Create an operation for the given sender with `data` as bytecode.
 /// Return a result that should be unwrapped in the root `#[test]` routine.
 fn create_execute_sc_operation(
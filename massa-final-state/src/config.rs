@@ -14,6 +14,8 @@ pub struct FinalStateConfig {
     pub async_pool_config: AsyncPoolConfig,
     /// final changes history length
     pub final_history_length: usize,
+    /// number of periods during which the receipts of the executed operations are kept
+    pub receipts_retention_periods: u64,
     /// thread count
     pub thread_count: u8,
 }
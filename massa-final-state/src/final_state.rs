@@ -1,17 +1,26 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 //! This file defines the final state of the node, which includes
-//! the final ledger and asynchronous message pool that are kept at
+//! the final ledger, asynchronous message pool and operation receipts that are kept at
 //! the output of a given final slot (the latest executed final slot),
 //! and need to be bootstrapped by nodes joining the network.
 
-use crate::{config::FinalStateConfig, error::FinalStateError, state_changes::StateChanges};
+use crate::{
+    config::FinalStateConfig,
+    error::FinalStateError,
+    receipts::{FinalReceipts, ReceiptsChanges},
+    state_changes::StateChanges,
+};
 use massa_async_pool::{AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
-use massa_ledger_exports::{LedgerChanges, LedgerController};
-use massa_models::{constants::THREAD_COUNT, Address, Slot};
+use massa_hash::Hash;
+use massa_ledger_exports::{
+    balance_key, bytecode_key, data_key, LedgerChanges, LedgerController, LedgerError, LedgerProof,
+    BALANCE_IDENT, BYTECODE_IDENT, DATASTORE_IDENT,
+};
+use massa_models::{constants::THREAD_COUNT, Address, OperationId, Slot};
 use std::collections::VecDeque;
 
-/// Represents a final state `(ledger, async pool)`
+/// Represents a final state `(ledger, async pool, receipts)`
 #[derive(Debug)]
 pub struct FinalState {
     /// execution state configuration
@@ -22,9 +31,13 @@ pub struct FinalState {
     pub ledger: Box<dyn LedgerController>,
     /// asynchronous pool containing messages sorted by priority and their data
     pub async_pool: AsyncPool,
+    /// receipts of the operations executed during the latest final periods
+    pub receipts: FinalReceipts,
     /// history of recent final state changes, useful for streaming bootstrap
     /// `front = oldest`, `back = newest`
     pub(crate) changes_history: VecDeque<(Slot, StateChanges)>,
+    /// fingerprint of the state at `slot`, see `compute_fingerprint`
+    pub fingerprint: Hash,
 }
 
 impl FinalState {
@@ -36,22 +49,138 @@ impl FinalState {
         config: FinalStateConfig,
         ledger: Box<dyn LedgerController>,
     ) -> Result<Self, FinalStateError> {
-        // attach at the output of the latest initial final slot, that is the last genesis slot
-        let slot = Slot::new(0, config.thread_count.saturating_sub(1));
+        // attach at the output of the slot of the ledger if it was kept from a previous run,
+        // otherwise at the output of the latest initial final slot, that is the last genesis slot
+        let slot = ledger
+            .get_slot()
+            .unwrap_or_else(|| Slot::new(0, config.thread_count.saturating_sub(1)));
 
         // create the async pool
         let async_pool = AsyncPool::new(config.async_pool_config.clone());
 
+        // create the final receipts
+        let receipts = FinalReceipts::new(config.receipts_retention_periods);
+
         // generate the final state
-        Ok(FinalState {
+        let mut final_state = FinalState {
             slot,
             ledger,
             async_pool,
+            receipts,
             config,
             changes_history: Default::default(), // no changes in history
+            fingerprint: Hash::zero(),
+        };
+        final_state.fingerprint = final_state.compute_fingerprint();
+        Ok(final_state)
+    }
+
+    /// Empties the ledger, the async pool and the receipts, attaching the state back to the last genesis slot.
+    /// Used to restart a bootstrap from scratch, since the parts streamed so far cannot be trusted.
+    pub fn reset(&mut self) {
+        self.ledger.reset();
+        self.async_pool.reset();
+        self.receipts.reset();
+        self.slot = Slot::new(0, self.config.thread_count.saturating_sub(1));
+        self.changes_history.clear();
+        self.fingerprint = self.compute_fingerprint();
+    }
+
+    /// Computes the fingerprint of the final state from the incrementally maintained hashes
+    /// of the ledger, of the async pool and of the receipts, along with the slot the state is attached to.
+    ///
+    /// Two nodes with the same final state at the same slot have the same fingerprint,
+    /// which allows bootstrapped nodes to check the state they received.
+    pub fn compute_fingerprint(&self) -> Hash {
+        let mut bytes = self.slot.to_bytes_key().to_vec();
+        bytes.extend(self.ledger.get_ledger_hash().to_bytes());
+        bytes.extend(self.async_pool.get_hash().to_bytes());
+        bytes.extend(self.receipts.get_hash().to_bytes());
+        Hash::compute_from(&bytes)
+    }
+
+    /// Get the balance, bytecode hash and requested datastore entries of an address,
+    /// along with their proofs against the ledger root of the final state.
+    ///
+    /// # Arguments
+    /// * `addr`: target address
+    /// * `keys`: datastore keys to prove
+    /// * `slot`: past final slot at the output of which the proof is generated, the current one if `None`.
+    ///   Past slots are served from the ledger history, which requires the ledger archive mode.
+    pub fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> Result<LedgerProof, FinalStateError> {
+        let slot = match slot {
+            Some(slot) if slot != self.slot => slot,
+            _ => return Ok(self.get_current_ledger_proof(addr, keys)),
+        };
+        let map_err = |err: LedgerError| FinalStateError::LedgerError(err.to_string());
+        let bytecode_proof = self
+            .ledger
+            .get_key_proof_at_slot(&bytecode_key!(addr), slot)
+            .map_err(map_err)?;
+        Ok(LedgerProof {
+            address: *addr,
+            slot,
+            ledger_root: self.ledger.get_ledger_hash_at_slot(slot).map_err(map_err)?,
+            // the async pool is not archived
+            async_pool_hash: None,
+            balance: self
+                .ledger
+                .get_parallel_balance_at_slot(addr, slot)
+                .map_err(map_err)?,
+            balance_proof: self
+                .ledger
+                .get_key_proof_at_slot(&balance_key!(addr), slot)
+                .map_err(map_err)?,
+            // the leaf of the bytecode commits to its hash
+            bytecode_hash: bytecode_proof.value_hash,
+            bytecode_proof,
+            datastore: keys
+                .into_iter()
+                .map(|key| {
+                    let value = self
+                        .ledger
+                        .get_data_entry_at_slot(addr, &key, slot)
+                        .map_err(map_err)?;
+                    let proof = self
+                        .ledger
+                        .get_key_proof_at_slot(&data_key!(addr, key[..]), slot)
+                        .map_err(map_err)?;
+                    Ok((key, value, proof))
+                })
+                .collect::<Result<_, FinalStateError>>()?,
         })
     }
 
+    /// Get the proofs of `get_ledger_proof` against the current ledger root of the final state
+    fn get_current_ledger_proof(&self, addr: &Address, keys: Vec<Vec<u8>>) -> LedgerProof {
+        LedgerProof {
+            address: *addr,
+            slot: self.slot,
+            ledger_root: self.ledger.get_ledger_hash(),
+            async_pool_hash: Some(self.async_pool.get_hash()),
+            balance: self.ledger.get_parallel_balance(addr),
+            balance_proof: self.ledger.get_key_proof(&balance_key!(addr)),
+            bytecode_hash: self
+                .ledger
+                .get_bytecode(addr)
+                .map(|bytecode| Hash::compute_from(&bytecode)),
+            bytecode_proof: self.ledger.get_key_proof(&bytecode_key!(addr)),
+            datastore: keys
+                .into_iter()
+                .map(|key| {
+                    let value = self.ledger.get_data_entry(addr, &key);
+                    let proof = self.ledger.get_key_proof(&data_key!(addr, key[..]));
+                    (key, value, proof)
+                })
+                .collect(),
+        }
+    }
+
     /// Applies changes to the execution state at a given slot, and settles that slot forever.
     /// Once this is called, the state is attached at the output of the provided slot.
     ///
@@ -74,6 +203,11 @@ impl FinalState {
             .apply_changes(changes.ledger_changes.clone(), self.slot);
         self.async_pool
             .apply_changes_unchecked(&changes.async_pool_changes);
+        self.receipts
+            .apply_changes(&changes.receipts_changes, self.slot);
+
+        // update the fingerprint of the state
+        self.fingerprint = self.compute_fingerprint();
 
         // push history element and limit history size
         if self.config.final_history_length > 0 {
@@ -84,17 +218,32 @@ impl FinalState {
         }
     }
 
+    /// Get the configuration of the final state
+    pub fn get_config(&self) -> &FinalStateConfig {
+        &self.config
+    }
+
+    /// Get the history of the latest final state changes, oldest first
+    pub fn get_changes_history(&self) -> &VecDeque<(Slot, StateChanges)> {
+        &self.changes_history
+    }
+
     /// Used for bootstrap
-    /// Take a part of the final state changes (ledger and async pool) using a `Slot`, a `Address` and a `AsyncMessageId`.
+    /// Take a part of the final state changes (ledger, async pool and receipts) using a `Slot`, a `Address`,
+    /// a `AsyncMessageId` and a `OperationId`.
     /// Every ledgers changes that are after `last_slot` and before or equal of `last_address` must be returned.
     /// Every async pool changes that are after `last_slot` and before or equal of `last_id_async_pool` must be returned.
+    /// Every receipt added after `last_slot` with an operation id before or equal of `last_receipt_id` must be returned.
+    /// A `None` address, message id or operation id means that nothing was streamed yet for that part of the state,
+    /// so no change is returned for it.
     ///
     /// Error case: When the last_slot is too old for `self.changes_history`
     pub fn get_state_changes_part(
         &self,
         last_slot: Slot,
-        last_address: Address,
-        last_id_async_pool: AsyncMessageId,
+        last_address: Option<Address>,
+        last_id_async_pool: Option<AsyncMessageId>,
+        last_receipt_id: Option<OperationId>,
     ) -> Result<StateChanges, FinalStateError> {
         let pos_slot = if !self.changes_history.is_empty() {
             // Safe because we checked that there is changes just above.
@@ -121,16 +270,14 @@ impl FinalState {
                     .ledger_changes
                     .0
                     .iter()
-                    .filter_map(|(address, change)| {
-                        if *address <= last_address {
+                    .filter_map(|(address, change)| match last_address {
+                        Some(last_address) if *address <= last_address => {
                             Some((*address, change.clone()))
-                        } else {
-                            None
                         }
+                        _ => None,
                     })
                     .collect(),
             );
-            res_changes.ledger_changes = ledger_changes;
 
             //Get async pool changes that concern ids <= last_id_async_pool
             let async_pool_changes: AsyncPoolChanges = AsyncPoolChanges(
@@ -138,15 +285,35 @@ impl FinalState {
                     .async_pool_changes
                     .0
                     .iter()
-                    .filter_map(|change| match change {
-                        Change::Add(id, _) if id <= &last_id_async_pool => Some(change.clone()),
-                        Change::Delete(id) if id <= &last_id_async_pool => Some(change.clone()),
-                        Change::Add(..) => None,
-                        Change::Delete(..) => None,
+                    .filter_map(|change| match (change, &last_id_async_pool) {
+                        (Change::Add(id, _), Some(last_id)) if id <= last_id => {
+                            Some(change.clone())
+                        }
+                        (Change::Delete(id), Some(last_id)) if id <= last_id => {
+                            Some(change.clone())
+                        }
+                        _ => None,
                     })
                     .collect(),
             );
-            res_changes.async_pool_changes = async_pool_changes;
+
+            //Get receipts of the operations with ids <= last_receipt_id
+            let receipts_changes = ReceiptsChanges(match last_receipt_id {
+                Some(last_id) => changes
+                    .receipts_changes
+                    .0
+                    .range(..=last_id)
+                    .map(|(id, receipt)| (*id, receipt.clone()))
+                    .collect(),
+                None => Default::default(),
+            });
+
+            // accumulate the changes of each slot
+            res_changes.apply(StateChanges {
+                ledger_changes,
+                async_pool_changes,
+                receipts_changes,
+            });
         }
         Ok(res_changes)
     }
@@ -160,7 +327,7 @@ mod tests {
     use crate::{FinalState, StateChanges};
     use massa_async_pool::test_exports::get_random_message;
     use massa_ledger_exports::SetUpdateOrDelete;
-    use massa_models::{Address, Slot};
+    use massa_models::{execution::OperationReceipt, Address, Amount, OperationId, Slot};
     use massa_signature::KeyPair;
 
     fn get_random_address() -> Address {
@@ -206,13 +373,109 @@ mod tests {
         final_state.changes_history = history_state_changes;
         // Test slot filter
         let part = final_state
-            .get_state_changes_part(Slot::new(2, 0), low_address, message.compute_id())
+            .get_state_changes_part(
+                Slot::new(2, 0),
+                Some(low_address),
+                Some(message.compute_id()),
+                None,
+            )
             .unwrap();
         assert_eq!(part.ledger_changes.0.len(), 1);
         // Test address filter
         let part = final_state
-            .get_state_changes_part(Slot::new(2, 0), high_address, message.compute_id())
+            .get_state_changes_part(
+                Slot::new(2, 0),
+                Some(high_address),
+                Some(message.compute_id()),
+                None,
+            )
             .unwrap();
         assert_eq!(part.ledger_changes.0.len(), 1);
     }
+
+    #[test]
+    fn get_state_changes_part_accumulates_slots() {
+        let message = get_random_message();
+        let (low_address, high_address) = {
+            let address1 = get_random_address();
+            let address2 = get_random_address();
+            if address1 < address2 {
+                (address1, address2)
+            } else {
+                (address2, address1)
+            }
+        };
+        // one change per slot, on a different address each time
+        let mut history_state_changes: VecDeque<(Slot, StateChanges)> = VecDeque::new();
+        let mut state_changes = StateChanges::default();
+        state_changes
+            .ledger_changes
+            .0
+            .insert(low_address, SetUpdateOrDelete::Delete);
+        state_changes
+            .async_pool_changes
+            .0
+            .push(massa_async_pool::Change::Add(
+                message.compute_id(),
+                message.clone(),
+            ));
+        history_state_changes.push_back((Slot::new(1, 0), state_changes));
+        let mut state_changes = StateChanges::default();
+        state_changes
+            .ledger_changes
+            .0
+            .insert(high_address, SetUpdateOrDelete::Delete);
+        let op_id = OperationId::from_bytes(&[1; 32]);
+        state_changes.receipts_changes.0.insert(
+            op_id,
+            OperationReceipt {
+                slot: Slot::new(2, 0),
+                success: true,
+                error: None,
+                gas_used: 1000,
+                gas_charged: 11000,
+                refunded_coins: Amount::zero(),
+                event_count: 0,
+                storage_cost_charged: Amount::zero(),
+                storage_cost_refunded: Amount::zero(),
+            },
+        );
+        history_state_changes.push_back((Slot::new(2, 0), state_changes));
+        let mut final_state: FinalState = Default::default();
+        final_state.changes_history = history_state_changes;
+
+        // the changes of every slot since `last_slot` are returned, not only the last ones
+        let part = final_state
+            .get_state_changes_part(
+                Slot::new(1, 0),
+                Some(high_address),
+                Some(message.compute_id()),
+                None,
+            )
+            .unwrap();
+        assert_eq!(part.ledger_changes.0.len(), 2);
+        assert_eq!(part.async_pool_changes.0.len(), 1);
+        assert!(part.receipts_changes.0.is_empty());
+        let part = final_state
+            .get_state_changes_part(
+                Slot::new(1, 0),
+                Some(high_address),
+                Some(message.compute_id()),
+                Some(op_id),
+            )
+            .unwrap();
+        assert_eq!(part.receipts_changes.0.len(), 1);
+
+        // nothing streamed yet for a part of the state means no change for that part
+        let part = final_state
+            .get_state_changes_part(Slot::new(1, 0), Some(high_address), None, None)
+            .unwrap();
+        assert_eq!(part.ledger_changes.0.len(), 2);
+        assert!(part.async_pool_changes.0.is_empty());
+        let part = final_state
+            .get_state_changes_part(Slot::new(1, 0), None, Some(message.compute_id()), None)
+            .unwrap();
+        assert!(part.ledger_changes.0.is_empty());
+        assert_eq!(part.async_pool_changes.0.len(), 1);
+    }
 }
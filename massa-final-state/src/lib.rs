@@ -2,7 +2,8 @@
 
 //! # General description
 //!
-//! This crate implements a final state that encompasses a final ledger and asynchronous message pool.
+//! This crate implements a final state that encompasses a final ledger, asynchronous message pool
+//! and the receipts of the recently executed operations.
 //! Nodes store only one copy of this final state which is very large
 //! (the copy is attached to the output of the last executed final slot),
 //! and apply speculative changes on it to deduce its value at a non-final slot
@@ -13,10 +14,16 @@
 //!
 //! ## `final_state.rs`
 //! Defines the `FinalState` that matches that represents the state of the node at
-//! the latest executed final slot. It contains the final ledger and the asynchronous event pool.
+//! the latest executed final slot. It contains the final ledger, the asynchronous event pool
+//! and the final operation receipts.
 //! It can be manipulated using `StateChanges` (see `state_changes.rs`).
+//! Its fingerprint, derived from the hashes of the ledger, of the asynchronous pool and of the receipts,
+//! is updated at each finalized slot and lets bootstrapped nodes check the state they received.
 //! The `FinalState` is bootstrapped using tooling available in bootstrap.rs
 //!
+//! ## `receipts.rs`
+//! Defines the `FinalReceipts` holding the receipts of the operations executed during the latest final periods.
+//!
 //! ## `state_changes.rs`
 //! Represents a list of changes the final state.
 //! It can be modified, combined or applied to the final ledger.
@@ -38,11 +45,15 @@
 mod config;
 mod error;
 mod final_state;
+mod receipts;
 mod state_changes;
 
 pub use config::FinalStateConfig;
 pub use error::FinalStateError;
 pub use final_state::FinalState;
+pub use receipts::{
+    FinalReceipts, ReceiptsChanges, ReceiptsChangesDeserializer, ReceiptsChangesSerializer,
+};
 pub use state_changes::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
 
 #[cfg(test)]
@@ -0,0 +1,353 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the final receipts of the `ExecuteSC` and `CallSC` operations.
+//! They are kept in the final state for a fixed number of periods after the execution of their operation,
+//! so that they survive restarts and are bootstrapped by nodes joining the network.
+
+use massa_hash::Hash;
+use massa_models::{
+    constants::default::RECEIPTS_PART_SIZE_MESSAGE_BYTES,
+    execution::{OperationReceipt, OperationReceiptDeserializer, OperationReceiptSerializer},
+    ModelsError, OperationId, OperationIdDeserializer, OperationIdSerializer, Slot,
+};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    multi::{length_count, many0},
+    sequence::tuple,
+    IResult, Parser,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+/// Receipts of the operations executed during a slot, or a range of slots, by operation ID
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptsChanges(pub BTreeMap<OperationId, OperationReceipt>);
+
+/// `ReceiptsChanges` serializer
+pub struct ReceiptsChangesSerializer {
+    u64_serializer: U64VarIntSerializer,
+    id_serializer: OperationIdSerializer,
+    receipt_serializer: OperationReceiptSerializer,
+}
+
+impl ReceiptsChangesSerializer {
+    /// Creates a `ReceiptsChangesSerializer`
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            id_serializer: OperationIdSerializer::new(),
+            receipt_serializer: OperationReceiptSerializer::new(),
+        }
+    }
+}
+
+impl Default for ReceiptsChangesSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<ReceiptsChanges> for ReceiptsChangesSerializer {
+    fn serialize(
+        &self,
+        value: &ReceiptsChanges,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u64_serializer.serialize(
+            &(value.0.len().try_into().map_err(|_| {
+                SerializeError::GeneralError("Fail to transform usize to u64".to_string())
+            })?),
+            buffer,
+        )?;
+        for (id, receipt) in &value.0 {
+            self.id_serializer.serialize(id, buffer)?;
+            self.receipt_serializer.serialize(receipt, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// `ReceiptsChanges` deserializer
+pub struct ReceiptsChangesDeserializer {
+    u64_deserializer: U64VarIntDeserializer,
+    id_deserializer: OperationIdDeserializer,
+    receipt_deserializer: OperationReceiptDeserializer,
+}
+
+impl ReceiptsChangesDeserializer {
+    /// Creates a `ReceiptsChangesDeserializer`
+    pub fn new() -> Self {
+        Self {
+            u64_deserializer: U64VarIntDeserializer::new(Included(u64::MIN), Included(1000000)),
+            id_deserializer: OperationIdDeserializer::new(),
+            receipt_deserializer: OperationReceiptDeserializer::new(),
+        }
+    }
+}
+
+impl Default for ReceiptsChangesDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<ReceiptsChanges> for ReceiptsChangesDeserializer {
+    /// ```
+    /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
+    /// use massa_models::{Amount, OperationId, Slot, execution::OperationReceipt};
+    /// use massa_final_state::{ReceiptsChanges, ReceiptsChangesSerializer, ReceiptsChangesDeserializer};
+    ///
+    /// let receipt = OperationReceipt {
+    ///     slot: Slot::new(1, 2),
+    ///     success: true,
+    ///     error: None,
+    ///     gas_used: 1000,
+    ///     gas_charged: 11000,
+    ///     refunded_coins: Amount::from_raw(10),
+    ///     event_count: 2,
+    ///     storage_cost_charged: Amount::from_raw(5),
+    ///     storage_cost_refunded: Amount::zero(),
+    /// };
+    /// let mut changes = ReceiptsChanges::default();
+    /// changes.0.insert(OperationId::from_bytes(&[1; 32]), receipt);
+    /// let mut serialized = Vec::new();
+    /// ReceiptsChangesSerializer::new().serialize(&changes, &mut serialized).unwrap();
+    /// let (rest, changes_deser) = ReceiptsChangesDeserializer::new().deserialize::<DeserializeError>(&serialized).unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(changes_deser, changes);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], ReceiptsChanges, E> {
+        context(
+            "Failed ReceiptsChanges deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.u64_deserializer.deserialize(input)
+                }),
+                tuple((
+                    |input| self.id_deserializer.deserialize(input),
+                    |input| self.receipt_deserializer.deserialize(input),
+                )),
+            ),
+        )
+        .map(|receipts| ReceiptsChanges(receipts.into_iter().collect()))
+        .parse(buffer)
+    }
+}
+
+/// Receipts of the operations executed during the latest final periods.
+/// The receipts of the operations executed more than `retention_periods` periods before
+/// the slot at which the final state is attached are pruned, so that all nodes hold the same receipts.
+#[derive(Debug, Clone)]
+pub struct FinalReceipts {
+    /// number of periods during which a receipt is kept after the execution of its operation
+    retention_periods: u64,
+
+    /// receipts by operation ID
+    receipts: BTreeMap<OperationId, OperationReceipt>,
+
+    /// operation IDs sorted by the slot of their execution, used for pruning
+    by_slot: BTreeSet<(Slot, OperationId)>,
+
+    /// XOR of the hashes of all the `(id, receipt)` pairs, maintained incrementally
+    hash: Hash,
+}
+
+/// Hash of a receipt, along with the ID of its operation
+fn receipt_hash(id: &OperationId, receipt: &OperationReceipt) -> Hash {
+    let mut bytes = id.to_bytes().to_vec();
+    // the serializer never fails on valid receipts
+    OperationReceiptSerializer::new()
+        .serialize(receipt, &mut bytes)
+        .expect("critical: operation receipt serialization failed");
+    Hash::compute_from(&bytes)
+}
+
+impl FinalReceipts {
+    /// Creates an empty `FinalReceipts`
+    pub fn new(retention_periods: u64) -> FinalReceipts {
+        FinalReceipts {
+            retention_periods,
+            receipts: Default::default(),
+            by_slot: Default::default(),
+            hash: Hash::zero(),
+        }
+    }
+
+    /// Removes every receipt.
+    /// Used to restart a bootstrap from scratch.
+    pub fn reset(&mut self) {
+        self.receipts.clear();
+        self.by_slot.clear();
+        self.hash = Hash::zero();
+    }
+
+    /// Get the hash of the receipts.
+    /// It is the XOR of the hashes of all the receipts with the IDs of their operations,
+    /// so that it does not depend on the order in which receipts were added or removed.
+    pub fn get_hash(&self) -> Hash {
+        self.hash
+    }
+
+    /// Get the receipt of an operation, if it was executed during the retained periods
+    pub fn get(&self, id: &OperationId) -> Option<&OperationReceipt> {
+        self.receipts.get(id)
+    }
+
+    /// Iterates over the receipts, by operation ID
+    pub fn iter(&self) -> impl Iterator<Item = (&OperationId, &OperationReceipt)> {
+        self.receipts.iter()
+    }
+
+    /// Insert a receipt, keeping the index and the hash up to date
+    fn insert_receipt(&mut self, id: OperationId, receipt: OperationReceipt) {
+        let slot = receipt.slot;
+        self.hash ^= receipt_hash(&id, &receipt);
+        if let Some(previous) = self.receipts.insert(id, receipt) {
+            self.hash ^= receipt_hash(&id, &previous);
+            self.by_slot.remove(&(previous.slot, id));
+        }
+        self.by_slot.insert((slot, id));
+    }
+
+    /// Applies the receipts of the operations executed at a final slot,
+    /// then prunes the receipts that are out of retention at that slot.
+    ///
+    /// # Arguments
+    /// * `changes`: receipts to add
+    /// * `slot`: slot at the output of which the final state is attached after the changes
+    pub fn apply_changes(&mut self, changes: &ReceiptsChanges, slot: Slot) {
+        for (id, receipt) in changes.0.iter() {
+            self.insert_receipt(*id, receipt.clone());
+        }
+        let min_period = slot.period.saturating_sub(self.retention_periods);
+        while let Some((receipt_slot, id)) = self.by_slot.first().copied() {
+            if receipt_slot.period >= min_period {
+                break;
+            }
+            self.by_slot.pop_first();
+            if let Some(receipt) = self.receipts.remove(&id) {
+                self.hash ^= receipt_hash(&id, &receipt);
+            }
+        }
+    }
+
+    /// Used for bootstrap
+    /// Take a part of the receipts starting from the next element after `last_id` and with a max length of the constant `RECEIPTS_PART_SIZE_MESSAGE_BYTES`.
+    /// Should always follow the same behavior as the `get_pool_part` method of `AsyncPool`.
+    pub fn get_receipts_part(
+        &self,
+        last_id: Option<OperationId>,
+    ) -> Result<(Vec<u8>, Option<OperationId>), ModelsError> {
+        let last_id = if let Some(last_id) = last_id {
+            Excluded(last_id)
+        } else if self.receipts.first_key_value().is_some() {
+            Unbounded
+        } else {
+            return Ok((Vec::new(), None));
+        };
+        let mut part = Vec::new();
+        let mut next_last_id = None;
+        let id_serializer = OperationIdSerializer::new();
+        let receipt_serializer = OperationReceiptSerializer::new();
+        for (id, receipt) in self.receipts.range((last_id, Unbounded)) {
+            if part.len() < RECEIPTS_PART_SIZE_MESSAGE_BYTES as usize {
+                id_serializer.serialize(id, &mut part)?;
+                receipt_serializer.serialize(receipt, &mut part)?;
+                next_last_id = Some(*id);
+            }
+        }
+        Ok((part, next_last_id))
+    }
+
+    /// Set a part of the receipts.
+    /// We deserialize in this function because we insert in the receipts while deserializing.
+    /// Used for bootstrap.
+    ///
+    /// # Arguments
+    /// * data: must be the serialized version provided by `get_receipts_part`
+    ///
+    /// # Returns
+    /// The last id of the inserted entry (this is an optimization to easily keep a reference to the last id)
+    pub fn set_receipts_part<'a>(
+        &mut self,
+        part: &'a [u8],
+    ) -> Result<Option<OperationId>, ModelsError> {
+        let id_deserializer = OperationIdDeserializer::new();
+        let receipt_deserializer = OperationReceiptDeserializer::new();
+        let (rest, receipts) = many0(|input: &'a [u8]| {
+            if input.is_empty() {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::LengthValue,
+                )));
+            }
+            tuple((
+                |input| id_deserializer.deserialize(input),
+                |input| receipt_deserializer.deserialize(input),
+            ))(input)
+        })(part)?;
+        if rest.is_empty() {
+            for (id, receipt) in receipts {
+                self.insert_receipt(id, receipt);
+            }
+            Ok(self.receipts.last_key_value().map(|(id, _)| *id))
+        } else {
+            Err(ModelsError::SerializeError(
+                "receipts part deserialization has data left".to_string(),
+            ))
+        }
+    }
+}
+
+#[test]
+fn test_receipts_pruning_and_parts() {
+    use massa_models::Amount;
+
+    let receipt = |period: u64| OperationReceipt {
+        slot: Slot::new(period, 0),
+        success: true,
+        error: None,
+        gas_used: 1000,
+        gas_charged: 11000,
+        refunded_coins: Amount::zero(),
+        event_count: 0,
+        storage_cost_charged: Amount::zero(),
+        storage_cost_refunded: Amount::zero(),
+    };
+    let mut receipts = FinalReceipts::new(2);
+    for period in 1..=4 {
+        let mut changes = ReceiptsChanges::default();
+        changes.0.insert(
+            OperationId::from_bytes(&[period as u8; 32]),
+            receipt(period),
+        );
+        receipts.apply_changes(&changes, Slot::new(period, 0));
+    }
+    // the receipts executed more than 2 periods ago are pruned
+    assert!(receipts.get(&OperationId::from_bytes(&[1; 32])).is_none());
+    for period in 2..=4u8 {
+        assert!(receipts
+            .get(&OperationId::from_bytes(&[period; 32]))
+            .is_some());
+    }
+
+    // the receipts and their hash are restored from their parts
+    let mut restored = FinalReceipts::new(2);
+    let mut last_id = None;
+    loop {
+        let (part, next_last_id) = receipts.get_receipts_part(last_id).unwrap();
+        if part.is_empty() {
+            break;
+        }
+        assert_eq!(restored.set_receipts_part(&part).unwrap(), next_last_id);
+        last_id = next_last_id;
+    }
+    assert!(restored.iter().eq(receipts.iter()));
+    assert_eq!(restored.get_hash(), receipts.get_hash());
+}
@@ -2,6 +2,7 @@
 
 //! This file provides structures representing changes to the final state
 
+use crate::receipts::{ReceiptsChanges, ReceiptsChangesDeserializer, ReceiptsChangesSerializer};
 use massa_async_pool::{
     AsyncPoolChanges, AsyncPoolChangesDeserializer, AsyncPoolChangesSerializer,
 };
@@ -20,12 +21,15 @@ pub struct StateChanges {
     pub ledger_changes: LedgerChanges,
     /// asynchronous pool changes
     pub async_pool_changes: AsyncPoolChanges,
+    /// receipts of the executed operations
+    pub receipts_changes: ReceiptsChanges,
 }
 
 /// Basic `StateChanges` serializer.
 pub struct StateChangesSerializer {
     ledger_changes_serializer: LedgerChangesSerializer,
     async_pool_changes_serializer: AsyncPoolChangesSerializer,
+    receipts_changes_serializer: ReceiptsChangesSerializer,
 }
 
 impl StateChangesSerializer {
@@ -34,6 +38,7 @@ impl StateChangesSerializer {
         Self {
             ledger_changes_serializer: LedgerChangesSerializer::new(),
             async_pool_changes_serializer: AsyncPoolChangesSerializer::new(),
+            receipts_changes_serializer: ReceiptsChangesSerializer::new(),
         }
     }
 }
@@ -92,6 +97,8 @@ impl Serializer<StateChanges> for StateChangesSerializer {
             .serialize(&value.ledger_changes, buffer)?;
         self.async_pool_changes_serializer
             .serialize(&value.async_pool_changes, buffer)?;
+        self.receipts_changes_serializer
+            .serialize(&value.receipts_changes, buffer)?;
         Ok(())
     }
 }
@@ -100,6 +107,7 @@ impl Serializer<StateChanges> for StateChangesSerializer {
 pub struct StateChangesDeserializer {
     ledger_changes_deserializer: LedgerChangesDeserializer,
     async_pool_changes_deserializer: AsyncPoolChangesDeserializer,
+    receipts_changes_deserializer: ReceiptsChangesDeserializer,
 }
 
 impl StateChangesDeserializer {
@@ -108,6 +116,7 @@ impl StateChangesDeserializer {
         Self {
             ledger_changes_deserializer: LedgerChangesDeserializer::new(),
             async_pool_changes_deserializer: AsyncPoolChangesDeserializer::new(),
+            receipts_changes_deserializer: ReceiptsChangesDeserializer::new(),
         }
     }
 }
@@ -121,7 +130,7 @@ impl Default for StateChangesDeserializer {
 impl Deserializer<StateChanges> for StateChangesDeserializer {
     /// ```
     /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
-    /// use massa_models::{Address, prehash::Map, Amount, Slot};
+    /// use massa_models::{Address, prehash::Map, Amount, OperationId, Slot, execution::OperationReceipt};
     /// use massa_final_state::{StateChanges, StateChangesSerializer, StateChangesDeserializer};
     /// use std::str::FromStr;
     /// use std::collections::BTreeMap;
@@ -158,6 +167,17 @@ impl Deserializer<StateChanges> for StateChangesDeserializer {
     ///    SetUpdateOrDelete::Update(ledger_entry),
     /// );
     /// state_changes.ledger_changes = ledger_changes;
+    /// state_changes.receipts_changes.0.insert(OperationId::from_bytes(&[1; 32]), OperationReceipt {
+    ///     slot: Slot::new(1, 0),
+    ///     success: true,
+    ///     error: None,
+    ///     gas_used: 1000,
+    ///     gas_charged: 11000,
+    ///     refunded_coins: Amount::from_str("1").unwrap(),
+    ///     event_count: 0,
+    ///     storage_cost_charged: Amount::zero(),
+    ///     storage_cost_refunded: Amount::zero(),
+    /// });
     /// let mut serialized = Vec::new();
     /// StateChangesSerializer::new().serialize(&state_changes, &mut serialized).unwrap();
     /// let (rest, state_changes_deser) = StateChangesDeserializer::new().deserialize::<DeserializeError>(&serialized).unwrap();
@@ -177,12 +197,18 @@ impl Deserializer<StateChanges> for StateChangesDeserializer {
                 context("Failed async_pool_changes deserialization", |input| {
                     self.async_pool_changes_deserializer.deserialize(input)
                 }),
+                context("Failed receipts_changes deserialization", |input| {
+                    self.receipts_changes_deserializer.deserialize(input)
+                }),
             )),
         )
-        .map(|(ledger_changes, async_pool_changes)| StateChanges {
-            ledger_changes,
-            async_pool_changes,
-        })
+        .map(
+            |(ledger_changes, async_pool_changes, receipts_changes)| StateChanges {
+                ledger_changes,
+                async_pool_changes,
+                receipts_changes,
+            },
+        )
         .parse(buffer)
     }
 }
@@ -193,5 +219,6 @@ impl StateChanges {
         use massa_ledger_exports::Applicable;
         self.ledger_changes.apply(changes.ledger_changes);
         self.async_pool_changes.extend(changes.async_pool_changes);
+        self.receipts_changes.0.extend(changes.receipts_changes.0);
     }
 }
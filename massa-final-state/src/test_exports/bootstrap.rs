@@ -5,10 +5,11 @@
 use std::collections::VecDeque;
 
 use massa_async_pool::AsyncPool;
+use massa_hash::Hash;
 use massa_ledger_exports::LedgerController;
 use massa_models::Slot;
 
-use crate::{FinalState, FinalStateConfig, StateChanges};
+use crate::{FinalReceipts, FinalState, FinalStateConfig, StateChanges};
 
 /// Create a `FinalState` from pre-set values
 pub fn create_final_state(
@@ -16,15 +17,26 @@ pub fn create_final_state(
     slot: Slot,
     ledger: Box<dyn LedgerController>,
     async_pool: AsyncPool,
+    receipts: FinalReceipts,
     changes_history: VecDeque<(Slot, StateChanges)>,
 ) -> FinalState {
-    FinalState {
+    let mut final_state = FinalState {
         config,
         slot,
         ledger,
         async_pool,
+        receipts,
         changes_history,
-    }
+        fingerprint: Hash::zero(),
+    };
+    final_state.fingerprint = final_state.compute_fingerprint();
+    final_state
+}
+
+/// asserts that two `FinalReceipts` are equal
+pub fn assert_eq_final_receipts(v1: &FinalReceipts, v2: &FinalReceipts) {
+    assert!(v1.iter().eq(v2.iter()), "final receipts mismatch");
+    assert_eq!(v1.get_hash(), v2.get_hash(), "final receipts hash mismatch");
 }
 
 /// asserts that two `FinalState` are equal
@@ -38,4 +50,12 @@ pub fn assert_eq_final_state(v1: &FinalState, v2: &FinalState) {
         &v1.async_pool,
         &v2.async_pool,
     );
+    assert_eq_final_receipts(&v1.receipts, &v2.receipts);
+
+    // compare fingerprints
+    assert_eq!(
+        v1.compute_fingerprint(),
+        v2.compute_fingerprint(),
+        "fingerprint mismatch"
+    );
 }
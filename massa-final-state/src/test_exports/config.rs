@@ -2,8 +2,9 @@
 
 //! This file defines testing tools related to the configuration
 
-use crate::{FinalState, FinalStateConfig};
+use crate::{FinalReceipts, FinalState, FinalStateConfig};
 use massa_async_pool::{AsyncPool, AsyncPoolConfig};
+use massa_hash::Hash;
 use massa_ledger_exports::LedgerConfig;
 use massa_ledger_worker::FinalLedger;
 use massa_models::Slot;
@@ -20,14 +21,21 @@ impl Default for FinalState {
         // create the async pool
         let async_pool = AsyncPool::new(config.async_pool_config.clone());
 
+        // create the final receipts
+        let receipts = FinalReceipts::new(config.receipts_retention_periods);
+
         // generate the final state
-        FinalState {
+        let mut final_state = FinalState {
             slot,
             ledger: Box::new(ledger),
             async_pool,
+            receipts,
             config,
             changes_history: Default::default(), // no changes in history
-        }
+            fingerprint: Hash::zero(),
+        };
+        final_state.fingerprint = final_state.compute_fingerprint();
+        final_state
     }
 }
 
@@ -38,6 +46,7 @@ impl Default for FinalStateConfig {
             ledger_config: LedgerConfig::default(),
             async_pool_config: AsyncPoolConfig::default(),
             final_history_length: 10,
+            receipts_retention_periods: 10,
             thread_count: 2,
         }
     }
@@ -0,0 +1,517 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Optional on-disk archive of the blocks that became final.
+//!
+//! Blocks, headers, operations and endorsements are stored in a RocksDB database, keyed by their id,
+//! with indexes of the blocks by slot and by creator address,
+//! and an index of the operations by the addresses they involve (the address history).
+//! It allows serving final blocks and their content after they were pruned from the graph and from the storage.
+
+use massa_models::api::{
+    AddressHistoryCursor, AddressHistoryEntry, CreatorBlocksCursor, OperationKind,
+};
+use massa_models::constants::{BLOCK_ID_SIZE_BYTES, SLOT_KEY_SIZE};
+use massa_models::operation::OperationDeserializer;
+use massa_models::wrapped::{WrappedDeserializer, WrappedSerializer};
+use massa_models::{
+    Address, BlockDeserializer, BlockHeaderDeserializer, BlockId, EndorsementDeserializer,
+    EndorsementId, OperationId, Slot, WrappedBlock, WrappedEndorsement, WrappedHeader,
+    WrappedOperation,
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use rocksdb::{ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB};
+use std::path::PathBuf;
+
+const BLOCKS_CF: &str = "blocks";
+const HEADERS_CF: &str = "headers";
+const OPERATIONS_CF: &str = "operations";
+const ENDORSEMENTS_CF: &str = "endorsements";
+const SLOT_INDEX_CF: &str = "blocks_by_slot";
+const CREATOR_INDEX_CF: &str = "blocks_by_creator";
+const ADDRESS_INDEX_CF: &str = "operations_by_address";
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const SER_ERROR: &str = "critical: archived block serialization failed";
+const DESER_ERROR: &str = "critical: archived block deserialization failed";
+
+/// Size of the location of an operation or endorsement: id of the block, followed by the index in the block
+const LOCATION_SIZE: usize = BLOCK_ID_SIZE_BYTES + 8;
+
+/// On-disk archive of final blocks
+pub(crate) struct BlockArchive {
+    /// RocksDB instance
+    db: DB,
+    /// number of endorsements per block, used to deserialize endorsements
+    endorsement_count: u32,
+}
+
+impl BlockArchive {
+    /// Open or create the block archive
+    ///
+    /// # Arguments
+    /// * `path`: path to the archive db directory
+    /// * `endorsement_count`: number of endorsements per block
+    pub fn new(path: PathBuf, endorsement_count: u32) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            path,
+            vec![
+                ColumnFamilyDescriptor::new(BLOCKS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(HEADERS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(OPERATIONS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(ENDORSEMENTS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(SLOT_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(CREATOR_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(ADDRESS_INDEX_CF, Options::default()),
+            ],
+        )
+        .expect(OPEN_ERROR);
+
+        BlockArchive {
+            db,
+            endorsement_count,
+        }
+    }
+
+    /// Archive a final block along with its header, operations, endorsements and index entries.
+    /// Every operation is indexed under the addresses involved in it from a ledger or rolls point of view.
+    pub fn archive_block(&self, block: &WrappedBlock) {
+        let serializer = WrappedSerializer::new();
+        let header = &block.content.header;
+        let slot_key = header.content.slot.to_bytes_key();
+        let mut batch = WriteBatch::default();
+
+        let mut block_bytes = Vec::new();
+        serializer
+            .serialize(block, &mut block_bytes)
+            .expect(SER_ERROR);
+        batch.put_cf(self.cf(BLOCKS_CF), block.id.to_bytes(), block_bytes);
+
+        let mut header_bytes = Vec::new();
+        serializer
+            .serialize(header, &mut header_bytes)
+            .expect(SER_ERROR);
+        batch.put_cf(self.cf(HEADERS_CF), block.id.to_bytes(), header_bytes);
+
+        for (index, operation) in block.content.operations.iter().enumerate() {
+            let mut value = location(&block.id, index);
+            serializer
+                .serialize(operation, &mut value)
+                .expect(SER_ERROR);
+            batch.put_cf(self.cf(OPERATIONS_CF), operation.id.to_bytes(), value);
+
+            let mut addresses = operation.get_ledger_involved_addresses();
+            addresses.extend(operation.get_roll_involved_addresses().unwrap_or_default());
+            for address in addresses {
+                batch.put_cf(
+                    self.cf(ADDRESS_INDEX_CF),
+                    history_key(&address, &slot_key, index as u64),
+                    operation.id.to_bytes(),
+                );
+            }
+        }
+        for (index, endorsement) in header.content.endorsements.iter().enumerate() {
+            let mut value = location(&block.id, index);
+            serializer
+                .serialize(endorsement, &mut value)
+                .expect(SER_ERROR);
+            batch.put_cf(self.cf(ENDORSEMENTS_CF), endorsement.id.to_bytes(), value);
+        }
+
+        batch.put_cf(
+            self.cf(SLOT_INDEX_CF),
+            [&slot_key[..], block.id.to_bytes()].concat(),
+            b"",
+        );
+        batch.put_cf(
+            self.cf(CREATOR_INDEX_CF),
+            [
+                &block.creator_address.to_bytes()[..],
+                &slot_key[..],
+                block.id.to_bytes(),
+            ]
+            .concat(),
+            b"",
+        );
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    /// Get a column family handle
+    fn cf(&self, name: &str) -> &rocksdb::ColumnFamily {
+        self.db.cf_handle(name).expect(CF_ERROR)
+    }
+
+    /// Get an archived block
+    pub fn get_block(&self, block_id: &BlockId) -> Option<WrappedBlock> {
+        self.db
+            .get_cf(self.cf(BLOCKS_CF), block_id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|value| {
+                let (_, block) = WrappedDeserializer::new(BlockDeserializer::new())
+                    .deserialize::<DeserializeError>(&value)
+                    .expect(DESER_ERROR);
+                block
+            })
+    }
+
+    /// Get the header of an archived block
+    pub fn get_header(&self, block_id: &BlockId) -> Option<WrappedHeader> {
+        self.db
+            .get_cf(self.cf(HEADERS_CF), block_id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|value| {
+                let (_, header) = WrappedDeserializer::new(BlockHeaderDeserializer::new())
+                    .deserialize::<DeserializeError>(&value)
+                    .expect(DESER_ERROR);
+                header
+            })
+    }
+
+    /// Get an archived operation, along with the id of its block and its index in the block
+    pub fn get_operation(
+        &self,
+        operation_id: &OperationId,
+    ) -> Option<(BlockId, usize, WrappedOperation)> {
+        self.db
+            .get_cf(self.cf(OPERATIONS_CF), operation_id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|value| {
+                let (block_id, index) = parse_location(&value);
+                let (_, operation) = WrappedDeserializer::new(OperationDeserializer::new())
+                    .deserialize::<DeserializeError>(&value[LOCATION_SIZE..])
+                    .expect(DESER_ERROR);
+                (block_id, index, operation)
+            })
+    }
+
+    /// Get an archived endorsement, along with the id of its block
+    pub fn get_endorsement(
+        &self,
+        endorsement_id: &EndorsementId,
+    ) -> Option<(BlockId, WrappedEndorsement)> {
+        self.db
+            .get_cf(self.cf(ENDORSEMENTS_CF), endorsement_id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|value| {
+                let (block_id, _) = parse_location(&value);
+                let (_, endorsement) =
+                    WrappedDeserializer::new(EndorsementDeserializer::new(self.endorsement_count))
+                        .deserialize::<DeserializeError>(&value[LOCATION_SIZE..])
+                        .expect(DESER_ERROR);
+                (block_id, endorsement)
+            })
+    }
+
+    /// Get the headers of the archived blocks whose slot is in `[start, end)`, oldest first,
+    /// up to `limit` headers
+    pub fn get_headers_in_slot_range(
+        &self,
+        start: Option<Slot>,
+        end: Option<Slot>,
+        limit: usize,
+    ) -> Vec<(BlockId, WrappedHeader)> {
+        let start_key = start.map(|slot| slot.to_bytes_key().to_vec());
+        let end_key = end.map(|slot| slot.to_bytes_key());
+        let mode = match &start_key {
+            Some(key) => IteratorMode::From(key, Direction::Forward),
+            None => IteratorMode::Start,
+        };
+        let mut res = Vec::new();
+        for (key, _) in self.db.iterator_cf(self.cf(SLOT_INDEX_CF), mode) {
+            if res.len() >= limit
+                || matches!(&end_key, Some(end_key) if key[..SLOT_KEY_SIZE] >= end_key[..])
+            {
+                break;
+            }
+            let block_id = block_id_from_slice(&key[SLOT_KEY_SIZE..]);
+            if let Some(header) = self.get_header(&block_id) {
+                res.push((block_id, header));
+            }
+        }
+        res
+    }
+
+    /// Get the archived blocks created by an address, oldest first, up to `limit` blocks
+    ///
+    /// # Arguments
+    /// * `address`: the creator address
+    /// * `cursor`: optional position after which (excluded) blocks are listed
+    /// * `limit`: maximum number of listed blocks
+    pub fn get_blocks_by_creator(
+        &self,
+        address: &Address,
+        cursor: Option<CreatorBlocksCursor>,
+        limit: usize,
+    ) -> Vec<(Slot, BlockId)> {
+        let prefix = address.to_bytes();
+        let from = match cursor {
+            Some(cursor) => [
+                &prefix[..],
+                &cursor.slot.to_bytes_key()[..],
+                cursor.block_id.to_bytes(),
+            ]
+            .concat(),
+            None => prefix.to_vec(),
+        };
+        let mut res = Vec::new();
+        for (key, _) in self.db.iterator_cf(
+            self.cf(CREATOR_INDEX_CF),
+            IteratorMode::From(&from, Direction::Forward),
+        ) {
+            if res.len() >= limit || !key.starts_with(prefix) {
+                break;
+            }
+            if cursor.is_some() && *key == *from {
+                continue;
+            }
+            let slot_key: [u8; SLOT_KEY_SIZE] = key[prefix.len()..prefix.len() + SLOT_KEY_SIZE]
+                .try_into()
+                .expect(DESER_ERROR);
+            res.push((
+                Slot::from_bytes_key(&slot_key),
+                block_id_from_slice(&key[prefix.len() + SLOT_KEY_SIZE..]),
+            ));
+        }
+        res
+    }
+
+    /// Get the archived operations involving an address, oldest first, up to `limit` operations
+    ///
+    /// # Arguments
+    /// * `address`: the involved address
+    /// * `cursor`: optional position after which (excluded) operations are listed
+    /// * `op_type`: optional kind of the listed operations
+    /// * `limit`: maximum number of listed operations
+    pub fn get_address_history(
+        &self,
+        address: &Address,
+        cursor: Option<AddressHistoryCursor>,
+        op_type: Option<OperationKind>,
+        limit: usize,
+    ) -> Vec<AddressHistoryEntry> {
+        let prefix = address.to_bytes();
+        let from = match cursor {
+            Some(cursor) => {
+                history_key(address, &cursor.slot.to_bytes_key(), cursor.index_in_block)
+            }
+            None => prefix.to_vec(),
+        };
+        let mut res = Vec::new();
+        for (key, value) in self.db.iterator_cf(
+            self.cf(ADDRESS_INDEX_CF),
+            IteratorMode::From(&from, Direction::Forward),
+        ) {
+            if res.len() >= limit || !key.starts_with(prefix) {
+                break;
+            }
+            if cursor.is_some() && *key == *from {
+                continue;
+            }
+            let id_bytes: &[u8] = &value;
+            let operation_id = OperationId::from_bytes(id_bytes.try_into().expect(DESER_ERROR));
+            let (block_id, index, operation) =
+                self.get_operation(&operation_id).expect(DESER_ERROR);
+            if matches!(op_type, Some(kind) if OperationKind::of(&operation.content.op) != kind) {
+                continue;
+            }
+            let slot_key: [u8; SLOT_KEY_SIZE] = key[prefix.len()..prefix.len() + SLOT_KEY_SIZE]
+                .try_into()
+                .expect(DESER_ERROR);
+            res.push(AddressHistoryEntry {
+                slot: Slot::from_bytes_key(&slot_key),
+                block_id,
+                index_in_block: index as u64,
+                operation,
+            });
+        }
+        res
+    }
+}
+
+/// Key of the address history index: address, followed by the slot key and the index of the operation in its block
+fn history_key(address: &Address, slot_key: &[u8], index: u64) -> Vec<u8> {
+    [&address.to_bytes()[..], slot_key, &index.to_be_bytes()].concat()
+}
+
+/// Location of an operation or endorsement: id of its block followed by its index in the block
+fn location(block_id: &BlockId, index: usize) -> Vec<u8> {
+    let mut value = Vec::with_capacity(LOCATION_SIZE);
+    value.extend(block_id.to_bytes());
+    value.extend((index as u64).to_be_bytes());
+    value
+}
+
+/// Parse the location of an operation or endorsement
+fn parse_location(value: &[u8]) -> (BlockId, usize) {
+    let index_bytes: [u8; 8] = value[BLOCK_ID_SIZE_BYTES..LOCATION_SIZE]
+        .try_into()
+        .expect(DESER_ERROR);
+    (
+        block_id_from_slice(&value[..BLOCK_ID_SIZE_BYTES]),
+        u64::from_be_bytes(index_bytes) as usize,
+    )
+}
+
+/// Read a block id from a slice of `BLOCK_ID_SIZE_BYTES` bytes
+fn block_id_from_slice(bytes: &[u8]) -> BlockId {
+    BlockId::from_bytes(bytes.try_into().expect(DESER_ERROR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::wrapped::WrappedContent;
+    use massa_models::{
+        Amount, Block, BlockHeader, BlockHeaderSerializer, BlockSerializer, Operation,
+        OperationSerializer, OperationType,
+    };
+    use massa_signature::KeyPair;
+    use serial_test::serial;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    /// Create a block at `slot` containing a single transaction
+    fn create_block(keypair: &KeyPair, slot: Slot) -> WrappedBlock {
+        let operation = Operation::new_wrapped(
+            Operation {
+                fee: Amount::zero(),
+                expire_period: slot.period + 10,
+                op: OperationType::Transaction {
+                    recipient_address: Address::from_public_key(
+                        &KeyPair::generate().get_public_key(),
+                    ),
+                    amount: Amount::from_str("1").unwrap(),
+                },
+            },
+            OperationSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        let header = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot,
+                parents: vec![],
+                operation_merkle_root: Hash::compute_from(&operation.serialized_data),
+                endorsements: vec![],
+            },
+            BlockHeaderSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        Block::new_wrapped(
+            Block {
+                header,
+                operations: vec![operation],
+            },
+            BlockSerializer::new(),
+            keypair,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test_block_archive() {
+        massa_models::init_serialization_context(massa_models::SerializationContext::default());
+        let temp_dir = TempDir::new().unwrap();
+        let archive = BlockArchive::new(temp_dir.path().to_path_buf(), 9);
+        let keypair_a = KeyPair::generate();
+        let keypair_b = KeyPair::generate();
+        let blocks = vec![
+            create_block(&keypair_a, Slot::new(1, 0)),
+            create_block(&keypair_b, Slot::new(1, 1)),
+            create_block(&keypair_a, Slot::new(2, 0)),
+        ];
+        for block in blocks.iter() {
+            archive.archive_block(block);
+        }
+
+        // items by id
+        let block = archive.get_block(&blocks[1].id).unwrap();
+        assert_eq!(block.id, blocks[1].id);
+        assert_eq!(block.content.header.content.slot, Slot::new(1, 1));
+        let header = archive.get_header(&blocks[1].id).unwrap();
+        assert_eq!(header.id, blocks[1].content.header.id);
+        let op_id = blocks[2].content.operations[0].id;
+        let (block_id, index, operation) = archive.get_operation(&op_id).unwrap();
+        assert_eq!((block_id, index, operation.id), (blocks[2].id, 0, op_id));
+        assert!(archive
+            .get_block(&BlockId(Hash::compute_from(b"unknown")))
+            .is_none());
+
+        // blocks by slot range and by creator
+        let in_range: Vec<BlockId> = archive
+            .get_headers_in_slot_range(Some(Slot::new(1, 1)), Some(Slot::new(2, 0)), 10)
+            .into_iter()
+            .map(|(block_id, _)| block_id)
+            .collect();
+        assert_eq!(in_range, vec![blocks[1].id]);
+        assert_eq!(archive.get_headers_in_slot_range(None, None, 2).len(), 2);
+        let creator_a = blocks[0].creator_address;
+        assert_eq!(
+            archive.get_blocks_by_creator(&creator_a, None, 10),
+            vec![
+                (Slot::new(1, 0), blocks[0].id),
+                (Slot::new(2, 0), blocks[2].id)
+            ]
+        );
+        let cursor = CreatorBlocksCursor {
+            slot: Slot::new(1, 0),
+            block_id: blocks[0].id,
+        };
+        assert_eq!(
+            archive.get_blocks_by_creator(&creator_a, None, 1),
+            vec![(Slot::new(1, 0), blocks[0].id)]
+        );
+        assert_eq!(
+            archive.get_blocks_by_creator(&creator_a, Some(cursor), 10),
+            vec![(Slot::new(2, 0), blocks[2].id)]
+        );
+
+        // address history, paginated and filtered by operation kind
+        let address_a = blocks[0].creator_address;
+        let history: Vec<OperationId> = archive
+            .get_address_history(&address_a, None, None, 10)
+            .into_iter()
+            .map(|entry| entry.operation.id)
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                blocks[0].content.operations[0].id,
+                blocks[2].content.operations[0].id
+            ]
+        );
+        let first_page = archive.get_address_history(&address_a, None, None, 1);
+        assert_eq!(first_page.len(), 1);
+        let cursor = AddressHistoryCursor {
+            slot: first_page[0].slot,
+            index_in_block: first_page[0].index_in_block,
+        };
+        let second_page = archive.get_address_history(&address_a, Some(cursor), None, 10);
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].block_id, blocks[2].id);
+        assert_eq!(second_page[0].slot, Slot::new(2, 0));
+        let recipient = match blocks[1].content.operations[0].content.op {
+            OperationType::Transaction {
+                recipient_address, ..
+            } => recipient_address,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            archive
+                .get_address_history(&recipient, None, Some(OperationKind::Transaction), 10)
+                .len(),
+            1
+        );
+        assert!(archive
+            .get_address_history(&recipient, None, Some(OperationKind::RollBuy), 10)
+            .is_empty());
+    }
+}
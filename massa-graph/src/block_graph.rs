@@ -2,6 +2,7 @@
 
 //! All information concerning blocks, the block graph and cliques is managed here.
 use crate::{
+    block_archive::BlockArchive,
     bootstrapable_graph::BootstrapableGraph,
     error::{GraphError, GraphResult as Result},
     export_active_block::ExportActiveBlock,
@@ -14,7 +15,10 @@ use massa_logging::massa_trace;
 use massa_models::clique::Clique;
 use massa_models::{
     active_block::ActiveBlock,
-    api::EndorsementInfo,
+    api::{
+        AddressHistoryCursor, AddressHistoryInput, AddressHistoryOutput, CreatorBlockEntry,
+        CreatorBlocksCursor, CreatorBlocksInput, CreatorBlocksOutput, EndorsementInfo,
+    },
     ledger_models::LedgerChange,
     rolls::{RollCounts, RollUpdate, RollUpdates},
     WrappedEndorsement, WrappedHeader, WrappedOperation,
@@ -38,7 +42,7 @@ use serde::{Deserialize, Serialize};
 use std::mem;
 use std::{collections::HashSet, usize};
 use std::{
-    collections::{hash_map, BTreeSet, HashMap, VecDeque},
+    collections::{hash_map, BTreeMap, BTreeSet, HashMap, VecDeque},
     convert::TryFrom,
 };
 use tracing::{debug, error, info, warn};
@@ -246,6 +250,27 @@ impl<'a> BlockGraphExport {
             }
         }
 
+        // add the final blocks that were pruned from the graph, whose children are not archived
+        if let Some(archive) = block_graph.block_archive.as_ref() {
+            for (block_id, header) in archive.get_headers_in_slot_range(
+                slot_start,
+                slot_end,
+                block_graph.cfg.max_item_return_count,
+            ) {
+                if block_graph.block_statuses.contains_key(&block_id) {
+                    continue;
+                }
+                export.active_blocks.insert(
+                    block_id,
+                    ExportCompiledBlock {
+                        header,
+                        children: vec![Set::default(); block_graph.cfg.thread_count as usize],
+                        is_final: true,
+                    },
+                );
+            }
+        }
+
         Ok(export)
     }
 }
@@ -298,6 +323,8 @@ pub struct BlockGraph {
     active_index: Set<BlockId>,
     /// ids of discarded blocks
     discarded_index: Set<BlockId>,
+    /// ids of the checked blocks/headers by (slot, creator), pruned when their slot is final
+    slot_creator_index: BTreeMap<(Slot, Address), Set<BlockId>>,
     /// One (block id, period) per thread
     latest_final_blocks_periods: Vec<(BlockId, u64)>,
     /// One `(block id, period)` per thread TODO not sure I understand the difference with `latest_final_blocks_periods`
@@ -319,6 +346,8 @@ pub struct BlockGraph {
     ledger: Ledger,
     /// Shared storage,
     pub storage: Storage,
+    /// optional on-disk archive of final blocks, serving them once pruned from the graph
+    block_archive: Option<BlockArchive>,
 }
 
 /// Possible output of a header check
@@ -483,6 +512,11 @@ impl BlockGraph {
         }
 
         massa_trace!("consensus.block_graph.new", {});
+        // open the optional block archive
+        let block_archive = cfg
+            .block_archive_path
+            .clone()
+            .map(|path| BlockArchive::new(path, cfg.endorsement_count));
         if let Some(boot_graph) = init {
             // load from boot graph
             let ledger = Ledger::from_export(
@@ -516,6 +550,7 @@ impl BlockGraph {
                 waiting_for_slot_index: Default::default(),
                 waiting_for_dependencies_index: Default::default(),
                 discarded_index: Default::default(),
+                slot_creator_index: Default::default(),
                 latest_final_blocks_periods: boot_graph.latest_final_blocks_periods,
                 best_parents: boot_graph.best_parents,
                 gi_head: boot_graph.gi_head,
@@ -526,6 +561,7 @@ impl BlockGraph {
                 new_final_blocks: Default::default(),
                 new_stale_blocks: Default::default(),
                 storage,
+                block_archive,
             };
             // compute block descendants
             let active_blocks_map: Map<BlockId, Vec<BlockId>> = res_graph
@@ -568,6 +604,7 @@ impl BlockGraph {
                 waiting_for_dependencies_index: Default::default(),
                 active_index: genesis_block_ids.iter().copied().collect(),
                 discarded_index: Default::default(),
+                slot_creator_index: Default::default(),
                 latest_final_blocks_periods: genesis_block_ids.iter().map(|h| (*h, 0)).collect(),
                 best_parents: genesis_block_ids.iter().map(|v| (*v, 0)).collect(),
                 genesis_hashes: genesis_block_ids,
@@ -583,6 +620,7 @@ impl BlockGraph {
                 new_final_blocks: Default::default(),
                 new_stale_blocks: Default::default(),
                 storage,
+                block_archive,
             })
         }
     }
@@ -1082,6 +1120,80 @@ impl BlockGraph {
             .collect()
     }
 
+    /// Returns a page of the blocks created by an address, oldest first:
+    /// the active blocks, and the pruned final blocks if the block archive is enabled.
+    pub fn get_blocks_by_creator(&self, input: &CreatorBlocksInput) -> CreatorBlocksOutput {
+        let limit = input.limit.unwrap_or(usize::MAX);
+        let mut blocks: BTreeMap<CreatorBlocksCursor, bool> = self
+            .active_index
+            .iter()
+            .filter_map(|block_id| match self.block_statuses.get(block_id) {
+                Some(BlockStatus::Active(active_block))
+                    if active_block.creator_address == input.address =>
+                {
+                    Some((
+                        CreatorBlocksCursor {
+                            slot: active_block.slot,
+                            block_id: *block_id,
+                        },
+                        active_block.is_final,
+                    ))
+                }
+                _ => None,
+            })
+            .filter(|(position, _)| input.cursor.map_or(true, |cursor| *position > cursor))
+            .collect();
+        // the first `limit` archived blocks after the cursor are enough to fill the page
+        if let Some(archive) = self.block_archive.as_ref() {
+            blocks.extend(
+                archive
+                    .get_blocks_by_creator(&input.address, input.cursor, limit)
+                    .into_iter()
+                    .map(|(slot, block_id)| (CreatorBlocksCursor { slot, block_id }, true)),
+            );
+        }
+        let blocks: Vec<CreatorBlockEntry> = blocks
+            .into_iter()
+            .take(limit)
+            .map(|(position, is_final)| CreatorBlockEntry {
+                slot: position.slot,
+                block_id: position.block_id,
+                is_final,
+            })
+            .collect();
+        let next_cursor = match blocks.last() {
+            Some(block) if blocks.len() == limit => Some(CreatorBlocksCursor {
+                slot: block.slot,
+                block_id: block.block_id,
+            }),
+            _ => None,
+        };
+        CreatorBlocksOutput {
+            blocks,
+            next_cursor,
+        }
+    }
+
+    /// Returns a page of the final operations involving an address, oldest first.
+    /// The address history is indexed by the block archive: returns `None` if it is disabled.
+    pub fn get_address_history(&self, input: &AddressHistoryInput) -> Option<AddressHistoryOutput> {
+        let archive = self.block_archive.as_ref()?;
+        let limit = input.limit.unwrap_or(usize::MAX);
+        let entries =
+            archive.get_address_history(&input.address, input.cursor, input.op_type, limit);
+        let next_cursor = match entries.last() {
+            Some(entry) if entries.len() == limit => Some(AddressHistoryCursor {
+                slot: entry.slot,
+                index_in_block: entry.index_in_block,
+            }),
+            _ => None,
+        };
+        Some(AddressHistoryOutput {
+            entries,
+            next_cursor,
+        })
+    }
+
     /// for algorithm see `pos.md`
     /// if `addrs_opt` is `Some(addrs)`, restrict to address. If None, return all addresses.
     /// returns (`roll_counts`, `cycle_roll_updates`)
@@ -1273,7 +1385,14 @@ impl BlockGraph {
     /// get export version of a block
     pub fn get_export_block_status(&self, block_id: &BlockId) -> Result<Option<ExportBlockStatus>> {
         let block_status = match self.block_statuses.get(block_id) {
-            None => return Ok(None),
+            // the block may have been pruned after becoming final
+            None => {
+                return Ok(self
+                    .block_archive
+                    .as_ref()
+                    .and_then(|archive| archive.get_block(block_id))
+                    .map(|block| ExportBlockStatus::Final(block.content)))
+            }
             Some(block_status) => block_status,
         };
         let export = match block_status {
@@ -1338,7 +1457,19 @@ impl BlockGraph {
                 }
             }
 
-            // If we found the operation in at least one active block.
+            // Otherwise, the operation may be in a final block that was pruned.
+            if operation.is_none() {
+                if let Some((block_id, idx, op)) = self
+                    .block_archive
+                    .as_ref()
+                    .and_then(|archive| archive.get_operation(&op_id))
+                {
+                    operation = Some(op);
+                    in_blocks.insert(block_id, (idx, true));
+                }
+            }
+
+            // If we found the operation in at least one active or archived block.
             if let Some(op) = operation {
                 let result = OperationSearchResult {
                     op,
@@ -1586,6 +1717,10 @@ impl BlockGraph {
                         block_id
                     )));
                 };
+                self.slot_creator_index
+                    .entry((header.content.slot, header.creator_address))
+                    .or_default()
+                    .insert(block_id);
                 match self.check_header(&block_id, &header, pos, current_slot)? {
                     HeaderCheckOutcome::Proceed { .. } => {
                         // set as waiting dependencies
@@ -1698,6 +1833,10 @@ impl BlockGraph {
                             block_id
                         )));
                     };
+                self.slot_creator_index
+                    .entry((slot, stored_block.creator_address))
+                    .or_default()
+                    .insert(block_id);
                 match self.check_block(&stored_block, &operation_set, pos, current_slot)? {
                     BlockCheckOutcome::Proceed {
                         parents_hash_period,
@@ -1928,6 +2067,33 @@ impl BlockGraph {
         }
     }
 
+    /// Looks for another block or header known by the graph that was created by `creator` for `slot`
+    fn get_same_slot_block(
+        &self,
+        block_id: &BlockId,
+        slot: Slot,
+        creator: &Address,
+    ) -> Option<BlockId> {
+        self.slot_creator_index
+            .get(&(slot, *creator))?
+            .iter()
+            .find(|other_block_id| {
+                *other_block_id != block_id
+                    && matches!(
+                        self.block_statuses.get(*other_block_id),
+                        Some(
+                            BlockStatus::Active(_)
+                                | BlockStatus::Discarded { .. }
+                                | BlockStatus::WaitingForDependencies {
+                                    header_or_block: HeaderOrBlock::Header(_),
+                                    ..
+                                }
+                        )
+                    )
+            })
+            .copied()
+    }
+
     /// Gets whole `ActiveBlock` corresponding to given `block_id`
     ///
     /// # Argument
@@ -2051,8 +2217,21 @@ impl BlockGraph {
             return Ok(HeaderCheckOutcome::WaitForSlot);
         }
 
-        // Note: here we will check if we already have a block for that slot
-        // and if someone double staked, they will be denounced
+        // check if the creator already created another block for that slot:
+        // the equivocation is noted for information
+        if let Some(other_block_id) =
+            self.get_same_slot_block(block_id, header.content.slot, &creator_addr)
+        {
+            info!(
+                "address {} created blocks {} and {} for slot {}",
+                creator_addr, other_block_id, block_id, header.content.slot
+            );
+            pos.note_equivocation(
+                header.content.slot,
+                creator_addr,
+                &[other_block_id, *block_id],
+            );
+        }
 
         // list parents and ensure they are present
         let parent_set: Set<BlockId> = header.content.parents.iter().copied().collect();
@@ -3694,6 +3873,21 @@ impl BlockGraph {
         Ok(())
     }
 
+    /// Forgets the blocks/headers that are not known anymore, and the final slots:
+    /// headers for final slots are discarded as stale before being checked for equivocations.
+    fn prune_slot_creator_index(&mut self) {
+        let block_statuses = &self.block_statuses;
+        let latest_final_blocks_periods = &self.latest_final_blocks_periods;
+        self.slot_creator_index.retain(|(slot, _), block_ids| {
+            block_ids.retain(|block_id| block_statuses.contains_key(block_id));
+            !block_ids.is_empty()
+                && matches!(
+                    latest_final_blocks_periods.get(slot.thread as usize),
+                    Some((_, final_period)) if slot.period > *final_period
+                )
+        });
+    }
+
     /// prune and return final blocks, return discarded final blocks
     pub fn prune(&mut self) -> Result<Map<BlockId, ActiveBlock>> {
         let before = self.max_cliques.len();
@@ -3709,6 +3903,9 @@ impl BlockGraph {
         // Step 4: prune discarded
         self.prune_discarded()?;
 
+        // Step 5: prune the (slot, creator) index
+        self.prune_slot_creator_index();
+
         let after = self.max_cliques.len();
         if before != after {
             debug!(
@@ -3792,10 +3989,19 @@ impl BlockGraph {
         mem::take(&mut self.attack_attempts)
     }
 
-    /// Get the ids of blocks that became final.
+    /// Get the ids of blocks that became final, and archive these blocks if the archive is enabled.
     /// Must be called by the consensus worker within `block_db_changed`.
     pub fn get_new_final_blocks(&mut self) -> Set<BlockId> {
-        mem::take(&mut self.new_final_blocks)
+        let new_final_blocks = mem::take(&mut self.new_final_blocks);
+        if let Some(archive) = self.block_archive.as_ref() {
+            for block_id in new_final_blocks.iter() {
+                match self.storage.retrieve_block(block_id) {
+                    Some(block) => archive.archive_block(&block.read()),
+                    None => warn!("missing final block {} in storage, not archived", block_id),
+                }
+            }
+        }
+        new_final_blocks
     }
 
     /// Get the ids of blocks that became stale.
@@ -3874,6 +4080,26 @@ impl BlockGraph {
                 }
             }
         }
+        // look for the remaining endorsements in the archived final blocks
+        if let Some(archive) = self.block_archive.as_ref() {
+            for id in endorsements.iter() {
+                if res.contains_key(id) {
+                    continue;
+                }
+                if let Some((block_id, endorsement)) = archive.get_endorsement(id) {
+                    res.insert(
+                        *id,
+                        EndorsementInfo {
+                            id: *id,
+                            in_pool: false,
+                            in_blocks: vec![block_id],
+                            is_final: true,
+                            endorsement,
+                        },
+                    );
+                }
+            }
+        }
         Ok(res)
     }
 }
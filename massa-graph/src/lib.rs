@@ -17,6 +17,8 @@ pub mod export_active_block;
 mod bootstrapable_graph;
 pub use bootstrapable_graph::BootstrapableGraph;
 
+mod block_archive;
+
 mod block_graph;
 pub use block_graph::*;
 
@@ -65,6 +65,8 @@ pub struct GraphConfig {
     pub roll_price: Amount,
     /// force keep at least this number of final periods in RAM for each thread
     pub force_keep_final_periods: u64,
+    /// path to the on-disk archive of final blocks, no archive if None
+    pub block_archive_path: Option<PathBuf>,
     /// target number of endorsement per block
     pub endorsement_count: u32,
     /// pub `block_db_prune_interval`: `MassaTime`,
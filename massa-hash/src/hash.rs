@@ -25,6 +25,25 @@ impl Ord for Hash {
     }
 }
 
+impl std::ops::BitXor for Hash {
+    type Output = Self;
+
+    /// Byte-wise XOR of two hashes, used to build order-independent hashes of sets
+    fn bitxor(self, other: Self) -> Self {
+        let mut bytes = self.into_bytes();
+        for (byte, other_byte) in bytes.iter_mut().zip(other.to_bytes()) {
+            *byte ^= other_byte;
+        }
+        Hash::from_bytes(&bytes)
+    }
+}
+
+impl std::ops::BitXorAssign for Hash {
+    fn bitxor_assign(&mut self, other: Self) {
+        *self = *self ^ other;
+    }
+}
+
 impl std::fmt::Display for Hash {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.to_bs58_check())
@@ -38,6 +57,19 @@ impl std::fmt::Debug for Hash {
 }
 
 impl Hash {
+    /// The all-zero hash, neutral element of the XOR of hashes.
+    ///
+    /// # Example
+    ///  ```
+    /// # use massa_hash::Hash;
+    /// let hash = Hash::compute_from(&"hello world".as_bytes());
+    /// assert_eq!(hash ^ Hash::zero(), hash);
+    /// assert_eq!(hash ^ hash, Hash::zero());
+    /// ```
+    pub fn zero() -> Self {
+        Hash::from_bytes(&[0; HASH_SIZE_BYTES])
+    }
+
     /// Compute a hash from data.
     ///
     /// # Example
@@ -0,0 +1,103 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the bytecode metadata the node maintains in the ledger entry of each address:
+//! the history of its bytecode versions.
+//!
+//! This metadata is stored in datastore entries under reserved keys
+//! that contracts can read but not write,
+//! so that it follows the ledger entry through changes, bootstrap and proofs.
+//! Each version is stored in its own entry, so that recording a version does not rewrite the previous ones.
+
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::constants::SLOT_KEY_SIZE;
+use massa_models::Slot;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the datastore keys reserved for the metadata maintained by the node.
+///
+/// User entries cannot collide with them: contracts cannot write keys with this prefix,
+/// and contracts are the only source of datastore entries, as the initial ledger only holds balances.
+/// Ledgers received at bootstrap or resumed from disk were built by nodes applying the same rule.
+pub const RESERVED_DATASTORE_PREFIX: &[u8] = b"\0massa/";
+
+/// Reserved datastore key holding the number of bytecode versions of an address, as a big-endian `u64`
+pub const BYTECODE_VERSION_COUNT_KEY: &[u8] = b"\0massa/bytecode_version_count";
+
+/// Prefix of the reserved datastore keys holding the bytecode versions of an address,
+/// followed by the version number as a big-endian `u64` (see `bytecode_version_key`)
+pub const BYTECODE_VERSION_PREFIX: &[u8] = b"\0massa/bytecode_version/";
+
+/// Size of the binary representation of a `BytecodeVersion`
+const BYTECODE_VERSION_SIZE: usize = SLOT_KEY_SIZE + HASH_SIZE_BYTES;
+
+/// Returns true if a datastore key is reserved for the metadata maintained by the node
+pub fn is_reserved_datastore_key(key: &[u8]) -> bool {
+    key.starts_with(RESERVED_DATASTORE_PREFIX)
+}
+
+/// Returns the reserved datastore key holding a bytecode version.
+/// Keys sort by version number.
+///
+/// # Arguments
+/// * `version`: the version number, starting at 1
+pub fn bytecode_version_key(version: u64) -> Vec<u8> {
+    [BYTECODE_VERSION_PREFIX, &version.to_be_bytes()].concat()
+}
+
+/// Parses a number of bytecode versions, as stored under `BYTECODE_VERSION_COUNT_KEY`.
+/// An absent or malformed entry counts no version.
+pub fn bytecode_version_count_from_bytes(bytes: Option<&[u8]>) -> u64 {
+    bytes
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(0, u64::from_be_bytes)
+}
+
+/// A version of the bytecode of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BytecodeVersion {
+    /// slot at which the bytecode was set
+    pub slot: Slot,
+    /// hash of the bytecode
+    pub hash: Hash,
+}
+
+impl BytecodeVersion {
+    /// Binary representation of the version, as stored under its `bytecode_version_key`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&self.slot.to_bytes_key()[..], self.hash.to_bytes()].concat()
+    }
+
+    /// Parses a version stored under a `bytecode_version_key`
+    pub fn from_bytes(bytes: &[u8]) -> Option<BytecodeVersion> {
+        if bytes.len() != BYTECODE_VERSION_SIZE {
+            return None;
+        }
+        Some(BytecodeVersion {
+            // cannot fail: the size was checked
+            slot: Slot::from_bytes_key(bytes[..SLOT_KEY_SIZE].try_into().unwrap()),
+            hash: Hash::from_bytes(bytes[SLOT_KEY_SIZE..].try_into().unwrap()),
+        })
+    }
+}
+
+#[test]
+fn test_bytecode_version_bytes() {
+    let version = BytecodeVersion {
+        slot: Slot::new(1, 2),
+        hash: Hash::compute_from(b"first"),
+    };
+    assert_eq!(
+        BytecodeVersion::from_bytes(&version.to_bytes()),
+        Some(version)
+    );
+    assert_eq!(BytecodeVersion::from_bytes(&[0u8; 3]), None);
+    assert!(bytecode_version_key(2) < bytecode_version_key(10));
+    assert_eq!(
+        bytecode_version_count_from_bytes(Some(&7u64.to_be_bytes())),
+        7
+    );
+    assert_eq!(bytecode_version_count_from_bytes(None), 0);
+    assert!(is_reserved_datastore_key(BYTECODE_VERSION_COUNT_KEY));
+    assert!(is_reserved_datastore_key(&bytecode_version_key(1)));
+    assert!(!is_reserved_datastore_key(b"massa/bytecode_version/"));
+}
@@ -2,6 +2,7 @@
 
 //! This file defines a configuration structure containing all settings for the ledger system
 
+use massa_models::Amount;
 use std::path::PathBuf;
 
 /// Ledger configuration
@@ -11,4 +12,14 @@ pub struct LedgerConfig {
     pub initial_sce_ledger_path: PathBuf,
     /// disk ledger db directory
     pub disk_ledger_path: PathBuf,
+    /// whether to keep the history of the final ledger, allowing to query it at past final slots
+    pub archive_mode: bool,
+    /// number of periods during which the history of the final ledger is kept in archive mode
+    pub archive_retention_periods: u64,
+    /// cost in coins of each byte of bytecode or datastore (key and value) stored in the ledger,
+    /// paid as a deposit from the balance of the address when its storage grows,
+    /// and refunded from that deposit when it shrinks
+    pub storage_cost_per_byte: Amount,
+    /// period from which the storage cost is charged, or `None` if it is never charged
+    pub storage_cost_activation_period: Option<u64>,
 }
@@ -1,13 +1,23 @@
+use massa_hash::Hash;
 use massa_models::{Address, Amount, ModelsError, Slot};
 use std::collections::BTreeSet;
 use std::fmt::Debug;
+use std::ops::Bound;
 
-use crate::LedgerChanges;
+use crate::{LedgerChanges, LedgerError, LedgerKeyProof};
 
 pub trait LedgerController: Send + Sync + Debug {
     /// Allows applying `LedgerChanges` to the final ledger
     fn apply_changes(&mut self, changes: LedgerChanges, slot: Slot);
 
+    /// Gets the final slot at the output of which the ledger is attached,
+    /// `None` if no changes were ever applied to it (initial ledger)
+    fn get_slot(&self) -> Option<Slot>;
+
+    /// Removes every entry of the ledger, along with its history and its slot.
+    /// Used to restart a bootstrap from scratch.
+    fn reset(&mut self);
+
     /// Gets the parallel balance of a ledger entry
     ///
     /// # Returns
@@ -46,12 +56,52 @@ pub trait LedgerController: Send + Sync + Debug {
     /// true if the datastore entry was found, or false if the ledger entry or datastore entry was not found
     fn has_data_entry(&self, addr: &Address, key: &[u8]) -> bool;
 
+    /// Gets the parallel balance of a ledger entry at the output of a past final slot.
+    /// Only available in archive mode, for slots since archiving started.
+    ///
+    /// # Returns
+    /// The parallel balance, or None if the ledger entry did not exist at that slot
+    fn get_parallel_balance_at_slot(
+        &self,
+        addr: &Address,
+        slot: Slot,
+    ) -> Result<Option<Amount>, LedgerError>;
+
+    /// Gets a copy of the value of a datastore entry at the output of a past final slot.
+    /// Only available in archive mode, for slots since archiving started.
+    ///
+    /// # Arguments
+    /// * `addr`: target address
+    /// * `key`: datastore key
+    /// * `slot`: queried final slot
+    ///
+    /// # Returns
+    /// A copy of the datastore value, or `None` if the datastore entry did not exist at that slot
+    fn get_data_entry_at_slot(
+        &self,
+        addr: &Address,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, LedgerError>;
+
     /// Get every key of the datastore for a given address.
     ///
     /// # Returns
     /// A BTreeSet of the datastore keys
     fn get_datastore_keys(&self, addr: &Address) -> BTreeSet<Vec<u8>>;
 
+    /// Get the datastore keys of a given address within a range, in increasing order.
+    ///
+    /// # Arguments
+    /// * `range`: range of the datastore keys to return
+    /// * `limit`: maximum number of keys to return
+    fn get_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> BTreeSet<Vec<u8>>;
+
     /// Get a part of the ledger
     /// Used for bootstrap
     /// Return: Tuple with data and last key
@@ -65,6 +115,25 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Return: Last key inserted
     fn set_ledger_part(&self, data: Vec<u8>) -> Result<Option<Vec<u8>>, ModelsError>;
 
+    /// Get the hash of the ledger content, maintained incrementally as changes are applied.
+    /// It is the root of the ledger sparse Merkle tree: two ledgers with the same content have the same hash.
+    fn get_ledger_hash(&self) -> Hash;
+
+    /// Get the inclusion or non-inclusion proof of a raw ledger key against the current ledger hash
+    fn get_key_proof(&self, key: &[u8]) -> LedgerKeyProof;
+
+    /// Get the hash of the ledger content at the output of a past final slot.
+    /// Only available in archive mode, for slots since archiving started.
+    fn get_ledger_hash_at_slot(&self, slot: Slot) -> Result<Hash, LedgerError>;
+
+    /// Get the inclusion or non-inclusion proof of a raw ledger key against the ledger hash at the output of a past final slot.
+    /// Only available in archive mode, for slots since archiving started.
+    fn get_key_proof_at_slot(&self, key: &[u8], slot: Slot) -> Result<LedgerKeyProof, LedgerError>;
+
+    /// Get the cost in coins of each byte stored in the ledger at a given slot
+    /// (see `LedgerConfig::storage_cost_per_byte`), which is zero before the activation of the storage cost
+    fn get_storage_cost_per_byte(&self, slot: &Slot) -> Amount;
+
     /// Get every address and their corresponding balance.
     ///
     /// IMPORTANT: This should only be used for debug and test purposes.
@@ -5,7 +5,7 @@ use massa_models::{
 };
 use massa_serialization::{DeserializeError, Deserializer, SerializeError, Serializer};
 use nom::error::{ContextError, ParseError};
-use std::ops::Bound::Included;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
 
 pub const BALANCE_IDENT: u8 = 0u8;
 pub const BYTECODE_IDENT: u8 = 1u8;
@@ -47,6 +47,37 @@ macro_rules! data_prefix {
     };
 }
 
+/// For a given start prefix (inclusive), returns the correct end prefix (non-inclusive).
+/// This assumes the key bytes are ordered in lexicographical order.
+/// Since key length is not limited, for some case we return `None` because there is
+/// no bounded limit (every keys in the serie `[]`, `[255]`, `[255, 255]` ...).
+pub fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end_range = prefix.to_vec();
+    while let Some(0xff) = end_range.last() {
+        end_range.pop();
+    }
+    if let Some(byte) = end_range.last_mut() {
+        *byte += 1;
+        Some(end_range)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_end_prefix() {
+    assert_eq!(end_prefix(&[5, 6, 7]), Some(vec![5, 6, 8]));
+    assert_eq!(end_prefix(&[5, 6, 255]), Some(vec![5, 7]));
+}
+
+/// Range of the datastore keys starting with a prefix
+pub fn datastore_prefix_range(prefix: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+    (
+        Included(prefix.to_vec()),
+        end_prefix(prefix).map_or(Unbounded, Excluded),
+    )
+}
+
 /// Extract an address from a key
 pub fn get_address_from_key(key: &[u8]) -> Option<Address> {
     let address_deserializer = AddressDeserializer::new();
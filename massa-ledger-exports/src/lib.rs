@@ -2,26 +2,42 @@
 //!
 //! TODO
 
+mod bytecode_history;
 mod config;
 mod controller;
 mod error;
 mod key;
 mod ledger_changes;
 mod ledger_entry;
+mod proof;
+mod storage_deposit;
 mod types;
 
+pub use bytecode_history::{
+    bytecode_version_count_from_bytes, bytecode_version_key, is_reserved_datastore_key,
+    BytecodeVersion, BYTECODE_VERSION_COUNT_KEY, BYTECODE_VERSION_PREFIX,
+    RESERVED_DATASTORE_PREFIX,
+};
 pub use config::LedgerConfig;
 pub use controller::LedgerController;
 pub use error::LedgerError;
 pub use key::{
-    get_address_from_key, KeyDeserializer, KeySerializer, BALANCE_IDENT, BYTECODE_IDENT,
-    DATASTORE_IDENT,
+    datastore_prefix_range, end_prefix, get_address_from_key, KeyDeserializer, KeySerializer,
+    BALANCE_IDENT, BYTECODE_IDENT, DATASTORE_IDENT,
 };
 pub use ledger_changes::{
     LedgerChanges, LedgerChangesDeserializer, LedgerChangesSerializer, LedgerEntryUpdate,
     LedgerEntryUpdateDeserializer, LedgerEntryUpdateSerializer,
 };
 pub use ledger_entry::{LedgerEntry, LedgerEntryDeserializer, LedgerEntrySerializer};
+pub use proof::{
+    ledger_common_prefix_len, ledger_key_path, ledger_leaf_hash, ledger_node_hash, ledger_path_bit,
+    LedgerKeyProof, LedgerProof, LEDGER_TREE_DEPTH,
+};
+pub use storage_deposit::{
+    storage_deposit_from_bytes, storage_deposit_key, storage_deposit_to_bytes,
+    STORAGE_DEPOSIT_BYTECODE_KEY, STORAGE_DEPOSIT_DATASTORE_PREFIX, STORAGE_DEPOSIT_SIZE,
+};
 pub use types::{Applicable, SetOrDelete, SetOrKeep, SetUpdateOrDelete};
 
 #[cfg(feature = "testing")]
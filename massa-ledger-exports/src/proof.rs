@@ -0,0 +1,235 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the sparse Merkle tree committing to the content of the final ledger,
+//! and the proofs allowing light clients to check ledger values against its root.
+//!
+//! The tree has a depth of `LEDGER_TREE_DEPTH` bits.
+//! Each ledger key is placed along the path given by the hash of the key.
+//! The hash of an empty subtree is the zero hash, and a subtree containing a single key
+//! is replaced by the leaf of that key, so that a key update only touches the nodes
+//! where at least two keys branch off (about `log2(key count)` of them).
+//! The root does not depend on the insertion order.
+//!
+//! Proofs are only generated against the latest final ledger:
+//! the tree is not archived, so past roots cannot be proven against.
+
+use crate::{BALANCE_IDENT, BYTECODE_IDENT, DATASTORE_IDENT};
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::{Address, Amount, SerializeCompact, Slot};
+use serde::{Deserialize, Serialize};
+
+/// Depth of the ledger sparse Merkle tree, in bits
+pub const LEDGER_TREE_DEPTH: usize = HASH_SIZE_BYTES * 8;
+
+/// Path of a ledger key in the tree
+pub fn ledger_key_path(key: &[u8]) -> Hash {
+    Hash::compute_from(key)
+}
+
+/// Direction taken by a path at a given depth: `true` for the right child
+pub fn ledger_path_bit(path: &Hash, depth: usize) -> bool {
+    (path.to_bytes()[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Number of leading bits shared by two paths
+pub fn ledger_common_prefix_len(a: &Hash, b: &Hash) -> usize {
+    for (index, (a, b)) in a.to_bytes().iter().zip(b.to_bytes()).enumerate() {
+        if a != b {
+            return index * 8 + (a ^ b).leading_zeros() as usize;
+        }
+    }
+    LEDGER_TREE_DEPTH
+}
+
+/// Hash of the leaf of a ledger key, given the path of the key.
+/// It only depends on the hash of the value, so that large values such as bytecode
+/// can be proven without being sent.
+/// Since the path is included, a leaf cannot be placed elsewhere in the tree.
+pub fn ledger_leaf_hash(path: &Hash, value_hash: &Hash) -> Hash {
+    Hash::compute_from(&[&[0u8][..], path.to_bytes(), value_hash.to_bytes()].concat())
+}
+
+/// Hash of an inner node of the tree from the hashes of its children.
+/// The hash of an empty subtree is the zero hash.
+pub fn ledger_node_hash(left: &Hash, right: &Hash) -> Hash {
+    let zero = Hash::zero();
+    if *left == zero && *right == zero {
+        zero
+    } else {
+        Hash::compute_from(&[&[1u8][..], left.to_bytes(), right.to_bytes()].concat())
+    }
+}
+
+/// Inclusion or non-inclusion proof of a single ledger key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerKeyProof {
+    /// hash of the value of the key, `None` if the key is absent from the ledger
+    pub value_hash: Option<Hash>,
+    /// for an absent key, the `(path, value hash)` of the only key of the subtree
+    /// where the key would be, if that subtree is not empty
+    pub other_leaf: Option<(Hash, Hash)>,
+    /// non-empty siblings of the nodes on the path of the key, as `(depth, hash)` pairs
+    /// by decreasing depth. Siblings that are not listed are empty subtrees.
+    /// The leaf (or the empty subtree) proven is at the depth of the deepest sibling.
+    pub siblings: Vec<(u16, Hash)>,
+}
+
+impl LedgerKeyProof {
+    /// Compute the root of the tree implied by the proof for a given key
+    pub fn compute_root(&self, key: &[u8]) -> Hash {
+        let path = ledger_key_path(key);
+        let mut hash = match (&self.value_hash, &self.other_leaf) {
+            (Some(value_hash), _) => ledger_leaf_hash(&path, value_hash),
+            (None, Some((other_path, other_value_hash))) => {
+                ledger_leaf_hash(other_path, other_value_hash)
+            }
+            (None, None) => Hash::zero(),
+        };
+        let leaf_depth = self
+            .siblings
+            .first()
+            .map_or(0, |(depth, _)| *depth as usize);
+        let mut siblings = self.siblings.iter().peekable();
+        for depth in (1..=leaf_depth).rev() {
+            let sibling = match siblings.peek() {
+                Some((sibling_depth, sibling)) if *sibling_depth as usize == depth => {
+                    siblings.next();
+                    *sibling
+                }
+                _ => Hash::zero(),
+            };
+            hash = if ledger_path_bit(&path, depth - 1) {
+                ledger_node_hash(&sibling, &hash)
+            } else {
+                ledger_node_hash(&hash, &sibling)
+            };
+        }
+        hash
+    }
+
+    /// Check that the proof is valid for a key against a ledger root,
+    /// and that it proves the given value (or its absence)
+    pub fn verify(&self, key: &[u8], value: Option<&[u8]>, ledger_root: &Hash) -> bool {
+        self.verify_hash(key, value.map(Hash::compute_from).as_ref(), ledger_root)
+    }
+
+    /// Check that the proof is valid for a key against a ledger root,
+    /// and that it proves the given value hash (or the absence of the key)
+    pub fn verify_hash(&self, key: &[u8], value_hash: Option<&Hash>, ledger_root: &Hash) -> bool {
+        if self.value_hash.as_ref() != value_hash {
+            return false;
+        }
+        // siblings must be listed once each, by decreasing depth
+        let leaf_depth = match self.siblings.first() {
+            Some((depth, _)) if *depth as usize > LEDGER_TREE_DEPTH => return false,
+            Some((depth, _)) => *depth as usize,
+            None => 0,
+        };
+        if self.siblings.windows(2).any(|pair| pair[0].0 <= pair[1].0) {
+            return false;
+        }
+        // another leaf only proves an absence if it is another key placed where the key would be
+        if let Some((other_path, _)) = &self.other_leaf {
+            let path = ledger_key_path(key);
+            if self.value_hash.is_some()
+                || *other_path == path
+                || ledger_common_prefix_len(other_path, &path) < leaf_depth
+            {
+                return false;
+            }
+        }
+        self.compute_root(key) == *ledger_root
+    }
+}
+
+/// Values of a ledger entry along with the proofs of these values against the final ledger root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerProof {
+    /// address of the entry
+    pub address: Address,
+    /// final slot at the output of which the proof was generated
+    pub slot: Slot,
+    /// root of the final ledger tree at that slot
+    pub ledger_root: Hash,
+    /// hash of the final asynchronous pool at that slot, `None` for past slots since the pool is not archived.
+    /// Along with `slot` and `ledger_root`, it gives the final state fingerprint
+    /// reported by the `get_status` of the nodes.
+    pub async_pool_hash: Option<Hash>,
+    /// balance of the entry, `None` if the entry does not exist
+    pub balance: Option<Amount>,
+    /// proof of the balance
+    pub balance_proof: LedgerKeyProof,
+    /// hash of the bytecode of the entry
+    pub bytecode_hash: Option<Hash>,
+    /// proof of the bytecode
+    pub bytecode_proof: LedgerKeyProof,
+    /// requested datastore entries as `(key, value, proof)`, with a `None` value for absent keys
+    pub datastore: Vec<(Vec<u8>, Option<Vec<u8>>, LedgerKeyProof)>,
+}
+
+impl LedgerProof {
+    /// Check every proof against the ledger root
+    pub fn verify(&self) -> bool {
+        // Amount::to_bytes_compact() never fails
+        let balance_bytes = self
+            .balance
+            .map(|balance| balance.to_bytes_compact().unwrap());
+        if !self.balance_proof.verify(
+            &crate::balance_key!(self.address),
+            balance_bytes.as_deref(),
+            &self.ledger_root,
+        ) {
+            return false;
+        }
+        if !self.bytecode_proof.verify_hash(
+            &crate::bytecode_key!(self.address),
+            self.bytecode_hash.as_ref(),
+            &self.ledger_root,
+        ) {
+            return false;
+        }
+        self.datastore.iter().all(|(key, value, proof)| {
+            proof.verify(
+                &crate::data_key!(self.address, key[..]),
+                value.as_deref(),
+                &self.ledger_root,
+            )
+        })
+    }
+}
+
+#[test]
+fn test_ledger_key_proof() {
+    let key = b"key".to_vec();
+    let value = b"value".to_vec();
+    let path = ledger_key_path(&key);
+
+    // single key tree: the root is the leaf of the key
+    let root = ledger_leaf_hash(&path, &Hash::compute_from(&value));
+    let proof = LedgerKeyProof {
+        value_hash: Some(Hash::compute_from(&value)),
+        other_leaf: None,
+        siblings: Vec::new(),
+    };
+    assert!(proof.verify(&key, Some(&value), &root));
+    assert!(!proof.verify(&key, Some(b"other value"), &root));
+
+    // absence of another key, proven by the leaf in its place
+    let other_key = b"other key".to_vec();
+    let absence_proof = LedgerKeyProof {
+        value_hash: None,
+        other_leaf: Some((path, Hash::compute_from(&value))),
+        siblings: Vec::new(),
+    };
+    assert!(absence_proof.verify(&other_key, None, &root));
+    assert!(!absence_proof.verify(&key, None, &root));
+
+    // absence of the key in the empty tree
+    let empty_proof = LedgerKeyProof {
+        value_hash: None,
+        other_leaf: None,
+        siblings: Vec::new(),
+    };
+    assert!(empty_proof.verify(&key, None, &Hash::zero()));
+    assert!(!empty_proof.verify(&key, None, &root));
+}
@@ -0,0 +1,73 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the storage deposits the node records in the ledger entry of each address:
+//! the coins paid for the storage of its bytecode and of each of its datastore entries.
+//!
+//! A deposit is stored in a datastore entry under a reserved key derived from the entry it pays for,
+//! so that it follows the ledger entry through changes, bootstrap and proofs,
+//! and so that only the coins actually paid are refunded when the entry shrinks or is deleted.
+//! Entries written before storage costs were charged have no deposit, and nothing is refunded for them.
+
+use massa_hash::Hash;
+use massa_models::Amount;
+
+/// Reserved datastore key holding the deposit paid for the bytecode of an address
+pub const STORAGE_DEPOSIT_BYTECODE_KEY: &[u8] = b"\0massa/storage_deposit/bytecode";
+
+/// Prefix of the reserved datastore keys holding the deposits paid for the datastore entries of an address,
+/// followed by the hash of the datastore key (see `storage_deposit_key`)
+pub const STORAGE_DEPOSIT_DATASTORE_PREFIX: &[u8] = b"\0massa/storage_deposit/datastore/";
+
+/// Size of the binary representation of a deposit
+pub const STORAGE_DEPOSIT_SIZE: usize = 8;
+
+/// Returns the reserved datastore key holding the deposit paid for a datastore entry.
+/// The datastore key is hashed to keep the deposit key within the maximal key length.
+///
+/// # Arguments
+/// * `key`: the datastore key of the entry
+pub fn storage_deposit_key(key: &[u8]) -> Vec<u8> {
+    [
+        STORAGE_DEPOSIT_DATASTORE_PREFIX,
+        Hash::compute_from(key).to_bytes(),
+    ]
+    .concat()
+}
+
+/// Binary representation of a deposit, as stored under a deposit key
+pub fn storage_deposit_to_bytes(deposit: Amount) -> Vec<u8> {
+    deposit.to_raw().to_be_bytes().to_vec()
+}
+
+/// Parses a deposit stored under a deposit key.
+/// An absent or malformed entry counts as no deposit.
+pub fn storage_deposit_from_bytes(bytes: Option<&[u8]>) -> Amount {
+    bytes
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(Amount::zero(), |bytes| {
+            Amount::from_raw(u64::from_be_bytes(bytes))
+        })
+}
+
+#[test]
+fn test_storage_deposit_bytes() {
+    use crate::is_reserved_datastore_key;
+    use massa_models::constants::default::MAX_DATASTORE_KEY_LENGTH;
+
+    let deposit = Amount::from_mantissa_scale(123, 4);
+    assert_eq!(
+        storage_deposit_to_bytes(deposit).len(),
+        STORAGE_DEPOSIT_SIZE
+    );
+    assert_eq!(
+        storage_deposit_from_bytes(Some(&storage_deposit_to_bytes(deposit))),
+        deposit
+    );
+    assert_eq!(storage_deposit_from_bytes(None), Amount::zero());
+    assert_eq!(storage_deposit_from_bytes(Some(&[1, 2])), Amount::zero());
+    let long_key = vec![1u8; MAX_DATASTORE_KEY_LENGTH as usize];
+    assert!(storage_deposit_key(&long_key).len() <= MAX_DATASTORE_KEY_LENGTH as usize);
+    assert_ne!(storage_deposit_key(b"a"), storage_deposit_key(b"b"));
+    assert!(is_reserved_datastore_key(&storage_deposit_key(b"a")));
+    assert!(is_reserved_datastore_key(STORAGE_DEPOSIT_BYTECODE_KEY));
+}
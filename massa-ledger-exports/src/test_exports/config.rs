@@ -15,6 +15,10 @@ impl Default for LedgerConfig {
             // a NamedTempFile in addition)
             initial_sce_ledger_path: "".into(),
             disk_ledger_path: "".into(),
+            archive_mode: false,
+            archive_retention_periods: 1000,
+            storage_cost_per_byte: Amount::zero(),
+            storage_cost_activation_period: None,
         }
     }
 }
@@ -34,6 +38,10 @@ impl LedgerConfig {
             Self {
                 initial_sce_ledger_path: initial_ledger.path().to_path_buf(),
                 disk_ledger_path: disk_ledger.path().to_path_buf(),
+                archive_mode: false,
+                archive_retention_periods: 1000,
+                storage_cost_per_byte: Amount::zero(),
+                storage_cost_activation_period: None,
             },
             initial_ledger,
             disk_ledger,
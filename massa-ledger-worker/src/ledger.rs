@@ -3,13 +3,15 @@
 //! This file defines the final ledger associating addresses to their balances, bytecode and data.
 
 use crate::ledger_db::{LedgerDB, LedgerSubEntry};
+use massa_hash::Hash;
 use massa_ledger_exports::{
-    LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerError,
+    LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerError, LedgerKeyProof,
 };
 use massa_models::{Address, Amount, ModelsError};
 use massa_models::{DeserializeCompact, Slot};
 use nom::AsBytes;
 use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
 
 /// Represents a final ledger associating addresses to their balances, bytecode and data.
 /// The final ledger is part of the final state which is attached to a final slot, can be bootstrapped and allows others to bootstrap.
@@ -18,7 +20,7 @@ use std::collections::{BTreeSet, HashMap};
 #[derive(Debug)]
 pub struct FinalLedger {
     /// ledger configuration
-    pub(crate) _config: LedgerConfig,
+    pub(crate) config: LedgerConfig,
     /// ledger tree, sorted by address
     pub(crate) sorted_ledger: LedgerDB,
 }
@@ -41,7 +43,22 @@ pub(crate) use init_file_error;
 
 impl FinalLedger {
     /// Initializes a new `FinalLedger` by reading its initial state from file.
+    ///
+    /// If the disk ledger was left attached to a final slot by a previous run, it is kept as is
+    /// and the initial state is not applied, so that the node can resume from it.
     pub fn new(config: LedgerConfig) -> Result<Self, LedgerError> {
+        let mut sorted_ledger = LedgerDB::new(
+            config.disk_ledger_path.clone(),
+            config.archive_mode,
+            config.archive_retention_periods,
+        );
+        if sorted_ledger.get_slot().is_some() {
+            return Ok(FinalLedger {
+                sorted_ledger,
+                config,
+            });
+        }
+
         // load the ledger tree from file
         let initial_ledger: HashMap<Address, LedgerEntry> =
             serde_json::from_str::<HashMap<Address, Amount>>(
@@ -61,14 +78,13 @@ impl FinalLedger {
             })
             .collect();
 
-        // create and initialize the disk ledger
-        let mut sorted_ledger = LedgerDB::new(config.disk_ledger_path.clone());
+        // initialize the disk ledger
         sorted_ledger.set_initial_ledger(initial_ledger);
 
         // generate the final ledger
         Ok(FinalLedger {
             sorted_ledger,
-            _config: config,
+            config,
         })
     }
 }
@@ -79,6 +95,16 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.apply_changes(changes, slot);
     }
 
+    /// Gets the slot the disk ledger is attached to, if any changes were applied to it
+    fn get_slot(&self) -> Option<Slot> {
+        self.sorted_ledger.get_slot()
+    }
+
+    /// Empties the disk ledger
+    fn reset(&mut self) {
+        self.sorted_ledger.reset();
+    }
+
     /// Gets the parallel balance of a ledger entry
     ///
     /// # Returns
@@ -139,6 +165,42 @@ impl LedgerController for FinalLedger {
             .is_some()
     }
 
+    /// Gets the parallel balance of a ledger entry at the output of a past final slot
+    ///
+    /// # Returns
+    /// The parallel balance, or None if the ledger entry did not exist at that slot
+    fn get_parallel_balance_at_slot(
+        &self,
+        addr: &Address,
+        slot: Slot,
+    ) -> Result<Option<Amount>, LedgerError> {
+        Ok(self
+            .sorted_ledger
+            .get_sub_entry_at_slot(addr, LedgerSubEntry::Balance, slot)?
+            .map(|bytes| {
+                Amount::from_bytes_compact(&bytes)
+                    .expect("critical: invalid balance format")
+                    .0
+            }))
+    }
+
+    /// Gets a copy of the value of a datastore entry at the output of a past final slot
+    ///
+    /// # Returns
+    /// A copy of the datastore value, or `None` if the datastore entry did not exist at that slot
+    fn get_data_entry_at_slot(
+        &self,
+        addr: &Address,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, LedgerError> {
+        self.sorted_ledger.get_sub_entry_at_slot(
+            addr,
+            LedgerSubEntry::Datastore(key.to_owned()),
+            slot,
+        )
+    }
+
     /// Get every key of the datastore for a given address.
     ///
     /// # Returns
@@ -147,6 +209,21 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_datastore_keys(addr)
     }
 
+    /// Get the datastore keys of a given address within a range, in increasing order.
+    ///
+    /// # Arguments
+    /// * `range`: range of the datastore keys to return
+    /// * `limit`: maximum number of keys to return
+    fn get_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> BTreeSet<Vec<u8>> {
+        self.sorted_ledger
+            .get_datastore_keys_in_range(addr, range, limit)
+    }
+
     /// Get a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -170,6 +247,41 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.set_ledger_part(data.as_bytes())
     }
 
+    /// Get the hash of the ledger content.
+    ///
+    /// # Returns
+    /// The root of the sparse Merkle tree over every `(key, value)` pair of the disk ledger
+    fn get_ledger_hash(&self) -> Hash {
+        self.sorted_ledger.get_ledger_hash()
+    }
+
+    /// Get the proof of a raw ledger key against the ledger hash.
+    ///
+    /// # Returns
+    /// An inclusion proof if the key exists, a non-inclusion proof otherwise
+    fn get_key_proof(&self, key: &[u8]) -> LedgerKeyProof {
+        self.sorted_ledger.get_key_proof(key)
+    }
+
+    /// Get the ledger hash at the output of a past final slot, from the disk ledger history
+    fn get_ledger_hash_at_slot(&self, slot: Slot) -> Result<Hash, LedgerError> {
+        self.sorted_ledger.get_ledger_hash_at_slot(slot)
+    }
+
+    /// Get the proof of a raw ledger key against the ledger hash at the output of a past final slot,
+    /// from the disk ledger history
+    fn get_key_proof_at_slot(&self, key: &[u8], slot: Slot) -> Result<LedgerKeyProof, LedgerError> {
+        self.sorted_ledger.get_key_proof_at_slot(key, slot)
+    }
+
+    /// Get the cost in coins of each byte stored in the ledger at a given slot
+    fn get_storage_cost_per_byte(&self, slot: &Slot) -> Amount {
+        match self.config.storage_cost_activation_period {
+            Some(period) if slot.period >= period => self.config.storage_cost_per_byte,
+            _ => Amount::zero(),
+        }
+    }
+
     /// Get every address and their corresponding balance.
     ///
     /// IMPORTANT: This should only be used for debug and test purposes.
@@ -2,10 +2,13 @@
 
 //! Module to interact with the disk ledger
 
+use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::*;
+use massa_models::constants::SLOT_KEY_SIZE;
 use massa_models::constants::{ADDRESS_SIZE_BYTES, LEDGER_PART_SIZE_MESSAGE_BYTES};
 use massa_models::{
-    Address, ModelsError, SerializeCompact, Slot, VecU8Deserializer, VecU8Serializer,
+    Address, DeserializeCompact, ModelsError, SerializeCompact, Slot, VecU8Deserializer,
+    VecU8Serializer,
 };
 use massa_serialization::{Deserializer, Serializer};
 use nom::multi::many0;
@@ -13,17 +16,31 @@ use nom::sequence::tuple;
 use rocksdb::{
     ColumnFamilyDescriptor, Direction, IteratorMode, Options, ReadOptions, WriteBatch, DB,
 };
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ops::Bound;
 use std::path::PathBuf;
 use std::rc::Rc;
 
 const LEDGER_CF: &str = "ledger";
 const METADATA_CF: &str = "metadata";
+const MERKLE_CF: &str = "merkle";
+const HISTORY_CF: &str = "history";
+const HISTORY_INDEX_CF: &str = "history_index";
 const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
 const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
 const CF_ERROR: &str = "critical: rocksdb column family operation failed";
 const SLOT_KEY: &[u8; 1] = b"s";
+const HISTORY_START_KEY: &[u8; 1] = b"a";
+const MERKLE_HISTORY_START_KEY: &[u8; 1] = b"t";
+const SLOT_ERROR: &str = "critical: saved slot is corrupted";
+const HISTORY_VALUE_ERROR: &str = "critical: saved history value is corrupted";
+const MERKLE_NODE_ERROR: &str = "critical: saved merkle node is corrupted";
+const MERKLE_ROOT_KEY: &[u8; 1] = b"r";
+const MERKLE_VERSION_KEY: &[u8; 1] = b"m";
+/// version of the Merkle tree, the tree is rebuilt from the ledger when opening a ledger of another version
+const MERKLE_VERSION: u8 = 1;
+/// number of keys processed per write while rebuilding the Merkle tree or clearing a column family
+const MERKLE_REBUILD_BATCH_SIZE: usize = 10_000;
 
 /// Ledger sub entry enum
 pub enum LedgerSubEntry {
@@ -35,42 +52,205 @@ pub enum LedgerSubEntry {
     Datastore(Vec<u8>),
 }
 
+impl LedgerSubEntry {
+    /// Disk ledger key of the sub-entry for a given address
+    fn derive_key(&self, addr: &Address) -> Vec<u8> {
+        match self {
+            LedgerSubEntry::Balance => balance_key!(addr),
+            LedgerSubEntry::Bytecode => bytecode_key!(addr),
+            LedgerSubEntry::Datastore(hash) => data_key!(addr, hash[..]),
+        }
+    }
+}
+
 /// Disk ledger DB module
 ///
 /// Contains a RocksDB DB instance
 #[derive(Debug)]
-pub(crate) struct LedgerDB(DB);
-
-/// For a given start prefix (inclusive), returns the correct end prefix (non-inclusive).
-/// This assumes the key bytes are ordered in lexicographical order.
-/// Since key length is not limited, for some case we return `None` because there is
-/// no bounded limit (every keys in the serie `[]`, `[255]`, `[255, 255]` ...).
-fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
-    let mut end_range = prefix.to_vec();
-    while let Some(0xff) = end_range.last() {
-        end_range.pop();
-    }
-    if let Some(byte) = end_range.last_mut() {
-        *byte += 1;
-        Some(end_range)
-    } else {
-        None
+pub(crate) struct LedgerDB {
+    /// RocksDB instance
+    db: DB,
+    /// whether the previous values of the keys modified at each final slot are kept,
+    /// allowing to query the ledger at a past final slot
+    archive_mode: bool,
+    /// number of periods during which the history is kept in archive mode
+    archive_retention_periods: u64,
+}
+
+/// Batch of ledger writes, along with the ledger Merkle tree nodes they update
+#[derive(Default)]
+pub(crate) struct LedgerBatch {
+    /// RocksDB write batch
+    write_batch: WriteBatch,
+    /// Merkle tree branches written by the batch so far (`None` for removed branches),
+    /// needed to update the tree several times before the batch is written
+    merkle_branches: HashMap<Vec<u8>, Option<[MerkleSubtree; 2]>>,
+    /// Merkle tree root written by the batch, if any
+    merkle_root: Option<MerkleSubtree>,
+    /// slot whose changes are written by the batch, if their previous values must be archived
+    history_slot: Option<Slot>,
+    /// keys whose previous value was already archived by the batch
+    archived_keys: HashSet<Vec<u8>>,
+}
+
+/// Subtree of the ledger Merkle tree, as referenced by its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MerkleSubtree {
+    /// subtree without any key
+    Empty,
+    /// subtree containing a single key, replaced by the leaf of that key
+    Leaf {
+        /// path of the key
+        path: Hash,
+        /// hash of the value of the key
+        value_hash: Hash,
+    },
+    /// subtree whose keys split into both children of the branch node at `depth`
+    Branch {
+        /// depth of the branch node
+        depth: usize,
+        /// path leading to the branch node, zeroed after `depth` bits
+        prefix: Hash,
+        /// hash of the branch node
+        hash: Hash,
+    },
+}
+
+impl MerkleSubtree {
+    /// Hash of the subtree seen from a given depth, above the subtree.
+    /// The nodes between that depth and a branch node have an empty child.
+    fn hash_at(&self, depth: usize) -> Hash {
+        match self {
+            MerkleSubtree::Empty => Hash::zero(),
+            MerkleSubtree::Leaf { path, value_hash } => ledger_leaf_hash(path, value_hash),
+            MerkleSubtree::Branch {
+                depth: branch_depth,
+                prefix,
+                hash,
+            } => {
+                let mut hash = *hash;
+                for node_depth in (depth..*branch_depth).rev() {
+                    hash = if ledger_path_bit(prefix, node_depth) {
+                        ledger_node_hash(&Hash::zero(), &hash)
+                    } else {
+                        ledger_node_hash(&hash, &Hash::zero())
+                    };
+                }
+                hash
+            }
+        }
+    }
+
+    /// Path of a key of the subtree, its bits before the subtree position are the ones of the subtree
+    fn path(&self) -> Hash {
+        match self {
+            MerkleSubtree::Empty => Hash::zero(),
+            MerkleSubtree::Leaf { path, .. } => *path,
+            MerkleSubtree::Branch { prefix, .. } => *prefix,
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        match self {
+            MerkleSubtree::Empty => vec![0],
+            MerkleSubtree::Leaf { path, value_hash } => {
+                [&[1u8][..], path.to_bytes(), value_hash.to_bytes()].concat()
+            }
+            MerkleSubtree::Branch {
+                depth,
+                prefix,
+                hash,
+            } => [
+                &[2u8][..],
+                &(depth as u16).to_be_bytes(),
+                prefix.to_bytes(),
+                hash.to_bytes(),
+            ]
+            .concat(),
+        }
     }
+
+    /// Read a subtree from bytes written by `to_bytes`, and return the remaining bytes
+    fn from_bytes(bytes: &[u8]) -> (MerkleSubtree, &[u8]) {
+        let hash_at = |offset: usize| {
+            Hash::from_bytes(
+                bytes
+                    .get(offset..offset + HASH_SIZE_BYTES)
+                    .and_then(|hash| hash.try_into().ok())
+                    .expect(MERKLE_NODE_ERROR),
+            )
+        };
+        match bytes.first() {
+            Some(0) => (MerkleSubtree::Empty, &bytes[1..]),
+            Some(1) => (
+                MerkleSubtree::Leaf {
+                    path: hash_at(1),
+                    value_hash: hash_at(1 + HASH_SIZE_BYTES),
+                },
+                &bytes[1 + 2 * HASH_SIZE_BYTES..],
+            ),
+            Some(2) => (
+                MerkleSubtree::Branch {
+                    depth: u16::from_be_bytes(
+                        bytes
+                            .get(1..3)
+                            .and_then(|depth| depth.try_into().ok())
+                            .expect(MERKLE_NODE_ERROR),
+                    ) as usize,
+                    prefix: hash_at(3),
+                    hash: hash_at(3 + HASH_SIZE_BYTES),
+                },
+                &bytes[3 + 2 * HASH_SIZE_BYTES..],
+            ),
+            _ => panic!("{}", MERKLE_NODE_ERROR),
+        }
+    }
+}
+
+/// Path leading to the node at `depth` on a path: the first `depth` bits of the path
+fn merkle_prefix(path: &Hash, depth: usize) -> Hash {
+    let mut path_bytes = path.into_bytes();
+    for (index, byte) in path_bytes.iter_mut().enumerate() {
+        let kept_bits = depth.saturating_sub(index * 8).min(8);
+        *byte &= !(0xffu16 >> kept_bits) as u8;
+    }
+    Hash::from_bytes(&path_bytes)
+}
+
+/// Key of a branch node of the ledger Merkle tree: its depth followed by the path leading to it
+fn merkle_node_key(path: &Hash, depth: usize) -> Vec<u8> {
+    [
+        &(depth as u16).to_be_bytes()[..],
+        merkle_prefix(path, depth).to_bytes(),
+    ]
+    .concat()
 }
 
-#[test]
-fn test_end_prefix() {
-    assert_eq!(end_prefix(&[5, 6, 7]), Some(vec![5, 6, 8]));
-    assert_eq!(end_prefix(&[5, 6, 255]), Some(vec![5, 7]));
+/// Key of a ledger history entry: hash of the ledger key followed by the slot at which the key was modified
+fn history_key(key: &[u8], slot: &Slot) -> Vec<u8> {
+    [Hash::compute_from(key).to_bytes(), &slot.to_bytes_key()[..]].concat()
+}
+
+/// Key under which the history of a Merkle tree node is archived.
+/// Ledger keys start with an address hash, so they cannot be mistaken for those.
+fn merkle_history_subject(node_key: &[u8]) -> Vec<u8> {
+    [MERKLE_CF.as_bytes(), node_key].concat()
+}
+
+/// Key of a ledger history index entry, allowing to prune the history by slot:
+/// the slot at which the key was modified followed by the history key
+fn history_index_key(key: &[u8], slot: &Slot) -> Vec<u8> {
+    [&slot.to_bytes_key()[..], &history_key(key, slot)].concat()
 }
 
-// TODO: save attached slot in metadata for a lighter bootstrap after disconnection
 impl LedgerDB {
     /// Create and initialize a new LedgerDB.
     ///
     /// # Arguments
     /// * path: path to the desired disk ledger db directory
-    pub fn new(path: PathBuf) -> Self {
+    /// * archive_mode: whether to keep the history of the values modified at each final slot
+    /// * archive_retention_periods: number of periods during which the history is kept
+    pub fn new(path: PathBuf, archive_mode: bool, archive_retention_periods: u64) -> Self {
         let mut db_opts = Options::default();
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
@@ -81,11 +261,23 @@ impl LedgerDB {
             vec![
                 ColumnFamilyDescriptor::new(LEDGER_CF, Options::default()),
                 ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+                ColumnFamilyDescriptor::new(MERKLE_CF, Options::default()),
+                ColumnFamilyDescriptor::new(HISTORY_CF, Options::default()),
+                ColumnFamilyDescriptor::new(HISTORY_INDEX_CF, Options::default()),
             ],
         )
         .expect(OPEN_ERROR);
 
-        LedgerDB(db)
+        let ledger_db = LedgerDB {
+            db,
+            archive_mode,
+            archive_retention_periods,
+        };
+        if ledger_db.get_merkle_version() != Some(MERKLE_VERSION) {
+            ledger_db.rebuild_merkle_tree();
+        }
+        ledger_db.rebuild_history_index();
+        ledger_db
     }
 
     /// Set the initial disk ledger
@@ -93,7 +285,7 @@ impl LedgerDB {
     /// # Arguments
     /// * initial_ledger: initial entries to put in the disk
     pub fn set_initial_ledger(&mut self, initial_ledger: HashMap<Address, LedgerEntry>) {
-        let mut batch = WriteBatch::default();
+        let mut batch = LedgerBatch::default();
         for (address, entry) in initial_ledger {
             self.put_entry(&address, entry, &mut batch);
         }
@@ -107,7 +299,26 @@ impl LedgerDB {
     /// * slot: new slot associated to the final ledger
     pub fn apply_changes(&mut self, changes: LedgerChanges, slot: Slot) {
         // create the batch
-        let mut batch = WriteBatch::default();
+        let mut batch = LedgerBatch::default();
+        // in archive mode, keep the previous values of the keys modified at this slot
+        if self.archive_mode {
+            let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+            for (start_key, start) in [
+                (HISTORY_START_KEY, self.get_history_start()),
+                (MERKLE_HISTORY_START_KEY, self.get_merkle_history_start()),
+            ] {
+                if start.is_none() {
+                    // Slot::to_bytes_compact() never fails
+                    batch
+                        .write_batch
+                        .put_cf(handle, start_key, slot.to_bytes_compact().unwrap());
+                }
+            }
+            batch.history_slot = Some(slot);
+            if let Some(min_period) = slot.period.checked_sub(self.archive_retention_periods) {
+                self.prune_history_before(Slot::new(min_period, 0), &mut batch);
+            }
+        }
         // for all incoming changes
         for (addr, change) in changes.0 {
             match change {
@@ -138,8 +349,558 @@ impl LedgerDB {
     /// Apply the given operation batch to the disk ledger.
     ///
     /// NOTE: the batch is not saved within the object because it cannot be shared between threads safely
-    fn write_batch(&self, batch: WriteBatch) {
-        self.0.write(batch).expect(CRUD_ERROR);
+    fn write_batch(&self, batch: LedgerBatch) {
+        self.db.write(batch.write_batch).expect(CRUD_ERROR);
+    }
+
+    /// Get the root of the ledger sparse Merkle tree, which commits to every `(key, value)` pair of the ledger.
+    /// It does not depend on the order in which entries were written.
+    pub fn get_ledger_hash(&self) -> Hash {
+        self.get_merkle_root(None).hash_at(0)
+    }
+
+    /// Get the root of the ledger sparse Merkle tree at the output of a past final slot.
+    /// Requires the archive mode, and the slot to be between the first archived slot and the ledger slot.
+    pub fn get_ledger_hash_at_slot(&self, slot: Slot) -> Result<Hash, LedgerError> {
+        self.check_merkle_history_slot(slot)?;
+        let root_bytes = self.get_archived_value(
+            MERKLE_CF,
+            MERKLE_ROOT_KEY,
+            &merkle_history_subject(MERKLE_ROOT_KEY),
+            slot,
+        );
+        Ok(match root_bytes {
+            Some(bytes) => MerkleSubtree::from_bytes(&bytes).0.hash_at(0),
+            None => Hash::zero(),
+        })
+    }
+
+    /// Get the version of the Merkle tree stored along the ledger, if any
+    fn get_merkle_version(&self) -> Option<u8> {
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, MERKLE_VERSION_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| *bytes.first().expect(MERKLE_NODE_ERROR))
+    }
+
+    /// Rebuild the Merkle tree from the content of the ledger.
+    /// Used for ledgers written without the current version of the tree.
+    fn rebuild_merkle_tree(&self) {
+        let merkle_handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        let ledger_handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+
+        // remove the previous tree, whose archived nodes cannot be used to prove past slots anymore
+        self.clear_cf(MERKLE_CF);
+        let metadata_handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.db
+            .delete_cf(metadata_handle, MERKLE_HISTORY_START_KEY)
+            .expect(CRUD_ERROR);
+
+        // insert every key of the ledger
+        let mut batch = LedgerBatch::default();
+        for (index, (key, value)) in self
+            .db
+            .iterator_cf(ledger_handle, IteratorMode::Start)
+            .enumerate()
+        {
+            self.update_merkle_leaf(&key, Some(&value), &mut batch);
+            if (index + 1) % MERKLE_REBUILD_BATCH_SIZE == 0 {
+                self.write_batch(std::mem::take(&mut batch));
+            }
+        }
+        batch
+            .write_batch
+            .put_cf(metadata_handle, MERKLE_VERSION_KEY, [MERKLE_VERSION]);
+        self.write_batch(batch);
+    }
+
+    /// Delete every key of a column family
+    fn clear_cf(&self, cf_name: &str) {
+        let handle = self.db.cf_handle(cf_name).expect(CF_ERROR);
+        let mut batch = LedgerBatch::default();
+        for (index, (key, _)) in self.db.iterator_cf(handle, IteratorMode::Start).enumerate() {
+            batch.write_batch.delete_cf(handle, key);
+            if (index + 1) % MERKLE_REBUILD_BATCH_SIZE == 0 {
+                self.write_batch(std::mem::take(&mut batch));
+            }
+        }
+        self.write_batch(batch);
+    }
+
+    /// Remove every entry of the ledger, along with its Merkle tree, its history and its slot.
+    /// Used to restart a bootstrap from scratch.
+    pub fn reset(&mut self) {
+        for cf_name in [LEDGER_CF, MERKLE_CF, HISTORY_CF, HISTORY_INDEX_CF] {
+            self.clear_cf(cf_name);
+        }
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        let mut batch = LedgerBatch::default();
+        batch.write_batch.delete_cf(handle, SLOT_KEY);
+        batch.write_batch.delete_cf(handle, HISTORY_START_KEY);
+        batch
+            .write_batch
+            .delete_cf(handle, MERKLE_HISTORY_START_KEY);
+        self.write_batch(batch);
+    }
+
+    /// Get the root subtree of the Merkle tree, as seen by a batch if any
+    fn get_merkle_root(&self, batch: Option<&LedgerBatch>) -> MerkleSubtree {
+        if let Some(root) = batch.and_then(|batch| batch.merkle_root) {
+            return root;
+        }
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        match self.db.get_cf(handle, MERKLE_ROOT_KEY).expect(CRUD_ERROR) {
+            Some(bytes) => MerkleSubtree::from_bytes(&bytes).0,
+            None => MerkleSubtree::Empty,
+        }
+    }
+
+    /// Add a Merkle tree root write to the batch
+    fn set_merkle_root(&self, root: MerkleSubtree, batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        let root_bytes = root.to_bytes();
+        self.archive_previous_merkle_node(MERKLE_ROOT_KEY, Some(&root_bytes), batch);
+        batch
+            .write_batch
+            .put_cf(handle, MERKLE_ROOT_KEY, root_bytes);
+        batch.merkle_root = Some(root);
+    }
+
+    /// Get the children of a branch node of the Merkle tree, as seen by a batch if any
+    fn get_merkle_branch(
+        &self,
+        depth: usize,
+        prefix: &Hash,
+        batch: Option<&LedgerBatch>,
+    ) -> [MerkleSubtree; 2] {
+        let node_key = merkle_node_key(prefix, depth);
+        if let Some(children) = batch.and_then(|batch| batch.merkle_branches.get(&node_key)) {
+            return children.expect(MERKLE_NODE_ERROR);
+        }
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        let bytes = self
+            .db
+            .get_cf(handle, &node_key)
+            .expect(CRUD_ERROR)
+            .expect(MERKLE_NODE_ERROR);
+        let (left, rest) = MerkleSubtree::from_bytes(&bytes);
+        let (right, _) = MerkleSubtree::from_bytes(rest);
+        [left, right]
+    }
+
+    /// Add a Merkle tree branch node write to the batch
+    ///
+    /// # Returns
+    /// The subtree of the branch node
+    fn put_merkle_branch(
+        &self,
+        depth: usize,
+        prefix: &Hash,
+        children: [MerkleSubtree; 2],
+        batch: &mut LedgerBatch,
+    ) -> MerkleSubtree {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        let node_key = merkle_node_key(prefix, depth);
+        let node_bytes = [children[0].to_bytes(), children[1].to_bytes()].concat();
+        self.archive_previous_merkle_node(&node_key, Some(&node_bytes), batch);
+        batch.write_batch.put_cf(handle, &node_key, node_bytes);
+        batch.merkle_branches.insert(node_key, Some(children));
+        MerkleSubtree::Branch {
+            depth,
+            prefix: merkle_prefix(prefix, depth),
+            hash: ledger_node_hash(
+                &children[0].hash_at(depth + 1),
+                &children[1].hash_at(depth + 1),
+            ),
+        }
+    }
+
+    /// Add a Merkle tree branch node deletion to the batch
+    fn delete_merkle_branch(&self, depth: usize, prefix: &Hash, batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        let node_key = merkle_node_key(prefix, depth);
+        self.archive_previous_merkle_node(&node_key, None, batch);
+        batch.write_batch.delete_cf(handle, &node_key);
+        batch.merkle_branches.insert(node_key, None);
+    }
+
+    /// Update the leaf of a key in the Merkle tree and the branch nodes above it
+    ///
+    /// # Arguments
+    /// * key: ledger key
+    /// * value: new value of the key, `None` if the key is deleted
+    /// * batch: the given operation batch to update
+    fn update_merkle_leaf(&self, key: &[u8], value: Option<&[u8]>, batch: &mut LedgerBatch) {
+        let path = ledger_key_path(key);
+        let leaf = match value {
+            Some(value) => MerkleSubtree::Leaf {
+                path,
+                value_hash: Hash::compute_from(value),
+            },
+            None => MerkleSubtree::Empty,
+        };
+        let root = self.get_merkle_root(Some(batch));
+        let new_root = self.update_merkle_subtree(root, &path, leaf, batch);
+        if new_root != root {
+            self.set_merkle_root(new_root, batch);
+        }
+    }
+
+    /// Set the leaf of a key in a subtree of the Merkle tree
+    ///
+    /// # Arguments
+    /// * subtree: subtree in which the key is (or would be)
+    /// * path: path of the key
+    /// * leaf: new leaf of the key, `MerkleSubtree::Empty` if the key is deleted
+    /// * batch: the given operation batch to update
+    ///
+    /// # Returns
+    /// The updated subtree
+    fn update_merkle_subtree(
+        &self,
+        subtree: MerkleSubtree,
+        path: &Hash,
+        leaf: MerkleSubtree,
+        batch: &mut LedgerBatch,
+    ) -> MerkleSubtree {
+        let branch_depth = match subtree {
+            MerkleSubtree::Empty => return leaf,
+            MerkleSubtree::Leaf {
+                path: leaf_path, ..
+            } if leaf_path == *path => return leaf,
+            MerkleSubtree::Leaf { .. } => None,
+            MerkleSubtree::Branch { depth, prefix, .. } => {
+                Some(depth).filter(|depth| ledger_common_prefix_len(&prefix, path) >= *depth)
+            }
+        };
+        let depth = match branch_depth {
+            Some(depth) => depth,
+            // the key is not in the subtree
+            None if leaf == MerkleSubtree::Empty => return subtree,
+            // the key and the subtree split at a new branch node
+            None => {
+                let depth = ledger_common_prefix_len(&subtree.path(), path);
+                let children = if ledger_path_bit(path, depth) {
+                    [subtree, leaf]
+                } else {
+                    [leaf, subtree]
+                };
+                return self.put_merkle_branch(depth, path, children, batch);
+            }
+        };
+
+        // the key is below the branch node
+        let prefix = subtree.path();
+        let mut children = self.get_merkle_branch(depth, &prefix, Some(batch));
+        let side = ledger_path_bit(path, depth) as usize;
+        let child = self.update_merkle_subtree(children[side], path, leaf, batch);
+        if child == children[side] {
+            subtree
+        } else if child == MerkleSubtree::Empty {
+            // the other child is the only one left, it takes the place of the branch node
+            self.delete_merkle_branch(depth, &prefix, batch);
+            children[1 - side]
+        } else {
+            children[side] = child;
+            self.put_merkle_branch(depth, &prefix, children, batch)
+        }
+    }
+
+    /// Get the inclusion or non-inclusion proof of a key against the current Merkle root
+    pub fn get_key_proof(&self, key: &[u8]) -> LedgerKeyProof {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        self.build_key_proof(key, |node_key| {
+            self.db.get_cf(handle, node_key).expect(CRUD_ERROR)
+        })
+    }
+
+    /// Get the inclusion or non-inclusion proof of a key against the Merkle root at the output of a past final slot.
+    /// Requires the archive mode, and the slot to be between the first archived slot and the ledger slot.
+    pub fn get_key_proof_at_slot(
+        &self,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<LedgerKeyProof, LedgerError> {
+        self.check_merkle_history_slot(slot)?;
+        Ok(self.build_key_proof(key, |node_key| {
+            self.get_archived_value(MERKLE_CF, node_key, &merkle_history_subject(node_key), slot)
+        }))
+    }
+
+    /// Walk the Merkle tree from its root down to a key to build its proof
+    ///
+    /// # Arguments
+    /// * key: ledger key to prove
+    /// * get_node: reads the bytes of the Merkle tree node stored under a key of the Merkle column, if any
+    fn build_key_proof(
+        &self,
+        key: &[u8],
+        get_node: impl Fn(&[u8]) -> Option<Vec<u8>>,
+    ) -> LedgerKeyProof {
+        let path = ledger_key_path(key);
+        let mut siblings = Vec::new();
+        let mut subtree = match get_node(MERKLE_ROOT_KEY) {
+            Some(bytes) => MerkleSubtree::from_bytes(&bytes).0,
+            None => MerkleSubtree::Empty,
+        };
+        let (value_hash, other_leaf) = loop {
+            match subtree {
+                MerkleSubtree::Empty => break (None, None),
+                MerkleSubtree::Leaf {
+                    path: leaf_path,
+                    value_hash,
+                } if leaf_path == path => break (Some(value_hash), None),
+                MerkleSubtree::Leaf {
+                    path: leaf_path,
+                    value_hash,
+                } => break (None, Some((leaf_path, value_hash))),
+                MerkleSubtree::Branch { depth, prefix, .. } => {
+                    let common_depth = ledger_common_prefix_len(&prefix, &path);
+                    if common_depth < depth {
+                        // the key would be in the empty sibling of the node leading to the branch
+                        siblings.push((common_depth as u16 + 1, subtree.hash_at(common_depth + 1)));
+                        break (None, None);
+                    }
+                    let bytes =
+                        get_node(&merkle_node_key(&prefix, depth)).expect(MERKLE_NODE_ERROR);
+                    let (left, rest) = MerkleSubtree::from_bytes(&bytes);
+                    let (right, _) = MerkleSubtree::from_bytes(rest);
+                    let children = [left, right];
+                    let side = ledger_path_bit(&path, depth) as usize;
+                    siblings.push((depth as u16 + 1, children[1 - side].hash_at(depth + 1)));
+                    subtree = children[side];
+                }
+            }
+        };
+        siblings.reverse();
+        LedgerKeyProof {
+            value_hash,
+            other_leaf,
+            siblings,
+        }
+    }
+
+    /// Add a `(key, value)` write to the batch, updating the Merkle tree and the history
+    fn put_entry_value(&self, key: &[u8], value: &[u8], batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        self.archive_previous_value(key, Some(value), batch);
+        batch.write_batch.put_cf(handle, key, value);
+        self.update_merkle_leaf(key, Some(value), batch);
+    }
+
+    /// Add a key deletion to the batch, updating the Merkle tree and the history
+    fn delete_key(&self, key: &[u8], batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        self.archive_previous_value(key, None, batch);
+        batch.write_batch.delete_cf(handle, key);
+        self.update_merkle_leaf(key, None, batch);
+    }
+
+    /// If the batch archives its changes, add the value of a key before the batch to the history,
+    /// unless it was already archived by the batch or the key is not modified.
+    ///
+    /// History values are the previous value prefixed with 1, or `[0]` if the key was absent.
+    fn archive_previous_value(&self, key: &[u8], value: Option<&[u8]>, batch: &mut LedgerBatch) {
+        self.archive_previous_cf_value(LEDGER_CF, key, key, value, batch);
+    }
+
+    /// If the batch archives its changes, add the value of a Merkle tree node before the batch to the history,
+    /// so that proofs can be built against the Merkle root of past slots
+    fn archive_previous_merkle_node(
+        &self,
+        node_key: &[u8],
+        value: Option<&[u8]>,
+        batch: &mut LedgerBatch,
+    ) {
+        self.archive_previous_cf_value(
+            MERKLE_CF,
+            node_key,
+            &merkle_history_subject(node_key),
+            value,
+            batch,
+        );
+    }
+
+    /// Archive the value of a key of a column before the batch, under a history subject
+    /// (see `archive_previous_value`)
+    fn archive_previous_cf_value(
+        &self,
+        cf_name: &str,
+        key: &[u8],
+        subject: &[u8],
+        value: Option<&[u8]>,
+        batch: &mut LedgerBatch,
+    ) {
+        let slot = match batch.history_slot {
+            Some(slot) => slot,
+            None => return,
+        };
+        if batch.archived_keys.contains(subject) {
+            return;
+        }
+        let handle = self.db.cf_handle(cf_name).expect(CF_ERROR);
+        let previous = self.db.get_cf(handle, key).expect(CRUD_ERROR);
+        if previous.as_deref() == value {
+            return;
+        }
+        let history_value = match previous {
+            Some(previous) => [&[1u8][..], &previous].concat(),
+            None => vec![0u8],
+        };
+        let history_handle = self.db.cf_handle(HISTORY_CF).expect(CF_ERROR);
+        let history_index_handle = self.db.cf_handle(HISTORY_INDEX_CF).expect(CF_ERROR);
+        batch
+            .write_batch
+            .put_cf(history_handle, history_key(subject, &slot), history_value);
+        batch
+            .write_batch
+            .put_cf(history_index_handle, history_index_key(subject, &slot), b"");
+        batch.archived_keys.insert(subject.to_vec());
+    }
+
+    /// Index the history written before the history index existed, so that it can be pruned
+    fn rebuild_history_index(&self) {
+        let history_handle = self.db.cf_handle(HISTORY_CF).expect(CF_ERROR);
+        let history_index_handle = self.db.cf_handle(HISTORY_INDEX_CF).expect(CF_ERROR);
+        if self
+            .db
+            .iterator_cf(history_index_handle, IteratorMode::Start)
+            .next()
+            .is_some()
+        {
+            return;
+        }
+        let mut batch = WriteBatch::default();
+        for (history_key, _) in self.db.iterator_cf(history_handle, IteratorMode::Start) {
+            let slot_key = &history_key[history_key.len() - SLOT_KEY_SIZE..];
+            batch.put_cf(history_index_handle, [slot_key, &history_key].concat(), b"");
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    /// Add the removal of the history archived before a slot to the batch,
+    /// and move the start of the history to that slot
+    fn prune_history_before(&self, slot: Slot, batch: &mut LedgerBatch) {
+        if !matches!(self.get_history_start(), Some(start) if start < slot) {
+            return;
+        }
+        let history_handle = self.db.cf_handle(HISTORY_CF).expect(CF_ERROR);
+        let history_index_handle = self.db.cf_handle(HISTORY_INDEX_CF).expect(CF_ERROR);
+        let mut opt = ReadOptions::default();
+        opt.set_iterate_upper_bound(slot.to_bytes_key().to_vec());
+        for (index_key, _) in
+            self.db
+                .iterator_cf_opt(history_index_handle, opt, IteratorMode::Start)
+        {
+            batch
+                .write_batch
+                .delete_cf(history_handle, &index_key[SLOT_KEY_SIZE..]);
+            batch.write_batch.delete_cf(history_index_handle, index_key);
+        }
+        let metadata_handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        // Slot::to_bytes_compact() never fails
+        batch.write_batch.put_cf(
+            metadata_handle,
+            HISTORY_START_KEY,
+            slot.to_bytes_compact().unwrap(),
+        );
+    }
+
+    /// Get the slot associated to the disk ledger, if any
+    pub fn get_slot(&self) -> Option<Slot> {
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, SLOT_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| Slot::from_bytes_compact(&bytes).expect(SLOT_ERROR).0)
+    }
+
+    /// Get the first slot whose changes were archived, if any
+    fn get_history_start(&self) -> Option<Slot> {
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, HISTORY_START_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| Slot::from_bytes_compact(&bytes).expect(SLOT_ERROR).0)
+    }
+
+    /// Get the first slot whose changes to the Merkle tree were archived, if any
+    fn get_merkle_history_start(&self) -> Option<Slot> {
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, MERKLE_HISTORY_START_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| Slot::from_bytes_compact(&bytes).expect(SLOT_ERROR).0)
+    }
+
+    /// Check that the values at the output of a slot can be read from the history
+    fn check_history_slot(&self, slot: Slot) -> Result<(), LedgerError> {
+        if !self.archive_mode {
+            return Err(LedgerError::HistoryUnavailable(
+                "the ledger archive mode is disabled".into(),
+            ));
+        }
+        if !matches!(self.get_history_start(), Some(start) if slot >= start) {
+            return Err(LedgerError::HistoryUnavailable(format!(
+                "slot {} is before the start of the ledger history",
+                slot
+            )));
+        }
+        if !matches!(self.get_slot(), Some(ledger_slot) if slot <= ledger_slot) {
+            return Err(LedgerError::HistoryUnavailable(format!(
+                "slot {} is not final yet",
+                slot
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that the Merkle tree at the output of a slot can be read from the history.
+    /// The tree history starts later than the values history on ledgers archived before the tree was,
+    /// or whose tree was rebuilt.
+    fn check_merkle_history_slot(&self, slot: Slot) -> Result<(), LedgerError> {
+        self.check_history_slot(slot)?;
+        if !matches!(self.get_merkle_history_start(), Some(start) if slot >= start) {
+            return Err(LedgerError::HistoryUnavailable(format!(
+                "slot {} is before the start of the ledger tree history",
+                slot
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the value of a key of a column at the output of a past slot, checked with `check_history_slot`.
+    /// It is the previous value archived by the first change after `slot`,
+    /// or the current value if the key was not modified since then.
+    fn get_archived_value(
+        &self,
+        cf_name: &str,
+        key: &[u8],
+        subject: &[u8],
+        slot: Slot,
+    ) -> Option<Vec<u8>> {
+        let subject_hash = Hash::compute_from(subject);
+        let from = [history_key(subject, &slot), vec![0u8]].concat();
+        let history_handle = self.db.cf_handle(HISTORY_CF).expect(CF_ERROR);
+        if let Some((history_key, value)) = self
+            .db
+            .iterator_cf(
+                history_handle,
+                IteratorMode::From(&from, Direction::Forward),
+            )
+            .next()
+        {
+            if history_key[..history_key.len() - SLOT_KEY_SIZE] == *subject_hash.to_bytes() {
+                return match value.split_first() {
+                    Some((1, previous)) => Some(previous.to_vec()),
+                    Some((0, [])) => None,
+                    _ => panic!("{}", HISTORY_VALUE_ERROR),
+                };
+            }
+        }
+        let handle = self.db.cf_handle(cf_name).expect(CF_ERROR);
+        self.db.get_cf(handle, key).expect(CRUD_ERROR)
     }
 
     /// Set the disk ledger metadata
@@ -149,11 +910,13 @@ impl LedgerDB {
     /// * batch: the given operation batch to update
     ///
     /// NOTE: right now the metadata is only a Slot, use a struct in the future
-    fn set_metadata(&self, slot: Slot, batch: &mut WriteBatch) {
-        let handle = self.0.cf_handle(METADATA_CF).expect(CF_ERROR);
+    fn set_metadata(&self, slot: Slot, batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
 
         // Slot::to_bytes_compact() never fails
-        batch.put_cf(handle, SLOT_KEY, slot.to_bytes_compact().unwrap());
+        batch
+            .write_batch
+            .put_cf(handle, SLOT_KEY, slot.to_bytes_compact().unwrap());
     }
 
     /// Add every sub-entry individually for a given entry.
@@ -162,23 +925,21 @@ impl LedgerDB {
     /// * addr: associated address
     /// * ledger_entry: complete entry to be added
     /// * batch: the given operation batch to update
-    fn put_entry(&mut self, addr: &Address, ledger_entry: LedgerEntry, batch: &mut WriteBatch) {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
-
+    fn put_entry(&mut self, addr: &Address, ledger_entry: LedgerEntry, batch: &mut LedgerBatch) {
         // balance
-        batch.put_cf(
-            handle,
-            balance_key!(addr),
+        self.put_entry_value(
+            &balance_key!(addr),
             // Amount::to_bytes_compact() never fails
-            ledger_entry.parallel_balance.to_bytes_compact().unwrap(),
+            &ledger_entry.parallel_balance.to_bytes_compact().unwrap(),
+            batch,
         );
 
         // bytecode
-        batch.put_cf(handle, bytecode_key!(addr), ledger_entry.bytecode);
+        self.put_entry_value(&bytecode_key!(addr), &ledger_entry.bytecode, batch);
 
         // datastore
         for (hash, entry) in ledger_entry.datastore {
-            batch.put_cf(handle, data_key!(addr, hash), entry);
+            self.put_entry_value(&data_key!(addr, hash), &entry, batch);
         }
     }
 
@@ -191,19 +952,32 @@ impl LedgerDB {
     /// # Returns
     /// An Option of the sub-entry value as bytes
     pub fn get_sub_entry(&self, addr: &Address, ty: LedgerSubEntry) -> Option<Vec<u8>> {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
 
-        match ty {
-            LedgerSubEntry::Balance => self.0.get_cf(handle, balance_key!(addr)).expect(CRUD_ERROR),
-            LedgerSubEntry::Bytecode => self
-                .0
-                .get_cf(handle, bytecode_key!(addr))
-                .expect(CRUD_ERROR),
-            LedgerSubEntry::Datastore(hash) => self
-                .0
-                .get_cf(handle, data_key!(addr, hash))
-                .expect(CRUD_ERROR),
-        }
+        self.db
+            .get_cf(handle, ty.derive_key(addr))
+            .expect(CRUD_ERROR)
+    }
+
+    /// Get the given sub-entry of a given address at the output of a past final slot.
+    /// Requires the archive mode, and the slot to be between the first archived slot and the ledger slot.
+    ///
+    /// # Arguments
+    /// * addr: associated address
+    /// * ty: type of the queried sub-entry
+    /// * slot: slot at the output of which the sub-entry is queried
+    ///
+    /// # Returns
+    /// An Option of the sub-entry value as bytes, or an error if the history of that slot is not available
+    pub fn get_sub_entry_at_slot(
+        &self,
+        addr: &Address,
+        ty: LedgerSubEntry,
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, LedgerError> {
+        self.check_history_slot(slot)?;
+        let key = ty.derive_key(addr);
+        Ok(self.get_archived_value(LEDGER_CF, &key, &key, slot))
     }
 
     /// Get every key of the datastore for a given address.
@@ -211,12 +985,12 @@ impl LedgerDB {
     /// # Returns
     /// A BTreeSet of the datastore keys
     pub fn get_datastore_keys(&self, addr: &Address) -> BTreeSet<Vec<u8>> {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
 
         let mut opt = ReadOptions::default();
         opt.set_iterate_upper_bound(end_prefix(data_prefix!(addr)).unwrap());
 
-        self.0
+        self.db
             .iterator_cf_opt(
                 handle,
                 opt,
@@ -226,6 +1000,42 @@ impl LedgerDB {
             .collect()
     }
 
+    /// Get the datastore keys of a given address within a range, in increasing order.
+    ///
+    /// # Arguments
+    /// * `range`: range of the datastore keys to return
+    /// * `limit`: maximum number of keys to return
+    pub fn get_datastore_keys_in_range(
+        &self,
+        addr: &Address,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+        limit: usize,
+    ) -> BTreeSet<Vec<u8>> {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+
+        let mut opt = ReadOptions::default();
+        opt.set_iterate_upper_bound(match &range.1 {
+            Bound::Excluded(end) => data_key!(addr, end[..]),
+            _ => end_prefix(data_prefix!(addr)).unwrap(),
+        });
+        let start_key = match &range.0 {
+            Bound::Included(start) | Bound::Excluded(start) => data_key!(addr, start[..]),
+            Bound::Unbounded => data_prefix!(addr).to_vec(),
+        };
+
+        self.db
+            .iterator_cf_opt(
+                handle,
+                opt,
+                IteratorMode::From(&start_key, Direction::Forward),
+            )
+            .map(|(key, _)| key.split_at(ADDRESS_SIZE_BYTES + 1).1.to_vec())
+            .filter(|key| !matches!(&range.0, Bound::Excluded(start) if key == start))
+            .take_while(|key| !matches!(&range.1, Bound::Included(end) if key > end))
+            .take(limit)
+            .collect()
+    }
+
     /// Update the ledger entry of a given address.
     ///
     /// # Arguments
@@ -235,30 +1045,30 @@ impl LedgerDB {
         &mut self,
         addr: &Address,
         entry_update: LedgerEntryUpdate,
-        batch: &mut WriteBatch,
+        batch: &mut LedgerBatch,
     ) {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
-
         // balance
         if let SetOrKeep::Set(balance) = entry_update.parallel_balance {
-            batch.put_cf(
-                handle,
-                balance_key!(addr),
+            self.put_entry_value(
+                &balance_key!(addr),
                 // Amount::to_bytes_compact() never fails
-                balance.to_bytes_compact().unwrap(),
+                &balance.to_bytes_compact().unwrap(),
+                batch,
             );
         }
 
         // bytecode
         if let SetOrKeep::Set(bytecode) = entry_update.bytecode {
-            batch.put_cf(handle, bytecode_key!(addr), bytecode);
+            self.put_entry_value(&bytecode_key!(addr), &bytecode, batch);
         }
 
         // datastore
         for (hash, update) in entry_update.datastore {
             match update {
-                SetOrDelete::Set(entry) => batch.put_cf(handle, data_key!(addr, hash), entry),
-                SetOrDelete::Delete => batch.delete_cf(handle, data_key!(addr, hash)),
+                SetOrDelete::Set(entry) => {
+                    self.put_entry_value(&data_key!(addr, hash), &entry, batch)
+                }
+                SetOrDelete::Delete => self.delete_key(&data_key!(addr, hash), batch),
             }
         }
     }
@@ -267,24 +1077,24 @@ impl LedgerDB {
     ///
     /// # Arguments
     /// * batch: the given operation batch to update
-    fn delete_entry(&self, addr: &Address, batch: &mut WriteBatch) {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+    fn delete_entry(&self, addr: &Address, batch: &mut LedgerBatch) {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
 
         // balance
-        batch.delete_cf(handle, balance_key!(addr));
+        self.delete_key(&balance_key!(addr), batch);
 
         // bytecode
-        batch.delete_cf(handle, balance_key!(addr));
+        self.delete_key(&bytecode_key!(addr), batch);
 
         // datastore
         let mut opt = ReadOptions::default();
         opt.set_iterate_upper_bound(end_prefix(data_prefix!(addr)).unwrap());
-        for (key, _) in self.0.iterator_cf_opt(
+        for (key, _) in self.db.iterator_cf_opt(
             handle,
             opt,
             IteratorMode::From(data_prefix!(addr), Direction::Forward),
         ) {
-            batch.delete_cf(handle, key);
+            self.delete_key(&key, batch);
         }
     }
 
@@ -304,19 +1114,19 @@ impl LedgerDB {
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), ModelsError> {
         let ser = VecU8Serializer::new();
         let key_serializer = KeySerializer::new();
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
         let mut part = Vec::new();
         let opt = ReadOptions::default();
 
         // Creates an iterator from the next element after the last if defined, otherwise initialize it at the first key of the ledger.
         let db_iterator = if let Some(key) = last_key {
             let mut iter =
-                self.0
+                self.db
                     .iterator_cf_opt(handle, opt, IteratorMode::From(key, Direction::Forward));
             iter.next();
             iter
         } else {
-            self.0.iterator_cf_opt(handle, opt, IteratorMode::Start)
+            self.db.iterator_cf_opt(handle, opt, IteratorMode::Start)
         };
         let mut last_key = None;
 
@@ -343,12 +1153,11 @@ impl LedgerDB {
     /// # Returns
     /// The last key of the inserted entry (this is an optimization to easily keep a reference to the last key)
     pub fn set_ledger_part<'a>(&self, data: &'a [u8]) -> Result<Option<Vec<u8>>, ModelsError> {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
         let vec_u8_deserializer =
             VecU8Deserializer::new(Bound::Included(0), Bound::Excluded(u64::MAX));
         let key_deserializer = KeyDeserializer::new();
         let mut last_key = Rc::new(None);
-        let mut batch = WriteBatch::default();
+        let mut batch = LedgerBatch::default();
 
         // Since this data is coming from the network, deser to address and ser back to bytes for a security check.
         let (rest, _) = many0(|input: &'a [u8]| {
@@ -359,14 +1168,14 @@ impl LedgerDB {
             *Rc::get_mut(&mut last_key).ok_or_else(|| {
                 nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Fail))
             })? = Some(key.clone());
-            batch.put_cf(handle, key, value);
+            self.put_entry_value(&key, &value, &mut batch);
             Ok((rest, ()))
         })(data)
         .map_err(|_| ModelsError::SerializeError("Error in deserialization".to_string()))?;
 
         // Every byte should have been read
         if rest.is_empty() {
-            self.0.write(batch).expect(CRUD_ERROR);
+            self.write_batch(batch);
             Ok((*last_key).clone())
         } else {
             println!("REST LEN = {}", rest.len());
@@ -384,13 +1193,13 @@ impl LedgerDB {
     /// A BTreeMap with the address as key and the balance as value
     #[cfg(feature = "testing")]
     pub fn get_every_address(&self) -> std::collections::BTreeMap<Address, massa_models::Amount> {
-        use massa_models::{address::AddressDeserializer, DeserializeCompact};
+        use massa_models::address::AddressDeserializer;
         use massa_serialization::DeserializeError;
 
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
 
         let ledger = self
-            .0
+            .db
             .iterator_cf(handle, IteratorMode::Start)
             .collect::<Vec<_>>();
 
@@ -421,12 +1230,12 @@ impl LedgerDB {
         &self,
         addr: &Address,
     ) -> std::collections::BTreeMap<Vec<u8>, Vec<u8>> {
-        let handle = self.0.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
 
         let mut opt = ReadOptions::default();
         opt.set_iterate_upper_bound(end_prefix(data_prefix!(addr)).unwrap());
 
-        self.0
+        self.db
             .iterator_cf_opt(
                 handle,
                 opt,
@@ -445,11 +1254,18 @@ impl LedgerDB {
 #[cfg(test)]
 mod tests {
     use super::LedgerDB;
-    use crate::ledger_db::LedgerSubEntry;
-    use massa_ledger_exports::{LedgerEntry, LedgerEntryUpdate, SetOrKeep};
-    use massa_models::{Address, Amount, DeserializeCompact};
+    use crate::ledger_db::{
+        LedgerBatch, LedgerSubEntry, HISTORY_CF, MERKLE_CF, MERKLE_VERSION_KEY, METADATA_CF,
+    };
+    use massa_hash::Hash;
+    use massa_ledger_exports::{
+        balance_key, data_key, ledger_key_path, ledger_leaf_hash, ledger_node_hash,
+        ledger_path_bit, LedgerChanges, LedgerEntry, LedgerEntryUpdate, SetOrDelete, SetOrKeep,
+        SetUpdateOrDelete, BALANCE_IDENT, DATASTORE_IDENT,
+    };
+    use massa_models::{Address, Amount, DeserializeCompact, SerializeCompact, Slot};
     use massa_signature::KeyPair;
-    use rocksdb::WriteBatch;
+    use rocksdb::IteratorMode;
     use std::collections::BTreeMap;
     use tempfile::TempDir;
 
@@ -473,8 +1289,8 @@ mod tests {
 
         // write data
         let temp_dir = TempDir::new().unwrap();
-        let mut db = LedgerDB::new(temp_dir.path().to_path_buf());
-        let mut batch = WriteBatch::default();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        let mut batch = LedgerBatch::default();
         db.put_entry(&addr, entry, &mut batch);
         db.update_entry(&addr, entry_update, &mut batch);
         db.write_batch(batch);
@@ -504,14 +1320,157 @@ mod tests {
         assert!(db.get_sub_entry(&b, LedgerSubEntry::Balance).is_none());
         assert_eq!(data, db.get_entire_datastore(&a));
 
+        assert_ne!(db.get_ledger_hash(), Hash::zero());
+
+        // proofs of present and absent keys
+        let balance = db.get_sub_entry(&a, LedgerSubEntry::Balance).unwrap();
+        assert!(db.get_key_proof(&balance_key!(a)).verify(
+            &balance_key!(a),
+            Some(&balance),
+            &db.get_ledger_hash()
+        ));
+        assert!(db.get_key_proof(&balance_key!(b)).verify(
+            &balance_key!(b),
+            None,
+            &db.get_ledger_hash()
+        ));
+        assert!(!db.get_key_proof(&balance_key!(a)).verify(
+            &balance_key!(a),
+            None,
+            &db.get_ledger_hash()
+        ));
+
         // delete entry
-        let mut batch = WriteBatch::default();
+        let mut batch = LedgerBatch::default();
         db.delete_entry(&a, &mut batch);
         db.write_batch(batch);
 
         // second assert
         assert!(db.get_sub_entry(&a, LedgerSubEntry::Balance).is_none());
+        assert!(db.get_sub_entry(&a, LedgerSubEntry::Bytecode).is_none());
         assert!(db.get_entire_datastore(&a).is_empty());
+        assert_eq!(db.get_ledger_hash(), Hash::zero());
+    }
+
+    #[test]
+    fn test_ledger_history() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), true, 1000);
+        let balance_at = |db: &LedgerDB, period| {
+            db.get_sub_entry_at_slot(&a, LedgerSubEntry::Balance, Slot::new(period, 0))
+                .map(|bytes| bytes.map(|bytes| Amount::from_bytes_compact(&bytes).unwrap().0))
+        };
+        let data_at = |db: &LedgerDB, period| {
+            db.get_sub_entry_at_slot(
+                &a,
+                LedgerSubEntry::Datastore(b"key".to_vec()),
+                Slot::new(period, 0),
+            )
+        };
+
+        // slot 1: create the entry
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(
+            a,
+            SetUpdateOrDelete::Set(LedgerEntry {
+                parallel_balance: Amount::from_mantissa_scale(10, 0),
+                datastore: BTreeMap::from([(b"key".to_vec(), b"one".to_vec())]),
+                ..Default::default()
+            }),
+        );
+        db.apply_changes(changes, Slot::new(1, 0));
+
+        // slot 2: nothing changes
+        db.apply_changes(LedgerChanges::default(), Slot::new(2, 0));
+
+        // slot 3: update the balance and delete the datastore entry
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(
+            a,
+            SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                parallel_balance: SetOrKeep::Set(Amount::from_mantissa_scale(20, 0)),
+                datastore: BTreeMap::from([(b"key".to_vec(), SetOrDelete::Delete)]),
+                ..Default::default()
+            }),
+        );
+        db.apply_changes(changes, Slot::new(3, 0));
+
+        // slot 4: delete the entry
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(a, SetUpdateOrDelete::Delete);
+        db.apply_changes(changes, Slot::new(4, 0));
+
+        assert!(balance_at(&db, 0).is_err());
+        assert_eq!(
+            balance_at(&db, 1).unwrap(),
+            Some(Amount::from_mantissa_scale(10, 0))
+        );
+        assert_eq!(
+            balance_at(&db, 2).unwrap(),
+            Some(Amount::from_mantissa_scale(10, 0))
+        );
+        assert_eq!(
+            balance_at(&db, 3).unwrap(),
+            Some(Amount::from_mantissa_scale(20, 0))
+        );
+        assert_eq!(balance_at(&db, 4).unwrap(), None);
+        assert!(balance_at(&db, 5).is_err());
+        assert_eq!(data_at(&db, 2).unwrap(), Some(b"one".to_vec()));
+        assert_eq!(data_at(&db, 3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_ledger_reopen() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        assert_eq!(db.get_slot(), None);
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(
+            a,
+            SetUpdateOrDelete::Set(LedgerEntry {
+                parallel_balance: Amount::from_mantissa_scale(10, 0),
+                ..Default::default()
+            }),
+        );
+        db.apply_changes(changes, Slot::new(3, 1));
+        let hash = db.get_ledger_hash();
+        drop(db);
+
+        // the reopened ledger keeps its content, hash and attached slot
+        let db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        assert_eq!(db.get_slot(), Some(Slot::new(3, 1)));
+        assert_eq!(db.get_ledger_hash(), hash);
+        assert_eq!(
+            db.get_sub_entry(&a, LedgerSubEntry::Balance)
+                .map(|bytes| Amount::from_bytes_compact(&bytes).unwrap().0),
+            Some(Amount::from_mantissa_scale(10, 0))
+        );
+    }
+
+    #[test]
+    fn test_ledger_reset() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), true, 10);
+        let empty_hash = db.get_ledger_hash();
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(
+            a,
+            SetUpdateOrDelete::Set(LedgerEntry {
+                parallel_balance: Amount::from_mantissa_scale(10, 0),
+                ..Default::default()
+            }),
+        );
+        db.apply_changes(changes, Slot::new(3, 1));
+        assert_ne!(db.get_ledger_hash(), empty_hash);
+
+        // the reset ledger is empty, with no slot
+        db.reset();
+        assert_eq!(db.get_slot(), None);
+        assert_eq!(db.get_ledger_hash(), empty_hash);
+        assert_eq!(db.get_sub_entry(&a, LedgerSubEntry::Balance), None);
     }
 
     #[test]
@@ -521,5 +1480,274 @@ mod tests {
         let (db, _) = init_test_ledger(a);
         let res = db.get_ledger_part(&None).unwrap();
         db.set_ledger_part(&res.0[..]).unwrap();
+
+        // a ledger rebuilt from the parts has the same hash
+        let temp_dir = TempDir::new().unwrap();
+        let rebuilt_db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        rebuilt_db.set_ledger_part(&res.0[..]).unwrap();
+        assert_eq!(rebuilt_db.get_ledger_hash(), db.get_ledger_hash());
+    }
+
+    /// Root of the ledger Merkle tree computed from scratch for a set of `(path, value hash)` leaves
+    fn reference_root(leaves: &[(Hash, Hash)], depth: usize) -> Hash {
+        match leaves {
+            [] => Hash::zero(),
+            [(path, value_hash)] => ledger_leaf_hash(path, value_hash),
+            _ => {
+                let (right, left): (Vec<(Hash, Hash)>, Vec<(Hash, Hash)>) = leaves
+                    .iter()
+                    .partition(|(path, _)| ledger_path_bit(path, depth));
+                ledger_node_hash(
+                    &reference_root(&left, depth + 1),
+                    &reference_root(&right, depth + 1),
+                )
+            }
+        }
+    }
+
+    /// Write `(key, value)` pairs to the ledger, `None` values being deletions
+    fn write_keys(db: &LedgerDB, keys: &[(Vec<u8>, Option<Vec<u8>>)]) {
+        let mut batch = LedgerBatch::default();
+        for (key, value) in keys {
+            match value {
+                Some(value) => db.put_entry_value(key, value, &mut batch),
+                None => db.delete_key(key, &mut batch),
+            }
+        }
+        db.write_batch(batch);
+    }
+
+    #[test]
+    fn test_ledger_merkle_tree() {
+        let keys: Vec<(Vec<u8>, Option<Vec<u8>>)> = (0..200u32)
+            .map(|index| {
+                (
+                    format!("key {}", index).into_bytes(),
+                    Some(index.to_be_bytes().to_vec()),
+                )
+            })
+            .collect();
+        let leaves = |keys: &[(Vec<u8>, Option<Vec<u8>>)]| -> Vec<(Hash, Hash)> {
+            keys.iter()
+                .filter_map(|(key, value)| {
+                    value
+                        .as_ref()
+                        .map(|value| (ledger_key_path(key), Hash::compute_from(value)))
+                })
+                .collect()
+        };
+
+        // the root does not depend on the insertion order nor on the batches
+        let temp_dir = TempDir::new().unwrap();
+        let db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        write_keys(&db, &keys[..50]);
+        write_keys(&db, &keys[50..]);
+        let reversed_temp_dir = TempDir::new().unwrap();
+        let reversed_db = LedgerDB::new(reversed_temp_dir.path().to_path_buf(), false, 0);
+        let reversed_keys: Vec<_> = keys.iter().rev().cloned().collect();
+        write_keys(&reversed_db, &reversed_keys);
+        assert_eq!(db.get_ledger_hash(), reference_root(&leaves(&keys), 0));
+        assert_eq!(reversed_db.get_ledger_hash(), db.get_ledger_hash());
+
+        // proofs of present and absent keys
+        for (key, value) in keys.iter() {
+            assert!(db
+                .get_key_proof(key)
+                .verify(key, value.as_deref(), &db.get_ledger_hash()));
+        }
+        for index in 200..300u32 {
+            let key = format!("key {}", index).into_bytes();
+            let proof = db.get_key_proof(&key);
+            assert!(proof.verify(&key, None, &db.get_ledger_hash()));
+            assert!(!proof.verify(&key, Some(b"value"), &db.get_ledger_hash()));
+        }
+
+        // update and delete half of the keys, in the same batch as other writes of the same keys
+        let updates: Vec<(Vec<u8>, Option<Vec<u8>>)> = keys
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (key, _))| {
+                let value = if index % 2 == 0 {
+                    None
+                } else {
+                    Some(b"updated".to_vec())
+                };
+                [
+                    (key.clone(), Some(b"temporary".to_vec())),
+                    (key.clone(), value),
+                ]
+            })
+            .collect();
+        write_keys(&db, &updates);
+        let remaining: Vec<_> = updates.iter().skip(1).step_by(2).cloned().collect();
+        assert_eq!(db.get_ledger_hash(), reference_root(&leaves(&remaining), 0));
+        for (key, value) in remaining.iter() {
+            assert!(db
+                .get_key_proof(key)
+                .verify(key, value.as_deref(), &db.get_ledger_hash()));
+        }
+
+        // deleting every key empties the tree
+        let deletions: Vec<_> = keys.iter().map(|(key, _)| (key.clone(), None)).collect();
+        write_keys(&db, &deletions);
+        assert_eq!(db.get_ledger_hash(), Hash::zero());
+    }
+
+    #[test]
+    fn test_ledger_merkle_tree_rebuild() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        let mut changes = LedgerChanges::default();
+        changes.0.insert(
+            a,
+            SetUpdateOrDelete::Set(LedgerEntry {
+                parallel_balance: Amount::from_mantissa_scale(10, 0),
+                datastore: BTreeMap::from([(b"key".to_vec(), b"value".to_vec())]),
+                ..Default::default()
+            }),
+        );
+        db.apply_changes(changes, Slot::new(1, 0));
+        let hash = db.get_ledger_hash();
+
+        // simulate a ledger written with another version of the tree
+        let merkle_handle = db.db.cf_handle(MERKLE_CF).unwrap();
+        db.db
+            .put_cf(merkle_handle, b"stale node", b"stale")
+            .unwrap();
+        let metadata_handle = db.db.cf_handle(METADATA_CF).unwrap();
+        db.db
+            .delete_cf(metadata_handle, MERKLE_VERSION_KEY)
+            .unwrap();
+        drop(db);
+
+        // the tree is rebuilt when the ledger is reopened
+        let db = LedgerDB::new(temp_dir.path().to_path_buf(), false, 0);
+        assert_eq!(db.get_ledger_hash(), hash);
+        let merkle_handle = db.db.cf_handle(MERKLE_CF).unwrap();
+        assert!(db
+            .db
+            .get_cf(merkle_handle, b"stale node")
+            .unwrap()
+            .is_none());
+        let balance = db.get_sub_entry(&a, LedgerSubEntry::Balance).unwrap();
+        assert!(db
+            .get_key_proof(&balance_key!(a))
+            .verify(&balance_key!(a), Some(&balance), &hash));
+    }
+
+    #[test]
+    fn test_ledger_history_retention() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), true, 2);
+        let balance_at = |db: &LedgerDB, period| {
+            db.get_sub_entry_at_slot(&a, LedgerSubEntry::Balance, Slot::new(period, 0))
+                .map(|bytes| bytes.map(|bytes| Amount::from_bytes_compact(&bytes).unwrap().0))
+        };
+
+        // the balance changes at each period
+        for period in 1..=5 {
+            let mut changes = LedgerChanges::default();
+            changes.0.insert(
+                a,
+                SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                    parallel_balance: SetOrKeep::Set(Amount::from_mantissa_scale(period, 0)),
+                    ..Default::default()
+                }),
+            );
+            db.apply_changes(changes, Slot::new(period, 0));
+        }
+
+        // the history before period 3 is pruned
+        assert!(balance_at(&db, 2).is_err());
+        assert!(db.get_ledger_hash_at_slot(Slot::new(2, 0)).is_err());
+        assert_eq!(
+            balance_at(&db, 3).unwrap(),
+            Some(Amount::from_mantissa_scale(3, 0))
+        );
+        assert_eq!(
+            balance_at(&db, 4).unwrap(),
+            Some(Amount::from_mantissa_scale(4, 0))
+        );
+        // the balance and the tree root archived at periods 3 to 5 remain
+        let history_handle = db.db.cf_handle(HISTORY_CF).unwrap();
+        assert_eq!(
+            db.db
+                .iterator_cf(history_handle, IteratorMode::Start)
+                .count(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_ledger_history_proofs() {
+        let a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let b = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), true, 1000);
+
+        // the balance of `a` changes at each period, `b` is created at period 2 and deleted at period 4
+        let mut roots = Vec::new();
+        for period in 1..=5 {
+            let mut changes = LedgerChanges::default();
+            changes.0.insert(
+                a,
+                SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                    parallel_balance: SetOrKeep::Set(Amount::from_mantissa_scale(period, 0)),
+                    ..Default::default()
+                }),
+            );
+            match period {
+                2 => {
+                    changes.0.insert(
+                        b,
+                        SetUpdateOrDelete::Set(LedgerEntry {
+                            parallel_balance: Amount::from_mantissa_scale(1, 0),
+                            datastore: BTreeMap::from([(b"key".to_vec(), b"value".to_vec())]),
+                            ..Default::default()
+                        }),
+                    );
+                }
+                4 => {
+                    changes.0.insert(b, SetUpdateOrDelete::Delete);
+                }
+                _ => (),
+            }
+            db.apply_changes(changes, Slot::new(period, 0));
+            roots.push(db.get_ledger_hash());
+        }
+
+        // past proofs verify against the root of their slot only
+        for period in 1..=5u64 {
+            let slot = Slot::new(period, 0);
+            let root = db.get_ledger_hash_at_slot(slot).unwrap();
+            assert_eq!(root, roots[period as usize - 1]);
+            let balance = Amount::from_mantissa_scale(period, 0)
+                .to_bytes_compact()
+                .unwrap();
+            let proof = db.get_key_proof_at_slot(&balance_key!(a), slot).unwrap();
+            assert!(proof.verify(&balance_key!(a), Some(&balance), &root));
+            assert_eq!(
+                proof.verify(&balance_key!(a), Some(&balance), &db.get_ledger_hash()),
+                period == 5
+            );
+            let data = (2..4).contains(&period).then(|| b"value".to_vec());
+            assert!(db
+                .get_key_proof_at_slot(&data_key!(b, b"key"[..]), slot)
+                .unwrap()
+                .verify(&data_key!(b, b"key"[..]), data.as_deref(), &root));
+        }
+
+        // slots out of the history cannot be proven
+        assert!(db
+            .get_key_proof_at_slot(&balance_key!(a), Slot::new(0, 0))
+            .is_err());
+        assert!(db
+            .get_key_proof_at_slot(&balance_key!(a), Slot::new(6, 0))
+            .is_err());
+        let other_temp_dir = TempDir::new().unwrap();
+        let db = LedgerDB::new(other_temp_dir.path().to_path_buf(), false, 0);
+        assert!(db.get_ledger_hash_at_slot(Slot::new(0, 0)).is_err());
     }
 }
@@ -1,15 +1,19 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::address::AddressCycleProductionStats;
+use crate::execution::OperationReceipt;
 use crate::ledger_models::LedgerData;
 use crate::node::NodeId;
+use crate::output_event::SCOutputEvent;
 use crate::prehash::Set;
 use crate::stats::{ConsensusStats, NetworkStats, PoolStats};
 use crate::WrappedEndorsement;
 use crate::WrappedOperation;
 use crate::{
-    Address, Amount, Block, BlockId, CompactConfig, EndorsementId, OperationId, Slot, Version,
+    Address, Amount, Block, BlockId, CompactConfig, EndorsementId, ModelsError, OperationId,
+    OperationType, Slot, Version,
 };
+use massa_hash::Hash;
 use massa_signature::{PublicKey, Signature};
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
@@ -47,6 +51,10 @@ pub struct NodeStatus {
     pub last_slot: Option<Slot>,
     /// next slot
     pub next_slot: Slot,
+    /// slot the final state is attached to
+    pub final_state_slot: Slot,
+    /// fingerprint of the final state at `final_state_slot`
+    pub final_state_fingerprint: Hash,
     /// consensus stats
     pub consensus_stats: ConsensusStats,
     /// pool stats
@@ -77,6 +85,11 @@ impl std::fmt::Display for NodeStatus {
             writeln!(f, "Last slot: {}", self.last_slot.unwrap())?;
         }
         writeln!(f, "Next slot: {}", self.next_slot)?;
+        writeln!(
+            f,
+            "Final state fingerprint: {} (at slot {})",
+            self.final_state_fingerprint, self.final_state_slot
+        )?;
         writeln!(f)?;
 
         writeln!(f, "{}", self.consensus_stats)?;
@@ -113,6 +126,8 @@ pub struct OperationInfo {
     pub is_final: bool,
     /// the operation itself
     pub operation: WrappedOperation,
+    /// outcome of the latest execution of the operation, if it is an executed `ExecuteSC` or `CallSC`
+    pub receipt: Option<OperationReceipt>,
 }
 
 impl OperationInfo {
@@ -122,6 +137,9 @@ impl OperationInfo {
         self.in_pool = self.in_pool || other.in_pool;
         self.in_blocks.extend(other.in_blocks.iter());
         self.is_final = self.is_final || other.is_final;
+        if self.receipt.is_none() {
+            self.receipt = other.receipt.clone();
+        }
     }
 }
 
@@ -139,10 +157,34 @@ impl std::fmt::Display for OperationInfo {
             writeln!(f, "\t- {}", block_id)?;
         }
         writeln!(f, "{}", self.operation)?;
+        if let Some(receipt) = &self.receipt {
+            writeln!(f, "Receipt:")?;
+            write!(f, "{}", receipt)?;
+        }
         Ok(())
     }
 }
 
+/// Status change of an operation, notified to the subscribers of its status
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum OperationStatus {
+    /// the operation was included in a block
+    IncludedInBlock(BlockId),
+    /// the block including the operation became final
+    Final(BlockId),
+}
+
+impl std::fmt::Display for OperationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationStatus::IncludedInBlock(block_id) => {
+                writeln!(f, "Included in block: {}", block_id)
+            }
+            OperationStatus::Final(block_id) => writeln!(f, "Final in block: {}", block_id),
+        }
+    }
+}
+
 /// Current Parallel balance ledger info
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub struct LedgerInfo {
@@ -317,7 +359,7 @@ impl AddressInfo {
 }
 
 /// When an address is drawn to create an endorsement it is selected for a specific index
-#[derive(Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct IndexedSlot {
     /// slot
     pub slot: Slot,
@@ -331,6 +373,116 @@ impl std::fmt::Display for IndexedSlot {
     }
 }
 
+/// Every slot an address is selected for over a range of cycles
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SelectionSchedule {
+    /// the address
+    pub address: Address,
+    /// first cycle of the schedule
+    pub start_cycle: u64,
+    /// last cycle of the schedule (included), the last one whose draws are known
+    pub end_cycle: u64,
+    /// slots at which the address is selected to create a block, in increasing order
+    pub block_draws: Vec<Slot>,
+    /// slots at which the address is selected to create an endorsement, in increasing order
+    pub endorsement_draws: Vec<IndexedSlot>,
+}
+
+impl std::fmt::Display for SelectionSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Cycles: {} to {}", self.start_cycle, self.end_cycle)?;
+        writeln!(
+            f,
+            "Block draws: {}",
+            self.block_draws
+                .iter()
+                .fold("\n".to_string(), |acc, s| format!("{}    {}\n", acc, s))
+        )?;
+        writeln!(
+            f,
+            "Endorsement draws: {}",
+            self.endorsement_draws
+                .iter()
+                .fold("\n".to_string(), |acc, s| format!(
+                    "{}    Slot: {}, Index: {}\n",
+                    acc, s.slot, s.index
+                ))
+        )?;
+        Ok(())
+    }
+}
+
+/// Several headers created by the same staker for the same slot
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Equivocation {
+    /// slot of the headers
+    pub slot: Slot,
+    /// address of their creator
+    pub address: Address,
+    /// ids of the conflicting blocks
+    pub block_ids: Set<BlockId>,
+}
+
+impl std::fmt::Display for Equivocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Slot: {}, Blocks: {}",
+            self.slot,
+            self.block_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Misbehaviors of a staker recorded over the cycles kept by proof of stake
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StakerMisbehaviors {
+    /// the address
+    pub address: Address,
+    /// final slots at which the address was selected to create a block but did not, in increasing order
+    pub missed_blocks: Vec<Slot>,
+    /// endorsements the address was selected for that are missing from final blocks, in increasing order
+    pub missed_endorsements: Vec<IndexedSlot>,
+    /// equivocations detected by this node, in increasing slot order
+    pub equivocations: Vec<Equivocation>,
+}
+
+impl std::fmt::Display for StakerMisbehaviors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(
+            f,
+            "Missed blocks: {}",
+            self.missed_blocks
+                .iter()
+                .fold("\n".to_string(), |acc, s| format!("{}    {}\n", acc, s))
+        )?;
+        writeln!(
+            f,
+            "Missed endorsements: {}",
+            self.missed_endorsements
+                .iter()
+                .fold("\n".to_string(), |acc, s| format!(
+                    "{}    Slot: {}, Index: {}\n",
+                    acc, s.slot, s.index
+                ))
+        )?;
+        writeln!(
+            f,
+            "Equivocations: {}",
+            self.equivocations
+                .iter()
+                .fold("\n".to_string(), |acc, s| format!("{}    {}", acc, s))
+        )?;
+        Ok(())
+    }
+}
+
 /// Less information about an address
 #[derive(Debug, Serialize)]
 pub struct CompactAddressInfo {
@@ -445,7 +597,7 @@ impl std::fmt::Display for BlockInfo {
 }
 
 /// A block resume (without the block itself)
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct BlockSummary {
     /// id
     pub id: BlockId,
@@ -527,6 +679,434 @@ impl std::fmt::Display for DatastoreEntryOutput {
     }
 }
 
+/// Datastore keys page query input struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct DatastoreKeysInput {
+    /// address whose datastore keys are listed
+    pub address: Address,
+    /// optional prefix of the listed keys
+    pub prefix: Option<Vec<u8>>,
+    /// optional cursor: the keys are listed after it (excluded),
+    /// set it to the `next_cursor` of the previous page
+    pub cursor: Option<Vec<u8>>,
+    /// optional maximum number of keys in the page
+    pub limit: Option<usize>,
+}
+
+/// Datastore keys page query output struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct DatastoreKeysOutput {
+    /// candidate datastore keys of the page, in increasing order
+    pub keys: Vec<Vec<u8>>,
+    /// cursor of the next page, `None` if this page is the last one
+    pub next_cursor: Option<Vec<u8>>,
+}
+
+impl std::fmt::Display for DatastoreKeysOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in &self.keys {
+            writeln!(f, "{}", String::from_utf8_lossy(key))?;
+        }
+        if let Some(cursor) = &self.next_cursor {
+            writeln!(f, "next cursor: {}", String::from_utf8_lossy(cursor))?;
+        }
+        Ok(())
+    }
+}
+
+/// Kind of an operation, used to filter the history of an address
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum OperationKind {
+    /// transfer of coins
+    Transaction,
+    /// purchase of rolls
+    RollBuy,
+    /// sale of rolls
+    RollSell,
+    /// execution of a bytecode
+    ExecuteSC,
+    /// call of a smart contract function
+    CallSC,
+}
+
+impl OperationKind {
+    /// kind of an operation type
+    pub fn of(op: &OperationType) -> Self {
+        match op {
+            OperationType::Transaction { .. } => OperationKind::Transaction,
+            OperationType::RollBuy { .. } => OperationKind::RollBuy,
+            OperationType::RollSell { .. } => OperationKind::RollSell,
+            OperationType::ExecuteSC { .. } => OperationKind::ExecuteSC,
+            OperationType::CallSC { .. } => OperationKind::CallSC,
+        }
+    }
+}
+
+impl FromStr for OperationKind {
+    type Err = ModelsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "transaction" => Ok(OperationKind::Transaction),
+            "rollbuy" => Ok(OperationKind::RollBuy),
+            "rollsell" => Ok(OperationKind::RollSell),
+            "executesc" => Ok(OperationKind::ExecuteSC),
+            "callsc" => Ok(OperationKind::CallSC),
+            _ => Err(ModelsError::DeserializeError(format!(
+                "unknown operation kind {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Position of a final operation in the history of an address, used as a pagination cursor.
+/// Operations are ordered by slot, then by index in their block.
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AddressHistoryCursor {
+    /// slot of the block containing the operation
+    pub slot: Slot,
+    /// index of the operation in its block
+    pub index_in_block: u64,
+}
+
+impl FromStr for AddressHistoryCursor {
+    type Err = ModelsError;
+
+    /// parses the `period,thread,index_in_block` format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(',') {
+            Some((slot, index_in_block)) => Ok(AddressHistoryCursor {
+                slot: slot.parse()?,
+                index_in_block: index_in_block.parse().map_err(|_| {
+                    ModelsError::DeserializeError("invalid operation index".to_string())
+                })?,
+            }),
+            None => Err(ModelsError::DeserializeError(
+                "invalid address history cursor format".to_string(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AddressHistoryCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{}",
+            self.slot.period, self.slot.thread, self.index_in_block
+        )
+    }
+}
+
+/// Address history page query input struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AddressHistoryInput {
+    /// address whose final operations are listed
+    pub address: Address,
+    /// optional cursor: the operations are listed after it (excluded),
+    /// set it to the `next_cursor` of the previous page
+    pub cursor: Option<AddressHistoryCursor>,
+    /// optional maximum number of operations in the page
+    pub limit: Option<usize>,
+    /// optional kind of the listed operations
+    pub op_type: Option<OperationKind>,
+}
+
+/// Final operation involving an address
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AddressHistoryEntry {
+    /// slot of the block containing the operation
+    pub slot: Slot,
+    /// id of the block containing the operation
+    pub block_id: BlockId,
+    /// index of the operation in its block
+    pub index_in_block: u64,
+    /// the operation
+    pub operation: WrappedOperation,
+}
+
+impl std::fmt::Display for AddressHistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Operation {} at slot {} (block {}, index {})",
+            self.operation.id, self.slot, self.block_id, self.index_in_block
+        )?;
+        write!(f, "{}", self.operation.content.op)
+    }
+}
+
+/// Address history page query output struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AddressHistoryOutput {
+    /// final operations involving the address, oldest first
+    pub entries: Vec<AddressHistoryEntry>,
+    /// cursor of the next page, `None` if this page is the last one
+    pub next_cursor: Option<AddressHistoryCursor>,
+}
+
+impl std::fmt::Display for AddressHistoryOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        if let Some(cursor) = &self.next_cursor {
+            writeln!(f, "next cursor: {}", cursor)?;
+        }
+        Ok(())
+    }
+}
+
+/// Position of a block in the list of the blocks created by an address, used as a pagination cursor.
+/// Blocks are ordered by slot, then by id.
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CreatorBlocksCursor {
+    /// slot of the block
+    pub slot: Slot,
+    /// id of the block
+    pub block_id: BlockId,
+}
+
+impl FromStr for CreatorBlocksCursor {
+    type Err = ModelsError;
+
+    /// parses the `period,thread,block_id` format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(',') {
+            Some((slot, block_id)) => Ok(CreatorBlocksCursor {
+                slot: slot.parse()?,
+                block_id: block_id.parse()?,
+            }),
+            None => Err(ModelsError::DeserializeError(
+                "invalid creator blocks cursor format".to_string(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CreatorBlocksCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{}",
+            self.slot.period, self.slot.thread, self.block_id
+        )
+    }
+}
+
+/// Creator blocks page query input struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct CreatorBlocksInput {
+    /// address whose created blocks are listed
+    pub address: Address,
+    /// optional cursor: the blocks are listed after it (excluded),
+    /// set it to the `next_cursor` of the previous page
+    pub cursor: Option<CreatorBlocksCursor>,
+    /// optional maximum number of blocks in the page
+    pub limit: Option<usize>,
+}
+
+/// Block created by an address
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct CreatorBlockEntry {
+    /// slot of the block
+    pub slot: Slot,
+    /// id of the block
+    pub block_id: BlockId,
+    /// true if the block is final
+    pub is_final: bool,
+}
+
+/// Creator blocks page query output struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct CreatorBlocksOutput {
+    /// blocks created by the address, oldest first
+    pub blocks: Vec<CreatorBlockEntry>,
+    /// cursor of the next page, `None` if this page is the last one
+    pub next_cursor: Option<CreatorBlocksCursor>,
+}
+
+impl std::fmt::Display for CreatorBlocksOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for block in &self.blocks {
+            writeln!(
+                f,
+                "Block {} at slot {}{}",
+                block.block_id,
+                block.slot,
+                if block.is_final { " (final)" } else { "" }
+            )?;
+        }
+        if let Some(cursor) = &self.next_cursor {
+            writeln!(f, "next cursor: {}", cursor)?;
+        }
+        Ok(())
+    }
+}
+
+/// Version of the bytecode of an address
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct BytecodeVersionInfo {
+    /// version number, starting at 1
+    pub version: u64,
+    /// slot at which the bytecode was set
+    pub slot: Slot,
+    /// hash of the bytecode
+    pub hash: Hash,
+}
+
+/// Bytecode history query output struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct BytecodeHistoryOutput {
+    /// the address
+    pub address: Address,
+    /// final bytecode versions, oldest first
+    pub final_history: Vec<BytecodeVersionInfo>,
+    /// candidate bytecode versions, oldest first
+    pub candidate_history: Vec<BytecodeVersionInfo>,
+}
+
+impl std::fmt::Display for BytecodeHistoryOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Final versions:")?;
+        for v in &self.final_history {
+            writeln!(f, "\tversion {} at slot {}: {}", v.version, v.slot, v.hash)?;
+        }
+        writeln!(f, "Candidate versions:")?;
+        for v in &self.candidate_history {
+            writeln!(f, "\tversion {} at slot {}: {}", v.version, v.slot, v.hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operation receipt query output struct
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct OperationReceiptOutput {
+    /// the operation
+    pub operation_id: OperationId,
+    /// receipt of the execution of the operation in a final slot
+    pub final_receipt: Option<OperationReceipt>,
+    /// receipt of the latest execution of the operation, final or not
+    pub candidate_receipt: Option<OperationReceipt>,
+}
+
+impl std::fmt::Display for OperationReceiptOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation {}", self.operation_id)?;
+        match &self.final_receipt {
+            Some(receipt) => write!(f, "Final receipt:\n{}", receipt)?,
+            None => writeln!(f, "Final receipt: none")?,
+        }
+        match &self.candidate_receipt {
+            Some(receipt) => write!(f, "Candidate receipt:\n{}", receipt)?,
+            None => writeln!(f, "Candidate receipt: none")?,
+        }
+        Ok(())
+    }
+}
+
+/// Final ledger information of an address at the output of a past final slot
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AddressInfoAtSlot {
+    /// the address
+    pub address: Address,
+    /// the queried final slot
+    pub slot: Slot,
+    /// final balance at that slot, `None` if the address had no ledger entry
+    pub final_balance: Option<Amount>,
+}
+
+impl std::fmt::Display for AddressInfoAtSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address {} at slot {}", self.address, self.slot)?;
+        writeln!(f, "\tfinal balance: {:?}", self.final_balance)?;
+        Ok(())
+    }
+}
+
+/// Datastore entry value at the output of a past final slot
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct DatastoreEntryAtSlotOutput {
+    /// the queried final slot
+    pub slot: Slot,
+    /// final datastore entry value at that slot
+    pub final_value: Option<Vec<u8>>,
+}
+
+impl std::fmt::Display for DatastoreEntryAtSlotOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "final value at slot {}: {:?}",
+            self.slot, self.final_value
+        )?;
+        Ok(())
+    }
+}
+
+/// filter used when retrieving pending asynchronous messages
+#[derive(Default, Debug, Deserialize, Clone, Serialize)]
+pub struct AsyncMessageFilter {
+    /// optional sender address
+    pub sender: Option<Address>,
+    /// optional destination address
+    pub destination: Option<Address>,
+    /// optional start emission slot (included)
+    pub start: Option<Slot>,
+    /// optional end emission slot (excluded)
+    pub end: Option<Slot>,
+}
+
+/// pending asynchronous message, as seen in the candidate asynchronous pool
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AsyncMessageInfo {
+    /// slot at which the message was emitted
+    pub emission_slot: Slot,
+    /// index of the message among the messages emitted at that slot
+    pub emission_index: u64,
+    /// sender of the message
+    pub sender: Address,
+    /// destination of the message
+    pub destination: Address,
+    /// handler function of the destination
+    pub handler: String,
+    /// maximum gas of the message
+    pub max_gas: u64,
+    /// gas price of the message
+    pub gas_price: Amount,
+    /// coins sent with the message
+    pub coins: Amount,
+    /// slot from which the message is valid (included)
+    pub validity_start: Slot,
+    /// slot until which the message is valid (excluded)
+    pub validity_end: Slot,
+    /// raw payload data of the message
+    pub data: Vec<u8>,
+}
+
+impl std::fmt::Display for AsyncMessageInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Message ({}, {}): {} -> {}::{}",
+            self.emission_slot, self.emission_index, self.sender, self.destination, self.handler
+        )?;
+        writeln!(
+            f,
+            "	Validity: from {} to {}",
+            self.validity_start, self.validity_end
+        )?;
+        writeln!(
+            f,
+            "	Max gas: {}, gas price: {}, coins: {}",
+            self.max_gas, self.gas_price, self.coins
+        )
+    }
+}
+
 /// filter used when retrieving SC output events
 #[derive(Default, Debug, Deserialize, Clone, Serialize)]
 pub struct EventFilter {
@@ -540,6 +1120,125 @@ pub struct EventFilter {
     pub original_caller_address: Option<Address>,
     /// optional operation id
     pub original_operation_id: Option<OperationId>,
+    /// optional finality status: only final events if true, only candidate events if false
+    pub is_final: Option<bool>,
+    /// optional substring that the event data must contain
+    pub data_substring: Option<String>,
+    /// optional prefix that the event data must start with
+    pub data_prefix: Option<String>,
+    /// optional pagination cursor: only events strictly after it are returned.
+    /// To get the next page, set it to the position of the last event of the previous page.
+    pub cursor: Option<EventCursor>,
+    /// optional maximum number of returned events
+    pub limit: Option<usize>,
+}
+
+/// Position of an SC output event, used as a pagination cursor.
+/// Events are ordered by slot, then by index in the slot.
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventCursor {
+    /// slot of the event
+    pub slot: Slot,
+    /// index of the event in the slot
+    pub index_in_slot: u64,
+}
+
+impl EventCursor {
+    /// position of an event
+    pub fn of_event(event: &SCOutputEvent) -> Self {
+        EventCursor {
+            slot: event.context.slot,
+            index_in_slot: event.context.index_in_slot,
+        }
+    }
+}
+
+impl FromStr for EventCursor {
+    type Err = ModelsError;
+
+    /// parses the `period,thread,index_in_slot` format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(',') {
+            Some((slot, index_in_slot)) => Ok(EventCursor {
+                slot: slot.parse()?,
+                index_in_slot: index_in_slot.parse().map_err(|_| {
+                    ModelsError::DeserializeError("invalid event index".to_string())
+                })?,
+            }),
+            None => Err(ModelsError::DeserializeError(
+                "invalid event cursor format".to_string(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for EventCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{}",
+            self.slot.period, self.slot.thread, self.index_in_slot
+        )
+    }
+}
+
+impl EventFilter {
+    /// Returns true if the event matches every criterion of the filter.
+    /// The limit is not a matching criterion: it is applied by the caller.
+    pub fn matches(&self, event: &SCOutputEvent) -> bool {
+        if let Some(start) = self.start {
+            if event.context.slot < start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end {
+            if event.context.slot >= end {
+                return false;
+            }
+        }
+        match (self.emitter_address, event.context.call_stack.front()) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_caller_address,
+            event.context.call_stack.back(),
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_operation_id,
+            event.context.origin_operation_id,
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        if let Some(is_final) = self.is_final {
+            if event.context.is_final != is_final {
+                return false;
+            }
+        }
+        if let Some(substring) = &self.data_substring {
+            if !event.data.contains(substring.as_str()) {
+                return false;
+            }
+        }
+        if let Some(prefix) = &self.data_prefix {
+            if !event.data.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(cursor) = self.cursor {
+            if EventCursor::of_event(event) <= cursor {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 /// read only bytecode execution request
@@ -553,6 +1252,19 @@ pub struct ReadOnlyBytecodeExecution {
     pub bytecode: Vec<u8>,
     /// caller's address, optional
     pub address: Option<Address>,
+    /// whether to return the trace of the execution
+    #[serde(default)]
+    pub trace: bool,
+}
+
+/// gas estimation request: the operation is executed in read-only mode on behalf of its sender,
+/// with its coins, and its `max_gas` (capped to the maximum gas of a block) as gas limit
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct GasEstimationRequest {
+    /// address of the operation sender
+    pub caller_address: Address,
+    /// operation to estimate, must be an `ExecuteSC` or a `CallSC`
+    pub operation: OperationType,
 }
 
 /// read SC call request
@@ -570,4 +1282,7 @@ pub struct ReadOnlyCall {
     pub parameter: String,
     /// caller's address, optional
     pub caller_address: Option<Address>,
+    /// whether to return the trace of the execution
+    #[serde(default)]
+    pub trace: bool,
 }
@@ -1,6 +1,19 @@
-use std::{collections::VecDeque, fmt::Display};
+use std::{collections::VecDeque, fmt::Display, ops::Bound::Included};
 
-use crate::{output_event::SCOutputEvent, Slot};
+use crate::{
+    constants::THREAD_COUNT, output_event::SCOutputEvent, Address, Amount, AmountDeserializer,
+    AmountSerializer, OperationId, Slot, SlotDeserializer, SlotSerializer, StringDeserializer,
+    StringSerializer,
+};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
+    U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    sequence::tuple,
+    IResult, Parser,
+};
 use serde::{Deserialize, Serialize};
 
 /// The result of the read-only execution.
@@ -20,8 +33,13 @@ pub struct ExecuteReadOnlyResponse {
     pub executed_at: Slot,
     /// The result of the read-only execution.
     pub result: ReadOnlyResult,
+    /// The gas consumed by the read-only execution.
+    pub gas_cost: u64,
     /// The output events generated by the read-only execution.
     pub output_events: VecDeque<SCOutputEvent>,
+    /// The trace of the read-only execution, if it was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<ExecutionTraceStep>>,
 }
 
 impl Display for ExecuteReadOnlyResponse {
@@ -36,12 +54,620 @@ impl Display for ExecuteReadOnlyResponse {
                 ReadOnlyResult::Ok => "ok".to_string(),
             }
         )?;
+        writeln!(f, "Gas cost: {}", self.gas_cost)?;
         if !self.output_events.is_empty() {
             writeln!(f, "Generated events:",)?;
             for event in self.output_events.iter() {
                 writeln!(f, "{}", event)?; // id already displayed in event
             }
         }
+        if let Some(trace) = &self.trace {
+            writeln!(f, "Trace:")?;
+            for step in trace.iter() {
+                writeln!(f, "{}", step)?;
+            }
+        }
         Ok(())
     }
 }
+
+/// A step of the trace of a read-only execution, recorded in execution order
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum ExecutionTraceStep {
+    /// The bytecode called an ABI function.
+    AbiCall {
+        /// The name of the ABI function.
+        function: String,
+        /// The arguments of the call, in their debug representation.
+        arguments: Vec<String>,
+        /// The returned value in its debug representation, `None` if the call failed.
+        result: Option<String>,
+        /// The error returned by the call, if any.
+        error: Option<String>,
+        /// The gas remaining after the call, `None` if the runtime does not report it.
+        remaining_gas: Option<u64>,
+    },
+    /// A datastore entry was read.
+    DatastoreRead {
+        /// The address owning the datastore.
+        address: Address,
+        /// The datastore key.
+        key: Vec<u8>,
+        /// The value read, truncated to its first bytes, `None` if the entry does not exist.
+        value: Option<Vec<u8>>,
+        /// The size in bytes of the whole value read.
+        value_size: usize,
+    },
+    /// A datastore entry was written.
+    DatastoreWrite {
+        /// The address owning the datastore.
+        address: Address,
+        /// The datastore key.
+        key: Vec<u8>,
+        /// The new value, truncated to its first bytes, `None` if the entry was deleted.
+        value: Option<Vec<u8>>,
+        /// The size in bytes of the whole new value.
+        value_size: usize,
+    },
+    /// Parallel coins were transferred.
+    Transfer {
+        /// The spending address, `None` for coin creation.
+        from: Option<Address>,
+        /// The credited address, `None` for coin destruction.
+        to: Option<Address>,
+        /// The transferred amount.
+        amount: Amount,
+    },
+    /// A call to another address was pushed on the call stack.
+    CallEnter {
+        /// The called address.
+        address: Address,
+        /// The coins sent with the call.
+        coins: Amount,
+    },
+    /// A call was popped from the call stack.
+    CallExit {
+        /// The address of the call that returned.
+        address: Address,
+        /// The gas used by the call, if known: only synchronous calls run on their own gas meter.
+        gas_used: Option<u64>,
+    },
+    /// The trace reached its maximal length: the following steps were not recorded.
+    Truncated,
+}
+
+impl Display for ExecutionTraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionTraceStep::AbiCall {
+                function,
+                arguments,
+                result,
+                error,
+                remaining_gas,
+            } => {
+                match error {
+                    Some(err) => write!(
+                        f,
+                        "\t{}({}) failed: {}",
+                        function,
+                        arguments.join(", "),
+                        err
+                    )?,
+                    None => write!(
+                        f,
+                        "\t{}({}) -> {}",
+                        function,
+                        arguments.join(", "),
+                        result.as_deref().unwrap_or("()")
+                    )?,
+                }
+                if let Some(remaining_gas) = remaining_gas {
+                    write!(f, " ({} gas remaining)", remaining_gas)?;
+                }
+                Ok(())
+            }
+            ExecutionTraceStep::DatastoreRead {
+                address,
+                key,
+                value,
+                value_size,
+            } => write!(
+                f,
+                "\tread {}[{:?}]: {:?} ({} bytes)",
+                address, key, value, value_size
+            ),
+            ExecutionTraceStep::DatastoreWrite {
+                address,
+                key,
+                value,
+                value_size,
+            } => match value {
+                Some(value) => write!(
+                    f,
+                    "\twrite {}[{:?}] = {:?} ({} bytes)",
+                    address, key, value, value_size
+                ),
+                None => write!(f, "\tdelete {}[{:?}]", address, key),
+            },
+            ExecutionTraceStep::Transfer { from, to, amount } => {
+                let display = |addr: &Option<Address>| {
+                    addr.map_or_else(|| "none".to_string(), |addr| addr.to_string())
+                };
+                write!(
+                    f,
+                    "\ttransfer {} coins from {} to {}",
+                    amount,
+                    display(from),
+                    display(to)
+                )
+            }
+            ExecutionTraceStep::CallEnter { address, coins } => {
+                write!(f, "\tenter call to {} with {} coins", address, coins)
+            }
+            ExecutionTraceStep::CallExit { address, gas_used } => match gas_used {
+                Some(gas_used) => write!(f, "\texit call to {} ({} gas used)", address, gas_used),
+                None => write!(f, "\texit call to {}", address),
+            },
+            ExecutionTraceStep::Truncated => write!(f, "\t(trace truncated)"),
+        }
+    }
+}
+
+/// Changes to the ledger entry of an address caused by an execution
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AddressLedgerChanges {
+    /// The modified address.
+    pub address: Address,
+    /// Whether the ledger entry is deleted.
+    pub deleted: bool,
+    /// The new candidate sequential balance, if modified.
+    /// Only reported by operation simulations, since the sequential ledger is handled by consensus.
+    #[serde(default)]
+    pub sequential_balance: Option<Amount>,
+    /// The new candidate roll count, if modified. Only reported by operation simulations.
+    #[serde(default)]
+    pub rolls: Option<u64>,
+    /// The new parallel balance, if modified.
+    pub parallel_balance: Option<Amount>,
+    /// The new bytecode, if modified.
+    pub bytecode: Option<Vec<u8>>,
+    /// The modified datastore entries with their new value, `None` for deleted entries.
+    pub datastore: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl Display for AddressLedgerChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.deleted {
+            return writeln!(f, "\t{}: entry deleted", self.address);
+        }
+        writeln!(f, "\t{}:", self.address)?;
+        if let Some(balance) = self.sequential_balance {
+            writeln!(f, "\t\tsequential balance: {}", balance)?;
+        }
+        if let Some(rolls) = self.rolls {
+            writeln!(f, "\t\trolls: {}", rolls)?;
+        }
+        if let Some(balance) = self.parallel_balance {
+            writeln!(f, "\t\tparallel balance: {}", balance)?;
+        }
+        if let Some(bytecode) = &self.bytecode {
+            writeln!(f, "\t\tbytecode: {} bytes", bytecode.len())?;
+        }
+        for (key, value) in &self.datastore {
+            writeln!(f, "\t\tdatastore {:?}: {:?}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The gas estimation of an operation, obtained by executing it in read-only mode.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GasEstimation {
+    /// The slot at which the estimation was executed.
+    pub executed_at: Slot,
+    /// The result of the execution.
+    pub result: ReadOnlyResult,
+    /// The gas the operation would be charged: the gas consumed by the execution plus the base cost of an operation.
+    pub gas_cost: u64,
+    /// The ledger changes the operation would produce.
+    pub ledger_changes: Vec<AddressLedgerChanges>,
+    /// The output events generated by the execution.
+    pub output_events: VecDeque<SCOutputEvent>,
+}
+
+impl Display for GasEstimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Executed at slot: {}", self.executed_at)?;
+        match &self.result {
+            ReadOnlyResult::Error(e) => {
+                writeln!(f, "Result: an error occurred during the execution: {}", e)?
+            }
+            ReadOnlyResult::Ok => writeln!(f, "Result: ok")?,
+        }
+        writeln!(f, "Gas cost: {}", self.gas_cost)?;
+        if !self.ledger_changes.is_empty() {
+            writeln!(f, "Ledger changes:")?;
+            for changes in self.ledger_changes.iter() {
+                write!(f, "{}", changes)?;
+            }
+        }
+        if !self.output_events.is_empty() {
+            writeln!(f, "Generated events:")?;
+            for event in self.output_events.iter() {
+                writeln!(f, "{}", event)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Change to the asynchronous message pool caused by an execution
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum AsyncMessageChange {
+    /// A message is emitted.
+    Added {
+        /// The slot at which the message is emitted.
+        emission_slot: Slot,
+        /// The index of the message among the messages emitted at that slot.
+        emission_index: u64,
+        /// The sender of the message.
+        sender: Address,
+        /// The destination of the message.
+        destination: Address,
+        /// The handler function of the destination.
+        handler: String,
+        /// The maximum gas of the message.
+        max_gas: u64,
+        /// The gas price of the message.
+        gas_price: Amount,
+        /// The coins sent with the message.
+        coins: Amount,
+        /// The slot from which the message is valid (included).
+        validity_start: Slot,
+        /// The slot until which the message is valid (excluded).
+        validity_end: Slot,
+    },
+    /// A message is removed from the pool.
+    Removed {
+        /// The slot at which the message was emitted.
+        emission_slot: Slot,
+        /// The index of the message among the messages emitted at that slot.
+        emission_index: u64,
+    },
+}
+
+impl Display for AsyncMessageChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsyncMessageChange::Added {
+                emission_slot,
+                emission_index,
+                sender,
+                destination,
+                handler,
+                max_gas,
+                coins,
+                ..
+            } => writeln!(
+                f,
+                "\tmessage ({}, {}) added: {} -> {}::{} with {} coins and {} max gas",
+                emission_slot, emission_index, sender, destination, handler, coins, max_gas
+            ),
+            AsyncMessageChange::Removed {
+                emission_slot,
+                emission_index,
+            } => writeln!(
+                f,
+                "\tmessage ({}, {}) removed",
+                emission_slot, emission_index
+            ),
+        }
+    }
+}
+
+/// The simulated execution of an operation against the active state.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OperationSimulation {
+    /// The ID of the simulated operation.
+    pub operation_id: OperationId,
+    /// The slot at which the simulation was executed.
+    pub executed_at: Slot,
+    /// The result of the simulation.
+    pub result: ReadOnlyResult,
+    /// The gas consumed by the operation.
+    pub gas_cost: u64,
+    /// The ledger changes the operation would produce.
+    pub ledger_changes: Vec<AddressLedgerChanges>,
+    /// The asynchronous pool changes the operation would produce.
+    pub async_pool_changes: Vec<AsyncMessageChange>,
+    /// The output events generated by the operation.
+    pub output_events: VecDeque<SCOutputEvent>,
+}
+
+impl Display for OperationSimulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation {}", self.operation_id)?;
+        writeln!(f, "Executed at slot: {}", self.executed_at)?;
+        match &self.result {
+            ReadOnlyResult::Error(e) => writeln!(f, "Result: the operation would fail: {}", e)?,
+            ReadOnlyResult::Ok => writeln!(f, "Result: ok")?,
+        }
+        writeln!(f, "Gas cost: {}", self.gas_cost)?;
+        if !self.ledger_changes.is_empty() {
+            writeln!(f, "Ledger changes:")?;
+            for changes in self.ledger_changes.iter() {
+                write!(f, "{}", changes)?;
+            }
+        }
+        if !self.async_pool_changes.is_empty() {
+            writeln!(f, "Asynchronous pool changes:")?;
+            for change in self.async_pool_changes.iter() {
+                write!(f, "{}", change)?;
+            }
+        }
+        if !self.output_events.is_empty() {
+            writeln!(f, "Generated events:")?;
+            for event in self.output_events.iter() {
+                writeln!(f, "{}", event)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of the execution of an `ExecuteSC` or `CallSC` operation included in a block
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OperationReceipt {
+    /// The slot at which the operation was executed.
+    pub slot: Slot,
+    /// Whether the execution succeeded. A failed execution is reverted.
+    pub success: bool,
+    /// The error that caused the execution to fail, if any.
+    pub error: Option<String>,
+    /// The gas used by the execution, which is given the `max_gas` of the operation minus the base cost.
+    /// A failed execution uses all the gas it was given.
+    pub gas_used: u64,
+    /// The gas charged to the sender: the gas used plus the base cost of an operation,
+    /// the whole `max_gas` for a failed execution.
+    pub gas_charged: u64,
+    /// The coins refunded to the sender for the gas that was paid but not charged.
+    pub refunded_coins: Amount,
+    /// The number of events emitted by the execution, including the error event of a failed one.
+    pub event_count: u64,
+    /// The coins charged for the ledger storage added by the execution.
+    #[serde(default)]
+    pub storage_cost_charged: Amount,
+    /// The coins refunded for the ledger storage freed by the execution.
+    #[serde(default)]
+    pub storage_cost_refunded: Amount,
+}
+
+impl Display for OperationReceipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Executed at slot: {}", self.slot)?;
+        match &self.error {
+            Some(err) if !self.success => writeln!(f, "Result: failed: {}", err)?,
+            _ => writeln!(f, "Result: ok")?,
+        }
+        writeln!(f, "Gas used: {}", self.gas_used)?;
+        writeln!(f, "Gas charged: {}", self.gas_charged)?;
+        writeln!(f, "Refunded coins: {}", self.refunded_coins)?;
+        writeln!(f, "Storage cost charged: {}", self.storage_cost_charged)?;
+        writeln!(f, "Storage cost refunded: {}", self.storage_cost_refunded)?;
+        writeln!(f, "Events emitted: {}", self.event_count)
+    }
+}
+
+/// Basic `OperationReceipt` serializer, used to store the receipts in the final state
+pub struct OperationReceiptSerializer {
+    slot_serializer: SlotSerializer,
+    u64_serializer: U64VarIntSerializer,
+    amount_serializer: AmountSerializer,
+    error_serializer: StringSerializer<U32VarIntSerializer, u32>,
+}
+
+impl OperationReceiptSerializer {
+    /// Creates a new `OperationReceiptSerializer`
+    pub fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+            amount_serializer: AmountSerializer::new(),
+            error_serializer: StringSerializer::new(U32VarIntSerializer::new()),
+        }
+    }
+}
+
+impl Default for OperationReceiptSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<OperationReceipt> for OperationReceiptSerializer {
+    /// ```
+    /// use massa_serialization::Serializer;
+    /// use massa_models::{Amount, Slot, execution::{OperationReceipt, OperationReceiptSerializer}};
+    ///
+    /// let receipt = OperationReceipt {
+    ///     slot: Slot::new(1, 2),
+    ///     success: false,
+    ///     error: Some("out of gas".to_string()),
+    ///     gas_used: 1000,
+    ///     gas_charged: 11000,
+    ///     refunded_coins: Amount::from_raw(10),
+    ///     event_count: 1,
+    ///     storage_cost_charged: Amount::zero(),
+    ///     storage_cost_refunded: Amount::zero(),
+    /// };
+    /// let mut serialized = Vec::new();
+    /// OperationReceiptSerializer::new().serialize(&receipt, &mut serialized).unwrap();
+    /// ```
+    fn serialize(
+        &self,
+        value: &OperationReceipt,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(&value.slot, buffer)?;
+        buffer.push(u8::from(value.success));
+        match &value.error {
+            Some(error) => {
+                buffer.push(1);
+                self.error_serializer.serialize(error, buffer)?;
+            }
+            None => buffer.push(0),
+        }
+        self.u64_serializer.serialize(&value.gas_used, buffer)?;
+        self.u64_serializer.serialize(&value.gas_charged, buffer)?;
+        self.amount_serializer
+            .serialize(&value.refunded_coins, buffer)?;
+        self.u64_serializer.serialize(&value.event_count, buffer)?;
+        self.amount_serializer
+            .serialize(&value.storage_cost_charged, buffer)?;
+        self.amount_serializer
+            .serialize(&value.storage_cost_refunded, buffer)?;
+        Ok(())
+    }
+}
+
+/// Basic `OperationReceipt` deserializer
+pub struct OperationReceiptDeserializer {
+    slot_deserializer: SlotDeserializer,
+    u64_deserializer: U64VarIntDeserializer,
+    amount_deserializer: AmountDeserializer,
+    error_deserializer: StringDeserializer<U32VarIntDeserializer, u32>,
+}
+
+impl OperationReceiptDeserializer {
+    /// Creates a new `OperationReceiptDeserializer`
+    pub const fn new() -> Self {
+        Self {
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Included(THREAD_COUNT)),
+            ),
+            u64_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            amount_deserializer: AmountDeserializer::new(Included(0), Included(u64::MAX)),
+            error_deserializer: StringDeserializer::new(U32VarIntDeserializer::new(
+                Included(0),
+                Included(u32::MAX),
+            )),
+        }
+    }
+}
+
+impl Default for OperationReceiptDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deserializes a boolean flag byte
+fn deserialize_flag<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], bool, E> {
+    match input.first() {
+        Some(0) => Ok((&input[1..], false)),
+        Some(1) => Ok((&input[1..], true)),
+        Some(_) => Err(nom::Err::Error(ParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::Digit,
+        ))),
+        None => Err(nom::Err::Error(ParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::LengthValue,
+        ))),
+    }
+}
+
+impl Deserializer<OperationReceipt> for OperationReceiptDeserializer {
+    /// ```
+    /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
+    /// use massa_models::{Amount, Slot, execution::{OperationReceipt, OperationReceiptSerializer, OperationReceiptDeserializer}};
+    ///
+    /// let receipt = OperationReceipt {
+    ///     slot: Slot::new(1, 2),
+    ///     success: false,
+    ///     error: Some("out of gas".to_string()),
+    ///     gas_used: 1000,
+    ///     gas_charged: 11000,
+    ///     refunded_coins: Amount::from_raw(10),
+    ///     event_count: 1,
+    ///     storage_cost_charged: Amount::zero(),
+    ///     storage_cost_refunded: Amount::from_raw(3),
+    /// };
+    /// let mut serialized = Vec::new();
+    /// OperationReceiptSerializer::new().serialize(&receipt, &mut serialized).unwrap();
+    /// let (rest, receipt_deser) = OperationReceiptDeserializer::new().deserialize::<DeserializeError>(&serialized).unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(receipt_deser, receipt);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], OperationReceipt, E> {
+        context(
+            "Failed OperationReceipt deserialization",
+            tuple((
+                context("Failed slot deserialization", |input| {
+                    self.slot_deserializer.deserialize(input)
+                }),
+                context("Failed success deserialization", deserialize_flag),
+                context("Failed error deserialization", |input| {
+                    let (rest, has_error) = deserialize_flag(input)?;
+                    if has_error {
+                        self.error_deserializer
+                            .deserialize(rest)
+                            .map(|(rest, error)| (rest, Some(error)))
+                    } else {
+                        Ok((rest, None))
+                    }
+                }),
+                context("Failed gas_used deserialization", |input| {
+                    self.u64_deserializer.deserialize(input)
+                }),
+                context("Failed gas_charged deserialization", |input| {
+                    self.u64_deserializer.deserialize(input)
+                }),
+                context("Failed refunded_coins deserialization", |input| {
+                    self.amount_deserializer.deserialize(input)
+                }),
+                context("Failed event_count deserialization", |input| {
+                    self.u64_deserializer.deserialize(input)
+                }),
+                context("Failed storage_cost_charged deserialization", |input| {
+                    self.amount_deserializer.deserialize(input)
+                }),
+                context("Failed storage_cost_refunded deserialization", |input| {
+                    self.amount_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(
+            |(
+                slot,
+                success,
+                error,
+                gas_used,
+                gas_charged,
+                refunded_coins,
+                event_count,
+                storage_cost_charged,
+                storage_cost_refunded,
+            )| OperationReceipt {
+                slot,
+                success,
+                error,
+                gas_used,
+                gas_charged,
+                refunded_coins,
+                event_count,
+                storage_cost_charged,
+                storage_cost_refunded,
+            },
+        )
+        .parse(buffer)
+    }
+}
@@ -64,7 +64,7 @@ lazy_static::lazy_static! {
         if cfg!(feature = "sandbox") {
             "SAND.0.0"
         } else {
-            "TEST.13.0"
+            "TEST.14.0"
         }
         .parse()
         .unwrap()
@@ -99,12 +99,22 @@ pub const PERIODS_PER_CYCLE: u64 = 128;
 pub const POS_LOOKBACK_CYCLES: u64 = 2;
 /// PoS lock cycles: when some rolls are released, we only credit the coins back to their owner after waiting `pos_lock_cycles`
 pub const POS_LOCK_CYCLES: u64 = 1;
+/// number of endorsements missed in a cycle (strictly) above which stakers are deactivated, `None` to disable
+pub const POS_MISSED_ENDORSEMENTS_DEACTIVATION_THRESHOLD: Option<u64> = None;
 /// Maximum size batch of data in a part of the ledger
 pub const LEDGER_PART_SIZE_MESSAGE_BYTES: u64 = 1000000;
 /// Maximum async messages in a batch of the bootstrap of the async pool
 pub const ASYNC_POOL_PART_SIZE_MESSAGE_BYTES: u64 = 1000000;
+/// Maximum size batch of data in a part of the bootstrap of the final operation receipts
+pub const RECEIPTS_PART_SIZE_MESSAGE_BYTES: u64 = 1000000;
+/// Number of periods during which the receipts of the executed operations are kept in the final state
+pub const FINAL_RECEIPTS_RETENTION_PERIODS: u64 = 1000;
 /// Maximum length of a datastore key
 pub const MAX_DATASTORE_KEY_LENGTH: u8 = 255;
+/// Cost of each byte of bytecode or datastore stored in the ledger
+pub const LEDGER_COST_PER_BYTE: Amount = Amount::from_mantissa_scale(1, 4);
+/// Period from which the ledger storage cost is charged (from genesis)
+pub const LEDGER_COST_ACTIVATION_PERIOD: Option<u64> = Some(0);
 
 // ***********************
 // Bootstrap constants
@@ -124,6 +134,8 @@ pub const MAX_BOOTSTRAP_CHILDREN: u32 = 1000;
 pub const MAX_BOOTSTRAP_POS_CYCLES: u32 = 5;
 /// Max number of address and random entries for PoS bootstrap
 pub const MAX_BOOTSTRAP_POS_ENTRIES: u32 = 1000000000;
+/// Max number of final state parts sent to catch up with new final slots once the state is fully streamed
+pub const MAX_BOOTSTRAP_FINAL_STATE_CATCH_UP_PARTS: u32 = 100;
 /// Max size of the IP list
 pub const IP_LIST_MAX_SIZE: usize = 10000;
 /// Size of the random bytes array used for the bootstrap, safe to import
@@ -137,6 +149,8 @@ pub const BOOTSTRAP_RANDOMNESS_SIZE_BYTES: usize = 32;
 pub const MAX_GAS_PER_BLOCK: u64 = 1_000_000_000;
 /// Maximum of GAS allowed for asynchronous messages execution on one slot
 pub const MAX_ASYNC_GAS: u64 = 1_000_000_000;
+/// GAS charged to every executed smart contract operation on top of the GAS it consumes
+pub const OPERATION_BASE_GAS_COST: u64 = 10_000;
 
 //
 // Constants used in network
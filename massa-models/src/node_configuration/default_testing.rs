@@ -89,10 +89,18 @@ pub const MAX_ASK_BLOCKS_PER_MESSAGE: u32 = 3;
 pub const MAX_BLOCK_SIZE: u32 = 3145728;
 /// max asynchronous pool length
 pub const MAX_ASYNC_POOL_LENGTH: u64 = 10_000;
+/// cost of each byte stored in the ledger
+pub const LEDGER_COST_PER_BYTE: Amount = Amount::from_mantissa_scale(1, 4);
+/// period from which the ledger storage cost is charged
+pub const LEDGER_COST_ACTIVATION_PERIOD: Option<u64> = Some(0);
 /// Maximum size batch of data in a part of the ledger
 pub const LEDGER_PART_SIZE_MESSAGE_BYTES: u64 = 30;
 /// Maximum async messages in a batch of the bootstrap of the async pool
 pub const ASYNC_POOL_PART_SIZE_MESSAGE_BYTES: u64 = 30;
+/// Maximum size batch of data in a part of the bootstrap of the final operation receipts
+pub const RECEIPTS_PART_SIZE_MESSAGE_BYTES: u64 = 30;
+/// number of periods during which the receipts of the executed operations are kept in the final state
+pub const FINAL_RECEIPTS_RETENTION_PERIODS: u64 = 10;
 /// max bootstrapped blocks
 pub const MAX_BOOTSTRAP_BLOCKS: u32 = 100;
 /// max bootstrapped children per block
@@ -111,10 +119,14 @@ pub const MAX_BOOTSTRAP_MESSAGE_SIZE: u32 = 100_000_000;
 pub const MAX_BOOTSTRAP_POS_ENTRIES: u32 = 1000;
 /// max bootstrapped proof of take cycles
 pub const MAX_BOOTSTRAP_POS_CYCLES: u32 = 5;
+/// max final state parts sent to catch up with new final slots
+pub const MAX_BOOTSTRAP_FINAL_STATE_CATCH_UP_PARTS: u32 = 10;
 /// max gas per block
 pub const MAX_GAS_PER_BLOCK: u64 = 100_000_000;
 /// max asynchronous gas
 pub const MAX_ASYNC_GAS: u64 = 10_000_000;
+/// base gas cost of smart contract operations
+pub const OPERATION_BASE_GAS_COST: u64 = 10_000;
 /// max message size 3 * 1024 * 1024
 pub const MAX_MESSAGE_SIZE: u32 = 3145728;
 /// max number of operation per block
@@ -133,6 +145,8 @@ pub const OPERATION_VALIDITY_PERIODS: u64 = 1;
 pub const PERIODS_PER_CYCLE: u64 = 100;
 /// proof of stake lock cycles
 pub const POS_LOCK_CYCLES: u64 = 1;
+/// number of endorsements missed in a cycle (strictly) above which stakers are deactivated
+pub const POS_MISSED_ENDORSEMENTS_DEACTIVATION_THRESHOLD: Option<u64> = None;
 /// proof of stake look back cycle
 pub const POS_LOOKBACK_CYCLES: u64 = 2;
 /// roll price
@@ -833,6 +833,51 @@ impl Deserializer<OperationIds> for OperationIdsDeserializer {
     }
 }
 
+/// Serializer for `OperationId`
+#[derive(Default)]
+pub struct OperationIdSerializer;
+
+impl OperationIdSerializer {
+    /// Creates a new serializer for `OperationId`
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Serializer<OperationId> for OperationIdSerializer {
+    fn serialize(&self, value: &OperationId, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        buffer.extend(value.to_bytes());
+        Ok(())
+    }
+}
+
+/// Deserializer for `OperationId`
+#[derive(Default)]
+pub struct OperationIdDeserializer {
+    hash_deserializer: HashDeserializer,
+}
+
+impl OperationIdDeserializer {
+    /// Creates a new deserializer for `OperationId`
+    pub const fn new() -> Self {
+        Self {
+            hash_deserializer: HashDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<OperationId> for OperationIdDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], OperationId, E> {
+        context("Failed OperationId deserialization", |input| {
+            let (rest, hash) = self.hash_deserializer.deserialize(input)?;
+            Ok((rest, OperationId(hash)))
+        })(buffer)
+    }
+}
+
 /// Deserializer for [OperationPrefixId]
 #[derive(Default)]
 pub struct OperationPrefixIdDeserializer;
@@ -27,6 +27,10 @@ pub struct EventExecutionContext {
     pub block: Option<BlockId>,
     /// if the event was generated during a read only execution
     pub read_only: bool,
+    /// if the event was generated during the execution of a final slot.
+    /// Defaults to `false` when missing, for events serialized before the field existed.
+    #[serde(default)]
+    pub is_final: bool,
     /// index of the event in the slot
     pub index_in_slot: u64,
     /// most recent at the end
@@ -48,6 +52,15 @@ impl Display for EventExecutionContext {
                 "On chain execution"
             }
         )?;
+        writeln!(
+            f,
+            "{}",
+            if self.is_final {
+                "Final event"
+            } else {
+                "Candidate event"
+            }
+        )?;
         if let Some(id) = self.block {
             writeln!(f, "Block id: {}", id)?;
         }
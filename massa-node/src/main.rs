@@ -8,16 +8,19 @@ extern crate massa_logging;
 use crate::settings::{POOL_CONFIG, SETTINGS};
 
 use dialoguer::Password;
-use massa_api::{Private, Public, RpcServer, StopHandle, API};
+use massa_api::{Private, Public, RpcServer, StopHandle, Ws, API};
 use massa_async_pool::AsyncPoolConfig;
-use massa_bootstrap::{get_state, start_bootstrap_server, BootstrapManager};
+use massa_bootstrap::{
+    get_state, resume_local_state, save_local_state, start_bootstrap_server, BootstrapManager,
+    GlobalBootstrapState,
+};
 use massa_cipher::{decrypt, encrypt};
 use massa_consensus_exports::{
     events::ConsensusEvent, settings::ConsensusChannels, ConsensusCommandSender, ConsensusConfig,
     ConsensusEventReceiver, ConsensusManager,
 };
 use massa_consensus_worker::start_consensus_controller;
-use massa_execution_exports::{ExecutionConfig, ExecutionManager};
+use massa_execution_exports::{ExecutionChannels, ExecutionConfig, ExecutionManager};
 use massa_execution_worker::start_execution_worker;
 use massa_final_state::{FinalState, FinalStateConfig};
 use massa_ledger_exports::LedgerConfig;
@@ -25,8 +28,10 @@ use massa_ledger_worker::FinalLedger;
 use massa_logging::massa_trace;
 use massa_models::{
     constants::{
-        END_TIMESTAMP, GENESIS_TIMESTAMP, MAX_ASYNC_GAS, MAX_ASYNC_POOL_LENGTH, MAX_GAS_PER_BLOCK,
-        OPERATION_VALIDITY_PERIODS, T0, THREAD_COUNT, VERSION,
+        CHANNEL_SIZE, END_TIMESTAMP, FINAL_RECEIPTS_RETENTION_PERIODS, GENESIS_TIMESTAMP,
+        LEDGER_COST_ACTIVATION_PERIOD, LEDGER_COST_PER_BYTE, MAX_ASYNC_GAS, MAX_ASYNC_POOL_LENGTH,
+        MAX_GAS_PER_BLOCK, OPERATION_BASE_GAS_COST, OPERATION_VALIDITY_PERIODS, T0, THREAD_COUNT,
+        VERSION,
     },
     init_serialization_context,
     prehash::Map,
@@ -44,8 +49,8 @@ use parking_lot::RwLock;
 use std::{path::Path, process, sync::Arc};
 use structopt::StructOpt;
 use tokio::signal;
-use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, info, warn};
 #[cfg(not(feature = "instrument"))]
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
 
@@ -54,7 +59,9 @@ mod settings;
 async fn launch(
     password: &str,
     staking_keys: &Map<Address, KeyPair>,
+    resume: bool,
 ) -> (
+    Arc<RwLock<FinalState>>,
     PoolCommandSender,
     ConsensusEventReceiver,
     ConsensusCommandSender,
@@ -68,6 +75,7 @@ async fn launch(
     mpsc::Receiver<()>,
     StopHandle,
     StopHandle,
+    StopHandle,
 ) {
     info!("Node version : {}", *VERSION);
     if let Some(end) = *END_TIMESTAMP {
@@ -83,12 +91,17 @@ async fn launch(
     let ledger_config = LedgerConfig {
         initial_sce_ledger_path: SETTINGS.ledger.initial_sce_ledger_path.clone(),
         disk_ledger_path: SETTINGS.ledger.disk_ledger_path.clone(),
+        archive_mode: SETTINGS.ledger.archive_mode,
+        archive_retention_periods: SETTINGS.ledger.archive_retention_periods,
+        storage_cost_per_byte: LEDGER_COST_PER_BYTE,
+        storage_cost_activation_period: LEDGER_COST_ACTIVATION_PERIOD,
     };
     let async_pool_config = AsyncPoolConfig {
         max_length: MAX_ASYNC_POOL_LENGTH,
     };
     let final_state_config = FinalStateConfig {
         final_history_length: SETTINGS.ledger.final_history_length,
+        receipts_retention_periods: FINAL_RECEIPTS_RETENTION_PERIODS,
         thread_count: THREAD_COUNT,
         ledger_config: ledger_config.clone(),
         async_pool_config,
@@ -97,42 +110,79 @@ async fn launch(
     // Init the global serialization context
     init_serialization_context(SerializationContext::default());
 
-    // Remove current disk ledger if there is one
+    // Create the final state on top of the disk ledger
+    let create_final_state = || {
+        let ledger = FinalLedger::new(ledger_config.clone()).expect("could not init final ledger");
+        FinalState::new(final_state_config.clone(), Box::new(ledger))
+            .expect("could not init final state")
+    };
+
+    // Remove current disk ledger if there is one, unless the node can resume from it
     // NOTE: this is temporary, since we cannot currently handle bootstrap from remaining ledger
-    if SETTINGS.ledger.disk_ledger_path.exists() {
+    let local_state_path = SETTINGS
+        .bootstrap
+        .local_state_path
+        .as_ref()
+        .filter(|path| resume && path.is_file());
+    if local_state_path.is_none() && SETTINGS.ledger.disk_ledger_path.exists() {
         std::fs::remove_dir_all(SETTINGS.ledger.disk_ledger_path.clone())
             .expect("disk ledger delete failed");
     }
-
-    // Create final ledger
-    let ledger = FinalLedger::new(ledger_config.clone()).expect("could not init final ledger");
-
-    // Create final state
-    let final_state = Arc::new(RwLock::new(
-        FinalState::new(final_state_config, Box::new(ledger)).expect("could not init final state"),
-    ));
+    let mut final_state = create_final_state();
+
+    // Resume from the consensus state saved on disk if it is consistent with the final state,
+    // otherwise start again from an empty disk ledger and bootstrap
+    let mut local_state = None;
+    if let Some(path) = local_state_path {
+        match resume_local_state(path, &mut final_state) {
+            Ok(state) => {
+                info!(
+                    "resuming from the consensus state saved at slot {}",
+                    final_state.slot
+                );
+                local_state = Some(state);
+            }
+            Err(err) => {
+                warn!("could not resume from the saved consensus state: {}", err);
+                drop(final_state);
+                std::fs::remove_dir_all(SETTINGS.ledger.disk_ledger_path.clone())
+                    .expect("disk ledger delete failed");
+                final_state = create_final_state();
+            }
+        }
+    }
+    let final_state = Arc::new(RwLock::new(final_state));
 
     // interrupt signal listener
     let stop_signal = signal::ctrl_c();
     tokio::pin!(stop_signal);
 
-    // bootstrap
-    let bootstrap_state = tokio::select! {
-        _ = &mut stop_signal => {
-            info!("interrupt signal received in bootstrap loop");
-            process::exit(0);
+    // bootstrap, unless resuming from the saved consensus state
+    let bootstrap_state = match local_state {
+        Some((pos, graph)) => GlobalBootstrapState {
+            pos: Some(pos),
+            graph: Some(graph),
+            compensation_millis: 0,
+            peers: None,
+            final_state: final_state.clone(),
+        },
+        None => tokio::select! {
+            _ = &mut stop_signal => {
+                info!("interrupt signal received in bootstrap loop");
+                process::exit(0);
+            },
+            res = get_state(
+                &SETTINGS.bootstrap,
+                final_state.clone(),
+                massa_bootstrap::types::Establisher::new(),
+                *VERSION,
+                *GENESIS_TIMESTAMP,
+                *END_TIMESTAMP,
+            ) => match res {
+                Ok(vals) => vals,
+                Err(err) => panic!("critical error detected in the bootstrap process: {}", err)
+            }
         },
-        res = get_state(
-            &SETTINGS.bootstrap,
-            final_state.clone(),
-            massa_bootstrap::types::Establisher::new(),
-            *VERSION,
-            *GENESIS_TIMESTAMP,
-            *END_TIMESTAMP,
-        ) => match res {
-            Ok(vals) => vals,
-            Err(err) => panic!("critical error detected in the bootstrap process: {}", err)
-        }
     };
 
     // launch network controller
@@ -182,19 +232,28 @@ async fn launch(
         cursor_delay: SETTINGS.execution.cursor_delay,
         clock_compensation: bootstrap_state.compensation_millis,
         max_async_gas: MAX_ASYNC_GAS,
+        operation_base_gas_cost: OPERATION_BASE_GAS_COST,
         thread_count: THREAD_COUNT,
         t0: T0,
         genesis_timestamp: *GENESIS_TIMESTAMP,
+        event_archive_path: SETTINGS.execution.event_archive_path.clone(),
+        event_archive_retention_periods: SETTINGS.execution.event_archive_retention_periods,
+        replay_recording_path: SETTINGS.execution.replay_recording_path.clone(),
+    };
+    let execution_channels = ExecutionChannels {
+        slot_execution_output_sender: broadcast::channel(CHANNEL_SIZE).0,
     };
     let (execution_manager, execution_controller) = start_execution_worker(
         execution_config,
         final_state.clone(),
         shared_storage.clone(),
+        execution_channels.clone(),
     );
 
     // init consensus configuration
     let consensus_config = ConsensusConfig::from(&SETTINGS.consensus);
     // launch consensus controller
+    let consensus_broadcast_sender = broadcast::channel(CHANNEL_SIZE).0;
     let (consensus_command_sender, consensus_event_receiver, consensus_manager) =
         start_consensus_controller(
             consensus_config.clone(),
@@ -203,6 +262,7 @@ async fn launch(
                 protocol_command_sender: protocol_command_sender.clone(),
                 protocol_event_receiver,
                 pool_command_sender: pool_command_sender.clone(),
+                broadcast_event_sender: consensus_broadcast_sender.clone(),
             },
             bootstrap_state.pos,
             bootstrap_state.graph,
@@ -253,7 +313,17 @@ async fn launch(
     );
     let api_public_handle = api_public.serve(&SETTINGS.api.bind_public);
 
+    // spawn websocket API
+    let api_ws = API::<Ws>::new(
+        consensus_command_sender.clone(),
+        consensus_broadcast_sender,
+        execution_channels.slot_execution_output_sender,
+        &SETTINGS.api,
+    );
+    let api_ws_handle = api_ws.serve(&SETTINGS.api.bind_ws);
+
     (
+        final_state,
         pool_command_sender,
         consensus_event_receiver,
         consensus_command_sender,
@@ -267,6 +337,7 @@ async fn launch(
         api_private_stop_rx,
         api_private_handle,
         api_public_handle,
+        api_ws_handle,
     )
 }
 
@@ -279,7 +350,25 @@ struct Managers {
     network_manager: NetworkManager,
 }
 
+/// Save the consensus state on disk if enabled, allowing to restart without bootstrapping.
+/// It is only saved on a clean shutdown, once execution is stopped, since the disk ledger
+/// keeps moving forward afterwards while the node runs.
+async fn save_consensus_state(
+    final_state: &Arc<RwLock<FinalState>>,
+    consensus_command_sender: &ConsensusCommandSender,
+) {
+    if let Some(path) = &SETTINGS.bootstrap.local_state_path {
+        match save_local_state(path, final_state, consensus_command_sender).await {
+            Ok(slot) => debug!("consensus state saved at slot {}", slot),
+            Err(err) => warn!("could not save the consensus state: {}", err),
+        }
+    }
+}
+
 async fn stop(
+    final_state: Arc<RwLock<FinalState>>,
+    consensus_command_sender: ConsensusCommandSender,
+    save_state: bool,
     consensus_event_receiver: ConsensusEventReceiver,
     Managers {
         bootstrap_manager,
@@ -291,6 +380,7 @@ async fn stop(
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
+    api_ws_handle: StopHandle,
 ) {
     // stop bootstrap
     if let Some(bootstrap_manager) = bootstrap_manager {
@@ -300,21 +390,30 @@ async fn stop(
             .expect("bootstrap server shutdown failed")
     }
 
+    // stop websocket API
+    api_ws_handle.stop();
+
     // stop public API
     api_public_handle.stop();
 
     // stop private API
     api_private_handle.stop();
 
+    // Stop execution controller.
+    // It is stopped before consensus so that the final state does not change while the consensus state is saved.
+    execution_manager.stop();
+
+    // save the consensus state
+    if save_state {
+        save_consensus_state(&final_state, &consensus_command_sender).await;
+    }
+
     // stop consensus controller
     let protocol_event_receiver = consensus_manager
         .stop(consensus_event_receiver)
         .await
         .expect("consensus shutdown failed");
 
-    // Stop execution controller.
-    execution_manager.stop();
-
     // stop pool controller
     let protocol_pool_event_receiver = pool_manager.stop().await.expect("pool shutdown failed");
 
@@ -415,11 +514,13 @@ async fn main(args: Args) -> anyhow::Result<()> {
     let (password, staking_keys) =
         load_or_create_staking_keys_file(args.password, &SETTINGS.consensus.staking_keys_path)
             .await?;
+    let mut resume = true;
     loop {
         let (
+            final_state,
             _pool_command_sender,
             mut consensus_event_receiver,
-            _consensus_command_sender,
+            consensus_command_sender,
             _network_command_sender,
             bootstrap_manager,
             consensus_manager,
@@ -430,7 +531,8 @@ async fn main(args: Args) -> anyhow::Result<()> {
             mut api_private_stop_rx,
             api_private_handle,
             api_public_handle,
-        ) = launch(&password, &staking_keys).await;
+            api_ws_handle,
+        ) = launch(&password, &staking_keys, resume).await;
 
         // interrupt signal listener
         let stop_signal = signal::ctrl_c();
@@ -466,6 +568,9 @@ async fn main(args: Args) -> anyhow::Result<()> {
             }
         };
         stop(
+            final_state,
+            consensus_command_sender,
+            !restart,
             consensus_event_receiver,
             Managers {
                 bootstrap_manager,
@@ -477,12 +582,15 @@ async fn main(args: Args) -> anyhow::Result<()> {
             },
             api_private_handle,
             api_public_handle,
+            api_ws_handle,
         )
         .await;
 
         if !restart {
             break;
         }
+        // a desynchronized node must not resume from its saved state
+        resume = false;
     }
     Ok(())
 }
@@ -32,6 +32,9 @@ pub struct ExecutionSettings {
     pub max_final_events: usize,
     pub readonly_queue_length: usize,
     pub cursor_delay: MassaTime,
+    pub event_archive_path: Option<PathBuf>,
+    pub event_archive_retention_periods: u64,
+    pub replay_recording_path: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -39,6 +42,8 @@ pub struct LedgerSettings {
     pub initial_sce_ledger_path: PathBuf,
     pub disk_ledger_path: PathBuf,
     pub final_history_length: usize,
+    pub archive_mode: bool,
+    pub archive_retention_periods: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
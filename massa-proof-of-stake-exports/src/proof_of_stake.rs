@@ -4,6 +4,7 @@ use bitvec::prelude::BitVec;
 use massa_hash::Hash;
 use massa_models::{
     active_block::ActiveBlock,
+    api::{Equivocation, IndexedSlot, SelectionSchedule, StakerMisbehaviors},
     prehash::{Map, Set},
     rolls::{RollCounts, RollUpdates},
     Address, Amount, BlockId, Slot, StakersCycleProductionStats,
@@ -37,6 +38,9 @@ pub struct ProofOfStake {
     initial_seeds: Vec<Vec<u8>>,
     /// watched addresses
     watched_addresses: Set<Address>,
+    /// Equivocations detected by this node, indexed by slot.
+    /// They are not part of the exported state as each node detects them from the headers it received
+    equivocations: BTreeMap<Slot, Equivocation>,
 }
 
 impl ProofOfStake {
@@ -83,6 +87,8 @@ impl ProofOfStake {
                     cycle_updates: RollUpdates::default(),
                     rng_seed,
                     production_stats: Default::default(),
+                    missed_blocks: Default::default(),
+                    missed_endorsements: Default::default(),
                 };
                 history.push_front(thread_cycle_state);
                 cycle_states.push(history);
@@ -100,6 +106,7 @@ impl ProofOfStake {
             cfg,
             draw_cache_counter,
             watched_addresses: Set::<Address>::default(),
+            equivocations: BTreeMap::new(),
         })
     }
 
@@ -156,6 +163,81 @@ impl ProofOfStake {
         }
     }
 
+    /// Every block and endorsement slot each address is selected for,
+    /// from `start_cycle` up to the last cycle whose draws can be computed.
+    /// The range is bounded to the cycles that can be drawn from the current one:
+    /// from `current_cycle` to `current_cycle + pos_lookback_cycles`.
+    /// Fails if `start_cycle` is out of that range or if its draws are not available.
+    ///
+    /// # Arguments
+    /// * `addresses`: the addresses whose selections are listed
+    /// * `start_cycle`: the first cycle of the schedule
+    /// * `current_cycle`: the cycle of the latest slot
+    ///
+    /// # Returns
+    /// One schedule per address, in the order of `addresses`
+    pub fn get_selection_schedule(
+        &mut self,
+        addresses: &[Address],
+        start_cycle: u64,
+        current_cycle: u64,
+    ) -> POSResult<Vec<SelectionSchedule>> {
+        let last_cycle = current_cycle.saturating_add(self.cfg.pos_lookback_cycles);
+        if start_cycle < current_cycle || start_cycle > last_cycle {
+            return Err(ProofOfStakeError::PosCycleUnavailable(format!(
+                "selection schedule start cycle {} is out of the cycles {} to {}",
+                start_cycle, current_cycle, last_cycle
+            )));
+        }
+        let mut block_draws: Map<Address, Vec<Slot>> =
+            addresses.iter().map(|addr| (*addr, Vec::new())).collect();
+        let mut endorsement_draws: Map<Address, Vec<IndexedSlot>> =
+            addresses.iter().map(|addr| (*addr, Vec::new())).collect();
+        let mut cycle = start_cycle;
+        while cycle <= last_cycle {
+            let draws = match self.get_cycle_draws(cycle) {
+                Ok(draws) => draws,
+                Err(err) if cycle == start_cycle => return Err(err),
+                // the next cycles are not drawable yet
+                Err(_) => break,
+            };
+            for (slot, (block_addr, endorsement_addrs)) in draws.iter() {
+                // genesis blocks are not drawn
+                if slot.period == 0 {
+                    continue;
+                }
+                if let Some(slots) = block_draws.get_mut(block_addr) {
+                    slots.push(*slot);
+                }
+                for (index, endorsement_addr) in endorsement_addrs.iter().enumerate() {
+                    if let Some(slots) = endorsement_draws.get_mut(endorsement_addr) {
+                        slots.push(IndexedSlot { slot: *slot, index });
+                    }
+                }
+            }
+            cycle += 1;
+        }
+        // the draws of `start_cycle` are known so the loop went past it
+        let end_cycle = cycle - 1;
+
+        Ok(addresses
+            .iter()
+            .map(|addr| {
+                let mut blocks = block_draws.get(addr).cloned().unwrap_or_default();
+                blocks.sort_unstable();
+                let mut endorsements = endorsement_draws.get(addr).cloned().unwrap_or_default();
+                endorsements.sort_unstable_by_key(|indexed| (indexed.slot, indexed.index));
+                SelectionSchedule {
+                    address: *addr,
+                    start_cycle,
+                    end_cycle,
+                    block_draws: blocks,
+                    endorsement_draws: endorsements,
+                }
+            })
+            .collect())
+    }
+
     /// returns map slot -> ( block producer, endorsement producers)
     fn get_cycle_draws(
         &mut self,
@@ -383,6 +465,8 @@ impl ProofOfStake {
                         roll_count,
                         rng_seed: BitVec::<u8>::new(),
                         production_stats: Default::default(),
+                        missed_blocks: Default::default(),
+                        missed_endorsements: Default::default(),
                     });
                     // If cycle_states becomes longer than pos_lookback_cycles+pos_lock_cycles+1, truncate it by removing the back elements
                     self.cycle_states[thread as usize].truncate(
@@ -410,6 +494,13 @@ impl ProofOfStake {
                             if let Some(entry) = self.cycle_states[thread as usize]
                                 .get_mut(neg_relative_cycle as usize)
                             {
+                                if !evt_ok {
+                                    entry
+                                        .missed_blocks
+                                        .entry(*evt_addr)
+                                        .or_default()
+                                        .push(evt_slot);
+                                }
                                 match entry.production_stats.entry(*evt_addr) {
                                     hash_map::Entry::Occupied(mut occ) => {
                                         let cur_val = *occ.get();
@@ -430,6 +521,35 @@ impl ProofOfStake {
                             }
                         }
                     }
+
+                    // the block endorses its parent in its own thread:
+                    // the endorsements drawn for the parent's slot that it does not include are missed.
+                    // They are recorded in the cycle of the block, so that a cycle holds all its misses once it is complete
+                    let parent_period = a_block.parents[thread as usize].1;
+                    if parent_period > 0 {
+                        let endorsed_slot = Slot::new(parent_period, thread);
+                        let included: Set<u32> =
+                            a_block.endorsement_ids.values().copied().collect();
+                        let endorsement_draws = self.draw_endorsement_producers(endorsed_slot)?;
+                        let entry = &mut self.cycle_states[thread as usize][0];
+                        for (index, endorser) in endorsement_draws.into_iter().enumerate() {
+                            let index = index as u32;
+                            if included.contains(&index) {
+                                continue;
+                            }
+                            if self.watched_addresses.contains(&endorser) {
+                                warn!(
+                                    "address {} missed an endorsement opportunity at slot {} (index {})",
+                                    endorser, endorsed_slot, index
+                                );
+                            }
+                            entry
+                                .missed_endorsements
+                                .entry(endorser)
+                                .or_default()
+                                .push((endorsed_slot, index));
+                        }
+                    }
                 }
 
                 // apply the miss/block to the latest cycle_states
@@ -453,6 +573,19 @@ impl ProofOfStake {
             }
         }
 
+        // forget the equivocations of the cycles that are not kept anymore
+        if let Some(oldest_cycle) = self
+            .cycle_states
+            .iter()
+            .filter_map(|thread_states| thread_states.back())
+            .map(|state| state.cycle)
+            .min()
+        {
+            self.equivocations = self
+                .equivocations
+                .split_off(&Slot::new(oldest_cycle * self.cfg.periods_per_cycle, 0));
+        }
+
         // if initial rolls are not needed, remove them to free memory
         if self.initial_rolls.is_some()
             && !self
@@ -466,6 +599,68 @@ impl ProofOfStake {
         Ok(())
     }
 
+    /// Note that several headers were created by `address` for `slot`.
+    /// The equivocation is only recorded for information, until its cycle is not kept anymore:
+    /// it is not penalized as it is detected from the headers this node received.
+    pub fn note_equivocation(&mut self, slot: Slot, address: Address, block_ids: &[BlockId]) {
+        // headers are not checked anymore once their slot is final
+        if slot <= self.get_last_final_block_slot(slot.thread) {
+            return;
+        }
+        let equivocation = self
+            .equivocations
+            .entry(slot)
+            .or_insert_with(|| Equivocation {
+                slot,
+                address,
+                block_ids: Set::default(),
+            });
+        equivocation.block_ids.extend(block_ids);
+        if self.watched_addresses.contains(&address) {
+            warn!(
+                "address {} created several blocks for slot {}: {:?}",
+                address, slot, equivocation.block_ids
+            );
+        }
+    }
+
+    /// get the missed slots and equivocations of addresses over the cycles kept in memory
+    pub fn get_stakers_misbehaviors(&self, addrs: &[Address]) -> Vec<StakerMisbehaviors> {
+        addrs
+            .iter()
+            .map(|addr| {
+                let mut missed_blocks = Vec::new();
+                let mut missed_endorsements = Vec::new();
+                for thread_state in self.cycle_states.iter().flatten() {
+                    if let Some(slots) = thread_state.missed_blocks.get(addr) {
+                        missed_blocks.extend(slots);
+                    }
+                    if let Some(endorsements) = thread_state.missed_endorsements.get(addr) {
+                        missed_endorsements.extend(endorsements.iter().map(|(slot, index)| {
+                            IndexedSlot {
+                                slot: *slot,
+                                index: *index as usize,
+                            }
+                        }));
+                    }
+                }
+                missed_blocks.sort_unstable();
+                missed_endorsements.sort_unstable_by_key(|indexed| (indexed.slot, indexed.index));
+                StakerMisbehaviors {
+                    address: *addr,
+                    missed_blocks,
+                    missed_endorsements,
+                    equivocations: self
+                        .equivocations
+                        .values()
+                        .filter(|equivocation| equivocation.address == *addr)
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
     /// get production stats for addresses
     pub fn get_stakers_production_stats(
         &self,
@@ -579,6 +774,7 @@ impl ProofOfStake {
 
         // get roll data from all threads for addresses belonging to address_thread
         let mut addr_stats: Map<Address, (u64, u64)> = Map::default();
+        let mut missed_endorsement_counts: Map<Address, u64> = Map::default();
         for thread in 0..self.cfg.thread_count {
             // get roll data
             let roll_data = self
@@ -588,6 +784,11 @@ impl ProofOfStake {
                 return Err(ProofOfStakeError::NotFinalRollError); // target_cycle not completely final
             }
             // accumulate counters
+            for (addr, missed) in roll_data.missed_endorsements.iter() {
+                if addr.get_thread(self.cfg.thread_count) == address_thread {
+                    *missed_endorsement_counts.entry(*addr).or_default() += missed.len() as u64;
+                }
+            }
             for (addr, (n_ok, n_nok)) in roll_data.production_stats.iter() {
                 if addr.get_thread(self.cfg.thread_count) != address_thread {
                     continue;
@@ -604,7 +805,7 @@ impl ProofOfStake {
             }
         }
         // list addresses with bad stats
-        let res: Set<Address> = addr_stats
+        let mut res: Set<Address> = addr_stats
             .into_iter()
             .filter_map(|(addr, (ok_count, nok_count))| {
                 if ok_count + nok_count == 0 {
@@ -618,6 +819,16 @@ impl ProofOfStake {
             })
             .collect();
 
+        // add addresses that missed too many endorsements
+        if let Some(threshold) = self.cfg.pos_missed_endorsements_deactivation_threshold {
+            res.extend(
+                missed_endorsement_counts
+                    .into_iter()
+                    .filter(|(_addr, count)| *count > threshold)
+                    .map(|(addr, _count)| addr),
+            );
+        }
+
         for alert_addr in res.intersection(&self.watched_addresses) {
             warn!("address {} is subject to an implicit roll sale at cycle {}. Check the stability of your node/connection to avoid further misses, then buy rolls again.", alert_addr, cycle);
         }
@@ -22,6 +22,8 @@ pub struct ProofOfStakeConfig {
     pub pos_draw_cached_cycles: usize,
     /// number of cycle misses (strictly) above which stakers are deactivated
     pub pos_miss_rate_deactivation_threshold: Ratio<u64>,
+    /// number of endorsements missed in a cycle (strictly) above which stakers are deactivated, `None` to disable
+    pub pos_missed_endorsements_deactivation_threshold: Option<u64>,
     /// path to initial rolls
     pub initial_rolls_path: PathBuf,
     /// initial seed
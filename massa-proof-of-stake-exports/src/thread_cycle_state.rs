@@ -33,6 +33,10 @@ pub struct ThreadCycleState {
     pub rng_seed: BitVec<u8>,
     /// Per-address production statistics `(ok_count, nok_count)`
     pub production_stats: Map<Address, (u64, u64)>,
+    /// Per-address slots of this thread at which the address missed creating a block
+    pub missed_blocks: Map<Address, Vec<Slot>>,
+    /// Per-address endorsements `(endorsed slot, index)` of this thread that are missing from final blocks
+    pub missed_endorsements: Map<Address, Vec<(Slot, u32)>>,
 }
 
 impl ThreadCycleState {
@@ -128,6 +132,51 @@ impl Serializer<ThreadCycleState> for ThreadCycleStateSerializer {
             self.u64_serializer.serialize(ok_count, buffer)?;
             self.u64_serializer.serialize(nok_count, buffer)?;
         }
+
+        // missed blocks
+        let n_entries: u32 = value.missed_blocks.len().try_into().map_err(|err| {
+            SerializeError::NumberTooBig(format!(
+                "too many entries when serializing ExportThreadCycleState missed_blocks: {}",
+                err
+            ))
+        })?;
+        self.u32_serializer.serialize(&n_entries, buffer)?;
+        for (address, slots) in value.missed_blocks.iter() {
+            buffer.extend(address.to_bytes());
+            let n_slots: u32 = slots.len().try_into().map_err(|err| {
+                SerializeError::NumberTooBig(format!(
+                    "too many slots when serializing ExportThreadCycleState missed_blocks: {}",
+                    err
+                ))
+            })?;
+            self.u32_serializer.serialize(&n_slots, buffer)?;
+            for slot in slots {
+                self.slot_serializer.serialize(slot, buffer)?;
+            }
+        }
+
+        // missed endorsements
+        let n_entries: u32 = value.missed_endorsements.len().try_into().map_err(|err| {
+            SerializeError::NumberTooBig(format!(
+                "too many entries when serializing ExportThreadCycleState missed_endorsements: {}",
+                err
+            ))
+        })?;
+        self.u32_serializer.serialize(&n_entries, buffer)?;
+        for (address, endorsements) in value.missed_endorsements.iter() {
+            buffer.extend(address.to_bytes());
+            let n_endorsements: u32 = endorsements.len().try_into().map_err(|err| {
+                SerializeError::NumberTooBig(format!(
+                    "too many endorsements when serializing ExportThreadCycleState missed_endorsements: {}",
+                    err
+                ))
+            })?;
+            self.u32_serializer.serialize(&n_endorsements, buffer)?;
+            for (slot, index) in endorsements {
+                self.slot_serializer.serialize(slot, buffer)?;
+                self.u32_serializer.serialize(index, buffer)?;
+            }
+        }
         Ok(())
     }
 }
@@ -242,18 +291,58 @@ impl Deserializer<ThreadCycleState> for ThreadCycleStateDeserializer {
                         production_stats
                     }),
                 ),
+                context(
+                    "Failed missed_blocks deserialization",
+                    length_count(
+                        |input| self.u32_deserializer.deserialize(input),
+                        tuple((
+                            |input| self.address_deserializer.deserialize(input),
+                            length_count(
+                                |input| self.u32_deserializer.deserialize(input),
+                                |input| self.slot_deserializer.deserialize(input),
+                            ),
+                        )),
+                    )
+                    .map(|res| res.into_iter().collect::<Map<Address, Vec<Slot>>>()),
+                ),
+                context(
+                    "Failed missed_endorsements deserialization",
+                    length_count(
+                        |input| self.u32_deserializer.deserialize(input),
+                        tuple((
+                            |input| self.address_deserializer.deserialize(input),
+                            length_count(
+                                |input| self.u32_deserializer.deserialize(input),
+                                tuple((
+                                    |input| self.slot_deserializer.deserialize(input),
+                                    |input| self.u32_deserializer.deserialize(input),
+                                )),
+                            ),
+                        )),
+                    )
+                    .map(|res| res.into_iter().collect::<Map<Address, Vec<(Slot, u32)>>>()),
+                ),
             )),
         )
         .map(
-            |(cycle, last_final_slot, roll_count, cycle_updates, rng_seed, production_stats)| {
-                ThreadCycleState {
-                    cycle,
-                    last_final_slot,
-                    roll_count,
-                    cycle_updates,
-                    rng_seed,
-                    production_stats,
-                }
+            |(
+                cycle,
+                last_final_slot,
+                roll_count,
+                cycle_updates,
+                rng_seed,
+                production_stats,
+                missed_blocks,
+                missed_endorsements,
+            )| ThreadCycleState {
+                cycle,
+                last_final_slot,
+                roll_count,
+                cycle_updates,
+                rng_seed,
+                production_stats,
+                missed_blocks,
+                missed_endorsements,
             },
         )
         .parse(buffer)
@@ -0,0 +1,160 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+//! Offline replay of final slots.
+//!
+//! Re-executes the final slots recorded by a node (see the `replay_recording_path` execution setting)
+//! from the final state snapshot of the recording with their final blocks,
+//! and reports the slots whose state changes differ from the ones recorded by the node.
+#![warn(missing_docs)]
+#![warn(unused_crate_dependencies)]
+
+use anyhow::{bail, Result};
+use massa_async_pool::AsyncPoolConfig;
+use massa_execution_worker::replay::{
+    load_blocks, load_state_changes, replay_final_slots, FinalStateSnapshot, ReplaySettings,
+    SlotReplayReport, BLOCKS_FILE, SETTINGS_FILE, SNAPSHOT_FILE, STATE_CHANGES_FILE,
+};
+use massa_final_state::FinalStateConfig;
+use massa_ledger_exports::LedgerConfig;
+use massa_ledger_worker::FinalLedger;
+use massa_models::{init_serialization_context, SerializationContext, Slot};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Args {
+    /// Directory of the recording of the final slots of a node
+    #[structopt(parse(from_os_str))]
+    recording: PathBuf,
+    /// Period of the last replayed slot, defaults to the latest slot of the blocks and recorded changes
+    #[structopt(long)]
+    end_period: Option<u64>,
+    /// Thread of the last replayed slot
+    #[structopt(long, default_value = "0")]
+    end_thread: u8,
+    /// Empty directory of the disk ledger rebuilt from the snapshot, a temporary directory by default
+    #[structopt(long, parse(from_os_str))]
+    ledger_path: Option<PathBuf>,
+    /// Print every replayed slot, not only the divergent ones
+    #[structopt(short = "v", long = "verbose")]
+    verbose: bool,
+}
+
+/// Print the result of the replay of a slot
+fn print_report(report: &SlotReplayReport) {
+    let block = report
+        .block_id
+        .map(|block_id| format!("block {}", block_id))
+        .unwrap_or_else(|| "miss".to_string());
+    if !report.checked {
+        println!("{} ({}): no recorded changes", report.slot, block);
+    } else if report.is_consistent() {
+        println!("{} ({}): ok", report.slot, block);
+    } else {
+        println!("{} ({}): DIVERGENCE", report.slot, block);
+        for (addr, (recorded, replayed)) in report.ledger_divergences.iter() {
+            println!("  ledger entry {}:", addr);
+            println!("    recorded: {:?}", recorded);
+            println!("    replayed: {:?}", replayed);
+        }
+        for change in report.missing_async_pool_changes.iter() {
+            println!(
+                "  async pool change recorded but not replayed: {:?}",
+                change
+            );
+        }
+        for change in report.unexpected_async_pool_changes.iter() {
+            println!(
+                "  async pool change replayed but not recorded: {:?}",
+                change
+            );
+        }
+        for (op_id, (recorded, replayed)) in report.receipt_divergences.iter() {
+            println!("  receipt of operation {}:", op_id);
+            println!("    recorded: {:?}", recorded);
+            println!("    replayed: {:?}", replayed);
+        }
+    }
+    println!("  final state fingerprint: {}", report.fingerprint);
+}
+
+#[paw::main]
+fn main(args: Args) -> Result<()> {
+    init_serialization_context(SerializationContext::default());
+
+    let settings = ReplaySettings::load(&args.recording.join(SETTINGS_FILE))?;
+    let snapshot = FinalStateSnapshot::load(&args.recording.join(SNAPSHOT_FILE))?;
+    let blocks = load_blocks(&args.recording.join(BLOCKS_FILE))?;
+    let recorded_changes = load_state_changes(&args.recording.join(STATE_CHANGES_FILE))?;
+    let end_slot = match args.end_period {
+        Some(period) => Slot::new(period, args.end_thread),
+        None => blocks
+            .iter()
+            .map(|block| block.content.header.content.slot)
+            .chain(recorded_changes.iter().map(|(slot, _)| *slot))
+            .max()
+            .unwrap_or(snapshot.slot),
+    };
+
+    // rebuild the final state in an empty disk ledger,
+    // an existing ledger being reopened with its content
+    if let Some(ledger_path) = &args.ledger_path {
+        if ledger_path.exists() && std::fs::read_dir(ledger_path)?.next().is_some() {
+            bail!(
+                "the ledger directory {} is not empty",
+                ledger_path.display()
+            );
+        }
+    }
+    let tempdir = tempfile::tempdir()?;
+    let initial_ledger_path = tempdir.path().join("initial_ledger.json");
+    std::fs::write(&initial_ledger_path, "{}")?;
+    let ledger_config = LedgerConfig {
+        initial_sce_ledger_path: initial_ledger_path,
+        disk_ledger_path: args
+            .ledger_path
+            .unwrap_or_else(|| tempdir.path().join("ledger")),
+        archive_mode: false,
+        archive_retention_periods: 0,
+        storage_cost_per_byte: settings.storage_cost_per_byte,
+        storage_cost_activation_period: settings.storage_cost_activation_period,
+    };
+    let final_state_config = FinalStateConfig {
+        final_history_length: 0,
+        receipts_retention_periods: settings.receipts_retention_periods,
+        thread_count: settings.thread_count,
+        ledger_config: ledger_config.clone(),
+        async_pool_config: AsyncPoolConfig {
+            max_length: settings.async_pool_max_length,
+        },
+    };
+    let ledger = FinalLedger::new(ledger_config)?;
+    let final_state = snapshot.restore(final_state_config, Box::new(ledger))?;
+
+    let reports = replay_final_slots(
+        settings.execution_config(),
+        final_state,
+        blocks,
+        recorded_changes,
+        end_slot,
+    )?;
+
+    let mut divergent_slots = 0;
+    for report in reports.iter() {
+        if !report.is_consistent() {
+            divergent_slots += 1;
+        }
+        if args.verbose || !report.is_consistent() {
+            print_report(report);
+        }
+    }
+    println!(
+        "replayed {} slots up to {}, {} divergent",
+        reports.len(),
+        end_slot,
+        divergent_slots
+    );
+    if divergent_slots > 0 {
+        bail!("{} divergent slots", divergent_slots);
+    }
+    Ok(())
+}
@@ -4,20 +4,24 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
-use jsonrpc_core_client::transports::http;
-use jsonrpc_core_client::{RpcChannel, RpcError, RpcResult, TypedClient};
+use jsonrpc_core_client::transports::{http, ws};
+use jsonrpc_core_client::{RpcChannel, RpcError, RpcResult, TypedClient, TypedSubscriptionStream};
+use massa_ledger_exports::LedgerProof;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressHistoryInput, AddressHistoryOutput, AddressInfo, AddressInfoAtSlot, AsyncMessageFilter,
+    AsyncMessageInfo, BlockInfo, BlockSummary, BytecodeHistoryOutput, CreatorBlocksInput,
+    CreatorBlocksOutput, DatastoreEntryAtSlotOutput, DatastoreEntryInput, DatastoreEntryOutput,
+    DatastoreKeysInput, DatastoreKeysOutput, EndorsementInfo, EventFilter, GasEstimationRequest,
+    NodeStatus, OperationInfo, OperationInput, OperationReceiptOutput, OperationStatus,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionSchedule, StakerMisbehaviors, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, GasEstimation, OperationSimulation};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{Map, Set};
-use massa_models::{Address, BlockId, EndorsementId, OperationId};
+use massa_models::{Address, BlockId, EndorsementId, OperationId, Slot};
 use massa_signature::KeyPair;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -185,6 +189,19 @@ impl RpcClient {
             .await
     }
 
+    /// get the execution receipts of operations
+    pub async fn get_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<OperationReceiptOutput>> {
+        self.call_method(
+            "get_operation_receipts",
+            "Vec<OperationReceiptOutput>",
+            vec![operation_ids],
+        )
+        .await
+    }
+
     /// get info on endorsements by ids
     pub async fn get_endorsements(
         &self,
@@ -204,7 +221,8 @@ impl RpcClient {
             .await
     }
 
-    /// Get events emitted by smart contracts with various filters
+    /// Get events emitted by smart contracts with various filters.
+    /// Use the `cursor` and `limit` of the filter to paginate through the results.
     pub async fn get_filtered_sc_output_event(
         &self,
         filter: EventFilter,
@@ -217,6 +235,15 @@ impl RpcClient {
         .await
     }
 
+    /// Get the pending asynchronous messages, filtered by sender, destination or emission slot range
+    pub async fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+    ) -> RpcResult<Vec<AsyncMessageInfo>> {
+        self.call_method("get_async_messages", "Vec<AsyncMessageInfo>", vec![filter])
+            .await
+    }
+
     /// Get the block graph within the specified time interval.
     /// Optional parameters: from `<time_start>` (included) and to `<time_end>` (excluded) millisecond timestamp
     pub(crate) async fn _get_graph_interval(
@@ -233,6 +260,54 @@ impl RpcClient {
             .await
     }
 
+    /// Get every block and endorsement slot addresses are selected for,
+    /// from `cycle` (the current one if `None`) to the last known cycle
+    pub async fn get_selection_schedule(
+        &self,
+        addresses: Vec<Address>,
+        cycle: Option<u64>,
+    ) -> RpcResult<Vec<SelectionSchedule>> {
+        self.call_method(
+            "get_selection_schedule",
+            "Vec<SelectionSchedule>",
+            (addresses, cycle),
+        )
+        .await
+    }
+
+    /// Get the missed slots and equivocations of stakers
+    pub async fn get_stakers_misbehaviors(
+        &self,
+        addresses: Vec<Address>,
+    ) -> RpcResult<Vec<StakerMisbehaviors>> {
+        self.call_method(
+            "get_stakers_misbehaviors",
+            "Vec<StakerMisbehaviors>",
+            vec![addresses],
+        )
+        .await
+    }
+
+    /// Get a page of the blocks created by an address.
+    /// Use the `next_cursor` of a page as the `cursor` of the input to get the next one.
+    pub async fn get_blocks_by_creator(
+        &self,
+        input: CreatorBlocksInput,
+    ) -> RpcResult<CreatorBlocksOutput> {
+        self.call_method("get_blocks_by_creator", "CreatorBlocksOutput", vec![input])
+            .await
+    }
+
+    /// Get a page of the final operations involving an address.
+    /// Use the `next_cursor` of a page as the `cursor` of the input to get the next one.
+    pub async fn get_address_history(
+        &self,
+        input: AddressHistoryInput,
+    ) -> RpcResult<AddressHistoryOutput> {
+        self.call_method("get_address_history", "AddressHistoryOutput", vec![input])
+            .await
+    }
+
     /// Get datastore entries
     pub async fn get_datastore_entries(
         &self,
@@ -246,6 +321,69 @@ impl RpcClient {
         .await
     }
 
+    /// Get a page of the candidate datastore keys of an address.
+    /// Use the `next_cursor` of a page as the `cursor` of the input to get the next one.
+    pub async fn get_datastore_keys(
+        &self,
+        input: DatastoreKeysInput,
+    ) -> RpcResult<DatastoreKeysOutput> {
+        self.call_method("get_datastore_keys", "DatastoreKeysOutput", vec![input])
+            .await
+    }
+
+    /// Get the bytecode history of addresses
+    pub async fn get_bytecode_history(
+        &self,
+        addresses: Vec<Address>,
+    ) -> RpcResult<Vec<BytecodeHistoryOutput>> {
+        self.call_method(
+            "get_bytecode_history",
+            "Vec<BytecodeHistoryOutput>",
+            vec![addresses],
+        )
+        .await
+    }
+
+    /// Get the final balances of addresses at the output of a past final slot
+    pub async fn get_addresses_at_slot(
+        &self,
+        addresses: Vec<Address>,
+        slot: Slot,
+    ) -> RpcResult<Vec<AddressInfoAtSlot>> {
+        self.call_method(
+            "get_addresses_at_slot",
+            "Vec<AddressInfoAtSlot>",
+            (addresses, slot),
+        )
+        .await
+    }
+
+    /// Get final datastore entries at the output of a past final slot
+    pub async fn get_datastore_entries_at_slot(
+        &self,
+        input: Vec<DatastoreEntryInput>,
+        slot: Slot,
+    ) -> RpcResult<Vec<DatastoreEntryAtSlotOutput>> {
+        self.call_method(
+            "get_datastore_entries_at_slot",
+            "Vec<DatastoreEntryAtSlotOutput>",
+            (input, slot),
+        )
+        .await
+    }
+
+    /// Get the final balance, bytecode hash and datastore entries of an address
+    /// along with their proofs against the final ledger root, at the latest or at a past final slot
+    pub async fn get_ledger_proof(
+        &self,
+        address: Address,
+        keys: Vec<Vec<u8>>,
+        slot: Option<Slot>,
+    ) -> RpcResult<LedgerProof> {
+        self.call_method("get_ledger_proof", "LedgerProof", (address, keys, slot))
+            .await
+    }
+
     // User (interaction with the node)
 
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
@@ -257,6 +395,19 @@ impl RpcClient {
             .await
     }
 
+    /// Simulates operations against the active state without sending them to the pool.
+    pub async fn simulate_operations(
+        &self,
+        operations: Vec<OperationInput>,
+    ) -> RpcResult<Vec<OperationSimulation>> {
+        self.call_method(
+            "simulate_operations",
+            "Vec<OperationSimulation>",
+            vec![operations],
+        )
+        .await
+    }
+
     /// execute read only bytecode
     pub async fn execute_read_only_bytecode(
         &self,
@@ -288,4 +439,88 @@ impl RpcClient {
         .pop()
         .ok_or_else(|| RpcError::Client("missing return value on execute_read_only_call".into()))
     }
+
+    /// estimate the gas cost of an `ExecuteSC` or `CallSC` operation sent by `caller_address`
+    pub async fn estimate_gas(&self, request: GasEstimationRequest) -> RpcResult<GasEstimation> {
+        self.call_method::<Vec<Vec<GasEstimationRequest>>, Vec<GasEstimation>>(
+            "estimate_gas",
+            "Vec<GasEstimation>",
+            vec![vec![request]],
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| RpcError::Client("missing return value on estimate_gas".into()))
+    }
+}
+
+/// Client of the websocket API, used to subscribe to streams of node events
+pub struct WsClient {
+    client: TypedClient,
+}
+
+/// This is required by `jsonrpc_core_client::transports::ws::connect`
+impl From<RpcChannel> for WsClient {
+    fn from(channel: RpcChannel) -> Self {
+        WsClient {
+            client: channel.into(),
+        }
+    }
+}
+
+impl WsClient {
+    /// Connect to the websocket API of a node
+    pub async fn new(ip: IpAddr, ws_port: u16) -> RpcResult<WsClient> {
+        let url = format!("ws://{}", SocketAddr::new(ip, ws_port));
+        ws::try_connect::<WsClient>(&url)?.await
+    }
+
+    /// Stream of the blocks integrated in the block graph
+    pub fn subscribe_new_blocks(&self) -> RpcResult<TypedSubscriptionStream<BlockSummary>> {
+        self.client.subscribe(
+            "subscribe_new_blocks",
+            (),
+            "new_blocks",
+            "unsubscribe_new_blocks",
+            "BlockSummary",
+        )
+    }
+
+    /// Stream of the blocks becoming final
+    pub fn subscribe_finalized_blocks(&self) -> RpcResult<TypedSubscriptionStream<BlockSummary>> {
+        self.client.subscribe(
+            "subscribe_finalized_blocks",
+            (),
+            "finalized_blocks",
+            "unsubscribe_finalized_blocks",
+            "BlockSummary",
+        )
+    }
+
+    /// Stream of the events emitted by smart contracts matching the filter
+    pub fn subscribe_sc_events(
+        &self,
+        filter: EventFilter,
+    ) -> RpcResult<TypedSubscriptionStream<SCOutputEvent>> {
+        self.client.subscribe(
+            "subscribe_sc_events",
+            vec![filter],
+            "sc_events",
+            "unsubscribe_sc_events",
+            "SCOutputEvent",
+        )
+    }
+
+    /// Stream of the status changes of an operation
+    pub fn subscribe_operation_status(
+        &self,
+        operation_id: OperationId,
+    ) -> RpcResult<TypedSubscriptionStream<OperationStatus>> {
+        self.client.subscribe(
+            "subscribe_operation_status",
+            vec![operation_id],
+            "operation_status",
+            "unsubscribe_operation_status",
+            "OperationStatus",
+        )
+    }
 }